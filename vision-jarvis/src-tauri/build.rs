@@ -1,3 +1,5 @@
 fn main() {
+    // 只有桌面应用需要 Tauri 的构建步骤（生成上下文、图标、权限清单）
+    #[cfg(feature = "desktop")]
     tauri_build::build()
 }
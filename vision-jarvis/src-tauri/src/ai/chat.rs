@@ -0,0 +1,197 @@
+/// 多轮对话模型
+///
+/// 与供应商无关的消息表示：system / user / assistant 三种角色，
+/// 每条消息由文本和图片片段混合组成，由各 Provider 转换为自己的请求格式
+
+use serde::{Deserialize, Serialize};
+
+/// 消息角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "system" => Some(ChatRole::System),
+            "user" => Some(ChatRole::User),
+            "assistant" => Some(ChatRole::Assistant),
+            _ => None,
+        }
+    }
+}
+
+/// 消息片段
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatContent {
+    Text { text: String },
+    /// base64 编码的图片（不含 data URL 前缀）
+    Image { media_type: String, data: String },
+}
+
+/// 一条对话消息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: Vec<ChatContent>,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: vec![ChatContent::Text { text: text.into() }],
+        }
+    }
+
+    pub fn system(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, text)
+    }
+
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, text)
+    }
+
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, text)
+    }
+
+    /// 追加一张 JPEG/PNG 等图片（builder 模式）
+    pub fn with_image(mut self, media_type: impl Into<String>, data_base64: impl Into<String>) -> Self {
+        self.content.push(ChatContent::Image {
+            media_type: media_type.into(),
+            data: data_base64.into(),
+        });
+        self
+    }
+
+    /// 拼接所有文本片段
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|c| match c {
+                ChatContent::Text { text } => Some(text.as_str()),
+                ChatContent::Image { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn has_images(&self) -> bool {
+        self.content.iter().any(|c| matches!(c, ChatContent::Image { .. }))
+    }
+}
+
+/// 多轮对话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Conversation {
+    pub fn new(title: impl Into<String>) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.into(),
+            messages: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+        self.updated_at = chrono::Utc::now().timestamp();
+    }
+
+    /// 合并所有 system 消息的文本（Claude、Gemini 将 system 作为独立字段传递）
+    pub fn system_prompt(messages: &[ChatMessage]) -> Option<String> {
+        let parts: Vec<String> = messages
+            .iter()
+            .filter(|m| m.role == ChatRole::System)
+            .map(ChatMessage::text)
+            .filter(|t| !t.is_empty())
+            .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n\n"))
+        }
+    }
+
+    /// 把对话展开为单条提示词（供只支持单轮文本的实现使用，图片会被忽略）
+    pub fn flatten(messages: &[ChatMessage]) -> String {
+        messages
+            .iter()
+            .map(|m| {
+                let label = match m.role {
+                    ChatRole::System => "系统",
+                    ChatRole::User => "用户",
+                    ChatRole::Assistant => "助手",
+                };
+                format!("[{}]\n{}", label, m.text())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_serde() {
+        let message = ChatMessage::user("这是什么？").with_image("image/png", "aGVsbG8=");
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            r#"{"role":"user","content":[{"type":"text","text":"这是什么？"},{"type":"image","media_type":"image/png","data":"aGVsbG8="}]}"#
+        );
+        let back: ChatMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, message);
+        assert!(back.has_images());
+        assert_eq!(back.text(), "这是什么？");
+    }
+
+    #[test]
+    fn test_system_prompt_and_flatten() {
+        let messages = vec![
+            ChatMessage::system("你是记忆助手"),
+            ChatMessage::user("昨天做了什么"),
+            ChatMessage::assistant("写代码"),
+        ];
+        assert_eq!(Conversation::system_prompt(&messages).as_deref(), Some("你是记忆助手"));
+        assert_eq!(Conversation::system_prompt(&messages[1..]), None);
+        assert_eq!(
+            Conversation::flatten(&messages),
+            "[系统]\n你是记忆助手\n\n[用户]\n昨天做了什么\n\n[助手]\n写代码"
+        );
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [ChatRole::System, ChatRole::User, ChatRole::Assistant] {
+            assert_eq!(ChatRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(ChatRole::parse("tool"), None);
+    }
+}
@@ -1,42 +1,377 @@
-use crate::error::AppResult;
-use crate::ai::provider::AIProviderConfig;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use log::{info, warn};
+use tokio::sync::oneshot;
+
+use crate::error::{AppError, AppResult};
+use crate::ai::provider::{AIConfig, AIProviderConfig};
 use crate::ai::factory::create_provider;
 use crate::ai::traits::AIProvider;
+use crate::ai::stream::TokenStream;
+use crate::ai::chat::ChatMessage;
+use crate::ai::retry::{is_retryable, AttemptRecord, RetryPolicy};
+use crate::ai::usage::{AIPurpose, Completion, TokenUsage, UsageRecord, UsageTracker};
+
+/// 保留的最近尝试记录数
+const MAX_ATTEMPT_RECORDS: usize = 200;
+
+type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = AppResult<T>> + Send + 'a>>;
+
+/// Provider 调用结果中携带的用量
+trait CallOutput {
+    fn usage(&self) -> Option<TokenUsage>;
+
+    /// 输出结束后才能得到的用量（流式响应）
+    fn deferred_usage(&mut self) -> Option<oneshot::Receiver<Option<TokenUsage>>> {
+        None
+    }
+}
+
+impl CallOutput for Completion {
+    fn usage(&self) -> Option<TokenUsage> {
+        self.usage
+    }
+}
+
+impl CallOutput for TokenStream {
+    fn usage(&self) -> Option<TokenUsage> {
+        None
+    }
+
+    fn deferred_usage(&mut self) -> Option<oneshot::Receiver<Option<TokenUsage>>> {
+        self.take_usage()
+    }
+}
 
 /// AI 客户端（facade，委托给具体 Provider 实现）
+///
+/// 按顺序持有一个主供应商和若干备用供应商：可重试的错误在同一供应商上指数退避重试，
+/// 重试用尽或遇到不可重试的错误时切换到下一个供应商
 pub struct AIClient {
-    inner: Box<dyn AIProvider>,
+    providers: Vec<Box<dyn AIProvider>>,
+    policy: RetryPolicy,
+    attempts: Mutex<VecDeque<AttemptRecord>>,
+    /// 用量记录与预算控制（未设置时不记录）
+    usage: Option<Arc<UsageTracker>>,
 }
 
 impl AIClient {
     pub fn new(config: AIProviderConfig) -> AppResult<Self> {
-        Ok(Self { inner: create_provider(config)? })
+        Ok(Self::from_provider(create_provider(config)?))
+    }
+
+    /// 直接包装已有的 Provider 实现
+    pub fn from_provider(inner: Box<dyn AIProvider>) -> Self {
+        Self::with_providers(vec![inner])
+    }
+
+    /// 按故障转移顺序包装多个 Provider（第一个为主供应商）；列表为空时返回错误
+    pub fn from_providers(providers: Vec<Box<dyn AIProvider>>) -> AppResult<Self> {
+        if providers.is_empty() {
+            return Err(AppError::ai(4, "AIClient 至少需要一个 Provider"));
+        }
+        Ok(Self::with_providers(providers))
+    }
+
+    fn with_providers(providers: Vec<Box<dyn AIProvider>>) -> Self {
+        Self {
+            providers,
+            policy: RetryPolicy::default(),
+            attempts: Mutex::new(VecDeque::new()),
+            usage: None,
+        }
+    }
+
+    /// 由 AIConfig 创建：激活的供应商优先，其余已启用的供应商按配置顺序作为备用
+    ///
+    /// 备用供应商配置无效时跳过；没有激活的供应商时返回错误
+    pub fn from_ai_config(config: &AIConfig) -> AppResult<Self> {
+        let chain = config.failover_chain();
+        let primary = chain
+            .first()
+            .ok_or_else(|| AppError::validation(20, "没有激活的 AI 提供商"))?;
+
+        let mut providers = vec![create_provider((*primary).clone())?];
+        for fallback in &chain[1..] {
+            match create_provider((*fallback).clone()) {
+                Ok(provider) => providers.push(provider),
+                Err(e) => warn!("[AIClient] 备用提供商 {} 配置无效，已跳过: {}", fallback.id, e),
+            }
+        }
+
+        Self::from_providers(providers)
+    }
+
+    /// 设置重试策略（builder 模式）
+    pub fn with_retry_policy(self, policy: RetryPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// 设置用量记录器（builder 模式）
+    pub fn with_usage_tracker(self, tracker: Arc<UsageTracker>) -> Self {
+        Self { usage: Some(tracker), ..self }
+    }
+
+    /// 按用途发起调用（用于用量统计与预算控制）
+    pub fn for_purpose(&self, purpose: AIPurpose) -> PurposeClient<'_> {
+        PurposeClient { client: self, purpose }
     }
 
     pub async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String> {
-        self.inner.analyze_image(image_base64, prompt).await
+        self.for_purpose(AIPurpose::Other).analyze_image(image_base64, prompt).await
     }
 
     pub async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String> {
-        self.inner.analyze_video(video_base64, prompt).await
+        self.for_purpose(AIPurpose::Other).analyze_video(video_base64, prompt).await
     }
 
     pub async fn send_text(&self, prompt: &str) -> AppResult<String> {
-        self.inner.send_text(prompt).await
+        self.for_purpose(AIPurpose::Other).send_text(prompt).await
+    }
+
+    pub async fn chat(&self, messages: &[ChatMessage]) -> AppResult<String> {
+        self.for_purpose(AIPurpose::Other).chat(messages).await
+    }
+
+    /// 流式发送；仅在建立连接阶段重试和故障转移，开始输出后的错误由调用方处理
+    pub async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        self.for_purpose(AIPurpose::Other).send_text_stream(prompt).await
     }
 
+    /// 只测试主供应商，不重试
     pub async fn test_connection(&self) -> AppResult<String> {
-        self.inner.test_connection().await
+        self.providers[0].test_connection().await
     }
 
+    /// 主供应商配置
     pub fn config(&self) -> &AIProviderConfig {
-        self.inner.config()
+        self.providers[0].config()
+    }
+
+    /// 故障转移顺序中的全部供应商配置
+    pub fn provider_configs(&self) -> Vec<&AIProviderConfig> {
+        self.providers.iter().map(|p| p.config()).collect()
+    }
+
+    /// 最近的调用尝试记录（按时间顺序）
+    pub fn recent_attempts(&self, limit: usize) -> Vec<AttemptRecord> {
+        let attempts = self.attempts.lock().unwrap();
+        attempts.iter().skip(attempts.len().saturating_sub(limit)).cloned().collect()
+    }
+
+    /// 依次尝试各供应商，返回第一个成功结果或最后一个错误
+    ///
+    /// 非必要用途在超出预算时直接返回错误，不发起请求
+    async fn execute<'a, T, M, F>(
+        &'a self,
+        purpose: AIPurpose,
+        operation: &str,
+        model: M,
+        call: F,
+    ) -> AppResult<T>
+    where
+        T: CallOutput,
+        M: Fn(&AIProviderConfig) -> String,
+        F: Fn(&'a dyn AIProvider) -> ProviderFuture<'a, T>,
+    {
+        if let Some(tracker) = &self.usage {
+            tracker.check(purpose)?;
+        }
+
+        let mut last_error = None;
+
+        for (index, provider) in self.providers.iter().enumerate() {
+            let config = provider.config();
+            let model_name = model(config);
+            if index > 0 {
+                info!("[AIClient] {} 切换到备用提供商 {} ({})", operation, config.id, model_name);
+            }
+
+            let mut attempt = 1;
+            loop {
+                let started = Instant::now();
+                let mut result = call(provider.as_ref()).await;
+                let latency_ms = started.elapsed().as_millis() as u64;
+
+                self.record_usage(config, &model_name, purpose, &mut result, started);
+
+                let err = match result {
+                    Ok(value) => {
+                        self.record(AttemptRecord {
+                            operation: operation.to_string(),
+                            provider_id: config.id.clone(),
+                            model: model_name,
+                            attempt,
+                            success: true,
+                            error: None,
+                            retryable: false,
+                            latency_ms,
+                            retry_delay_ms: None,
+                            timestamp: chrono::Utc::now().timestamp(),
+                        });
+                        return Ok(value);
+                    }
+                    Err(e) => e,
+                };
+
+                let delay = self.policy.next_delay(attempt, &err);
+                self.record(AttemptRecord {
+                    operation: operation.to_string(),
+                    provider_id: config.id.clone(),
+                    model: model_name.clone(),
+                    attempt,
+                    success: false,
+                    error: Some(err.to_string()),
+                    retryable: is_retryable(&err),
+                    latency_ms,
+                    retry_delay_ms: delay.map(|d| d.as_millis() as u64),
+                    timestamp: chrono::Utc::now().timestamp(),
+                });
+
+                match delay {
+                    Some(delay) => {
+                        warn!(
+                            "[AIClient] {} 失败（{} 第{}次），{}ms 后重试: {}",
+                            operation, config.id, attempt, delay.as_millis(), err
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    None => {
+                        warn!("[AIClient] {} 在 {} 上失败（共{}次）: {}", operation, config.id, attempt, err);
+                        last_error = Some(err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| AppError::ai(999, "没有可用的 AI 提供商")))
+    }
+
+    /// 写入用量记录；流式响应在输出结束后才记录，耗时按整个流计算
+    fn record_usage<T: CallOutput>(
+        &self,
+        config: &AIProviderConfig,
+        model: &str,
+        purpose: AIPurpose,
+        result: &mut AppResult<T>,
+        started: Instant,
+    ) {
+        let Some(tracker) = &self.usage else {
+            return;
+        };
+        let usage = result.as_ref().ok().and_then(|r| r.usage()).unwrap_or_default();
+        let record = UsageRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            provider_id: config.id.clone(),
+            model: model.to_string(),
+            purpose,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd: config.estimate_cost(model, &usage),
+            latency_ms: started.elapsed().as_millis() as u64,
+            success: result.is_ok(),
+        };
+
+        let Some(deferred) = result.as_mut().ok().and_then(|r| r.deferred_usage()) else {
+            write_usage(tracker, &record);
+            return;
+        };
+        let tracker = Arc::clone(tracker);
+        let config = config.clone();
+        tokio::spawn(async move {
+            let usage = deferred.await.ok().flatten().unwrap_or_default();
+            let record = UsageRecord {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                cost_usd: config.estimate_cost(&record.model, &usage),
+                latency_ms: started.elapsed().as_millis() as u64,
+                ..record
+            };
+            write_usage(&tracker, &record);
+        });
+    }
+
+    fn record(&self, record: AttemptRecord) {
+        let mut attempts = self.attempts.lock().unwrap();
+        if attempts.len() >= MAX_ATTEMPT_RECORDS {
+            attempts.pop_front();
+        }
+        attempts.push_back(record);
+    }
+}
+
+fn write_usage(tracker: &UsageTracker, record: &UsageRecord) {
+    if let Err(e) = tracker.record(record) {
+        warn!("[AIClient] 写入用量记录失败: {}", e);
+    }
+}
+
+/// 绑定了调用用途的客户端视图
+pub struct PurposeClient<'c> {
+    client: &'c AIClient,
+    purpose: AIPurpose,
+}
+
+impl PurposeClient<'_> {
+    pub async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String> {
+        self.client.execute(
+            self.purpose,
+            "analyze_image",
+            |c| c.effective_video_model().to_string(),
+            |p| p.analyze_image(image_base64, prompt),
+        ).await.map(|c| c.text)
+    }
+
+    pub async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String> {
+        self.client.execute(
+            self.purpose,
+            "analyze_video",
+            |c| c.effective_video_model().to_string(),
+            |p| p.analyze_video(video_base64, prompt),
+        ).await.map(|c| c.text)
+    }
+
+    pub async fn send_text(&self, prompt: &str) -> AppResult<String> {
+        self.client.execute(
+            self.purpose,
+            "send_text",
+            |c| c.model.clone(),
+            |p| p.send_text(prompt),
+        ).await.map(|c| c.text)
+    }
+
+    pub async fn chat(&self, messages: &[ChatMessage]) -> AppResult<String> {
+        self.client.execute(
+            self.purpose,
+            "chat",
+            |c| c.effective_chat_model(messages).to_string(),
+            |p| p.chat(messages),
+        ).await.map(|c| c.text)
+    }
+
+    /// 流式响应的用量在流结束后记录（供应商未返回 usage 时记为 0）
+    pub async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        self.client.execute(
+            self.purpose,
+            "send_text_stream",
+            |c| c.model.clone(),
+            |p| p.send_text_stream(prompt),
+        ).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
     fn create_test_config() -> AIProviderConfig {
         AIProviderConfig::new(
@@ -48,6 +383,63 @@ mod tests {
         )
     }
 
+    /// 按脚本依次返回错误，脚本用完后返回成功
+    struct ScriptedProvider {
+        config: AIProviderConfig,
+        script: Mutex<VecDeque<AppError>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ScriptedProvider {
+        fn boxed(id: &str, errors: Vec<AppError>) -> (Box<dyn AIProvider>, Arc<AtomicUsize>) {
+            Self::boxed_with(AIProviderConfig::new(id, id, "https://example.com", "key", "model"), errors)
+        }
+
+        fn boxed_with(config: AIProviderConfig, errors: Vec<AppError>) -> (Box<dyn AIProvider>, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let provider = Self {
+                config,
+                script: Mutex::new(errors.into()),
+                calls: Arc::clone(&calls),
+            };
+            (Box::new(provider), calls)
+        }
+    }
+
+    #[async_trait]
+    impl AIProvider for ScriptedProvider {
+        async fn send_text(&self, _prompt: &str) -> AppResult<Completion> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.script.lock().unwrap().pop_front() {
+                Some(err) => Err(err),
+                None => Ok(Completion::new(format!("ok from {}", self.config.id))
+                    .with_usage(Some(TokenUsage::new(1000, 200)))),
+            }
+        }
+        async fn analyze_video(&self, _: &str, prompt: &str) -> AppResult<Completion> {
+            self.send_text(prompt).await
+        }
+        async fn analyze_image(&self, _: &str, prompt: &str) -> AppResult<Completion> {
+            self.send_text(prompt).await
+        }
+        async fn test_connection(&self) -> AppResult<String> {
+            Ok("ok".to_string())
+        }
+        fn config(&self) -> &AIProviderConfig {
+            &self.config
+        }
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_retry_after: Duration::from_secs(2),
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+
     #[test]
     fn test_client_creation() {
         let config = create_test_config();
@@ -67,4 +459,153 @@ mod tests {
         let client = AIClient::new(config);
         assert!(client.is_err());
     }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let (provider, calls) = ScriptedProvider::boxed("primary", vec![
+            AppError::network(1, "请求超时"),
+            crate::ai::retry::status_error(503, "overloaded", None),
+        ]);
+        let client = AIClient::from_provider(provider).with_retry_policy(fast_policy());
+
+        assert_eq!(client.send_text("hi").await.unwrap(), "ok from primary");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let attempts = client.recent_attempts(10);
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(!attempts[0].success && attempts[0].retryable);
+        assert_eq!(attempts[0].retry_delay_ms, Some(1));
+        assert!(attempts[2].success);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_non_retryable_error() {
+        let (primary, primary_calls) = ScriptedProvider::boxed("primary", vec![crate::ai::retry::status_error(401, "", None)]);
+        let (backup, backup_calls) = ScriptedProvider::boxed("backup", vec![]);
+        let client = AIClient::from_providers(vec![primary, backup]).unwrap().with_retry_policy(fast_policy());
+
+        assert_eq!(client.send_text("hi").await.unwrap(), "ok from backup");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+
+        let attempts = client.recent_attempts(10);
+        assert_eq!(attempts[0].provider_id, "primary");
+        assert_eq!(attempts[0].retry_delay_ms, None);
+        assert_eq!(attempts[1].provider_id, "backup");
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_when_retry_after_too_long() {
+        let long_wait = crate::ai::retry::status_error(429, "", Some(Duration::from_secs(3600)));
+        let (primary, primary_calls) = ScriptedProvider::boxed("primary", vec![long_wait]);
+        let (backup, _) = ScriptedProvider::boxed("backup", vec![]);
+        let client = AIClient::from_providers(vec![primary, backup]).unwrap().with_retry_policy(fast_policy());
+
+        assert_eq!(client.analyze_video("", "p").await.unwrap(), "ok from backup");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_error_when_all_fail() {
+        let (primary, _) = ScriptedProvider::boxed("primary", vec![crate::ai::retry::status_error(403, "", None)]);
+        let (backup, backup_calls) = ScriptedProvider::boxed("backup", vec![
+            crate::ai::retry::status_error(429, "", None),
+            crate::ai::retry::status_error(429, "", None),
+            crate::ai::retry::status_error(429, "", None),
+        ]);
+        let client = AIClient::from_providers(vec![primary, backup]).unwrap().with_retry_policy(fast_policy());
+
+        let err = client.send_text("hi").await.unwrap_err();
+        assert!(matches!(err, AppError::Http { status: 429, .. }));
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 3);
+        assert_eq!(client.recent_attempts(2).len(), 2);
+    }
+
+    #[test]
+    fn test_from_providers_rejects_empty_list() {
+        assert!(matches!(AIClient::from_providers(Vec::new()), Err(AppError::AI(4, _))));
+    }
+
+    #[test]
+    fn test_from_ai_config_orders_failover_chain() {
+        let mut config = AIConfig::new();
+        for id in ["a", "b", "c"] {
+            config.add_provider(AIProviderConfig::new(id, id, "https://example.com", "key", "model")).unwrap();
+        }
+        config.providers[0].enabled = false;
+        config.set_active_provider("c").unwrap();
+
+        let client = AIClient::from_ai_config(&config).unwrap();
+        let ids: Vec<&str> = client.provider_configs().iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b"]);
+
+        assert!(AIClient::from_ai_config(&AIConfig::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_records_usage_and_enforces_budget() {
+        use crate::ai::usage::{UsageBudget, UsageGroup};
+        use crate::db::Database;
+
+        let tracker = Arc::new(UsageTracker::new(
+            Arc::new(Database::open_in_memory().unwrap()),
+            UsageBudget { daily_usd: 0.001, monthly_usd: 0.0 },
+        ));
+        let mut config = AIProviderConfig::new("primary", "primary", "https://example.com", "key", "model");
+        config.input_price_per_mtok = Some(1.0);
+        config.output_price_per_mtok = Some(0.0);
+        let (provider, _) = ScriptedProvider::boxed_with(config, vec![AppError::network(1, "请求超时")]);
+        let client = AIClient::from_provider(provider)
+            .with_retry_policy(fast_policy())
+            .with_usage_tracker(Arc::clone(&tracker));
+
+        client.for_purpose(AIPurpose::RecordingAnalysis).analyze_video("", "p").await.unwrap();
+
+        let by_purpose = tracker.aggregate(UsageGroup::Purpose, 0, i64::MAX).unwrap();
+        assert_eq!(by_purpose.len(), 1);
+        assert_eq!(by_purpose[0].key, "recording_analysis");
+        assert_eq!(by_purpose[0].calls, 2);
+        assert_eq!(by_purpose[0].failed_calls, 1);
+        assert_eq!(by_purpose[0].input_tokens, 1000);
+        assert!((by_purpose[0].cost_usd - 0.001).abs() < 1e-12);
+
+        // 预算已用完：后台任务被拒绝，用户问答不受影响
+        let err = client.for_purpose(AIPurpose::DailySummary).send_text("p").await.unwrap_err();
+        assert!(matches!(err, AppError::AI(30, _)));
+        assert!(client.for_purpose(AIPurpose::MemoryQa).send_text("p").await.is_ok());
+        // 未标注用途的调用同样受预算限制
+        assert!(matches!(client.send_text("p").await, Err(AppError::AI(30, _))));
+    }
+
+    #[tokio::test]
+    async fn test_records_stream_usage_when_stream_ends() {
+        use crate::ai::usage::{UsageBudget, UsageGroup};
+        use crate::db::Database;
+
+        let tracker = Arc::new(UsageTracker::new(
+            Arc::new(Database::open_in_memory().unwrap()),
+            UsageBudget::default(),
+        ));
+        let (provider, _) = ScriptedProvider::boxed("primary", vec![]);
+        let client = AIClient::from_provider(provider).with_usage_tracker(Arc::clone(&tracker));
+
+        let stream = client.for_purpose(AIPurpose::Chat).send_text_stream("p").await.unwrap();
+        assert_eq!(stream.collect_text().await.unwrap(), "ok from primary");
+
+        // 用量在后台任务中写入
+        let mut by_purpose = Vec::new();
+        for _ in 0..100 {
+            by_purpose = tracker.aggregate(UsageGroup::Purpose, 0, i64::MAX).unwrap();
+            if !by_purpose.is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(by_purpose.len(), 1);
+        assert_eq!(by_purpose[0].key, "chat");
+        assert_eq!(by_purpose[0].calls, 1);
+        assert_eq!(by_purpose[0].input_tokens, 1000);
+        assert_eq!(by_purpose[0].output_tokens, 200);
+    }
 }
@@ -0,0 +1,264 @@
+/// AI 配置状态
+///
+/// 提供商配置保存在 `ai_config` 表中，API Key 存放在凭据存储里；
+/// 桌面应用的 AI 配置命令与守护进程共用
+
+use crate::ai::{AIClient, AIConfig};
+use crate::crypto::secrets::SecretStore;
+use crate::db::Database;
+use crate::error::AppResult;
+use crate::memory::pipeline::PipelineScheduler;
+use log::{info, warn};
+use secrecy::ExposeSecret;
+use std::sync::{Arc, Mutex};
+
+/// AI 配置状态（克隆后共享同一份配置）
+#[derive(Clone)]
+pub struct AIConfigState {
+    config: Arc<Mutex<AIConfig>>,
+    db: Option<Arc<Database>>,
+    /// API Key 存放处（None 时只保存在内存中）
+    secrets: Option<Arc<dyn SecretStore>>,
+}
+
+impl AIConfigState {
+    pub fn new(db: Arc<Database>, secrets: Arc<dyn SecretStore>) -> Self {
+        let config = load_from_db(&db, secrets.as_ref()).unwrap_or_else(|e| {
+            warn!("Failed to load AI config: {}", e);
+            AIConfig::default()
+        });
+        info!(
+            "AI config loaded: {} providers, keys in {}",
+            config.providers.len(),
+            secrets.backend()
+        );
+        Self {
+            config: Arc::new(Mutex::new(config)),
+            db: Some(db),
+            secrets: Some(secrets),
+        }
+    }
+
+    pub fn get(&self) -> AIConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn update(&self, new_config: AIConfig) -> Result<(), String> {
+        if let Some(ref secrets) = self.secrets {
+            save_api_keys(secrets.as_ref(), &self.get(), &new_config)
+                .map_err(|e| format!("保存 API 密钥失败: {}", e))?;
+        }
+        if let Some(ref db) = self.db {
+            save_to_db(db, &new_config).map_err(|e| format!("保存配置失败: {}", e))?;
+        }
+        let mut config = self.config.lock().unwrap();
+        *config = new_config;
+        Ok(())
+    }
+
+    /// 重新从数据库加载（静态加密解锁后调用，启动时未解锁读到的是空配置）
+    pub fn reload(&self) -> Result<(), String> {
+        let (Some(db), Some(secrets)) = (&self.db, &self.secrets) else { return Ok(()) };
+        let loaded = load_from_db(db, secrets.as_ref()).map_err(|e| format!("加载配置失败: {}", e))?;
+        *self.config.lock().unwrap() = loaded;
+        Ok(())
+    }
+
+    /// 获取活跃提供商的配置（用于创建AIClient）
+    pub fn get_active_provider_config(&self) -> Option<crate::ai::AIProviderConfig> {
+        let config = self.config.lock().unwrap();
+        config.get_active_provider().cloned()
+    }
+}
+
+impl Default for AIConfigState {
+    fn default() -> Self {
+        Self {
+            config: Arc::new(Mutex::new(AIConfig::new())),
+            db: None,
+            secrets: None,
+        }
+    }
+}
+
+/// 提供商 API Key 在凭据存储中的名称
+fn api_key_name(provider_id: &str) -> String {
+    format!("ai-api-key:{}", provider_id)
+}
+
+/// 从数据库加载 AI 配置，API Key 从凭据存储补全
+///
+/// 旧版本把 Key 明文写在配置 JSON 里：读到时迁入凭据存储并重写配置
+fn load_from_db(db: &Database, secrets: &dyn SecretStore) -> Result<AIConfig, anyhow::Error> {
+    let json: Option<String> = db.with_connection(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT value FROM ai_config WHERE key = 'ai_config'",
+                [],
+                |row| row.get(0),
+            )
+            .ok())
+    })?;
+    let Some(json) = json else {
+        return Ok(AIConfig::new());
+    };
+
+    let mut config: AIConfig = serde_json::from_str(&db.vault().decrypt_text(json)?)?;
+    let mut migrated = false;
+    for provider in &mut config.providers {
+        let name = api_key_name(&provider.id);
+        if provider.has_api_key() {
+            secrets.set(&name, &provider.api_key)?;
+            migrated = true;
+        } else if let Some(key) = secrets.get(&name)? {
+            provider.api_key = key;
+        }
+    }
+    if migrated {
+        save_to_db(db, &config)?;
+        info!("Moved AI API keys from database to {}", secrets.backend());
+    }
+    Ok(config)
+}
+
+/// 把变更的 API Key 写入凭据存储，清除已删除提供商或已清空的 Key
+fn save_api_keys(secrets: &dyn SecretStore, old: &AIConfig, new: &AIConfig) -> AppResult<()> {
+    for provider in &new.providers {
+        let unchanged = old.get_provider(&provider.id)
+            .is_some_and(|p| p.api_key.expose_secret() == provider.api_key.expose_secret());
+        if unchanged {
+            continue;
+        }
+        let name = api_key_name(&provider.id);
+        if provider.has_api_key() {
+            secrets.set(&name, &provider.api_key)?;
+        } else {
+            secrets.delete(&name)?;
+        }
+    }
+    for removed in old.providers.iter().filter(|p| new.get_provider(&p.id).is_none()) {
+        secrets.delete(&api_key_name(&removed.id))?;
+    }
+    Ok(())
+}
+
+/// 保存 AI 配置到数据库（序列化时不含 API Key）
+fn save_to_db(db: &Database, config: &AIConfig) -> Result<(), anyhow::Error> {
+    let json = db.vault().encrypt_text(&serde_json::to_string(config)?)?;
+    db.with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO ai_config (key, value, updated_at) VALUES ('ai_config', ?1, strftime('%s', 'now'))",
+            [&json],
+        )?;
+        Ok(())
+    })
+}
+
+/// 切换活动提供商并重新连接管道（配置方案切换时也会调用）
+pub async fn activate_provider(
+    state: &AIConfigState,
+    pipeline: &PipelineScheduler,
+    provider_id: &str,
+) -> Result<(), String> {
+    let mut config = state.get();
+    config
+        .set_active_provider(provider_id)
+        .map_err(|e| format!("设置提供商失败: {}", e))?;
+    state.update(config).map_err(|e| format!("保存配置失败: {}", e))?;
+
+    // 自动连接到管道
+    if state.get_active_provider_config().is_some() {
+        match AIClient::from_ai_config(&state.get()) {
+            Ok(client) => pipeline.connect_ai(client).await,
+            Err(e) => log::warn!("[AIConfig] 创建 AI 客户端失败，管道未连接: {}", e),
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AIProviderConfig;
+
+    #[test]
+    fn test_ai_config_state_creation() {
+        let state = AIConfigState::default();
+        let config = state.get();
+        assert!(config.providers.is_empty());
+        assert!(config.active_provider_id.is_none());
+    }
+
+    #[test]
+    fn test_ai_config_state_update() {
+        let state = AIConfigState::default();
+        let mut new_config = AIConfig::new();
+        let provider = AIProviderConfig::new(
+            "test",
+            "Test",
+            "https://api.test.com",
+            "test-key",
+            "test-model",
+        );
+        new_config.add_provider(provider).unwrap();
+
+        assert!(state.update(new_config).is_ok());
+        assert_eq!(state.get().providers.len(), 1);
+    }
+
+    fn stored_json(db: &Database) -> String {
+        db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT value FROM ai_config WHERE key = 'ai_config'", [], |row| row.get(0))?)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_api_keys_live_in_secret_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let secrets: Arc<dyn SecretStore> =
+            Arc::new(crate::crypto::secrets::FileSecretStore::open(dir.path()).unwrap());
+        let state = AIConfigState::new(Arc::clone(&db), Arc::clone(&secrets));
+
+        let mut config = AIConfig::new();
+        config.add_provider(AIProviderConfig::new(
+            "openai", "OpenAI", "https://api.openai.com", "sk-live-1234567890", "gpt",
+        )).unwrap();
+        state.update(config).unwrap();
+
+        assert!(!stored_json(&db).contains("sk-live"));
+        let reloaded = AIConfigState::new(Arc::clone(&db), Arc::clone(&secrets));
+        assert_eq!(reloaded.get().providers[0].api_key.expose_secret(), "sk-live-1234567890");
+
+        // 删除提供商时一并删除 Key
+        let mut config = reloaded.get();
+        config.remove_provider("openai").unwrap();
+        reloaded.update(config).unwrap();
+        assert!(secrets.get(&api_key_name("openai")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_legacy_plaintext_keys_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO ai_config (key, value) VALUES ('ai_config', ?1)",
+                [r#"{"providers":[{"id":"claude","name":"Claude","api_base_url":"https://api.anthropic.com","api_key":"sk-ant-legacy","model":"m","enabled":true,"is_active":true}],"active_provider_id":"claude"}"#],
+            )?;
+            Ok(())
+        }).unwrap();
+        let secrets: Arc<dyn SecretStore> =
+            Arc::new(crate::crypto::secrets::FileSecretStore::open(dir.path()).unwrap());
+
+        let state = AIConfigState::new(Arc::clone(&db), Arc::clone(&secrets));
+        assert_eq!(state.get().providers[0].api_key.expose_secret(), "sk-ant-legacy");
+        assert!(!stored_json(&db).contains("sk-ant-legacy"));
+        assert_eq!(
+            secrets.get(&api_key_name("claude")).unwrap().unwrap().expose_secret(),
+            "sk-ant-legacy"
+        );
+    }
+}
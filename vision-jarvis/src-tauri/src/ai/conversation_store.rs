@@ -0,0 +1,234 @@
+/// 对话持久化 - conversations / conversation_messages 表
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::db::Database;
+use super::chat::{ChatContent, ChatMessage, ChatRole, Conversation};
+
+/// 会话列表项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub message_count: usize,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 对话存储
+pub struct ConversationStore {
+    db: Arc<Database>,
+}
+
+impl ConversationStore {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 新建会话（conversation 中已有的消息一并写入）
+    pub fn create(&self, conversation: &Conversation) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO conversations (id, title, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    conversation.id,
+                    conversation.title,
+                    conversation.created_at,
+                    conversation.updated_at,
+                ],
+            )?;
+            for message in &conversation.messages {
+                insert_message(&tx, &conversation.id, message, conversation.updated_at)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// 读取会话及全部消息
+    pub fn get(&self, id: &str) -> Result<Option<Conversation>> {
+        self.db.with_connection(|conn| {
+            let header = conn.query_row(
+                "SELECT title, created_at, updated_at FROM conversations WHERE id = ?1",
+                [id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+            );
+            let (title, created_at, updated_at) = match header {
+                Ok(h) => h,
+                Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+
+            let mut stmt = conn.prepare(
+                "SELECT role, content FROM conversation_messages
+                 WHERE conversation_id = ?1
+                 ORDER BY id ASC",
+            )?;
+            let rows = stmt
+                .query_map([id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut messages = Vec::with_capacity(rows.len());
+            for (role, content) in rows {
+                let role = ChatRole::parse(&role)
+                    .ok_or_else(|| anyhow::anyhow!("未知的消息角色: {}", role))?;
+                let content: Vec<ChatContent> = serde_json::from_str(&content)?;
+                messages.push(ChatMessage { role, content });
+            }
+
+            Ok(Some(Conversation {
+                id: id.to_string(),
+                title,
+                messages,
+                created_at,
+                updated_at,
+            }))
+        })
+    }
+
+    /// 最近更新的会话
+    pub fn list(&self, limit: usize) -> Result<Vec<ConversationSummary>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT c.id, c.title, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM conversation_messages m WHERE m.conversation_id = c.id)
+                 FROM conversations c
+                 ORDER BY c.updated_at DESC, c.created_at DESC
+                 LIMIT ?1",
+            )?;
+            let list = stmt
+                .query_map([limit as i64], |row| {
+                    Ok(ConversationSummary {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                        message_count: row.get::<_, i64>(4)? as usize,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(list)
+        })
+    }
+
+    /// 追加消息并刷新会话更新时间
+    pub fn append(&self, conversation_id: &str, messages: &[ChatMessage]) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let updated = tx.execute(
+                "UPDATE conversations SET updated_at = ?2 WHERE id = ?1",
+                rusqlite::params![conversation_id, now],
+            )?;
+            if updated == 0 {
+                anyhow::bail!("会话不存在: {}", conversation_id);
+            }
+            for message in messages {
+                insert_message(&tx, conversation_id, message, now)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    pub fn rename(&self, conversation_id: &str, title: &str) -> Result<bool> {
+        self.db.with_connection(|conn| {
+            let updated = conn.execute(
+                "UPDATE conversations SET title = ?2 WHERE id = ?1",
+                rusqlite::params![conversation_id, title],
+            )?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// 删除会话（消息级联删除）
+    pub fn delete(&self, conversation_id: &str) -> Result<bool> {
+        self.db.with_connection(|conn| {
+            let deleted = conn.execute("DELETE FROM conversations WHERE id = ?1", [conversation_id])?;
+            Ok(deleted > 0)
+        })
+    }
+}
+
+fn insert_message(
+    conn: &rusqlite::Connection,
+    conversation_id: &str,
+    message: &ChatMessage,
+    created_at: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO conversation_messages (conversation_id, role, content, created_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            conversation_id,
+            message.role.as_str(),
+            serde_json::to_string(&message.content)?,
+            created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ConversationStore {
+        ConversationStore::new(Arc::new(Database::open_in_memory().unwrap()))
+    }
+
+    #[test]
+    fn test_create_append_and_get() {
+        let store = store();
+        let mut conversation = Conversation::new("周二的调试");
+        conversation.push(ChatMessage::system("你是记忆助手"));
+        store.create(&conversation).unwrap();
+
+        store.append(&conversation.id, &[
+            ChatMessage::user("截图里是什么？").with_image("image/jpeg", "AAAA"),
+            ChatMessage::assistant("是 VSCode 的报错"),
+        ]).unwrap();
+
+        let loaded = store.get(&conversation.id).unwrap().unwrap();
+        assert_eq!(loaded.title, "周二的调试");
+        assert_eq!(loaded.messages.len(), 3);
+        assert_eq!(loaded.messages[0].role, ChatRole::System);
+        assert!(loaded.messages[1].has_images());
+        assert_eq!(loaded.messages[2].text(), "是 VSCode 的报错");
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let store = store();
+        let first = Conversation::new("a");
+        let second = Conversation::new("b");
+        store.create(&first).unwrap();
+        store.create(&second).unwrap();
+        store.append(&first.id, &[ChatMessage::user("hi")]).unwrap();
+
+        let list = store.list(10).unwrap();
+        assert_eq!(list.len(), 2);
+        let first_summary = list.iter().find(|c| c.id == first.id).unwrap();
+        assert_eq!(first_summary.message_count, 1);
+
+        assert!(store.rename(&second.id, "c").unwrap());
+        assert!(store.delete(&first.id).unwrap());
+        assert!(!store.delete(&first.id).unwrap());
+        assert!(store.get(&first.id).unwrap().is_none());
+
+        // 级联删除消息
+        let orphans: i64 = store.db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT COUNT(*) FROM conversation_messages", [], |r| r.get(0))?)
+        }).unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn test_append_to_missing_conversation() {
+        assert!(store().append("missing", &[ChatMessage::user("hi")]).is_err());
+    }
+}
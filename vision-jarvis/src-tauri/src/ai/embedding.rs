@@ -0,0 +1,369 @@
+/// 文本向量化（Embedding）
+///
+/// - `OpenAIEmbeddingProvider`: OpenAI 兼容的 `/v1/embeddings` 接口
+/// - `HashEmbeddingProvider`: 本地确定性哈希向量（离线可用，无需 API）
+
+use async_trait::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use crate::ai::provider::AIProviderConfig;
+use crate::ai::http::{self, post_json};
+
+/// 本地哈希向量默认维度
+pub const HASH_EMBEDDING_DIMS: usize = 256;
+
+/// 远程向量化请求超时
+const EMBEDDING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Embedding 提供者
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// 提供者标识（与 model 一起作为 embedding_cache 的键）
+    fn provider_id(&self) -> &str;
+
+    /// 模型名称
+    fn model(&self) -> &str;
+
+    /// 批量生成向量，返回顺序与输入一致
+    async fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
+}
+
+/// 根据 AI 提供商配置创建 Embedding 提供者
+///
+/// 不支持 embeddings 接口的供应商（Claude/Gemini/OpenRouter）回退到本地哈希向量；
+/// 本地服务（Ollama/llama.cpp）需显式配置 embedding_model 才会走 `/v1/embeddings`
+pub fn create_embedding_provider(config: &AIProviderConfig) -> Arc<dyn EmbeddingProvider> {
+    match config.effective_embedding_model() {
+        Some(model) => match OpenAIEmbeddingProvider::new(config, model) {
+            Ok(provider) => Arc::new(provider),
+            Err(e) => {
+                log::warn!("创建 Embedding 客户端失败，使用本地哈希向量: {}", e);
+                Arc::new(HashEmbeddingProvider::default())
+            }
+        },
+        None => Arc::new(HashEmbeddingProvider::default()),
+    }
+}
+
+// ============================================================================
+// OpenAI 兼容 /v1/embeddings
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+pub struct OpenAIEmbeddingProvider {
+    provider_id: String,
+    api_base_url: String,
+    api_key: SecretString,
+    model: String,
+    client: Client,
+}
+
+impl OpenAIEmbeddingProvider {
+    pub fn new(config: &AIProviderConfig, model: &str) -> AppResult<Self> {
+        Ok(Self {
+            provider_id: config.id.clone(),
+            api_base_url: config.api_base_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone(),
+            model: model.to_string(),
+            client: http::client()?,
+        })
+    }
+
+    fn api_url(&self) -> String {
+        format!("{}/v1/embeddings", self.api_base_url)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddingProvider {
+    fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut request = self.client.post(self.api_url());
+        if !self.api_key.expose_secret().is_empty() {
+            request = request.header("Authorization", format!("Bearer {}", self.api_key.expose_secret()));
+        }
+
+        let body = EmbeddingRequest { model: &self.model, input: texts };
+        let response = post_json(request, &body, false, EMBEDDING_TIMEOUT).await.map_err(|e| match e {
+            AppError::Http { status: 404, .. } => AppError::http(404, None, "Embedding 端点或模型不存在"),
+            e => e,
+        })?;
+
+        let mut body: EmbeddingResponse = response.json().await
+            .map_err(|e| AppError::ai(20, format!("解析 Embedding 响应失败: {}", e)))?;
+
+        if body.data.len() != texts.len() {
+            return Err(AppError::ai(21, format!(
+                "Embedding 数量不匹配: 请求 {} 条，返回 {} 条", texts.len(), body.data.len()
+            )));
+        }
+
+        body.data.sort_by_key(|d| d.index);
+        Ok(body.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+// ============================================================================
+// 本地哈希向量
+// ============================================================================
+
+/// 本地确定性哈希向量（feature hashing）
+///
+/// ASCII 按单词、CJK 按单字和相邻双字切分，哈希到固定维度后 L2 归一化。
+/// 只能捕捉词面重合，但离线可用且结果稳定。
+pub struct HashEmbeddingProvider {
+    dims: usize,
+    model: String,
+}
+
+impl HashEmbeddingProvider {
+    pub fn new(dims: usize) -> Self {
+        Self { dims, model: format!("local-hash-{}", dims) }
+    }
+
+    /// 同步计算单条文本的向量
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0f32; self.dims];
+
+        for token in tokenize(text) {
+            let hash = fnv1a(token.as_bytes());
+            let idx = (hash % self.dims as u64) as usize;
+            // 用高位决定符号，减少哈希冲突带来的偏置
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[idx] += sign;
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl Default for HashEmbeddingProvider {
+    fn default() -> Self {
+        Self::new(HASH_EMBEDDING_DIMS)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashEmbeddingProvider {
+    fn provider_id(&self) -> &str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_text(t)).collect())
+    }
+}
+
+/// 切分为哈希特征：ASCII 单词（小写）、CJK 单字及相邻双字
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut prev_cjk: Option<char> = None;
+
+    for ch in text.chars() {
+        if is_cjk(ch) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            tokens.push(ch.to_string());
+            if let Some(prev) = prev_cjk {
+                tokens.push(format!("{}{}", prev, ch));
+            }
+            prev_cjk = Some(ch);
+        } else {
+            prev_cjk = None;
+            if ch.is_alphanumeric() {
+                word.extend(ch.to_lowercase());
+            } else if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+
+    tokens
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch,
+        '\u{4E00}'..='\u{9FFF}' |
+        '\u{3400}'..='\u{4DBF}' |
+        '\u{3040}'..='\u{30FF}' |  // 日文假名
+        '\u{AC00}'..='\u{D7AF}' |  // 韩文音节
+        '\u{F900}'..='\u{FAFF}' |
+        '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// FNV-1a 64位哈希（跨版本、跨平台稳定）
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+// ============================================================================
+// 向量工具
+// ============================================================================
+
+/// 余弦相似度（维度不一致或零向量时返回 0）
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let mut dot = 0f32;
+    let mut norm_a = 0f32;
+    let mut norm_b = 0f32;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// 向量编码为 BLOB（f32 小端序）
+pub fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// 从 BLOB 解码向量
+pub fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::provider::ProviderType;
+
+    #[test]
+    fn test_hash_embedding_deterministic() {
+        let provider = HashEmbeddingProvider::default();
+        let a = provider.embed_text("调试 Rust 编译错误");
+        let b = provider.embed_text("调试 Rust 编译错误");
+
+        assert_eq!(a, b);
+        assert_eq!(a.len(), HASH_EMBEDDING_DIMS);
+        assert!((a.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_hash_embedding_similarity_ranking() {
+        let provider = HashEmbeddingProvider::default();
+        let query = provider.embed_text("rust compiler error");
+        let related = provider.embed_text("Fixing a rust compiler error in main.rs");
+        let unrelated = provider.embed_text("watching a cooking video");
+
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+    }
+
+    #[test]
+    fn test_hash_embedding_cjk_bigrams() {
+        let provider = HashEmbeddingProvider::default();
+        let query = provider.embed_text("数据库迁移");
+        let related = provider.embed_text("今天完成了数据库迁移脚本");
+        let unrelated = provider.embed_text("午饭后散步");
+
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+    }
+
+    #[test]
+    fn test_hash_embedding_empty_text() {
+        let provider = HashEmbeddingProvider::default();
+        let v = provider.embed_text("");
+        assert!(v.iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("Hello 世界!");
+        assert_eq!(tokens, vec!["hello", "世", "界", "世界"]);
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_blob_round_trip() {
+        let v = vec![0.5f32, -1.25, 3.0];
+        let blob = embedding_to_blob(&v);
+        assert_eq!(blob.len(), 12);
+        assert_eq!(blob_to_embedding(&blob), v);
+    }
+
+    #[test]
+    fn test_create_embedding_provider_fallback() {
+        let config = AIProviderConfig::new("claude", "Claude", "https://api.anthropic.com", "key", "claude-x")
+            .with_provider_type(ProviderType::Claude);
+        let provider = create_embedding_provider(&config);
+        assert_eq!(provider.provider_id(), "local");
+
+        let config = AIProviderConfig::new("openai", "OpenAI", "https://api.openai.com", "key", "gpt-4o");
+        let provider = create_embedding_provider(&config);
+        assert_eq!(provider.provider_id(), "openai");
+        assert_eq!(provider.model(), "text-embedding-3-small");
+    }
+}
@@ -13,5 +13,7 @@ pub fn create_provider(config: AIProviderConfig) -> AppResult<Box<dyn AIProvider
         ProviderType::AIHubMix => Ok(Box::new(AIHubMixProvider::new(config)?)),
         ProviderType::OpenRouter => Ok(Box::new(OpenRouterProvider::new(config)?)),
         ProviderType::SiliconFlow => Ok(Box::new(SiliconFlowProvider::new(config)?)),
+        ProviderType::Ollama => Ok(Box::new(OllamaProvider::new(config)?)),
+        ProviderType::OpenAICompatible => Ok(Box::new(OpenAIProvider::new(config)?)),
     }
 }
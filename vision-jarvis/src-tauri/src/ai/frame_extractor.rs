@@ -28,6 +28,14 @@ impl Default for FrameExtractConfig {
 /// 使用 ffmpeg 均匀提取帧，返回 base64 编码的 JPEG 图像列表。
 /// 需要系统安装 ffmpeg 和 ffprobe。
 pub fn extract_frames(video_base64: &str, config: &FrameExtractConfig) -> AppResult<Vec<String>> {
+    let video_data = BASE64.decode(video_base64)
+        .map_err(|e| AppError::ai(10, format!("解码 base64 视频失败: {}", e)))?;
+    let frames = extract_frame_bytes(&video_data, config)?;
+    Ok(frames.iter().map(|data| BASE64.encode(data)).collect())
+}
+
+/// 从视频数据中均匀提取帧，返回 JPEG 原始字节
+pub fn extract_frame_bytes(video_data: &[u8], config: &FrameExtractConfig) -> AppResult<Vec<Vec<u8>>> {
     let ffmpeg = find_ffmpeg()?;
     let ffprobe = find_ffprobe()?;
 
@@ -35,9 +43,7 @@ pub fn extract_frames(video_base64: &str, config: &FrameExtractConfig) -> AppRes
         .map_err(|e| AppError::io(1, format!("创建临时目录失败: {}", e)))?;
 
     let video_path = tmp_dir.path().join("input.mp4");
-    let video_data = BASE64.decode(video_base64)
-        .map_err(|e| AppError::ai(10, format!("解码 base64 视频失败: {}", e)))?;
-    std::fs::write(&video_path, &video_data)
+    std::fs::write(&video_path, video_data)
         .map_err(|e| AppError::io(2, format!("写入临时视频文件失败: {}", e)))?;
 
     let duration = get_video_duration(&ffprobe, &video_path);
@@ -62,7 +68,7 @@ pub fn extract_frames(video_base64: &str, config: &FrameExtractConfig) -> AppRes
         match result {
             Ok(output) if output.status.success() && frame_path.exists() => {
                 match std::fs::read(&frame_path) {
-                    Ok(data) => frames.push(BASE64.encode(&data)),
+                    Ok(data) => frames.push(data),
                     Err(e) => warn!("[FrameExtractor] 读取帧文件失败: {}", e),
                 }
             }
@@ -110,7 +116,7 @@ fn get_video_duration(ffprobe: &str, video_path: &std::path::Path) -> f64 {
 }
 
 /// 查找 ffmpeg 可执行文件
-fn find_ffmpeg() -> AppResult<String> {
+pub(crate) fn find_ffmpeg() -> AppResult<String> {
     find_executable("ffmpeg")
 }
 
@@ -0,0 +1,64 @@
+/// Provider 共用的 HTTP 请求
+///
+/// - 客户端只限制建立连接的时间，总超时按请求设置
+/// - 非流式请求在超时内完成整个响应；流式请求只在超时内等到响应头，
+///   响应体由 `TokenStream` 按相邻两段数据的间隔计时，长回答不会被总超时截断
+/// - 发送失败与非 2xx 响应统一映射为 AppError（见 `retry::status_error`）
+
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use super::retry::{retry_after_header, status_error};
+
+/// 建立连接的超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 云端 API 的请求超时
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 创建 Provider 使用的 HTTP 客户端
+pub fn client() -> AppResult<Client> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::network(1, format!("创建 HTTP 客户端失败: {}", e)))
+}
+
+/// 发送 JSON 请求，非 2xx 响应转为错误
+pub async fn post_json(
+    request: RequestBuilder,
+    body: &impl Serialize,
+    stream: bool,
+    timeout: Duration,
+) -> AppResult<Response> {
+    let request = request.header("Content-Type", "application/json").json(body);
+    let response = if stream {
+        tokio::time::timeout(timeout, request.send())
+            .await
+            .map_err(|_| AppError::network(1, "请求超时"))?
+    } else {
+        request.timeout(timeout).send().await
+    }
+    .map_err(send_error)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = retry_after_header(response.headers());
+        let error_text = response.text().await.unwrap_or_else(|_| "未知错误".to_string());
+        return Err(status_error(status.as_u16(), &error_text, retry_after));
+    }
+
+    Ok(response)
+}
+
+fn send_error(e: reqwest::Error) -> AppError {
+    if e.is_timeout() {
+        AppError::network(1, "请求超时")
+    } else if e.is_connect() {
+        AppError::network(2, "网络连接失败")
+    } else {
+        AppError::network(999, format!("请求失败: {}", e))
+    }
+}
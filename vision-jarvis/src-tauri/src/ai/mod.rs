@@ -5,10 +5,23 @@ pub mod traits;
 pub mod providers;
 pub mod factory;
 pub mod frame_extractor;
+pub mod embedding;
+pub mod stream;
+pub mod chat;
+pub mod conversation_store;
+pub mod retry;
+pub mod http;
+pub mod usage;
+pub mod config_state;
 
 pub use provider::{AIProviderConfig, AIConfig, ModelInfo, ProviderType, get_supported_models};
 pub use client::AIClient;
 pub use traits::AIProvider;
+pub use chat::{ChatContent, ChatMessage, ChatRole, Conversation};
+pub use stream::{SseEvent, SseParser, StreamFormat, TokenStream};
+pub use retry::{AttemptRecord, RetryPolicy};
+pub use usage::{AIPurpose, Completion, TokenUsage, UsageTracker};
+pub use embedding::{EmbeddingProvider, HashEmbeddingProvider, create_embedding_provider};
 pub use prompt::{
     PromptTemplate, PromptBuilder,
     screenshot_analysis_prompt,
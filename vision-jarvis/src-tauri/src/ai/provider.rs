@@ -2,8 +2,11 @@
 ///
 /// 管理 AI 模型提供商、API 配置和模型选择
 
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use crate::error::{AppError, AppResult};
+use crate::ai::chat::ChatMessage;
+use crate::ai::usage::TokenUsage;
 
 /// AI 供应商类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -16,10 +19,35 @@ pub enum ProviderType {
     AIHubMix,
     OpenRouter,
     SiliconFlow,
+    /// 本地 Ollama（原生 `/api/chat` 接口）
+    Ollama,
+    /// 无鉴权的 OpenAI 兼容服务（llama.cpp server、vLLM、LM Studio 等）
+    OpenAICompatible,
+}
+
+impl ProviderType {
+    /// 该供应商默认的 embedding 模型（None 表示不提供 OpenAI 兼容的 embeddings 接口）
+    pub fn default_embedding_model(&self) -> Option<&'static str> {
+        match self {
+            ProviderType::OpenAI | ProviderType::AIHubMix => Some("text-embedding-3-small"),
+            ProviderType::Qwen => Some("text-embedding-v3"),
+            ProviderType::SiliconFlow => Some("BAAI/bge-m3"),
+            ProviderType::Claude
+            | ProviderType::Gemini
+            | ProviderType::OpenRouter
+            | ProviderType::Ollama
+            | ProviderType::OpenAICompatible => None,
+        }
+    }
+
+    /// 是否必须配置 API Key（本地服务通常不鉴权）
+    pub fn requires_api_key(&self) -> bool {
+        !matches!(self, ProviderType::Ollama | ProviderType::OpenAICompatible)
+    }
 }
 
 /// AI 提供商配置
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIProviderConfig {
     /// 提供商 ID (唯一标识)
     pub id: String,
@@ -31,7 +59,10 @@ pub struct AIProviderConfig {
     pub api_base_url: String,
 
     /// API Key
-    pub api_key: String,
+    ///
+    /// 只反序列化不序列化：持久化经 `SecretStore`，返回前端时用 `masked_api_key`
+    #[serde(default = "empty_api_key", skip_serializing)]
+    pub api_key: SecretString,
 
     /// 当前选择的模型
     pub model: String,
@@ -49,6 +80,18 @@ pub struct AIProviderConfig {
     /// 视频/图像分析使用的模型（第三方供应商可能需要不同于文本的模型）
     #[serde(default)]
     pub video_model: Option<String>,
+
+    /// 文本向量化使用的模型（None 时按供应商类型取默认值）
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+
+    /// 输入 token 单价（美元 / 百万 token），用于估算花费；未配置时按内置参考价
+    #[serde(default)]
+    pub input_price_per_mtok: Option<f64>,
+
+    /// 输出 token 单价（美元 / 百万 token）
+    #[serde(default)]
+    pub output_price_per_mtok: Option<f64>,
 }
 
 impl AIProviderConfig {
@@ -64,12 +107,15 @@ impl AIProviderConfig {
             id: id.into(),
             name: name.into(),
             api_base_url: api_base_url.into(),
-            api_key: api_key.into(),
+            api_key: SecretString::new(api_key.into()),
             model: model.into(),
             enabled: true,
             is_active: false,
             provider_type: ProviderType::default(),
             video_model: None,
+            embedding_model: None,
+            input_price_per_mtok: None,
+            output_price_per_mtok: None,
         }
     }
 
@@ -99,7 +145,7 @@ impl AIProviderConfig {
             return Err(AppError::validation(13, "API 地址必须以 http:// 或 https:// 开头"));
         }
 
-        if self.api_key.is_empty() {
+        if !self.has_api_key() && self.provider_type.requires_api_key() {
             return Err(AppError::validation(14, "API Key 不能为空"));
         }
 
@@ -110,6 +156,25 @@ impl AIProviderConfig {
         Ok(())
     }
 
+    /// 是否配置了 API Key
+    pub fn has_api_key(&self) -> bool {
+        !self.api_key.expose_secret().is_empty()
+    }
+
+    /// 掩码后的 API Key（只保留首尾各 4 个字符，过短时全部隐藏）
+    pub fn masked_api_key(&self) -> String {
+        let chars: Vec<char> = self.api_key.expose_secret().chars().collect();
+        match chars.len() {
+            0 => String::new(),
+            n if n <= 12 => "•".repeat(8),
+            n => format!(
+                "{}••••{}",
+                chars[..4].iter().collect::<String>(),
+                chars[n - 4..].iter().collect::<String>(),
+            ),
+        }
+    }
+
     /// 设置为激活状态
     pub fn set_active(&mut self, active: bool) {
         self.is_active = active;
@@ -119,6 +184,87 @@ impl AIProviderConfig {
     pub fn effective_video_model(&self) -> &str {
         self.video_model.as_deref().unwrap_or(&self.model)
     }
+
+    /// 获取多轮对话使用的有效模型（消息中含图片时使用视觉模型）
+    pub fn effective_chat_model(&self, messages: &[ChatMessage]) -> &str {
+        if messages.iter().any(ChatMessage::has_images) {
+            self.effective_video_model()
+        } else {
+            &self.model
+        }
+    }
+
+    /// 估算一次调用的花费：优先用配置的单价，未配置时按模型的内置参考价，
+    /// 本机端点不计费；避免未填单价时花费恒为 0、预算形同虚设
+    pub fn estimate_cost(&self, model: &str, usage: &TokenUsage) -> f64 {
+        let (default_input, default_output) = if self.is_local_endpoint() {
+            (0.0, 0.0)
+        } else {
+            reference_price(model)
+        };
+        let input = self.input_price_per_mtok.unwrap_or(default_input) * usage.input_tokens as f64;
+        let output = self.output_price_per_mtok.unwrap_or(default_output) * usage.output_tokens as f64;
+        (input + output) / 1_000_000.0
+    }
+
+    /// API 地址是否指向本机（localhost / 127.0.0.0/8 / ::1）
+    pub fn is_local_endpoint(&self) -> bool {
+        let rest = self.api_base_url
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(&self.api_base_url);
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let authority = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+        let host = match authority.strip_prefix('[') {
+            Some(v6) => v6.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        host.eq_ignore_ascii_case("localhost")
+            || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// 获取文本向量化使用的有效模型（None 表示该供应商不支持 embeddings 接口）
+    pub fn effective_embedding_model(&self) -> Option<&str> {
+        self.embedding_model
+            .as_deref()
+            .filter(|m| !m.is_empty())
+            .or_else(|| self.provider_type.default_embedding_model())
+    }
+}
+
+/// 模型的参考单价（美元 / 百万 token，输入、输出），按模型名前缀匹配；
+/// 未知模型按偏高的通用价估算，宁可提前触发预算也不漏算
+fn reference_price(model: &str) -> (f64, f64) {
+    const PRICES: &[(&str, f64, f64)] = &[
+        ("claude-opus", 5.0, 25.0),
+        ("claude-sonnet", 3.0, 15.0),
+        ("claude-haiku", 1.0, 5.0),
+        ("gpt-5", 1.25, 10.0),
+        ("gpt-4o-mini", 0.15, 0.6),
+        ("gpt-4o", 2.5, 10.0),
+        ("gemini-3-pro", 2.0, 12.0),
+        ("gemini", 0.5, 3.0),
+        ("glm", 0.6, 2.2),
+        ("kimi", 0.6, 2.5),
+        ("qwen", 0.4, 1.2),
+        ("deepseek", 0.3, 1.2),
+    ];
+    const FALLBACK: (f64, f64) = (3.0, 15.0);
+
+    let model = model.to_lowercase();
+    let model = model.rsplit('/').next().unwrap_or(&model);
+    if model.ends_with("-free") || model.ends_with(":free") {
+        return (0.0, 0.0);
+    }
+    PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|(_, input, output)| (*input, *output))
+        .unwrap_or(FALLBACK)
+}
+
+fn empty_api_key() -> SecretString {
+    SecretString::new(String::new())
 }
 
 /// 预定义的模型信息
@@ -255,6 +401,22 @@ pub fn get_supported_models() -> Vec<ModelInfo> {
             description: "Step AI 免费模型".to_string(),
         },
 
+        // 本地模型（Ollama）
+        ModelInfo {
+            id: "qwen2.5vl:7b".to_string(),
+            name: "Qwen2.5-VL 7B (Ollama)".to_string(),
+            provider: "本地".to_string(),
+            is_free: true,
+            description: "本地运行的视觉模型，屏幕内容不出本机".to_string(),
+        },
+        ModelInfo {
+            id: "llama3.2-vision".to_string(),
+            name: "Llama 3.2 Vision (Ollama)".to_string(),
+            provider: "本地".to_string(),
+            is_free: true,
+            description: "Meta 开源视觉模型，本地运行".to_string(),
+        },
+
         // SiliconFlow 系列
         ModelInfo {
             id: "Pro/zai-org/GLM-4.7".to_string(),
@@ -355,6 +517,22 @@ impl AIConfig {
     pub fn get_provider(&self, provider_id: &str) -> Option<&AIProviderConfig> {
         self.providers.iter().find(|p| p.id == provider_id)
     }
+
+    /// 故障转移顺序：激活的提供商在前，其余已启用的提供商按配置顺序排列
+    ///
+    /// 没有激活的提供商时返回空列表；激活的是本机服务时只在本机服务之间转移，
+    /// 避免本地模型不可用时把屏幕内容发给云端
+    pub fn failover_chain(&self) -> Vec<&AIProviderConfig> {
+        let Some(active) = self.get_active_provider() else {
+            return Vec::new();
+        };
+        let local_only = active.is_local_endpoint();
+        std::iter::once(active)
+            .chain(self.providers.iter().filter(|p| {
+                p.enabled && p.id != active.id && (!local_only || p.is_local_endpoint())
+            }))
+            .collect()
+    }
 }
 
 impl Default for AIConfig {
@@ -383,6 +561,31 @@ mod tests {
         assert!(!config.is_active);
     }
 
+    #[test]
+    fn test_api_key_is_masked_and_never_serialized() {
+        let config = AIProviderConfig::new(
+            "test-1",
+            "Test Provider",
+            "https://api.example.com",
+            "sk-abcdefghijklmnop",
+            "test-model",
+        );
+        assert_eq!(config.masked_api_key(), "sk-a••••mnop");
+        assert!(!format!("{:?}", config).contains("abcdefgh"));
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("api_key"));
+
+        let short = AIProviderConfig::new("t", "T", "https://x", "short-key", "m");
+        assert_eq!(short.masked_api_key(), "••••••••");
+
+        // 反序列化仍接受 api_key（前端提交、旧版数据库中的明文配置）
+        let parsed: AIProviderConfig = serde_json::from_str(
+            r#"{"id":"a","name":"A","api_base_url":"https://x","api_key":"k","model":"m","enabled":true,"is_active":false}"#,
+        ).unwrap();
+        assert_eq!(parsed.api_key.expose_secret(), "k");
+    }
+
     #[test]
     fn test_provider_validation() {
         let mut config = AIProviderConfig::new(
@@ -405,6 +608,53 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_local_provider_allows_empty_api_key() {
+        let config = AIProviderConfig::new("ollama", "Ollama", "http://localhost:11434", "", "qwen2.5vl:7b");
+        assert!(matches!(config.validate(), Err(AppError::Validation(14, _))));
+
+        let ollama = config.clone().with_provider_type(ProviderType::Ollama);
+        assert!(ollama.validate().is_ok());
+        assert_eq!(ollama.effective_embedding_model(), None);
+
+        let llama_cpp = config.with_provider_type(ProviderType::OpenAICompatible);
+        assert!(llama_cpp.validate().is_ok());
+    }
+
+    #[test]
+    fn test_is_local_endpoint() {
+        let local = |url: &str| AIProviderConfig::new("p", "P", url, "", "m").is_local_endpoint();
+        assert!(local("http://localhost:11434"));
+        assert!(local("http://127.0.0.1:8080/v1"));
+        assert!(local("http://[::1]:8080"));
+        assert!(local("http://LOCALHOST"));
+        assert!(!local("https://api.openai.com"));
+        assert!(!local("http://192.168.1.20:11434"));
+        assert!(!local("http://localhost.evil.com"));
+    }
+
+    #[test]
+    fn test_local_failover_chain_stays_local() {
+        let mut config = AIConfig::new();
+        config.add_provider(
+            AIProviderConfig::new("ollama", "Ollama", "http://localhost:11434", "", "m")
+                .with_provider_type(ProviderType::Ollama),
+        ).unwrap();
+        config.add_provider(AIProviderConfig::new("cloud", "Cloud", "https://api.example.com", "key", "m")).unwrap();
+        config.add_provider(
+            AIProviderConfig::new("llama", "llama.cpp", "http://127.0.0.1:8080", "", "m")
+                .with_provider_type(ProviderType::OpenAICompatible),
+        ).unwrap();
+
+        config.set_active_provider("ollama").unwrap();
+        let chain: Vec<&str> = config.failover_chain().iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(chain, vec!["ollama", "llama"]);
+
+        config.set_active_provider("cloud").unwrap();
+        let chain: Vec<&str> = config.failover_chain().iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(chain, vec!["cloud", "ollama", "llama"]);
+    }
+
     #[test]
     fn test_ai_config_add_provider() {
         let mut config = AIConfig::new();
@@ -461,6 +711,23 @@ mod tests {
         assert!(config.active_provider_id.is_none());
     }
 
+    #[test]
+    fn test_estimate_cost() {
+        let mut config = AIProviderConfig::new("p", "P", "https://api.example.com", "key", "m");
+        let usage = TokenUsage::new(2_000_000, 500_000);
+        // 未配置单价：按参考价估算，不再记为 0
+        assert!((config.estimate_cost("claude-sonnet-4-5", &usage) - 13.5).abs() < 1e-9);
+        assert!((config.estimate_cost("vendor/unknown-model", &usage) - 13.5).abs() < 1e-9);
+        assert_eq!(config.estimate_cost("gemini-3-flash-preview-free", &usage), 0.0);
+
+        config.input_price_per_mtok = Some(0.5);
+        config.output_price_per_mtok = Some(2.0);
+        assert!((config.estimate_cost("claude-sonnet-4-5", &usage) - 2.0).abs() < 1e-9);
+
+        let local = AIProviderConfig::new("l", "L", "http://localhost:11434", "", "llama3");
+        assert_eq!(local.estimate_cost("llama3", &usage), 0.0);
+    }
+
     #[test]
     fn test_get_supported_models() {
         let models = get_supported_models();
@@ -485,6 +752,8 @@ mod tests {
         let json = serde_json::to_string(&config).unwrap();
         let deserialized: AIProviderConfig = serde_json::from_str(&json).unwrap();
 
-        assert_eq!(config, deserialized);
+        // API Key 不参与序列化，其余字段往返不变
+        assert!(!deserialized.has_api_key());
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
     }
 }
@@ -1,19 +1,27 @@
 use async_trait::async_trait;
 use log::{info, warn};
 use reqwest::Client;
+use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 use crate::error::{AppError, AppResult};
 use crate::ai::provider::AIProviderConfig;
 use crate::ai::traits::AIProvider;
+use crate::ai::stream::{StreamFormat, TokenStream, STREAM_IDLE_TIMEOUT};
+use crate::ai::http::{self, post_json, REQUEST_TIMEOUT};
+use crate::ai::usage::{Completion, TokenUsage};
+use crate::ai::chat::{ChatContent, ChatMessage, ChatRole, Conversation};
 use crate::ai::frame_extractor::{extract_frames, FrameExtractConfig};
 
 #[derive(Debug, Serialize)]
 struct ClaudeRequest {
     model: String,
     max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
     messages: Vec<ClaudeMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,6 +50,8 @@ struct ClaudeImageSource {
 #[derive(Debug, Deserialize)]
 struct ClaudeResponse {
     content: Vec<ClaudeResponseContent>,
+    #[serde(default)]
+    usage: Option<ClaudeUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +59,14 @@ struct ClaudeResponseContent {
     text: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ClaudeUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
 pub struct ClaudeProvider {
     config: AIProviderConfig,
     client: Client,
@@ -56,68 +74,87 @@ pub struct ClaudeProvider {
 
 impl ClaudeProvider {
     pub fn new(config: AIProviderConfig) -> AppResult<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(120))
-            .build()
-            .map_err(|e| AppError::network(1, format!("创建 HTTP 客户端失败: {}", e)))?;
-        Ok(Self { config, client })
+        Ok(Self { config, client: http::client()? })
     }
 
     fn api_url(&self) -> String {
         format!("{}/v1/messages", self.config.api_base_url.trim_end_matches('/'))
     }
 
-    async fn send_request(&self, messages: Vec<ClaudeMessage>, model: &str) -> AppResult<String> {
+    /// system 消息合并为顶层 system 字段，其余按原顺序转换
+    fn to_claude_messages(messages: &[ChatMessage]) -> Vec<ClaudeMessage> {
+        messages
+            .iter()
+            .filter(|m| m.role != ChatRole::System)
+            .map(|m| ClaudeMessage {
+                role: m.role.as_str().to_string(),
+                content: m.content
+                    .iter()
+                    .map(|c| match c {
+                        ChatContent::Text { text } => ClaudeContent::Text { text: text.clone() },
+                        ChatContent::Image { media_type, data } => ClaudeContent::Image {
+                            source: ClaudeImageSource {
+                                source_type: "base64".to_string(),
+                                media_type: media_type.clone(),
+                                data: data.clone(),
+                            },
+                        },
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    async fn post_request(
+        &self,
+        messages: Vec<ClaudeMessage>,
+        system: Option<String>,
+        model: &str,
+        stream: bool,
+    ) -> AppResult<reqwest::Response> {
         let request_body = ClaudeRequest {
             model: model.to_string(),
             max_tokens: 4096,
+            system,
             messages,
+            stream: stream.then_some(true),
         };
 
-        let response = self.client
+        let request = self.client
             .post(&self.api_url())
-            .header("x-api-key", &self.config.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AppError::network(1, "请求超时")
-                } else if e.is_connect() {
-                    AppError::network(2, "网络连接失败")
-                } else {
-                    AppError::network(999, format!("请求失败: {}", e))
-                }
-            })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "未知错误".to_string());
-            return Err(match status.as_u16() {
-                401 => AppError::ai(401, "API Key 无效或未授权"),
-                403 => AppError::ai(403, "访问被拒绝"),
-                404 => AppError::ai(404, "API 端点不存在"),
-                429 => AppError::ai(429, "请求过于频繁，请稍后重试"),
-                500..=599 => AppError::ai(500, format!("服务器错误: {}", error_text)),
-                _ => AppError::ai(999, format!("HTTP 错误 {}: {}", status, error_text)),
-            });
-        }
+            .header("x-api-key", self.config.api_key.expose_secret())
+            .header("anthropic-version", "2023-06-01");
+
+        post_json(request, &request_body, stream, REQUEST_TIMEOUT).await
+    }
+
+    async fn send_request(&self, messages: Vec<ClaudeMessage>, model: &str) -> AppResult<Completion> {
+        self.send_request_with_system(messages, None, model).await
+    }
+
+    async fn send_request_with_system(
+        &self,
+        messages: Vec<ClaudeMessage>,
+        system: Option<String>,
+        model: &str,
+    ) -> AppResult<Completion> {
+        let response = self.post_request(messages, system, model, false).await?;
 
         let claude_response: ClaudeResponse = response.json().await
             .map_err(|e| AppError::ai(1, format!("解析响应失败: {}", e)))?;
 
-        claude_response.content
+        let text = claude_response.content
             .first()
             .and_then(|c| c.text.clone())
-            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))
+            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))?;
+        let usage = claude_response.usage.map(|u| TokenUsage::new(u.input_tokens, u.output_tokens));
+        Ok(Completion::new(text).with_usage(usage))
     }
 }
 
 #[async_trait]
 impl AIProvider for ClaudeProvider {
-    async fn send_text(&self, prompt: &str) -> AppResult<String> {
+    async fn send_text(&self, prompt: &str) -> AppResult<Completion> {
         let messages = vec![ClaudeMessage {
             role: "user".to_string(),
             content: vec![ClaudeContent::Text { text: prompt.to_string() }],
@@ -125,7 +162,25 @@ impl AIProvider for ClaudeProvider {
         self.send_request(messages, &self.config.model).await
     }
 
-    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        let messages = vec![ClaudeMessage {
+            role: "user".to_string(),
+            content: vec![ClaudeContent::Text { text: prompt.to_string() }],
+        }];
+        let response = self.post_request(messages, None, &self.config.model, true).await?;
+        Ok(TokenStream::from_response(response, StreamFormat::Claude, STREAM_IDLE_TIMEOUT))
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> AppResult<Completion> {
+        let model = self.config.effective_chat_model(messages);
+        self.send_request_with_system(
+            Self::to_claude_messages(messages),
+            Conversation::system_prompt(messages),
+            model,
+        ).await
+    }
+
+    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<Completion> {
         info!("[Claude] 不支持原生视频分析，使用帧提取预处理");
         let config = FrameExtractConfig::default();
         match extract_frames(video_base64, &config) {
@@ -160,7 +215,7 @@ impl AIProvider for ClaudeProvider {
         }
     }
 
-    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<Completion> {
         let messages = vec![ClaudeMessage {
             role: "user".to_string(),
             content: vec![
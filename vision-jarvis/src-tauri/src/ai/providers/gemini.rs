@@ -1,21 +1,30 @@
 use async_trait::async_trait;
 use reqwest::Client;
+use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 use crate::error::{AppError, AppResult};
 use crate::ai::provider::AIProviderConfig;
 use crate::ai::traits::AIProvider;
+use crate::ai::stream::{StreamFormat, TokenStream, STREAM_IDLE_TIMEOUT};
+use crate::ai::http::{self, post_json, REQUEST_TIMEOUT};
+use crate::ai::usage::{Completion, TokenUsage};
+use crate::ai::chat::{ChatContent, ChatMessage, ChatRole, Conversation};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct GeminiRequest {
     contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
     generation_config: GeminiGenerationConfig,
 }
 
 #[derive(Debug, Serialize)]
 struct GeminiContent {
+    /// 多轮对话中为 user / model，单轮请求省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
     parts: Vec<GeminiPart>,
 }
 
@@ -40,8 +49,20 @@ struct GeminiGenerationConfig {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
+    #[serde(default)]
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,79 +87,119 @@ pub struct GeminiProvider {
 
 impl GeminiProvider {
     pub fn new(config: AIProviderConfig) -> AppResult<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(120))
-            .build()
-            .map_err(|e| AppError::network(1, format!("创建 HTTP 客户端失败: {}", e)))?;
-        Ok(Self { config, client })
+        Ok(Self { config, client: http::client()? })
     }
 
-    fn api_url(&self, model: &str) -> String {
+    fn api_url(&self, model: &str, stream: bool) -> String {
+        let method = if stream { "streamGenerateContent?alt=sse" } else { "generateContent" };
         format!(
-            "{}/v1beta/models/{}:generateContent",
+            "{}/v1beta/models/{}:{}",
             self.config.api_base_url.trim_end_matches('/'),
-            model
+            model,
+            method
         )
     }
 
-    async fn send_request(&self, parts: Vec<GeminiPart>, model: &str) -> AppResult<String> {
+    fn to_gemini_parts(content: &[ChatContent]) -> Vec<GeminiPart> {
+        content
+            .iter()
+            .map(|c| match c {
+                ChatContent::Text { text } => GeminiPart::Text { text: text.clone() },
+                ChatContent::Image { media_type, data } => GeminiPart::InlineData {
+                    inline_data: GeminiInlineData {
+                        mime_type: media_type.clone(),
+                        data: data.clone(),
+                    },
+                },
+            })
+            .collect()
+    }
+
+    /// system 消息转为 systemInstruction，assistant 对应 Gemini 的 model 角色
+    fn to_gemini_contents(messages: &[ChatMessage]) -> Vec<GeminiContent> {
+        messages
+            .iter()
+            .filter(|m| m.role != ChatRole::System)
+            .map(|m| GeminiContent {
+                role: Some(if m.role == ChatRole::Assistant { "model" } else { "user" }.to_string()),
+                parts: Self::to_gemini_parts(&m.content),
+            })
+            .collect()
+    }
+
+    async fn post_request(
+        &self,
+        contents: Vec<GeminiContent>,
+        system_instruction: Option<GeminiContent>,
+        model: &str,
+        stream: bool,
+    ) -> AppResult<reqwest::Response> {
         let request_body = GeminiRequest {
-            contents: vec![GeminiContent { parts }],
+            contents,
+            system_instruction,
             generation_config: GeminiGenerationConfig {
                 max_output_tokens: 4096,
                 temperature: 0.7,
             },
         };
 
-        let response = self.client
-            .post(&self.api_url(model))
-            .header("x-goog-api-key", &self.config.api_key)
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AppError::network(1, "请求超时")
-                } else if e.is_connect() {
-                    AppError::network(2, "网络连接失败")
-                } else {
-                    AppError::network(999, format!("请求失败: {}", e))
-                }
-            })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "未知错误".to_string());
-            return Err(match status.as_u16() {
-                401 => AppError::ai(401, "API Key 无效或未授权"),
-                403 => AppError::ai(403, "访问被拒绝"),
-                404 => AppError::ai(404, "API 端点不存在"),
-                429 => AppError::ai(429, "请求过于频繁，请稍后重试"),
-                500..=599 => AppError::ai(500, format!("服务器错误: {}", error_text)),
-                _ => AppError::ai(999, format!("HTTP 错误 {}: {}", status, error_text)),
-            });
-        }
+        let request = self.client
+            .post(self.api_url(model, stream))
+            .header("x-goog-api-key", self.config.api_key.expose_secret());
+
+        post_json(request, &request_body, stream, REQUEST_TIMEOUT).await
+    }
+
+    async fn send_request(&self, parts: Vec<GeminiPart>, model: &str) -> AppResult<Completion> {
+        self.send_contents(vec![GeminiContent { role: None, parts }], None, model).await
+    }
+
+    async fn send_contents(
+        &self,
+        contents: Vec<GeminiContent>,
+        system_instruction: Option<GeminiContent>,
+        model: &str,
+    ) -> AppResult<Completion> {
+        let response = self.post_request(contents, system_instruction, model, false).await?;
 
         let gemini_response: GeminiResponse = response.json().await
             .map_err(|e| AppError::ai(1, format!("解析响应失败: {}", e)))?;
 
-        gemini_response.candidates
+        let text = gemini_response.candidates
             .first()
             .and_then(|c| c.content.parts.first())
             .and_then(|p| p.text.clone())
-            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))
+            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))?;
+        let usage = gemini_response.usage_metadata
+            .map(|u| TokenUsage::new(u.prompt_token_count, u.candidates_token_count));
+        Ok(Completion::new(text).with_usage(usage))
     }
 }
 
 #[async_trait]
 impl AIProvider for GeminiProvider {
-    async fn send_text(&self, prompt: &str) -> AppResult<String> {
+    async fn send_text(&self, prompt: &str) -> AppResult<Completion> {
         let parts = vec![GeminiPart::Text { text: prompt.to_string() }];
         self.send_request(parts, &self.config.model).await
     }
 
-    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        let parts = vec![GeminiPart::Text { text: prompt.to_string() }];
+        let contents = vec![GeminiContent { role: None, parts }];
+        let response = self.post_request(contents, None, &self.config.model, true).await?;
+        Ok(TokenStream::from_response(response, StreamFormat::Gemini, STREAM_IDLE_TIMEOUT))
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> AppResult<Completion> {
+        let system_instruction = Conversation::system_prompt(messages).map(|text| GeminiContent {
+            role: None,
+            parts: vec![GeminiPart::Text { text }],
+        });
+        let model = self.config.effective_chat_model(messages);
+        self.send_contents(Self::to_gemini_contents(messages), system_instruction, model).await
+    }
+
+    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<Completion> {
         let parts = vec![
             GeminiPart::Text { text: prompt.to_string() },
             GeminiPart::InlineData {
@@ -151,7 +212,7 @@ impl AIProvider for GeminiProvider {
         self.send_request(parts, self.config.effective_video_model()).await
     }
 
-    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<Completion> {
         let parts = vec![
             GeminiPart::Text { text: prompt.to_string() },
             GeminiPart::InlineData {
@@ -5,6 +5,7 @@ pub mod qwen;
 pub mod aihubmix;
 pub mod openrouter;
 pub mod siliconflow;
+pub mod ollama;
 
 pub use openai::OpenAIProvider;
 pub use claude::ClaudeProvider;
@@ -13,3 +14,4 @@ pub use qwen::QwenProvider;
 pub use aihubmix::AIHubMixProvider;
 pub use openrouter::OpenRouterProvider;
 pub use siliconflow::SiliconFlowProvider;
+pub use ollama::OllamaProvider;
@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use log::{info, warn};
+use reqwest::Client;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use crate::ai::provider::AIProviderConfig;
+use crate::ai::traits::AIProvider;
+use crate::ai::stream::{StreamFormat, TokenStream};
+use crate::ai::http::{self, post_json};
+use crate::ai::usage::{Completion, TokenUsage};
+use crate::ai::chat::{ChatContent, ChatMessage};
+use crate::ai::frame_extractor::{extract_frames, FrameExtractConfig};
+
+/// 本地推理（尤其是 CPU 上的视觉模型）比云端慢得多：请求超时与流式数据间隔都放宽
+const OLLAMA_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    num_predict: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+    /// base64 图片（不含 data URL 前缀）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: Option<OllamaResponseMessage>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponseMessage {
+    content: String,
+}
+
+/// Ollama 本地模型（原生 `/api/chat`，图片通过 `images` 字段传入）
+pub struct OllamaProvider {
+    config: AIProviderConfig,
+    client: Client,
+}
+
+impl OllamaProvider {
+    pub fn new(config: AIProviderConfig) -> AppResult<Self> {
+        Ok(Self { config, client: http::client()? })
+    }
+
+    fn api_url(&self) -> String {
+        format!("{}/api/chat", self.config.api_base_url.trim_end_matches('/'))
+    }
+
+    fn user_message(prompt: &str, images: Vec<String>) -> OllamaMessage {
+        OllamaMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+            images,
+        }
+    }
+
+    /// 文本片段按顺序拼接为 content，图片收集到 images
+    fn to_ollama_messages(messages: &[ChatMessage]) -> Vec<OllamaMessage> {
+        messages
+            .iter()
+            .map(|m| {
+                let mut texts = Vec::new();
+                let mut images = Vec::new();
+                for c in &m.content {
+                    match c {
+                        ChatContent::Text { text } => texts.push(text.as_str()),
+                        ChatContent::Image { data, .. } => images.push(data.clone()),
+                    }
+                }
+                OllamaMessage {
+                    role: m.role.as_str().to_string(),
+                    content: texts.join("\n\n"),
+                    images,
+                }
+            })
+            .collect()
+    }
+
+    async fn post_request(&self, messages: Vec<OllamaMessage>, model: &str, stream: bool) -> AppResult<reqwest::Response> {
+        let request_body = OllamaRequest {
+            model: model.to_string(),
+            messages,
+            stream,
+            options: OllamaOptions {
+                temperature: 0.7,
+                num_predict: 4096,
+            },
+        };
+
+        let mut request = self.client.post(self.api_url());
+        // Ollama 本身不鉴权；经反向代理暴露时可能需要 Bearer Token
+        if self.config.has_api_key() {
+            request = request.header("Authorization", format!("Bearer {}", self.config.api_key.expose_secret()));
+        }
+
+        post_json(request, &request_body, stream, OLLAMA_TIMEOUT).await.map_err(|e| match e {
+            AppError::Network(2, _) => AppError::network(2, "无法连接本地 Ollama 服务，请确认已运行 ollama serve"),
+            e => e,
+        })
+    }
+
+    async fn send_request(&self, messages: Vec<OllamaMessage>, model: &str) -> AppResult<Completion> {
+        let response = self.post_request(messages, model, false).await?;
+
+        let ollama_response: OllamaResponse = response.json().await
+            .map_err(|e| AppError::ai(1, format!("解析响应失败: {}", e)))?;
+
+        let text = ollama_response.message
+            .map(|m| m.content)
+            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))?;
+        let usage = match (ollama_response.prompt_eval_count, ollama_response.eval_count) {
+            (None, None) => None,
+            (input, output) => Some(TokenUsage::new(input.unwrap_or(0), output.unwrap_or(0))),
+        };
+        Ok(Completion::new(text).with_usage(usage))
+    }
+}
+
+#[async_trait]
+impl AIProvider for OllamaProvider {
+    async fn send_text(&self, prompt: &str) -> AppResult<Completion> {
+        let messages = vec![Self::user_message(prompt, Vec::new())];
+        self.send_request(messages, &self.config.model).await
+    }
+
+    async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        let messages = vec![Self::user_message(prompt, Vec::new())];
+        let response = self.post_request(messages, &self.config.model, true).await?;
+        Ok(TokenStream::from_response(response, StreamFormat::Ollama, OLLAMA_TIMEOUT))
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> AppResult<Completion> {
+        let model = self.config.effective_chat_model(messages);
+        self.send_request(Self::to_ollama_messages(messages), model).await
+    }
+
+    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<Completion> {
+        info!("[Ollama] 不支持原生视频分析，使用帧提取预处理");
+        let config = FrameExtractConfig::default();
+        match extract_frames(video_base64, &config) {
+            Ok(frames) => {
+                info!("[Ollama] 帧提取成功，提取 {} 帧，发送多图分析", frames.len());
+                let prompt = format!("以下是从视频录屏中均匀提取的关键帧。请综合所有帧分析视频内容：\n\n{}", prompt);
+                let messages = vec![Self::user_message(&prompt, frames)];
+                self.send_request(messages, self.config.effective_video_model()).await
+            }
+            Err(e) => {
+                warn!("[Ollama] 帧提取失败({}), 回退到纯文本提示", e);
+                let fallback_prompt = format!(
+                    "用户提供了一段视频录屏，但当前无法处理视频。请根据以下分析提示尽量提供帮助：\n\n{}",
+                    prompt
+                );
+                self.send_text(&fallback_prompt).await
+            }
+        }
+    }
+
+    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<Completion> {
+        let messages = vec![Self::user_message(prompt, vec![image_base64.to_string()])];
+        self.send_request(messages, self.config.effective_video_model()).await
+    }
+
+    async fn test_connection(&self) -> AppResult<String> {
+        self.send_text("Hello").await?;
+        Ok("连接成功".to_string())
+    }
+
+    fn config(&self) -> &AIProviderConfig {
+        &self.config
+    }
+}
@@ -1,11 +1,15 @@
 use async_trait::async_trait;
 use reqwest::Client;
+use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 use crate::error::{AppError, AppResult};
 use crate::ai::provider::AIProviderConfig;
 use crate::ai::traits::AIProvider;
+use crate::ai::stream::{StreamFormat, StreamOptions, TokenStream, STREAM_IDLE_TIMEOUT};
+use crate::ai::http::{self, post_json, REQUEST_TIMEOUT};
+use crate::ai::chat::{ChatContent, ChatMessage};
+use crate::ai::usage::{Completion, TokenUsage};
 
 #[derive(Debug, Serialize)]
 struct OpenRouterRequest {
@@ -15,6 +19,10 @@ struct OpenRouterRequest {
     max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
 }
 
 #[derive(Debug, Serialize)]
@@ -40,6 +48,8 @@ struct OpenRouterImageUrl {
 #[derive(Debug, Deserialize)]
 struct OpenRouterResponse {
     choices: Vec<OpenRouterChoice>,
+    #[serde(default)]
+    usage: Option<OpenRouterUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,6 +62,14 @@ struct OpenRouterResponseMessage {
     content: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct OpenRouterUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
 pub struct OpenRouterProvider {
     config: AIProviderConfig,
     client: Client,
@@ -59,70 +77,70 @@ pub struct OpenRouterProvider {
 
 impl OpenRouterProvider {
     pub fn new(config: AIProviderConfig) -> AppResult<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(120))
-            .build()
-            .map_err(|e| AppError::network(1, format!("创建 HTTP 客户端失败: {}", e)))?;
-        Ok(Self { config, client })
+        Ok(Self { config, client: http::client()? })
     }
 
     fn api_url(&self) -> String {
         format!("{}/v1/chat/completions", self.config.api_base_url.trim_end_matches('/'))
     }
 
-    async fn send_request(&self, messages: Vec<OpenRouterMessage>, model: &str) -> AppResult<String> {
+    fn to_openrouter_messages(messages: &[ChatMessage]) -> Vec<OpenRouterMessage> {
+        messages
+            .iter()
+            .map(|m| OpenRouterMessage {
+                role: m.role.as_str().to_string(),
+                content: m.content
+                    .iter()
+                    .map(|c| match c {
+                        ChatContent::Text { text } => OpenRouterContent::Text { text: text.clone() },
+                        ChatContent::Image { media_type, data } => OpenRouterContent::ImageUrl {
+                            image_url: OpenRouterImageUrl {
+                                url: format!("data:{};base64,{}", media_type, data),
+                            },
+                        },
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    async fn post_request(&self, messages: Vec<OpenRouterMessage>, model: &str, stream: bool) -> AppResult<reqwest::Response> {
         let request_body = OpenRouterRequest {
             model: model.to_string(),
             messages,
             max_tokens: Some(4096),
             temperature: Some(0.7),
+            stream: stream.then_some(true),
+            stream_options: stream.then(StreamOptions::include_usage),
         };
 
-        let response = self.client
+        let request = self.client
             .post(&self.api_url())
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .header("Authorization", format!("Bearer {}", self.config.api_key.expose_secret()))
             .header("X-Title", "Vision-Jarvis")
-            .header("HTTP-Referer", "https://github.com/nicepkg/vision-jarvis")
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    AppError::network(1, "请求超时")
-                } else if e.is_connect() {
-                    AppError::network(2, "网络连接失败")
-                } else {
-                    AppError::network(999, format!("请求失败: {}", e))
-                }
-            })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "未知错误".to_string());
-            return Err(match status.as_u16() {
-                401 => AppError::ai(401, "API Key 无效或未授权"),
-                403 => AppError::ai(403, "访问被拒绝"),
-                404 => AppError::ai(404, "API 端点不存在"),
-                429 => AppError::ai(429, "请求过于频繁，请稍后重试"),
-                500..=599 => AppError::ai(500, format!("服务器错误: {}", error_text)),
-                _ => AppError::ai(999, format!("HTTP 错误 {}: {}", status, error_text)),
-            });
-        }
+            .header("HTTP-Referer", "https://github.com/nicepkg/vision-jarvis");
+
+        post_json(request, &request_body, stream, REQUEST_TIMEOUT).await
+    }
+
+    async fn send_request(&self, messages: Vec<OpenRouterMessage>, model: &str) -> AppResult<Completion> {
+        let response = self.post_request(messages, model, false).await?;
 
         let openrouter_response: OpenRouterResponse = response.json().await
             .map_err(|e| AppError::ai(1, format!("解析响应失败: {}", e)))?;
 
-        openrouter_response.choices
+        let text = openrouter_response.choices
             .first()
             .map(|c| c.message.content.clone())
-            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))
+            .ok_or_else(|| AppError::ai(2, "响应中没有内容"))?;
+        let usage = openrouter_response.usage.map(|u| TokenUsage::new(u.prompt_tokens, u.completion_tokens));
+        Ok(Completion::new(text).with_usage(usage))
     }
 }
 
 #[async_trait]
 impl AIProvider for OpenRouterProvider {
-    async fn send_text(&self, prompt: &str) -> AppResult<String> {
+    async fn send_text(&self, prompt: &str) -> AppResult<Completion> {
         let messages = vec![OpenRouterMessage {
             role: "user".to_string(),
             content: vec![OpenRouterContent::Text { text: prompt.to_string() }],
@@ -130,7 +148,21 @@ impl AIProvider for OpenRouterProvider {
         self.send_request(messages, &self.config.model).await
     }
 
-    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        let messages = vec![OpenRouterMessage {
+            role: "user".to_string(),
+            content: vec![OpenRouterContent::Text { text: prompt.to_string() }],
+        }];
+        let response = self.post_request(messages, &self.config.model, true).await?;
+        Ok(TokenStream::from_response(response, StreamFormat::OpenAI, STREAM_IDLE_TIMEOUT))
+    }
+
+    async fn chat(&self, messages: &[ChatMessage]) -> AppResult<Completion> {
+        let model = self.config.effective_chat_model(messages);
+        self.send_request(Self::to_openrouter_messages(messages), model).await
+    }
+
+    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<Completion> {
         let messages = vec![OpenRouterMessage {
             role: "user".to_string(),
             content: vec![
@@ -145,7 +177,7 @@ impl AIProvider for OpenRouterProvider {
         self.send_request(messages, self.config.effective_video_model()).await
     }
 
-    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String> {
+    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<Completion> {
         let messages = vec![OpenRouterMessage {
             role: "user".to_string(),
             content: vec![
@@ -0,0 +1,232 @@
+/// 重试与故障转移策略
+///
+/// - HTTP 错误统一映射为 `AppError::Http`，保留真实状态码与 Retry-After
+/// - 按错误码区分可重试（超时、连接失败、限流、服务端错误）与不可重试错误
+/// - 指数退避，Retry-After 优先；等待时间超过上限时不再重试，直接切换下一个供应商
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+
+/// 把非 2xx 响应映射为 AppError
+pub fn status_error(status: u16, body: &str, retry_after: Option<Duration>) -> AppError {
+    let hint = retry_after
+        .map(|d| format!("（Retry-After: {}s）", d.as_secs()))
+        .unwrap_or_default();
+
+    let message = match status {
+        401 => "API Key 无效或未授权".to_string(),
+        403 => "访问被拒绝".to_string(),
+        404 => "API 端点不存在".to_string(),
+        429 => format!("请求过于频繁，请稍后重试{}", hint),
+        500..=599 => format!("服务器错误 {}{}: {}", status, hint, body),
+        _ => format!("HTTP 错误 {}: {}", status, body),
+    };
+    AppError::http(status, retry_after, message)
+}
+
+/// 读取响应头中的 Retry-After（秒数或 HTTP 日期）
+pub fn retry_after_header(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, SystemTime::now())
+}
+
+/// 解析 Retry-After 的值
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some(Duration::from_secs((at.timestamp() - now).max(0) as u64))
+}
+
+/// 错误携带的 Retry-After
+pub fn retry_after_hint(err: &AppError) -> Option<Duration> {
+    match err {
+        AppError::Http { retry_after, .. } => *retry_after,
+        _ => None,
+    }
+}
+
+/// 是否值得对同一供应商重试
+///
+/// - Network：超时(1)、连接失败(2)、读取流失败(3)、其他请求错误(999)
+/// - Http：限流(429)、服务端错误(5xx)
+/// - AI：流式响应中的错误事件(3)
+pub fn is_retryable(err: &AppError) -> bool {
+    match err {
+        AppError::Network(code, _) => matches!(code, 1 | 2 | 3 | 999),
+        AppError::Http { status, .. } => *status == 429 || (500..=599).contains(status),
+        AppError::AI(code, _) => *code == 3,
+        _ => false,
+    }
+}
+
+/// 重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 单个供应商的最大尝试次数（含首次）
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间
+    pub base_delay: Duration,
+    /// 指数退避的上限
+    pub max_delay: Duration,
+    /// 愿意等待的最长 Retry-After，超过则切换供应商
+    pub max_retry_after: Duration,
+    /// 是否对退避时间加入 ±20% 抖动
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试（仍会故障转移）
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 第 attempt 次失败（1-based）后的指数退避时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let scale = 0.8 + (nanos % 1000) as f64 / 1000.0 * 0.4;
+        delay.mul_f64(scale)
+    }
+
+    /// 决定第 attempt 次失败后是否重试以及等待多久；None 表示放弃当前供应商
+    pub fn next_delay(&self, attempt: u32, err: &AppError) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_retryable(err) {
+            return None;
+        }
+        match retry_after_hint(err) {
+            Some(wait) if wait > self.max_retry_after => None,
+            Some(wait) => Some(wait.max(self.backoff(attempt))),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+}
+
+/// 单次调用尝试的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    /// 调用的方法（send_text / analyze_video / chat …）
+    pub operation: String,
+    pub provider_id: String,
+    pub model: String,
+    /// 在该供应商上的第几次尝试（1-based）
+    pub attempt: u32,
+    pub success: bool,
+    pub error: Option<String>,
+    pub retryable: bool,
+    pub latency_ms: u64,
+    /// 失败后等待多久再重试（切换供应商或放弃时为 None）
+    pub retry_delay_ms: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_status_error_carries_retry_after() {
+        let err = status_error(429, "", Some(Duration::from_secs(12)));
+        assert!(matches!(err, AppError::Http { status: 429, .. }));
+        assert_eq!(retry_after_hint(&err), Some(Duration::from_secs(12)));
+
+        // 5xx 保留真实状态码
+        let err = status_error(503, "overloaded", Some(Duration::from_secs(3)));
+        assert!(matches!(err, AppError::Http { status: 503, .. }));
+        assert_eq!(retry_after_hint(&err), Some(Duration::from_secs(3)));
+        assert!(err.to_string().contains("overloaded"));
+
+        assert!(matches!(status_error(401, "", None), AppError::Http { status: 401, .. }));
+        assert!(matches!(status_error(400, "bad", None), AppError::Http { status: 400, .. }));
+        // Retry-After 只来自结构化字段，不解析消息
+        assert_eq!(retry_after_hint(&AppError::ai(429, "Retry-After: 5s")), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_445_412_470);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        // Wed, 21 Oct 2015 07:28:00 GMT = 1445412480
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(&AppError::network(1, "请求超时")));
+        assert!(is_retryable(&AppError::network(2, "网络连接失败")));
+        assert!(is_retryable(&status_error(429, "", None)));
+        assert!(is_retryable(&status_error(502, "", None)));
+        assert!(!is_retryable(&status_error(401, "", None)));
+        assert!(!is_retryable(&status_error(400, "", None)));
+        assert!(!is_retryable(&AppError::ai(1, "解析响应失败")));
+        assert!(!is_retryable(&AppError::validation(14, "")));
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+
+        let jittered = RetryPolicy::default().backoff(2);
+        assert!(jittered >= Duration::from_millis(1600) && jittered <= Duration::from_millis(2400));
+    }
+
+    #[test]
+    fn test_next_delay() {
+        let policy = policy();
+        let rate_limited = status_error(429, "", Some(Duration::from_secs(5)));
+        assert_eq!(policy.next_delay(1, &rate_limited), Some(Duration::from_secs(5)));
+        // 退避时间更长时取退避时间
+        let short_hint = status_error(429, "", Some(Duration::from_secs(1)));
+        assert_eq!(policy.next_delay(2, &short_hint), Some(Duration::from_secs(2)));
+        // Retry-After 太长或次数用尽时放弃
+        let long_wait = status_error(429, "", Some(Duration::from_secs(600)));
+        assert_eq!(policy.next_delay(1, &long_wait), None);
+        assert_eq!(policy.next_delay(3, &rate_limited), None);
+        assert_eq!(policy.next_delay(1, &AppError::ai(401, "")), None);
+    }
+}
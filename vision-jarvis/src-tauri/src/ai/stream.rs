@@ -0,0 +1,551 @@
+/// 流式响应 - SSE 解析与增量文本流
+///
+/// 各供应商的流式接口都基于 Server-Sent Events，只是事件体格式不同：
+/// - OpenAI 兼容：`data: {"choices":[{"delta":{"content":"..."}}]}`，以 `data: [DONE]` 结束
+/// - Claude：`event: content_block_delta` 携带 `delta.text`，以 `message_stop` 结束
+/// - Gemini：`:streamGenerateContent?alt=sse`，每个事件是一段 `candidates[].content.parts[]`
+///
+/// Ollama 原生接口不是 SSE 而是 NDJSON（每行一个 `{"message":{"content":"..."},"done":false}`），
+/// 由 `NdjsonParser` 按行切分后复用同一套事件解析
+///
+/// 各格式的 token 用量也随事件返回（OpenAI 兼容接口需请求 `stream_options.include_usage`），
+/// 流结束时通过 `TokenStream::take_usage` 交给调用方记账
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::ai::usage::TokenUsage;
+use crate::error::{AppError, AppResult};
+
+/// 流式响应相邻两段数据之间的最长等待时间（云端 API）
+pub const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// OpenAI 兼容接口的 `stream_options`：要求在最后一个事件中附带 usage
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StreamOptions {
+    include_usage: bool,
+}
+
+impl StreamOptions {
+    pub fn include_usage() -> Self {
+        Self { include_usage: true }
+    }
+}
+
+/// 一条 SSE 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `event:` 字段（未指定时为 None，即默认的 message 事件）
+    pub event: Option<String>,
+    /// 多行 `data:` 以换行拼接后的内容
+    pub data: String,
+}
+
+/// 增量 SSE 解析器：按任意边界喂入字节，返回已完整的事件
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 喂入一段字节，返回其中已结束（遇到空行）的事件
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line[..line.len() - 1]);
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+
+            if line.is_empty() {
+                if let Some(event) = self.dispatch() {
+                    events.push(event);
+                }
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    /// 连接结束时取出最后一个未以空行结尾的事件
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            self.feed(b"\n");
+        }
+        self.dispatch()
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+/// 增量 NDJSON 解析器：每个非空行包装为一条无 event 名的事件
+#[derive(Debug, Default)]
+pub struct NdjsonParser {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 喂入一段字节，返回其中已完整的行
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            events.extend(Self::line_event(&line[..line.len() - 1]));
+        }
+        events
+    }
+
+    /// 连接结束时取出最后一个没有换行结尾的行
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let line = std::mem::take(&mut self.buffer);
+        Self::line_event(&line)
+    }
+
+    fn line_event(line: &[u8]) -> Option<SseEvent> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        (!line.is_empty()).then(|| SseEvent {
+            event: None,
+            data: line.to_string(),
+        })
+    }
+}
+
+/// 按响应格式选择的分帧方式
+enum Framing {
+    Sse(SseParser),
+    Ndjson(NdjsonParser),
+}
+
+impl Framing {
+    fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        match self {
+            Framing::Sse(parser) => parser.feed(chunk),
+            Framing::Ndjson(parser) => parser.feed(chunk),
+        }
+    }
+
+    fn finish(&mut self) -> Option<SseEvent> {
+        match self {
+            Framing::Sse(parser) => parser.finish(),
+            Framing::Ndjson(parser) => parser.finish(),
+        }
+    }
+}
+
+/// 流式事件体格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    OpenAI,
+    Claude,
+    Gemini,
+    /// Ollama `/api/chat`（NDJSON）
+    Ollama,
+}
+
+/// 单个事件解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamDelta {
+    Text(String),
+    Done,
+    /// 心跳、元数据等不含文本的事件
+    Skip,
+}
+
+impl StreamFormat {
+    /// 从事件中提取增量文本
+    pub fn parse_event(&self, event: &SseEvent) -> AppResult<StreamDelta> {
+        if *self == StreamFormat::OpenAI && event.data.trim() == "[DONE]" {
+            return Ok(StreamDelta::Done);
+        }
+
+        let value: Value = serde_json::from_str(&event.data)
+            .map_err(|e| AppError::ai(1, format!("解析流式响应失败: {}", e)))?;
+
+        if let Some(error) = value.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .or_else(|| error.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(AppError::ai(3, format!("流式响应错误: {}", message)));
+        }
+
+        let text = match self {
+            StreamFormat::OpenAI => value
+                .pointer("/choices/0/delta/content")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            StreamFormat::Claude => {
+                let event_type = event
+                    .event
+                    .as_deref()
+                    .or_else(|| value.get("type").and_then(Value::as_str));
+                match event_type {
+                    Some("message_stop") => return Ok(StreamDelta::Done),
+                    Some("content_block_delta") => value
+                        .pointer("/delta/text")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    _ => None,
+                }
+            }
+            StreamFormat::Gemini => value
+                .pointer("/candidates/0/content/parts")
+                .and_then(Value::as_array)
+                .map(|parts| {
+                    parts
+                        .iter()
+                        .filter_map(|p| p.get("text").and_then(Value::as_str))
+                        .collect::<String>()
+                }),
+            StreamFormat::Ollama => {
+                if value.get("done").and_then(Value::as_bool) == Some(true) {
+                    return Ok(StreamDelta::Done);
+                }
+                value
+                    .pointer("/message/content")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            }
+        };
+
+        Ok(match text {
+            Some(text) if !text.is_empty() => StreamDelta::Text(text),
+            _ => StreamDelta::Skip,
+        })
+    }
+
+    /// 从事件中提取 token 用量（没有 usage 字段时为 None）
+    ///
+    /// Claude 把输入/输出分在 message_start 与 message_delta 两个事件里，
+    /// Gemini 每个事件都带累计值，调用方按字段取最大值合并
+    pub fn parse_usage(&self, event: &SseEvent) -> Option<TokenUsage> {
+        let value: Value = serde_json::from_str(&event.data).ok()?;
+        let count = |pointer: &str| value.pointer(pointer).and_then(Value::as_u64);
+        let (input, output) = match self {
+            StreamFormat::OpenAI => (count("/usage/prompt_tokens"), count("/usage/completion_tokens")),
+            StreamFormat::Claude => (
+                count("/message/usage/input_tokens").or_else(|| count("/usage/input_tokens")),
+                count("/usage/output_tokens"),
+            ),
+            StreamFormat::Gemini => (
+                count("/usageMetadata/promptTokenCount"),
+                count("/usageMetadata/candidatesTokenCount"),
+            ),
+            StreamFormat::Ollama => (count("/prompt_eval_count"), count("/eval_count")),
+        };
+        if input.is_none() && output.is_none() {
+            return None;
+        }
+        Some(TokenUsage::new(input.unwrap_or(0), output.unwrap_or(0)))
+    }
+}
+
+/// 按字段取最大值合并两次用量
+fn merge_usage(current: Option<TokenUsage>, next: TokenUsage) -> TokenUsage {
+    let current = current.unwrap_or_default();
+    TokenUsage::new(
+        current.input_tokens.max(next.input_tokens),
+        current.output_tokens.max(next.output_tokens),
+    )
+}
+
+/// 增量文本流（由后台任务读取 HTTP 响应并逐段推送）
+///
+/// 丢弃 TokenStream 会关闭通道，后台任务随之结束并断开连接
+pub struct TokenStream {
+    rx: mpsc::Receiver<AppResult<String>>,
+    /// 流结束（正常结束、出错或被丢弃）后收到累计用量
+    usage: Option<oneshot::Receiver<Option<TokenUsage>>>,
+}
+
+impl TokenStream {
+    /// 把完整文本包装为只有一段的流（用于不支持流式的实现）
+    pub fn from_text(text: String) -> Self {
+        Self::from_text_with_usage(text, None)
+    }
+
+    /// 同 `from_text`，附带该次调用的用量
+    pub fn from_text_with_usage(text: String, usage: Option<TokenUsage>) -> Self {
+        let (tx, rx) = mpsc::channel(1);
+        let _ = tx.try_send(Ok(text));
+        let (usage_tx, usage_rx) = oneshot::channel();
+        let _ = usage_tx.send(usage);
+        Self { rx, usage: Some(usage_rx) }
+    }
+
+    /// 解析流式响应体（Ollama 为 NDJSON，其余为 SSE）
+    ///
+    /// idle_timeout 内没有收到新数据时以超时错误结束，不限制整个回答的总时长
+    pub fn from_response(response: reqwest::Response, format: StreamFormat, idle_timeout: Duration) -> Self {
+        let (tx, rx) = mpsc::channel(64);
+        let (usage_tx, usage_rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let mut usage = None;
+            pump_response(response, format, idle_timeout, &tx, &mut usage).await;
+            let _ = usage_tx.send(usage);
+        });
+
+        Self { rx, usage: Some(usage_rx) }
+    }
+
+    /// 取出用量接收端（只能取一次）；流结束后可得到累计用量，供应商未返回时为 None
+    pub fn take_usage(&mut self) -> Option<oneshot::Receiver<Option<TokenUsage>>> {
+        self.usage.take()
+    }
+
+    /// 下一段文本，流结束时返回 None
+    pub async fn next(&mut self) -> Option<AppResult<String>> {
+        self.rx.recv().await
+    }
+
+    /// 读取全部文本
+    pub async fn collect_text(mut self) -> AppResult<String> {
+        let mut text = String::new();
+        while let Some(token) = self.next().await {
+            text.push_str(&token?);
+        }
+        Ok(text)
+    }
+}
+
+/// 读取响应体并逐段推送文本，同时累计用量；接收端关闭或流结束时返回
+async fn pump_response(
+    mut response: reqwest::Response,
+    format: StreamFormat,
+    idle_timeout: Duration,
+    tx: &mpsc::Sender<AppResult<String>>,
+    usage: &mut Option<TokenUsage>,
+) {
+    let mut parser = match format {
+        StreamFormat::Ollama => Framing::Ndjson(NdjsonParser::new()),
+        _ => Framing::Sse(SseParser::new()),
+    };
+    loop {
+        let (events, finished) = match tokio::time::timeout(idle_timeout, response.chunk()).await {
+            Ok(Ok(Some(bytes))) => (parser.feed(&bytes), false),
+            Ok(Ok(None)) => (parser.finish().into_iter().collect(), true),
+            Ok(Err(e)) => {
+                let _ = tx.send(Err(AppError::network(3, format!("读取流式响应失败: {}", e)))).await;
+                return;
+            }
+            Err(_) => {
+                let message = format!("流式响应超时：{}s 内没有新数据", idle_timeout.as_secs());
+                let _ = tx.send(Err(AppError::network(1, message))).await;
+                return;
+            }
+        };
+
+        for event in &events {
+            if let Some(next) = format.parse_usage(event) {
+                *usage = Some(merge_usage(*usage, next));
+            }
+            match format.parse_event(event) {
+                Ok(StreamDelta::Text(text)) => {
+                    if tx.send(Ok(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(StreamDelta::Done) => return,
+                Ok(StreamDelta::Skip) => {}
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+
+        if finished {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.map(str::to_string),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parser_handles_split_chunks_and_crlf() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: ping\r\ndata: {\"a\"").is_empty());
+        let events = parser.feed(b":1}\r\n\r\n: comment\n\ndata: x\n");
+        assert_eq!(events, vec![event(Some("ping"), "{\"a\":1}")]);
+        assert_eq!(parser.finish(), Some(event(None, "x")));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn test_parser_joins_multiline_data_and_utf8_boundaries() {
+        let mut parser = SseParser::new();
+        let bytes = "data: 你好\ndata: 世界\n\n".as_bytes();
+        // 在多字节字符中间切开
+        let mut events = parser.feed(&bytes[..8]);
+        events.extend(parser.feed(&bytes[8..]));
+        assert_eq!(events, vec![event(None, "你好\n世界")]);
+    }
+
+    #[test]
+    fn test_parse_openai_events() {
+        let format = StreamFormat::OpenAI;
+        assert_eq!(
+            format.parse_event(&event(None, r#"{"choices":[{"delta":{"content":"Hi"}}]}"#)).unwrap(),
+            StreamDelta::Text("Hi".to_string())
+        );
+        assert_eq!(
+            format.parse_event(&event(None, r#"{"choices":[{"delta":{"role":"assistant"}}]}"#)).unwrap(),
+            StreamDelta::Skip
+        );
+        assert_eq!(format.parse_event(&event(None, "[DONE]")).unwrap(), StreamDelta::Done);
+        assert!(format.parse_event(&event(None, r#"{"error":{"message":"quota"}}"#)).is_err());
+    }
+
+    #[test]
+    fn test_parse_claude_events() {
+        let format = StreamFormat::Claude;
+        assert_eq!(
+            format.parse_event(&event(
+                Some("content_block_delta"),
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"你好"}}"#,
+            )).unwrap(),
+            StreamDelta::Text("你好".to_string())
+        );
+        assert_eq!(
+            format.parse_event(&event(Some("message_start"), r#"{"type":"message_start","message":{}}"#)).unwrap(),
+            StreamDelta::Skip
+        );
+        assert_eq!(
+            format.parse_event(&event(Some("message_stop"), r#"{"type":"message_stop"}"#)).unwrap(),
+            StreamDelta::Done
+        );
+        assert!(format
+            .parse_event(&event(Some("error"), r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_gemini_events() {
+        let format = StreamFormat::Gemini;
+        assert_eq!(
+            format.parse_event(&event(
+                None,
+                r#"{"candidates":[{"content":{"parts":[{"text":"a"},{"text":"b"}],"role":"model"}}]}"#,
+            )).unwrap(),
+            StreamDelta::Text("ab".to_string())
+        );
+        assert_eq!(
+            format.parse_event(&event(None, r#"{"usageMetadata":{"totalTokenCount":3}}"#)).unwrap(),
+            StreamDelta::Skip
+        );
+    }
+
+    #[test]
+    fn test_ndjson_parser_splits_lines() {
+        let mut parser = NdjsonParser::new();
+        assert!(parser.feed(b"{\"a\"").is_empty());
+        let events = parser.feed(b":1}\r\n\n{\"b\":2}\n{\"c\"");
+        assert_eq!(events, vec![event(None, r#"{"a":1}"#), event(None, r#"{"b":2}"#)]);
+        assert_eq!(parser.finish(), Some(event(None, r#"{"c""#)));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn test_parse_ollama_events() {
+        let format = StreamFormat::Ollama;
+        assert_eq!(
+            format.parse_event(&event(None, r#"{"message":{"role":"assistant","content":"你"},"done":false}"#)).unwrap(),
+            StreamDelta::Text("你".to_string())
+        );
+        assert_eq!(
+            format.parse_event(&event(None, r#"{"message":{"role":"assistant","content":""},"done":true,"eval_count":3}"#)).unwrap(),
+            StreamDelta::Done
+        );
+        let err = format.parse_event(&event(None, r#"{"error":"model 'x' not found"}"#)).unwrap_err();
+        assert!(err.to_string().contains("model 'x' not found"));
+    }
+
+    #[test]
+    fn test_parse_usage_events() {
+        let openai = StreamFormat::OpenAI;
+        assert_eq!(openai.parse_usage(&event(None, r#"{"choices":[{"delta":{"content":"Hi"}}]}"#)), None);
+        assert_eq!(
+            openai.parse_usage(&event(None, r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":5}}"#)),
+            Some(TokenUsage::new(12, 5))
+        );
+        assert_eq!(openai.parse_usage(&event(None, "[DONE]")), None);
+
+        let claude = StreamFormat::Claude;
+        let start = claude.parse_usage(&event(
+            Some("message_start"),
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":30,"output_tokens":1}}}"#,
+        )).unwrap();
+        let delta = claude.parse_usage(&event(
+            Some("message_delta"),
+            r#"{"type":"message_delta","delta":{},"usage":{"output_tokens":42}}"#,
+        )).unwrap();
+        assert_eq!(merge_usage(Some(start), delta), TokenUsage::new(30, 42));
+
+        assert_eq!(
+            StreamFormat::Gemini.parse_usage(&event(None, r#"{"usageMetadata":{"promptTokenCount":7,"candidatesTokenCount":3}}"#)),
+            Some(TokenUsage::new(7, 3))
+        );
+        assert_eq!(
+            StreamFormat::Ollama.parse_usage(&event(None, r#"{"done":true,"prompt_eval_count":9,"eval_count":4}"#)),
+            Some(TokenUsage::new(9, 4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_text() {
+        let stream = TokenStream::from_text("完整回答".to_string());
+        assert_eq!(stream.collect_text().await.unwrap(), "完整回答");
+    }
+}
@@ -1,12 +1,27 @@
 use async_trait::async_trait;
 use crate::error::AppResult;
 use crate::ai::provider::AIProviderConfig;
+use crate::ai::stream::TokenStream;
+use crate::ai::chat::{ChatMessage, Conversation};
+use crate::ai::usage::Completion;
 
 #[async_trait]
 pub trait AIProvider: Send + Sync {
-    async fn send_text(&self, prompt: &str) -> AppResult<String>;
-    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<String>;
-    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<String>;
+    async fn send_text(&self, prompt: &str) -> AppResult<Completion>;
+
+    /// 流式发送文本，逐段返回生成内容；默认退化为一次性返回完整结果
+    async fn send_text_stream(&self, prompt: &str) -> AppResult<TokenStream> {
+        let completion = self.send_text(prompt).await?;
+        Ok(TokenStream::from_text_with_usage(completion.text, completion.usage))
+    }
+
+    /// 多轮对话；默认把消息展开为单条文本提示词
+    async fn chat(&self, messages: &[ChatMessage]) -> AppResult<Completion> {
+        self.send_text(&Conversation::flatten(messages)).await
+    }
+
+    async fn analyze_video(&self, video_base64: &str, prompt: &str) -> AppResult<Completion>;
+    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> AppResult<Completion>;
     async fn test_connection(&self) -> AppResult<String>;
     fn config(&self) -> &AIProviderConfig;
 }
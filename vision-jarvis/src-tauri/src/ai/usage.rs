@@ -0,0 +1,389 @@
+/// AI 调用用量与花费统计
+///
+/// - 各 Provider 从响应的 usage 字段解析 token 数，随 `Completion` 一起返回
+/// - `UsageTracker` 把每次调用尝试写入 ai_usage 表，提供按日/月/用途/模型的汇总
+/// - 超出日/月预算时拒绝非必要的后台调用（录制分析、总结、提醒），用户主动发起的问答不受影响
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+use crate::db::Database;
+use crate::settings::AppSettings;
+use crate::error::{AppError, AppResult};
+
+/// 单次调用的 token 用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(input_tokens: u64, output_tokens: u64) -> Self {
+        Self { input_tokens, output_tokens }
+    }
+}
+
+/// Provider 返回的完整结果：文本 + 用量（响应中没有 usage 时为 None）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+impl Completion {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), usage: None }
+    }
+
+    pub fn with_usage(self, usage: Option<TokenUsage>) -> Self {
+        Self { usage, ..self }
+    }
+}
+
+/// 调用用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AIPurpose {
+    /// 录制分段视频理解
+    RecordingAnalysis,
+    /// 活动 Markdown 总结
+    MarkdownSummary,
+    /// 日/周/月总结
+    DailySummary,
+    /// 回归提醒等通知文案
+    Notification,
+    /// 记忆问答
+    MemoryQa,
+    /// 多轮对话
+    Chat,
+    /// 其他（连接测试、临时调用）
+    Other,
+}
+
+impl AIPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AIPurpose::RecordingAnalysis => "recording_analysis",
+            AIPurpose::MarkdownSummary => "markdown_summary",
+            AIPurpose::DailySummary => "daily_summary",
+            AIPurpose::Notification => "notification",
+            AIPurpose::MemoryQa => "memory_qa",
+            AIPurpose::Chat => "chat",
+            AIPurpose::Other => "other",
+        }
+    }
+
+    /// 是否为用户主动发起的调用（超出预算时仍然放行）；未标注用途的调用受预算限制
+    pub fn is_essential(&self) -> bool {
+        matches!(self, AIPurpose::MemoryQa | AIPurpose::Chat)
+    }
+}
+
+/// ai_usage 表中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: i64,
+    pub provider_id: String,
+    pub model: String,
+    pub purpose: AIPurpose,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub latency_ms: u64,
+    pub success: bool,
+}
+
+/// 汇总维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroup {
+    Day,
+    Month,
+    Purpose,
+    Model,
+}
+
+impl UsageGroup {
+    fn sql_key(&self) -> &'static str {
+        match self {
+            UsageGroup::Day => "strftime('%Y-%m-%d', timestamp, 'unixepoch', 'localtime')",
+            UsageGroup::Month => "strftime('%Y-%m', timestamp, 'unixepoch', 'localtime')",
+            UsageGroup::Purpose => "purpose",
+            UsageGroup::Model => "provider_id || '/' || model",
+        }
+    }
+}
+
+/// 一个汇总分组（key 为日期、月份、用途或 供应商/模型）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageAggregate {
+    pub key: String,
+    pub calls: u64,
+    pub failed_calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub avg_latency_ms: u64,
+}
+
+/// 超出预算时返回的 `AppError::AI` 错误码
+pub const BUDGET_EXCEEDED: u16 = 30;
+
+/// 是否为超出预算的拒绝
+pub fn is_budget_exceeded(err: &AppError) -> bool {
+    matches!(err, AppError::AI(BUDGET_EXCEEDED, _))
+}
+
+/// 花费预算（美元，0 表示不限制）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageBudget {
+    pub daily_usd: f64,
+    pub monthly_usd: f64,
+}
+
+impl From<&AppSettings> for UsageBudget {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            daily_usd: settings.ai_daily_budget_usd.max(0.0),
+            monthly_usd: settings.ai_monthly_budget_usd.max(0.0),
+        }
+    }
+}
+
+/// 当前预算使用情况
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub budget: UsageBudget,
+    pub daily_spent_usd: f64,
+    pub monthly_spent_usd: f64,
+    /// 超出预算，后台 AI 任务已暂停
+    pub exceeded: bool,
+}
+
+/// 用量记录与预算控制
+pub struct UsageTracker {
+    db: Arc<Database>,
+    budget: RwLock<UsageBudget>,
+}
+
+impl UsageTracker {
+    pub fn new(db: Arc<Database>, budget: UsageBudget) -> Self {
+        Self {
+            db,
+            budget: RwLock::new(budget),
+        }
+    }
+
+    pub fn budget(&self) -> UsageBudget {
+        *self.budget.read().unwrap()
+    }
+
+    pub fn set_budget(&self, budget: UsageBudget) {
+        *self.budget.write().unwrap() = budget;
+    }
+
+    /// 写入一条调用记录
+    pub fn record(&self, record: &UsageRecord) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO ai_usage (
+                    timestamp, provider_id, model, purpose,
+                    input_tokens, output_tokens, cost_usd, latency_ms, success
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![
+                    record.timestamp,
+                    record.provider_id,
+                    record.model,
+                    record.purpose.as_str(),
+                    record.input_tokens as i64,
+                    record.output_tokens as i64,
+                    record.cost_usd,
+                    record.latency_ms as i64,
+                    record.success,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// 按维度汇总 [start, end) 内的调用
+    pub fn aggregate(&self, group: UsageGroup, start: i64, end: i64) -> Result<Vec<UsageAggregate>> {
+        let sql = format!(
+            "SELECT {key} AS k,
+                    COUNT(*),
+                    SUM(CASE WHEN success = 0 THEN 1 ELSE 0 END),
+                    SUM(input_tokens),
+                    SUM(output_tokens),
+                    SUM(cost_usd),
+                    AVG(latency_ms)
+             FROM ai_usage
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             GROUP BY k
+             ORDER BY k",
+            key = group.sql_key()
+        );
+
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map([start, end], |row| {
+                    Ok(UsageAggregate {
+                        key: row.get(0)?,
+                        calls: row.get::<_, i64>(1)? as u64,
+                        failed_calls: row.get::<_, i64>(2)? as u64,
+                        input_tokens: row.get::<_, i64>(3)? as u64,
+                        output_tokens: row.get::<_, i64>(4)? as u64,
+                        cost_usd: row.get(5)?,
+                        avg_latency_ms: row.get::<_, f64>(6)?.round() as u64,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// 自 since 起的累计花费
+    pub fn spent_since(&self, since: i64) -> Result<f64> {
+        self.db.with_connection(|conn| {
+            let spent: f64 = conn.query_row(
+                "SELECT COALESCE(SUM(cost_usd), 0.0) FROM ai_usage WHERE timestamp >= ?1",
+                [since],
+                |row| row.get(0),
+            )?;
+            Ok(spent)
+        })
+    }
+
+    /// 按本地时间计算今日与本月的花费
+    pub fn budget_status(&self) -> Result<BudgetStatus> {
+        let today = Local::now().date_naive();
+        let month_start = today.with_day(1).unwrap_or(today);
+
+        let budget = self.budget();
+        let daily_spent_usd = self.spent_since(local_midnight(today))?;
+        let monthly_spent_usd = self.spent_since(local_midnight(month_start))?;
+        let exceeded = (budget.daily_usd > 0.0 && daily_spent_usd >= budget.daily_usd)
+            || (budget.monthly_usd > 0.0 && monthly_spent_usd >= budget.monthly_usd);
+
+        Ok(BudgetStatus {
+            budget,
+            daily_spent_usd,
+            monthly_spent_usd,
+            exceeded,
+        })
+    }
+
+    /// 调用前检查预算；查询失败时放行，避免统计问题阻断业务
+    pub fn check(&self, purpose: AIPurpose) -> AppResult<()> {
+        if purpose.is_essential() {
+            return Ok(());
+        }
+        let budget = self.budget();
+        if budget.daily_usd <= 0.0 && budget.monthly_usd <= 0.0 {
+            return Ok(());
+        }
+
+        match self.budget_status() {
+            Ok(status) if status.exceeded => Err(AppError::ai(
+                BUDGET_EXCEEDED,
+                format!(
+                    "已超出 AI 花费预算（今日 ${:.2} / 本月 ${:.2}），{} 已暂停",
+                    status.daily_spent_usd,
+                    status.monthly_spent_usd,
+                    purpose.as_str()
+                ),
+            )),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                warn!("[Usage] 查询预算失败，放行调用: {}", e);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 本地日期零点的时间戳
+fn local_midnight(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(budget: UsageBudget) -> UsageTracker {
+        UsageTracker::new(Arc::new(Database::open_in_memory().unwrap()), budget)
+    }
+
+    fn record(timestamp: i64, purpose: AIPurpose, cost_usd: f64, success: bool) -> UsageRecord {
+        UsageRecord {
+            timestamp,
+            provider_id: "p".to_string(),
+            model: "m".to_string(),
+            purpose,
+            input_tokens: 1000,
+            output_tokens: 200,
+            cost_usd,
+            latency_ms: 100,
+            success,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_by_purpose_and_day() {
+        let tracker = tracker(UsageBudget::default());
+        let noon = local_midnight(NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()) + 12 * 3600;
+        tracker.record(&record(noon, AIPurpose::RecordingAnalysis, 0.5, true)).unwrap();
+        tracker.record(&record(noon + 60, AIPurpose::RecordingAnalysis, 0.0, false)).unwrap();
+        tracker.record(&record(noon + 86400, AIPurpose::DailySummary, 0.25, true)).unwrap();
+
+        let by_purpose = tracker.aggregate(UsageGroup::Purpose, 0, i64::MAX).unwrap();
+        assert_eq!(by_purpose.len(), 2);
+        assert_eq!(by_purpose[1].key, "recording_analysis");
+        assert_eq!(by_purpose[1].calls, 2);
+        assert_eq!(by_purpose[1].failed_calls, 1);
+        assert_eq!(by_purpose[1].input_tokens, 2000);
+
+        let by_day = tracker.aggregate(UsageGroup::Day, 0, i64::MAX).unwrap();
+        let days: Vec<&str> = by_day.iter().map(|a| a.key.as_str()).collect();
+        assert_eq!(days, vec!["2026-03-02", "2026-03-03"]);
+
+        let by_month = tracker.aggregate(UsageGroup::Month, 0, i64::MAX).unwrap();
+        assert_eq!(by_month.len(), 1);
+        assert_eq!(by_month[0].key, "2026-03");
+        assert!((by_month[0].cost_usd - 0.75).abs() < 1e-9);
+
+        // 时间范围过滤
+        assert_eq!(tracker.aggregate(UsageGroup::Day, noon + 3600, i64::MAX).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_budget_pauses_background_work() {
+        let tracker = tracker(UsageBudget { daily_usd: 1.0, monthly_usd: 0.0 });
+        let now = chrono::Utc::now().timestamp();
+        tracker.record(&record(now, AIPurpose::RecordingAnalysis, 0.6, true)).unwrap();
+        assert!(tracker.check(AIPurpose::RecordingAnalysis).is_ok());
+
+        tracker.record(&record(now, AIPurpose::MarkdownSummary, 0.5, true)).unwrap();
+        let status = tracker.budget_status().unwrap();
+        assert!(status.exceeded);
+        assert!((status.daily_spent_usd - 1.1).abs() < 1e-9);
+
+        let err = tracker.check(AIPurpose::RecordingAnalysis).unwrap_err();
+        assert!(matches!(err, AppError::AI(30, _)));
+        assert!(tracker.check(AIPurpose::MemoryQa).is_ok());
+
+        // 取消预算后恢复
+        tracker.set_budget(UsageBudget::default());
+        assert!(tracker.check(AIPurpose::DailySummary).is_ok());
+    }
+}
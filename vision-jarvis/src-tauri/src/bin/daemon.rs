@@ -0,0 +1,125 @@
+/// Vision-Jarvis 无界面守护进程
+///
+/// 不启动 Tauri 窗口，只运行录制、记忆管道、保留策略、归档等后台任务，
+/// 用于 Linux 服务器与集成测试。收到 SIGINT / SIGTERM 时写完当前分段后退出。
+///
+/// 用法：`vision-jarvis-daemon [--config <file.yaml>] [--data-dir <dir>]`
+///
+/// 配置文件（YAML，均可省略）：
+///
+/// ```yaml
+/// data_dir: /var/lib/vision-jarvis
+/// log_level: info
+/// settings:                 # 覆盖已保存的设置，键为 AppSettings 字段名
+///   memory_enabled: true
+///   capture_interval_seconds: 60
+///   storage_path: /var/lib/vision-jarvis/recordings
+/// ```
+
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use vision_jarvis_lib::runtime::{Runtime, RuntimeConfig};
+
+const USAGE: &str = "用法: vision-jarvis-daemon [--config <file.yaml>] [--data-dir <dir>]";
+
+/// 守护进程配置文件
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct DaemonConfig {
+    /// 日志级别（RUST_LOG 优先）
+    log_level: Option<String>,
+    #[serde(flatten)]
+    runtime: RuntimeConfig,
+}
+
+/// 命令行参数
+#[derive(Debug, Default)]
+struct Args {
+    config: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-c" | "--config" => {
+                args.config = Some(iter.next().context("--config 需要文件路径")?.into());
+            }
+            "--data-dir" => {
+                args.data_dir = Some(iter.next().context("--data-dir 需要目录路径")?.into());
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => bail!("未知参数: {}\n{}", other, USAGE),
+        }
+    }
+    Ok(args)
+}
+
+fn load_config(args: &Args) -> Result<DaemonConfig> {
+    let mut config = match &args.config {
+        Some(path) => {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("读取配置文件失败: {}", path.display()))?;
+            serde_yaml::from_str(&raw)
+                .with_context(|| format!("配置文件格式错误: {}", path.display()))?
+        }
+        None => DaemonConfig::default(),
+    };
+    if let Some(data_dir) = &args.data_dir {
+        config.runtime.data_dir = data_dir.clone();
+    }
+    Ok(config)
+}
+
+/// 等待 Ctrl-C 或 SIGTERM
+async fn wait_for_shutdown() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).context("注册 SIGTERM 失败")?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.context("监听 Ctrl-C 失败")?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.context("监听 Ctrl-C 失败")?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let config = load_config(&parse_args()?)?;
+
+    let log_level = config.log_level.as_deref().unwrap_or("info");
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level))
+        .format_timestamp_secs()
+        .init();
+
+    info!("Vision-Jarvis daemon starting (data: {})", config.runtime.data_dir.display());
+    let runtime = Runtime::open(&config.runtime)?;
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("创建 tokio runtime 失败")?
+        .block_on(async {
+            runtime.start();
+            vision_jarvis_lib::runtime::start_idle_watcher(&runtime.state, &runtime.ai_config, |msg| {
+                info!("Return hint: {}", msg);
+            });
+
+            wait_for_shutdown().await?;
+            info!("Shutdown signal received, stopping...");
+            runtime.shutdown().await;
+            info!("Vision-Jarvis daemon stopped");
+            Ok(())
+        })
+}
@@ -0,0 +1,371 @@
+/// vjctl：Vision-Jarvis 命令行客户端
+///
+/// 直接读写本机数据目录（与桌面应用、守护进程共用数据库），查询逻辑与 Tauri 命令相同
+/// （见 `memory::queries`）。只打开数据库与设置（`DataAccess`），不执行迁移、不启动后台任务，
+/// 应用运行时也可以使用。加 `--json` 输出 JSON，便于脚本处理。
+
+use anyhow::{bail, Context, Result};
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use vision_jarvis_lib::ai::{create_embedding_provider, AIClient, HashEmbeddingProvider};
+use vision_jarvis_lib::queries::{self, SearchFilters};
+use vision_jarvis_lib::runtime::{default_data_dir, DataAccess};
+use vision_jarvis_lib::StorageManager;
+
+const USAGE: &str = "用法: vjctl [--json] [--data-dir <dir>] <命令>
+       vjctl --help
+
+命令:
+  activities [YYYY-MM-DD]               列出某天的活动（默认今天）
+  activity <ID>                         显示活动的 Markdown 笔记
+  search <关键词> [--limit N]           搜索记忆（默认 20 条）
+  summary <daily|weekly|monthly> [YYYY-MM-DD]
+                                        生成日 / 周 / 月总结（默认今天所在的周期）
+  projects                              列出项目
+  habits                                列出习惯
+  stats                                 录制与分析统计
+  storage                               存储占用";
+
+/// 总结周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SummaryKind {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Activities { date: String },
+    Activity { id: String },
+    Search { query: String, limit: usize },
+    Summary { kind: SummaryKind, date: String },
+    Projects,
+    Habits,
+    Stats,
+    Storage,
+    Help,
+}
+
+impl Command {
+    /// 是否需要 AI（语义搜索的向量模型、AI 总结）
+    fn needs_ai(&self) -> bool {
+        matches!(self, Self::Search { .. } | Self::Summary { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cli {
+    json: bool,
+    data_dir: Option<PathBuf>,
+    command: Command,
+}
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli> {
+    let mut json = false;
+    let mut data_dir = None;
+    let mut limit = None;
+    let mut positional = Vec::new();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--data-dir" => data_dir = Some(iter.next().context("--data-dir 需要目录路径")?.into()),
+            "--limit" => {
+                let value = iter.next().context("--limit 需要数量")?;
+                limit = Some(value.parse::<usize>().with_context(|| format!("无效的数量: {}", value))?);
+            }
+            "-h" | "--help" => return Ok(Cli { json, data_dir, command: Command::Help }),
+            flag if flag.starts_with("--") => bail!("未知参数: {}\n\n{}", flag, USAGE),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let name = positional.next().with_context(|| USAGE.to_string())?;
+    let mut next = || positional.next();
+    let command = match name.as_str() {
+        "activities" => Command::Activities { date: next().unwrap_or_else(today) },
+        "activity" => Command::Activity { id: next().context("activity 需要活动 ID")? },
+        "search" => Command::Search {
+            query: next().context("search 需要关键词")?,
+            limit: limit.unwrap_or(20),
+        },
+        "summary" => {
+            let kind = match next().as_deref() {
+                Some("daily") => SummaryKind::Daily,
+                Some("weekly") => SummaryKind::Weekly,
+                Some("monthly") => SummaryKind::Monthly,
+                _ => bail!("summary 需要周期：daily、weekly 或 monthly"),
+            };
+            Command::Summary { kind, date: next().unwrap_or_else(today) }
+        }
+        "projects" => Command::Projects,
+        "habits" => Command::Habits,
+        "stats" => Command::Stats,
+        "storage" => Command::Storage,
+        other => bail!("未知命令: {}\n\n{}", other, USAGE),
+    };
+    if let Some(extra) = next() {
+        bail!("多余的参数: {}", extra);
+    }
+
+    Ok(Cli { json, data_dir, command })
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// 时间戳格式化为本地时间
+fn format_time(timestamp: i64, format: &str) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format(format).to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+async fn run(cli: Cli, data: &DataAccess, ai_client: Option<AIClient>) -> Result<()> {
+    let db = &data.db;
+    let storage_root = data.storage_path();
+
+    match cli.command {
+        Command::Activities { date } => {
+            let activities = queries::activities(db, &date)?;
+            if cli.json {
+                return print_json(&activities);
+            }
+            if activities.is_empty() {
+                println!("{} 没有活动记录", date);
+            }
+            for a in activities {
+                println!(
+                    "{}-{}  {:>4}m  [{}] {} ({})  {}",
+                    format_time(a.start_time, "%H:%M"),
+                    format_time(a.end_time, "%H:%M"),
+                    a.duration_minutes,
+                    a.category,
+                    a.title,
+                    a.application,
+                    a.id,
+                );
+            }
+        }
+        Command::Activity { id } => {
+            if cli.json {
+                let detail = queries::activity_detail(db, &id)?;
+                let markdown = queries::activity_markdown(db, &storage_root, &id).ok();
+                return print_json(&json!({ "detail": detail, "markdown": markdown }));
+            }
+            println!("{}", queries::activity_markdown(db, &storage_root, &id)?);
+        }
+        Command::Search { query, limit } => {
+            // 语义检索使用 AI 提供的向量模型，未配置时退回本地哈希向量
+            let embedder = match &ai_client {
+                Some(client) => create_embedding_provider(client.config()),
+                None => Arc::new(HashEmbeddingProvider::default()),
+            };
+            let hits = queries::search(db, embedder.as_ref(), &query, &SearchFilters::default(), limit).await?;
+            if cli.json {
+                return print_json(&hits);
+            }
+            if hits.is_empty() {
+                println!("没有找到与 \"{}\" 相关的记忆", query);
+            }
+            for hit in hits {
+                let text = hit.snippet.as_deref().unwrap_or(&hit.text);
+                println!("{:.3}  [{}] {}:{}", hit.score, hit.source, hit.file_path, hit.start_line);
+                println!("    {}", text.lines().next().unwrap_or_default());
+            }
+        }
+        Command::Summary { kind, date } => {
+            if ai_client.is_none() {
+                log::info!("no AI configured, using template summary");
+            }
+            let generator = queries::summary_generator(db.clone(), ai_client.map(Arc::new), storage_root);
+            let summary = match kind {
+                SummaryKind::Daily => generator.generate_daily(&date).await,
+                SummaryKind::Weekly => generator.generate_weekly(&date).await,
+                SummaryKind::Monthly => generator.generate_monthly(&date).await,
+            }
+            .context("生成总结失败")?;
+            data.refresh_knowledge_base().await;
+
+            let summary = queries::SummaryInfo::from(summary);
+            if cli.json {
+                return print_json(&summary);
+            }
+            println!("{}", summary.content);
+        }
+        Command::Projects => {
+            let projects = queries::projects(db)?;
+            if cli.json {
+                return print_json(&projects);
+            }
+            for p in projects {
+                println!(
+                    "{}  [{}]  {} 个活动  最近 {}  {}",
+                    p.title,
+                    p.status,
+                    p.activity_count,
+                    format_time(p.last_activity_date, "%Y-%m-%d"),
+                    p.id,
+                );
+            }
+        }
+        Command::Habits => {
+            let habits = queries::habits(db)?;
+            if cli.json {
+                return print_json(&habits);
+            }
+            for h in habits {
+                println!(
+                    "{}  [{}]  置信度 {:.0}%  {}  {} 次  {}",
+                    h.pattern_name,
+                    h.pattern_type,
+                    h.confidence * 100.0,
+                    h.frequency,
+                    h.occurrence_count,
+                    h.typical_time.as_deref().unwrap_or("-"),
+                );
+            }
+        }
+        Command::Stats => {
+            let stats = queries::recording_stats(db)?;
+            if cli.json {
+                return print_json(&stats);
+            }
+            println!("录制: {}（已分析 {}）", stats.total_recordings, stats.analyzed_recordings);
+            println!("活动: {}", stats.total_activities);
+            println!("项目: {}", stats.total_projects);
+            println!("习惯: {}", stats.total_habits);
+        }
+        Command::Help => println!("{}", USAGE),
+        Command::Storage => {
+            let info = StorageManager::new(storage_root)?.get_storage_info()?;
+            if cli.json {
+                return print_json(&info);
+            }
+            println!("存储目录: {}", info.root_path);
+            println!("总计: {}（{} 个文件）", format_bytes(info.total_used_bytes), info.total_files);
+            for (name, bytes) in [
+                ("截图", info.screenshots_bytes),
+                ("录制", info.recordings_bytes),
+                ("长期记忆", info.long_term_memory_bytes),
+                ("项目", info.project_bytes),
+                ("习惯", info.habits_bytes),
+                ("数据库", info.database_bytes),
+                ("日志", info.logs_bytes),
+                ("临时文件", info.temp_bytes),
+            ] {
+                println!("  {:<8} {}", name, format_bytes(bytes));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let cli = match parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if cli.command == Command::Help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let result = (|| {
+        let data_dir = cli.data_dir.clone().unwrap_or_else(default_data_dir);
+        let data = DataAccess::open(&data_dir)?;
+        // 凭据存储需在进入 tokio runtime 之前打开（见 `DataAccess::ai_client`）
+        let ai_client = if cli.command.needs_ai() { data.ai_client()? } else { None };
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(run(cli, &data, ai_client))
+    })();
+
+    if let Err(e) = result {
+        eprintln!("错误: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cli = parse(&["--json", "activities", "2024-06-10"]).unwrap();
+        assert!(cli.json);
+        assert_eq!(cli.command, Command::Activities { date: "2024-06-10".to_string() });
+
+        let cli = parse(&["search", "rust 所有权", "--limit", "5", "--data-dir", "/tmp/vj"]).unwrap();
+        assert_eq!(cli.command, Command::Search { query: "rust 所有权".to_string(), limit: 5 });
+        assert_eq!(cli.data_dir, Some(PathBuf::from("/tmp/vj")));
+
+        let cli = parse(&["summary", "weekly", "2024-06-10"]).unwrap();
+        assert_eq!(cli.command, Command::Summary {
+            kind: SummaryKind::Weekly,
+            date: "2024-06-10".to_string(),
+        });
+        assert_eq!(parse(&["activities"]).unwrap().command, Command::Activities { date: today() });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["activity"]).is_err());
+        assert!(parse(&["summary", "yearly"]).is_err());
+        assert!(parse(&["search", "x", "--limit", "many"]).is_err());
+        assert!(parse(&["stats", "extra"]).is_err());
+        assert!(parse(&["stats", "--verbose"]).is_err());
+        assert_eq!(parse(&["--help"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&["search", "-h"]).unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
@@ -0,0 +1,206 @@
+/// 前台窗口探测
+///
+/// 录制期间定期采样前台应用名与窗口标题，供脱敏阶段判断分段是否出现过黑名单窗口。
+/// macOS 通过 osascript 查询 System Events，Linux X11 通过 xprop；
+/// 其他平台（含 Wayland 原生窗口）无法探测时返回 None
+
+use serde::{Deserialize, Serialize};
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::process::Command;
+
+/// 前台窗口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveWindow {
+    pub app_name: String,
+    pub title: String,
+}
+
+/// 分段内的一次窗口采样
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowSample {
+    /// 距分段开始的秒数
+    pub offset_secs: u32,
+    pub app_name: String,
+    pub title: String,
+}
+
+/// 追加采样；与上一条窗口相同时跳过，只保留切换点
+pub fn record_sample(samples: &mut Vec<WindowSample>, offset_secs: u32, window: ActiveWindow) {
+    if let Some(last) = samples.last() {
+        if last.app_name == window.app_name && last.title == window.title {
+            return;
+        }
+    }
+    samples.push(WindowSample {
+        offset_secs,
+        app_name: window.app_name,
+        title: window.title,
+    });
+}
+
+/// 查询当前前台窗口（阻塞调用外部命令）
+pub fn current() -> Option<ActiveWindow> {
+    #[cfg(target_os = "macos")]
+    {
+        current_macos()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        current_x11()
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn current_macos() -> Option<ActiveWindow> {
+    const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set appName to name of frontApp
+    set windowTitle to ""
+    try
+        set windowTitle to name of front window of frontApp
+    end try
+    return appName & linefeed & windowTitle
+end tell"#;
+
+    let output = Command::new("osascript").args(["-e", SCRIPT]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_osascript_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "linux")]
+fn current_x11() -> Option<ActiveWindow> {
+    std::env::var_os("DISPLAY")?;
+
+    let root = Command::new("xprop").args(["-root", "_NET_ACTIVE_WINDOW"]).output().ok()?;
+    if !root.status.success() {
+        return None;
+    }
+    let window_id = parse_active_window_id(&String::from_utf8_lossy(&root.stdout))?;
+
+    let props = Command::new("xprop")
+        .args(["-id", &window_id, "WM_CLASS", "_NET_WM_NAME", "WM_NAME"])
+        .output()
+        .ok()?;
+    if !props.status.success() {
+        return None;
+    }
+    parse_xprop_window(&String::from_utf8_lossy(&props.stdout))
+}
+
+/// 解析 osascript 输出：第一行应用名，第二行窗口标题
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_osascript_output(stdout: &str) -> Option<ActiveWindow> {
+    let stdout = stdout.trim_end_matches(['\r', '\n']);
+    let (app_name, title) = stdout.split_once('\n').unwrap_or((stdout, ""));
+    let app_name = app_name.trim();
+    if app_name.is_empty() {
+        return None;
+    }
+    Some(ActiveWindow {
+        app_name: app_name.to_string(),
+        title: title.trim().to_string(),
+    })
+}
+
+/// 解析 `xprop -root _NET_ACTIVE_WINDOW`：`_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_active_window_id(stdout: &str) -> Option<String> {
+    let id = stdout.rsplit('#').next()?.trim();
+    let id = id.split([',', ' ']).next()?;
+    (id.starts_with("0x") && id != "0x0").then(|| id.to_string())
+}
+
+/// 解析窗口属性：应用名取 WM_CLASS 的 class 部分，标题优先 _NET_WM_NAME
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_xprop_window(stdout: &str) -> Option<ActiveWindow> {
+    let mut app_name = None;
+    let mut net_title = None;
+    let mut title = None;
+
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once(" = ") else { continue };
+        let strings = quoted_strings(value);
+        if key.starts_with("WM_CLASS") {
+            app_name = strings.last().cloned();
+        } else if key.starts_with("_NET_WM_NAME") {
+            net_title = strings.into_iter().next();
+        } else if key.starts_with("WM_NAME") {
+            title = strings.into_iter().next();
+        }
+    }
+
+    Some(ActiveWindow {
+        app_name: app_name.filter(|a| !a.is_empty())?,
+        title: net_title.or(title).unwrap_or_default(),
+    })
+}
+
+/// 提取 xprop 输出中的双引号字符串（处理 `\"` 转义）
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn quoted_strings(value: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut chars = value.chars();
+    while chars.by_ref().any(|c| c == '"') {
+        let mut s = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => s.extend(chars.next()),
+                '"' => break,
+                _ => s.push(c),
+            }
+        }
+        result.push(s);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(app: &str, title: &str) -> ActiveWindow {
+        ActiveWindow { app_name: app.to_string(), title: title.to_string() }
+    }
+
+    #[test]
+    fn test_record_sample_keeps_switch_points() {
+        let mut samples = Vec::new();
+        record_sample(&mut samples, 0, window("Code", "main.rs"));
+        record_sample(&mut samples, 5, window("Code", "main.rs"));
+        record_sample(&mut samples, 10, window("1Password", "Vault"));
+        record_sample(&mut samples, 15, window("Code", "main.rs"));
+
+        let offsets: Vec<u32> = samples.iter().map(|s| s.offset_secs).collect();
+        assert_eq!(offsets, vec![0, 10, 15]);
+        assert_eq!(samples[1].app_name, "1Password");
+    }
+
+    #[test]
+    fn test_parse_osascript_output() {
+        assert_eq!(parse_osascript_output("Safari\nGitHub\n"), Some(window("Safari", "GitHub")));
+        assert_eq!(parse_osascript_output("Finder\n\n"), Some(window("Finder", "")));
+        assert_eq!(parse_osascript_output("\n"), None);
+    }
+
+    #[test]
+    fn test_parse_xprop() {
+        assert_eq!(
+            parse_active_window_id("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"),
+            Some("0x3a00007".to_string())
+        );
+        assert_eq!(parse_active_window_id("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+
+        let props = "WM_CLASS(STRING) = \"code\", \"Code\"\n\
+                     _NET_WM_NAME(UTF8_STRING) = \"main.rs - \\\"Vision\\\" - 编辑器\"\n\
+                     WM_NAME(STRING) = \"main.rs\"\n";
+        assert_eq!(parse_xprop_window(props), Some(window("Code", "main.rs - \"Vision\" - 编辑器")));
+
+        assert_eq!(parse_xprop_window("WM_CLASS:  not found.\n"), None);
+    }
+}
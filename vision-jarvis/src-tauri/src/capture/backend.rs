@@ -0,0 +1,448 @@
+/// 屏幕采集后端
+///
+/// 不同平台的屏幕采集方式差异很大，录制器只依赖 `CaptureBackend` 构建录制进程：
+/// - avfoundation: macOS
+/// - x11grab: Linux X11（依赖 DISPLAY，可在 Xvfb 下无头运行）
+/// - kmsgrab: Linux DRM/KMS（需要 CAP_SYS_ADMIN，仅显式指定时使用）
+/// - pipewire: Linux Wayland（通过 GStreamer pipewiresrc 录制）
+///
+/// 运行时通过 `VISION_JARVIS_CAPTURE_BACKEND` 环境变量强制指定后端，
+/// 否则按平台与会话类型自动选择。
+
+use crate::error::{AppError, AppResult};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// 强制指定采集后端的环境变量
+pub const BACKEND_ENV: &str = "VISION_JARVIS_CAPTURE_BACKEND";
+/// PipeWire 节点 ID（一般由 xdg-desktop-portal ScreenCast 会话提供）
+pub const PIPEWIRE_NODE_ENV: &str = "VISION_JARVIS_PIPEWIRE_NODE";
+/// kmsgrab 使用的 DRM 设备
+pub const KMS_DEVICE_ENV: &str = "VISION_JARVIS_KMS_DEVICE";
+
+/// FFmpeg 通用编码参数（所有 FFmpeg 后端共享）
+const FFMPEG_ENCODE_ARGS: [&str; 8] = [
+    "-c:v", "libx264",
+    "-preset", "ultrafast",
+    "-crf", "30",
+    "-pix_fmt", "yuv420p",
+];
+
+/// 屏幕采集后端
+pub trait CaptureBackend: Send + Sync {
+    /// 后端名称（用于日志和状态展示）
+    fn name(&self) -> &'static str;
+
+    /// 构建录制单个分段的命令，输出写入 `output`
+    fn build_command(&self, fps: u8, output: &Path) -> Command;
+
+    /// 优雅停止录制进程使用的信号（需保证输出文件被正确收尾）
+    #[cfg(unix)]
+    fn stop_signal(&self) -> libc::c_int {
+        libc::SIGTERM
+    }
+}
+
+/// 后端类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendKind {
+    AvFoundation,
+    X11Grab { display: String },
+    KmsGrab { device: String },
+    PipeWire { node: Option<u32> },
+}
+
+impl BackendKind {
+    /// 从名称解析（用于环境变量覆盖）
+    fn parse(name: &str, env: &dyn Fn(&str) -> Option<String>) -> AppResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "avfoundation" => Ok(BackendKind::AvFoundation),
+            "x11grab" | "x11" => Ok(BackendKind::X11Grab { display: x11_display(env) }),
+            "kmsgrab" | "kms" => Ok(BackendKind::KmsGrab { device: kms_device(env) }),
+            "pipewire" | "wayland" => Ok(BackendKind::PipeWire { node: pipewire_node(env) }),
+            other => Err(AppError::capture(20, format!("未知的采集后端: {}", other))),
+        }
+    }
+
+    /// 按平台和会话环境选择后端
+    ///
+    /// Linux 下优先 Wayland（PipeWire），其次 X11；没有任何图形会话时回退到 `:0`。
+    pub fn detect(os: &str, env: &dyn Fn(&str) -> Option<String>) -> AppResult<Self> {
+        if let Some(name) = env(BACKEND_ENV).filter(|v| !v.trim().is_empty()) {
+            return Self::parse(&name, env);
+        }
+
+        match os {
+            "macos" => Ok(BackendKind::AvFoundation),
+            "linux" => {
+                let session = env("XDG_SESSION_TYPE").unwrap_or_default().to_lowercase();
+                let has_wayland = session == "wayland" || env("WAYLAND_DISPLAY").is_some();
+                let has_x11 = env("DISPLAY").is_some();
+
+                if has_wayland && !(session == "x11" && has_x11) {
+                    Ok(BackendKind::PipeWire { node: pipewire_node(env) })
+                } else {
+                    Ok(BackendKind::X11Grab { display: x11_display(env) })
+                }
+            }
+            other => Err(AppError::capture(21, format!("当前平台不支持屏幕录制: {}", other))),
+        }
+    }
+
+    /// 创建后端实例
+    pub fn into_backend(self) -> Box<dyn CaptureBackend> {
+        match self {
+            BackendKind::AvFoundation => Box::new(AvFoundationBackend::probe()),
+            BackendKind::X11Grab { display } => Box::new(X11GrabBackend { display }),
+            BackendKind::KmsGrab { device } => Box::new(KmsGrabBackend { device }),
+            BackendKind::PipeWire { node } => Box::new(PipeWireBackend { node }),
+        }
+    }
+}
+
+/// 按当前运行环境选择并创建后端
+pub fn detect_backend() -> AppResult<Box<dyn CaptureBackend>> {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+    BackendKind::detect(std::env::consts::OS, &env).map(BackendKind::into_backend)
+}
+
+fn x11_display(env: &dyn Fn(&str) -> Option<String>) -> String {
+    env("DISPLAY").unwrap_or_else(|| ":0".to_string())
+}
+
+fn kms_device(env: &dyn Fn(&str) -> Option<String>) -> String {
+    env(KMS_DEVICE_ENV).unwrap_or_else(|| "/dev/dri/card0".to_string())
+}
+
+fn pipewire_node(env: &dyn Fn(&str) -> Option<String>) -> Option<u32> {
+    env(PIPEWIRE_NODE_ENV).and_then(|v| v.trim().parse().ok())
+}
+
+fn ffmpeg_command(input_args: &[String], filter: &str, output: &Path) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(input_args)
+        .args(["-vf", filter])
+        .args(FFMPEG_ENCODE_ARGS)
+        .arg("-y")
+        .arg(output)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    cmd
+}
+
+/// 偶数尺寸缩放（libx264 + yuv420p 要求宽高为偶数）
+const EVEN_SCALE_FILTER: &str = "scale=trunc(iw/2)*2:trunc(ih/2)*2";
+
+// ============================================================================
+// macOS: avfoundation
+// ============================================================================
+
+pub struct AvFoundationBackend {
+    screen_device_index: u32,
+}
+
+impl AvFoundationBackend {
+    /// 探测屏幕设备序号并创建后端
+    pub fn probe() -> Self {
+        let idx = find_screen_device_index();
+        log::info!("Screen device index: {}", idx);
+        Self { screen_device_index: idx }
+    }
+}
+
+impl CaptureBackend for AvFoundationBackend {
+    fn name(&self) -> &'static str {
+        "avfoundation"
+    }
+
+    fn build_command(&self, fps: u8, output: &Path) -> Command {
+        let input = vec![
+            "-f".to_string(), "avfoundation".to_string(),
+            "-framerate".to_string(), fps.to_string(),
+            "-i".to_string(), format!("{}:none", self.screen_device_index),
+        ];
+        ffmpeg_command(&input, EVEN_SCALE_FILTER, output)
+    }
+}
+
+fn find_screen_device_index() -> u32 {
+    let output = Command::new("ffmpeg")
+        .args(["-f", "avfoundation", "-list_devices", "true", "-i", ""])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+
+    match output {
+        Ok(out) => parse_avfoundation_screen_index(&String::from_utf8_lossy(&out.stderr)).unwrap_or(1),
+        Err(_) => 1,
+    }
+}
+
+/// 从 `ffmpeg -list_devices` 输出中解析 "Capture screen" 设备序号
+fn parse_avfoundation_screen_index(listing: &str) -> Option<u32> {
+    listing
+        .lines()
+        .filter(|line| line.contains("Capture screen"))
+        .find_map(|line| {
+            let end = line.rfind(']')?;
+            let start = line[..end].rfind('[')?;
+            line[start + 1..end].parse::<u32>().ok()
+        })
+}
+
+// ============================================================================
+// Linux: x11grab
+// ============================================================================
+
+pub struct X11GrabBackend {
+    display: String,
+}
+
+impl CaptureBackend for X11GrabBackend {
+    fn name(&self) -> &'static str {
+        "x11grab"
+    }
+
+    fn build_command(&self, fps: u8, output: &Path) -> Command {
+        let input = vec![
+            "-f".to_string(), "x11grab".to_string(),
+            "-framerate".to_string(), fps.to_string(),
+            "-draw_mouse".to_string(), "0".to_string(),
+            "-i".to_string(), self.display.clone(),
+        ];
+        ffmpeg_command(&input, EVEN_SCALE_FILTER, output)
+    }
+}
+
+// ============================================================================
+// Linux: kmsgrab
+// ============================================================================
+
+pub struct KmsGrabBackend {
+    device: String,
+}
+
+impl CaptureBackend for KmsGrabBackend {
+    fn name(&self) -> &'static str {
+        "kmsgrab"
+    }
+
+    fn build_command(&self, fps: u8, output: &Path) -> Command {
+        let input = vec![
+            "-device".to_string(), self.device.clone(),
+            "-f".to_string(), "kmsgrab".to_string(),
+            "-framerate".to_string(), fps.to_string(),
+            "-i".to_string(), "-".to_string(),
+        ];
+        // kmsgrab 输出 DRM 帧，需要先映射回系统内存
+        let filter = format!("hwdownload,format=bgr0,{}", EVEN_SCALE_FILTER);
+        ffmpeg_command(&input, &filter, output)
+    }
+}
+
+// ============================================================================
+// Linux: PipeWire (Wayland)
+// ============================================================================
+
+pub struct PipeWireBackend {
+    node: Option<u32>,
+}
+
+impl CaptureBackend for PipeWireBackend {
+    fn name(&self) -> &'static str {
+        "pipewire"
+    }
+
+    fn build_command(&self, fps: u8, output: &Path) -> Command {
+        let mut source = vec!["pipewiresrc".to_string(), "do-timestamp=true".to_string()];
+        if let Some(node) = self.node {
+            source.push(format!("path={}", node));
+        }
+
+        let mut cmd = Command::new("gst-launch-1.0");
+        // -e: 收到 SIGINT 时发送 EOS，保证 mp4 正确收尾
+        cmd.args(["-q", "-e"])
+            .args(&source)
+            .args([
+                "!", "videorate",
+                "!", &format!("video/x-raw,framerate={}/1", fps),
+                "!", "videoconvert",
+                "!", "videoscale",
+                "!", "video/x-raw,format=I420",
+                "!", "x264enc", "speed-preset=ultrafast", "quantizer=30",
+                "!", "mp4mux",
+                "!", "filesink",
+            ])
+            .arg(format!("location={}", output.display()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        cmd
+    }
+
+    #[cfg(unix)]
+    fn stop_signal(&self) -> libc::c_int {
+        libc::SIGINT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_of(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key: &str| map.get(key).cloned()
+    }
+
+    fn args_of(cmd: &Command) -> Vec<String> {
+        cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_detect_macos() {
+        let kind = BackendKind::detect("macos", &env_of(&[])).unwrap();
+        assert_eq!(kind, BackendKind::AvFoundation);
+    }
+
+    #[test]
+    fn test_detect_linux_x11() {
+        let kind = BackendKind::detect("linux", &env_of(&[("DISPLAY", ":99")])).unwrap();
+        assert_eq!(kind, BackendKind::X11Grab { display: ":99".to_string() });
+    }
+
+    #[test]
+    fn test_detect_linux_wayland() {
+        let env = env_of(&[
+            ("XDG_SESSION_TYPE", "wayland"),
+            ("WAYLAND_DISPLAY", "wayland-0"),
+            ("DISPLAY", ":0"),
+            (PIPEWIRE_NODE_ENV, "42"),
+        ]);
+        let kind = BackendKind::detect("linux", &env).unwrap();
+        assert_eq!(kind, BackendKind::PipeWire { node: Some(42) });
+    }
+
+    #[test]
+    fn test_detect_linux_x11_session_with_wayland_socket() {
+        let env = env_of(&[
+            ("XDG_SESSION_TYPE", "x11"),
+            ("WAYLAND_DISPLAY", "wayland-0"),
+            ("DISPLAY", ":1"),
+        ]);
+        let kind = BackendKind::detect("linux", &env).unwrap();
+        assert_eq!(kind, BackendKind::X11Grab { display: ":1".to_string() });
+    }
+
+    #[test]
+    fn test_detect_linux_headless_fallback() {
+        let kind = BackendKind::detect("linux", &env_of(&[])).unwrap();
+        assert_eq!(kind, BackendKind::X11Grab { display: ":0".to_string() });
+    }
+
+    #[test]
+    fn test_detect_env_override() {
+        let env = env_of(&[(BACKEND_ENV, "kmsgrab"), ("DISPLAY", ":0")]);
+        let kind = BackendKind::detect("linux", &env).unwrap();
+        assert_eq!(kind, BackendKind::KmsGrab { device: "/dev/dri/card0".to_string() });
+
+        let env = env_of(&[(BACKEND_ENV, "bogus")]);
+        assert!(BackendKind::detect("linux", &env).is_err());
+    }
+
+    #[test]
+    fn test_detect_unsupported_platform() {
+        assert!(BackendKind::detect("windows", &env_of(&[])).is_err());
+    }
+
+    #[test]
+    fn test_parse_avfoundation_screen_index() {
+        let listing = "\
+[AVFoundation indev @ 0x1] AVFoundation video devices:
+[AVFoundation indev @ 0x1] [0] FaceTime HD Camera
+[AVFoundation indev @ 0x1] [3] Capture screen 0
+[AVFoundation indev @ 0x1] AVFoundation audio devices:";
+        assert_eq!(parse_avfoundation_screen_index(listing), Some(3));
+        assert_eq!(parse_avfoundation_screen_index("no devices"), None);
+    }
+
+    #[test]
+    fn test_x11grab_command() {
+        let backend = X11GrabBackend { display: ":99".to_string() };
+        let cmd = backend.build_command(2, Path::new("/tmp/out.mp4"));
+        let args = args_of(&cmd);
+
+        assert_eq!(cmd.get_program(), "ffmpeg");
+        assert!(args.windows(2).any(|w| w == ["-f", "x11grab"]));
+        assert!(args.windows(2).any(|w| w == ["-i", ":99"]));
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libx264"]));
+        assert_eq!(args.last().unwrap(), "/tmp/out.mp4");
+    }
+
+    #[test]
+    fn test_kmsgrab_command() {
+        let backend = KmsGrabBackend { device: "/dev/dri/card1".to_string() };
+        let args = args_of(&backend.build_command(2, Path::new("/tmp/out.mp4")));
+
+        assert!(args.windows(2).any(|w| w == ["-device", "/dev/dri/card1"]));
+        assert!(args.windows(2).any(|w| w == ["-f", "kmsgrab"]));
+        assert!(args.iter().any(|a| a.starts_with("hwdownload")));
+    }
+
+    #[test]
+    fn test_pipewire_command() {
+        let backend = PipeWireBackend { node: Some(57) };
+        let cmd = backend.build_command(2, Path::new("/tmp/out.mp4"));
+        let args = args_of(&cmd);
+
+        assert_eq!(cmd.get_program(), "gst-launch-1.0");
+        assert!(args.contains(&"-e".to_string()));
+        assert!(args.contains(&"path=57".to_string()));
+        assert!(args.contains(&"video/x-raw,framerate=2/1".to_string()));
+        assert_eq!(args.last().unwrap(), "location=/tmp/out.mp4");
+        #[cfg(unix)]
+        assert_eq!(backend.stop_signal(), libc::SIGINT);
+    }
+
+    /// 在 Xvfb 虚拟屏幕上实际录制一小段
+    ///
+    /// 运行: cargo test xvfb -- --ignored（需要安装 Xvfb 和 ffmpeg）
+    #[test]
+    #[ignore]
+    fn test_x11grab_records_on_xvfb() {
+        let display = ":99";
+        let mut xvfb = Command::new("Xvfb")
+            .args([display, "-screen", "0", "640x480x24"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("启动 Xvfb 失败");
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("segment.mp4");
+        let backend = X11GrabBackend { display: display.to_string() };
+
+        // 在输出路径前插入 -t 限制录制时长，避免依赖信号终止
+        let cmd = backend.build_command(2, &output);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_os_string()).collect();
+        let (head, tail) = args.split_at(args.len() - 1);
+        let status = Command::new(cmd.get_program())
+            .args(head)
+            .args(["-t", "2"])
+            .args(tail)
+            .status()
+            .expect("启动 ffmpeg 失败");
+
+        let _ = xvfb.kill();
+        let _ = xvfb.wait();
+
+        assert!(status.success());
+        assert!(std::fs::metadata(&output).unwrap().len() > 0);
+    }
+}
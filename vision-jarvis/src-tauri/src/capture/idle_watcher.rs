@@ -2,18 +2,42 @@
 ///
 /// macOS: 使用 CoreGraphics CGEventSourceSecondsSinceLastEventType 轮询，
 ///        完全线程安全，无需 RunLoop，不调用 TSM API，消除 dispatch_assert_queue_fail 崩溃。
-/// 其他平台: 使用 rdev 监听全局鼠标事件。
+/// 其他平台: 使用 rdev 监听全局鼠标事件（需要 `desktop` feature，无界面构建不检测）。
+///
+/// 配置可在运行中更新（`set_config` / `watch_settings`），轮询循环每秒读取最新配置。
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use log::info;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 
-/// 鼠标 Idle 检测器
-pub struct IdleWatcher {
+use crate::settings::AppSettings;
+
+/// Idle 检测配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleConfig {
+    /// 是否启用（关闭时不检测也不触发回调）
+    pub enabled: bool,
     /// idle 阈值（秒）：鼠标静止超过此时长才认定为 idle
-    threshold_secs: u64,
+    pub threshold_secs: u64,
     /// 最小触发时长（秒）：idle 时长小于此值不触发回调
-    min_trigger_secs: u64,
+    pub min_trigger_secs: u64,
+}
+
+impl From<&AppSettings> for IdleConfig {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            enabled: settings.idle_reminder_enabled,
+            threshold_secs: settings.idle_threshold_secs,
+            min_trigger_secs: settings.idle_min_trigger_secs,
+        }
+    }
+}
+
+/// 鼠标 Idle 检测器
+pub struct IdleWatcher {
+    config: RwLock<IdleConfig>,
 }
 
 #[cfg(target_os = "macos")]
@@ -55,6 +79,7 @@ mod platform {
         }
     }
 
+    #[cfg(feature = "desktop")]
     pub fn start_rdev_listener(state: Arc<NonMacosState>) {
         if let Err(e) = rdev::listen(move |event| {
             use rdev::EventType;
@@ -71,38 +96,69 @@ mod platform {
             warn!("[IdleWatcher] rdev listen error: {:?}", e);
         }
     }
+
+    /// 无界面构建不链接 rdev（依赖 X11），没有鼠标事件可监听
+    #[cfg(not(feature = "desktop"))]
+    pub fn start_rdev_listener(_state: Arc<NonMacosState>) {
+        warn!("[IdleWatcher] Mouse listener unavailable without the desktop feature");
+    }
 }
 
 impl IdleWatcher {
-    /// 创建新的 IdleWatcher
-    ///
-    /// - `threshold_secs`: 鼠标静止多少秒后进入 idle 状态
-    /// - `min_trigger_secs`: idle 时长至少多少秒才触发回调
-    pub fn new(threshold_secs: u64, min_trigger_secs: u64) -> Self {
+    pub fn new(config: IdleConfig) -> Self {
         Self {
-            threshold_secs,
-            min_trigger_secs,
+            config: RwLock::new(config),
         }
     }
 
+    /// 当前配置
+    pub fn config(&self) -> IdleConfig {
+        *self.config.read().unwrap()
+    }
+
+    /// 更新配置（下一次轮询生效）
+    pub fn set_config(&self, config: IdleConfig) {
+        if self.config() != config {
+            info!(
+                "[IdleWatcher] Config updated (enabled={}, threshold={}s, min_trigger={}s)",
+                config.enabled, config.threshold_secs, config.min_trigger_secs
+            );
+        }
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 订阅设置变更，随 idle 相关设置更新配置
+    pub fn watch_settings(self: &Arc<Self>, mut settings: watch::Receiver<AppSettings>) -> JoinHandle<()> {
+        let watcher = Arc::clone(self);
+        tokio::spawn(async move {
+            while settings.changed().await.is_ok() {
+                let config = IdleConfig::from(&*settings.borrow_and_update());
+                watcher.set_config(config);
+            }
+        })
+    }
+
     /// 启动监听（阻塞调用，需在独立 OS 线程中运行）
     ///
     /// `on_return`: 用户从 idle 回归时的回调，参数为 idle 持续秒数
     #[cfg(target_os = "macos")]
-    pub fn start<F>(self, on_return: F)
+    pub fn start<F>(&self, on_return: F)
     where
         F: Fn(u64) + Send + Sync + 'static,
     {
-        let threshold = self.threshold_secs;
-        let min_trigger = self.min_trigger_secs;
         let on_return = Arc::new(on_return);
 
         loop {
             std::thread::sleep(Duration::from_secs(1));
 
+            let config = self.config();
+            if !config.enabled {
+                continue;
+            }
+
             let idle_secs = platform::seconds_since_last_mouse_activity() as u64;
 
-            if idle_secs < threshold {
+            if idle_secs < config.threshold_secs {
                 // 用户活跃，继续等待
                 continue;
             }
@@ -114,7 +170,7 @@ impl IdleWatcher {
             );
 
             // 等待用户回归（idle_secs 减小说明有新输入）
-            let was_long_idle = idle_secs >= min_trigger;
+            let was_long_idle = idle_secs >= config.min_trigger_secs;
             let snapshot = idle_secs;
 
             loop {
@@ -124,7 +180,7 @@ impl IdleWatcher {
                     // 检测到新输入，用户从 idle 回归
                     // 以回归时系统记录的 idle 时长为准（snapshot 是进入轮询时的值）
                     info!("[IdleWatcher] User returned after ~{}s idle", snapshot);
-                    if was_long_idle {
+                    if was_long_idle && self.config().enabled {
                         let cb = on_return.clone();
                         std::thread::spawn(move || cb(snapshot));
                     }
@@ -136,29 +192,40 @@ impl IdleWatcher {
 
     /// 启动监听（非 macOS 平台，使用 rdev）
     #[cfg(not(target_os = "macos"))]
-    pub fn start<F>(self, on_return: F)
+    pub fn start<F>(&self, on_return: F)
     where
         F: Fn(u64) + Send + Sync + 'static,
     {
         use std::time::Instant;
 
-        let threshold = Duration::from_secs(self.threshold_secs);
-        let min_trigger = self.min_trigger_secs;
         let on_return = Arc::new(on_return);
 
         let state = platform::NonMacosState::new();
-        let state_for_listener = state.clone();
-
-        // 启动 rdev 监听线程
-        std::thread::spawn(move || {
-            platform::start_rdev_listener(state_for_listener);
-        });
+        let mut listener_started = false;
 
         // 主轮询循环
         let mut idle_start: Option<Instant> = None;
 
         loop {
             std::thread::sleep(Duration::from_secs(1));
+
+            let config = self.config();
+            if !config.enabled {
+                idle_start = None;
+                continue;
+            }
+            let threshold = Duration::from_secs(config.threshold_secs);
+
+            // 首次启用时才启动 rdev 监听线程（监听无法停止，之后关闭只跳过检测）
+            if !listener_started {
+                let state_for_listener = state.clone();
+                std::thread::spawn(move || {
+                    platform::start_rdev_listener(state_for_listener);
+                });
+                *state.last_activity.lock().unwrap() = Instant::now();
+                listener_started = true;
+            }
+
             let last = *state.last_activity.lock().unwrap();
             let elapsed = last.elapsed();
 
@@ -177,7 +244,7 @@ impl IdleWatcher {
                         // 用户回归
                         let idle_secs = start.elapsed().as_secs();
                         info!("[IdleWatcher] User returned after {}s idle", idle_secs);
-                        if idle_secs >= min_trigger {
+                        if idle_secs >= config.min_trigger_secs {
                             let cb = on_return.clone();
                             std::thread::spawn(move || cb(idle_secs));
                         }
@@ -195,9 +262,37 @@ mod tests {
 
     #[test]
     fn test_idle_watcher_creation() {
-        let watcher = IdleWatcher::new(300, 60);
-        assert_eq!(watcher.threshold_secs, 300);
-        assert_eq!(watcher.min_trigger_secs, 60);
+        let watcher = IdleWatcher::new(IdleConfig::from(&AppSettings::default()));
+        let config = watcher.config();
+        assert!(config.enabled);
+        assert_eq!(config.threshold_secs, 300);
+        assert_eq!(config.min_trigger_secs, 60);
+    }
+
+    #[tokio::test]
+    async fn test_watch_settings_updates_config() {
+        let settings = crate::settings::SettingsManager::new();
+        let watcher = Arc::new(IdleWatcher::new(IdleConfig::from(&settings.get())));
+        let task = watcher.watch_settings(settings.subscribe());
+
+        settings
+            .update(AppSettings {
+                idle_reminder_enabled: false,
+                idle_threshold_secs: 900,
+                ..Default::default()
+            })
+            .unwrap();
+
+        for _ in 0..50 {
+            if watcher.config().threshold_secs == 900 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let config = watcher.config();
+        assert!(!config.enabled);
+        assert_eq!(config.threshold_secs, 900);
+        task.abort();
     }
 
     #[cfg(target_os = "macos")]
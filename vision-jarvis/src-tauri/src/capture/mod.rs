@@ -1,7 +1,10 @@
 /// 屏幕录制模块
 ///
-/// 使用 FFmpeg avfoundation 进行 macOS 屏幕分段录制
+/// 使用 FFmpeg（Linux Wayland 下为 GStreamer）进行屏幕分段录制，
+/// 采集方式由 `backend` 按平台选择：macOS avfoundation、Linux x11grab / kmsgrab / PipeWire
 
+pub mod backend;
 pub mod scheduler;
 pub mod screen_recorder;
 pub mod idle_watcher;
+pub mod active_window;
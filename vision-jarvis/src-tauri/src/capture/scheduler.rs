@@ -5,6 +5,15 @@ use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use log::{error, info};
 use super::screen_recorder::ScreenRecorder;
+use super::active_window::{self, WindowSample};
+use crate::privacy::PrivacyGuard;
+use crate::privacy::rules::ExclusionReason;
+use crate::settings::AppSettings;
+
+/// 录制期间前台窗口的采样间隔（秒）
+const WINDOW_SAMPLE_SECS: u64 = 5;
+/// 隐私暂停期间重新检查规则的间隔（秒）
+const PRIVACY_POLL_SECS: u64 = 5;
 
 pub struct CaptureScheduler {
     recorder: Arc<ScreenRecorder>,
@@ -13,6 +22,7 @@ pub struct CaptureScheduler {
     is_running: Arc<Mutex<bool>>,
     task_handle: Option<JoinHandle<()>>,
     analysis_tx: Option<tokio::sync::mpsc::Sender<(String, std::path::PathBuf)>>,
+    privacy_guard: Option<Arc<PrivacyGuard>>,
 }
 
 impl CaptureScheduler {
@@ -24,6 +34,7 @@ impl CaptureScheduler {
             is_running: Arc::new(Mutex::new(false)),
             task_handle: None,
             analysis_tx: None,
+            privacy_guard: None,
         }
     }
 
@@ -37,6 +48,11 @@ impl CaptureScheduler {
         self
     }
 
+    pub fn with_privacy_guard(mut self, guard: Arc<PrivacyGuard>) -> Self {
+        self.privacy_guard = Some(guard);
+        self
+    }
+
     pub async fn start(&mut self) -> AppResult<()> {
         let mut running = self.is_running.lock().await;
         if *running {
@@ -49,16 +65,45 @@ impl CaptureScheduler {
         let db = self.db.clone();
         let is_running = Arc::clone(&self.is_running);
         let analysis_tx = self.analysis_tx.clone();
+        let privacy_guard = self.privacy_guard.clone();
 
         let interval = self.interval_seconds;
 
         let handle = tokio::spawn(async move {
+            let mut waiting_unlock = false;
             loop {
                 if !*is_running.lock().await {
                     recorder.stop().await;
                     break;
                 }
 
+                // 已开启静态加密但未解锁：录下的内容无法加密保存，等待解锁
+                if db.as_ref().is_some_and(|db| db.vault().is_locked()) {
+                    if !waiting_unlock {
+                        info!("Recording paused: waiting for encryption unlock");
+                        waiting_unlock = true;
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_secs(PRIVACY_POLL_SECS)).await;
+                    continue;
+                }
+                waiting_unlock = false;
+
+                // 隐私排除：命中时不启动新分段，等规则解除后再录
+                if let Some(ref guard) = privacy_guard {
+                    if let Some(reason) = current_exclusion(guard).await {
+                        if guard.active().as_ref() != Some(&reason) {
+                            info!("Recording paused: {}", reason);
+                        }
+                        guard.set_active(Some(reason));
+                        guard.wait(tokio::time::Duration::from_secs(PRIVACY_POLL_SECS)).await;
+                        continue;
+                    }
+                    if guard.active().is_some() {
+                        info!("Recording resumed");
+                        guard.set_active(None);
+                    }
+                }
+
                 let output_path = match recorder.start_segment().await {
                     Ok(p) => p,
                     Err(e) => {
@@ -74,8 +119,8 @@ impl CaptureScheduler {
                     .unwrap_or_default();
                 info!("Recording: {}", filename);
 
-                // 异步等待分段时长，不阻塞 tokio
-                tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+                // 异步等待分段时长，期间采样前台窗口并检查排除规则
+                let watch = watch_segment(interval, privacy_guard.as_deref()).await;
 
                 // 被停止时不保存（scheduler.stop 已处理清理）
                 if !*is_running.lock().await {
@@ -83,9 +128,19 @@ impl CaptureScheduler {
                     break;
                 }
 
-                // 发送 SIGTERM 结束 FFmpeg，等待写入文件尾
+                // 发送停止信号结束录制进程，等待写入文件尾
                 recorder.stop().await;
 
+                // 分段中途命中排除规则：整段丢弃（单个视频文件无法只删一部分）
+                if let Some(reason) = watch.excluded {
+                    recorder.delete_current_file().await;
+                    info!("Segment discarded: {}", reason);
+                    if let Some(ref guard) = privacy_guard {
+                        guard.set_active(Some(reason));
+                    }
+                    continue;
+                }
+
                 let end_time = chrono::Local::now().timestamp();
                 let duration = end_time - start_time;
 
@@ -96,15 +151,30 @@ impl CaptureScheduler {
                     error!("Recording file missing or empty: {}", output_path.display());
                     continue;
                 }
+                // 已完成的分段不再由 stop() 的清理逻辑删除
+                recorder.release_current_file().await;
+
+                if let Some(ref db) = db {
+                    if let Err(e) = encrypt_segment(db, &output_path).await {
+                        error!("Failed to encrypt recording, discarded: {}", e);
+                        let _ = std::fs::remove_file(&output_path);
+                        continue;
+                    }
+                }
 
                 if let Some(ref db) = db {
                     let id = uuid::Uuid::new_v4().to_string();
                     let path_str = output_path.to_string_lossy().to_string();
+                    let samples_json = if watch.samples.is_empty() {
+                        None
+                    } else {
+                        serde_json::to_string(&watch.samples).ok()
+                    };
                     if let Err(e) = db.with_connection(|conn| {
                         conn.execute(
-                            "INSERT INTO recordings (id, path, start_time, end_time, duration_secs, fps, analyzed, created_at)
-                             VALUES (?1, ?2, ?3, ?4, ?5, 2, 0, ?3)",
-                            rusqlite::params![id, path_str, start_time, end_time, duration],
+                            "INSERT INTO recordings (id, path, start_time, end_time, duration_secs, fps, analyzed, created_at, window_samples)
+                             VALUES (?1, ?2, ?3, ?4, ?5, 2, 0, ?3, ?6)",
+                            rusqlite::params![id, path_str, start_time, end_time, duration, samples_json],
                         )?;
                         Ok(())
                     }) {
@@ -151,4 +221,119 @@ impl CaptureScheduler {
     pub async fn is_running(&self) -> bool {
         *self.is_running.lock().await
     }
+
+    /// 按设置启停录制：关闭记忆功能时停止；运行中分段时长变化时以新时长重启；未运行时启动
+    pub async fn reconfigure(&mut self, memory_enabled: bool, interval_seconds: u64) -> AppResult<()> {
+        let running = self.is_running().await;
+
+        if !memory_enabled {
+            if running {
+                self.stop().await?;
+                info!("Recorder stopped (memory disabled)");
+            }
+            return Ok(());
+        }
+
+        if running {
+            if self.interval_seconds == interval_seconds {
+                return Ok(());
+            }
+            self.stop().await?;
+        }
+        self.interval_seconds = interval_seconds;
+        self.start().await?;
+        info!("Recorder started (segment: {}s)", interval_seconds);
+        Ok(())
+    }
+
+    /// 订阅设置变更，随 `memory_enabled` / `capture_interval_seconds` 重新配置录制
+    pub fn watch_settings(
+        scheduler: Arc<Mutex<Self>>,
+        mut settings: tokio::sync::watch::Receiver<AppSettings>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while settings.changed().await.is_ok() {
+                let (enabled, interval) = {
+                    let settings = settings.borrow_and_update();
+                    (settings.memory_enabled, settings.capture_interval_seconds as u64)
+                };
+                if let Err(e) = scheduler.lock().await.reconfigure(enabled, interval).await {
+                    error!("Failed to reconfigure recorder: {}", e);
+                }
+            }
+        })
+    }
+
+    /// 当前使用的屏幕采集后端
+    pub fn capture_backend(&self) -> &'static str {
+        self.recorder.backend_name()
+    }
+}
+
+/// 静态加密开启时原地加密刚录完的分段
+async fn encrypt_segment(db: &Database, path: &std::path::Path) -> anyhow::Result<()> {
+    let vault = Arc::clone(db.vault());
+    if !vault.is_encrypting() {
+        return Ok(());
+    }
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let data = vault.encrypt_bytes(std::fs::read(&path)?)?;
+        let tmp = path.with_extension("vjtmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    })
+    .await?
+}
+
+/// 当前是否应暂停录制：先查手动暂停与时间段，再查前台窗口
+async fn current_exclusion(guard: &PrivacyGuard) -> Option<ExclusionReason> {
+    if let Some(reason) = guard.check_time(chrono::Local::now()) {
+        return Some(reason);
+    }
+    let window = tokio::task::spawn_blocking(active_window::current).await.ok().flatten()?;
+    guard.check_window(&window)
+}
+
+/// 分段录制期间的观察结果
+struct SegmentWatch {
+    samples: Vec<WindowSample>,
+    /// 命中排除规则时分段提前结束，应丢弃
+    excluded: Option<ExclusionReason>,
+}
+
+/// 等待 duration_secs 秒，期间每 WINDOW_SAMPLE_SECS 秒记录一次前台窗口；
+/// 命中排除规则（含中途手动暂停）时立即返回
+async fn watch_segment(duration_secs: u64, guard: Option<&PrivacyGuard>) -> SegmentWatch {
+    let started = tokio::time::Instant::now();
+    let deadline = started + tokio::time::Duration::from_secs(duration_secs);
+    let step = tokio::time::Duration::from_secs(WINDOW_SAMPLE_SECS);
+    let mut samples = Vec::new();
+
+    loop {
+        if let Some(reason) = guard.and_then(|g| g.check_time(chrono::Local::now())) {
+            return SegmentWatch { samples, excluded: Some(reason) };
+        }
+
+        if let Ok(Some(window)) = tokio::task::spawn_blocking(active_window::current).await {
+            if let Some(reason) = guard.and_then(|g| g.check_window(&window)) {
+                return SegmentWatch { samples, excluded: Some(reason) };
+            }
+            let offset = started.elapsed().as_secs() as u32;
+            active_window::record_sample(&mut samples, offset, window);
+        }
+
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            break;
+        }
+        let remaining = (deadline - now).min(step);
+        match guard {
+            Some(g) => g.wait(remaining).await,
+            None => tokio::time::sleep(remaining).await,
+        }
+    }
+
+    SegmentWatch { samples, excluded: None }
 }
@@ -1,35 +1,12 @@
 use crate::error::{AppError, AppResult};
 use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
+use std::process::Child;
 use std::sync::Mutex;
 use tokio::sync::Mutex as AsyncMutex;
 use chrono::{Local, Timelike};
 use log::{info, warn};
 use uuid::Uuid;
-
-fn find_screen_device_index() -> u32 {
-    let output = std::process::Command::new("ffmpeg")
-        .args(["-f", "avfoundation", "-list_devices", "true", "-i", ""])
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .output();
-
-    if let Ok(out) = output {
-        let stderr = String::from_utf8_lossy(&out.stderr);
-        for line in stderr.lines() {
-            if line.contains("Capture screen") {
-                if let Some(end) = line.rfind(']') {
-                    if let Some(start) = line[..end].rfind('[') {
-                        if let Ok(idx) = line[start + 1..end].parse::<u32>() {
-                            return idx;
-                        }
-                    }
-                }
-            }
-        }
-    }
-    1
-}
+use super::backend::{self, CaptureBackend};
 
 fn time_period(hour: u32) -> &'static str {
     match hour {
@@ -42,24 +19,35 @@ fn time_period(hour: u32) -> &'static str {
 pub struct ScreenRecorder {
     storage_path: PathBuf,
     fps: u8,
-    screen_device_index: u32,
+    backend: Box<dyn CaptureBackend>,
     /// std::sync::Mutex 使得 Drop 可以同步获取锁，确保 FFmpeg 进程被正确清理
     process: Mutex<Option<Child>>,
     current_path: AsyncMutex<Option<PathBuf>>,
 }
 
 impl ScreenRecorder {
-    pub fn new(storage_path: PathBuf, _segment_duration_secs: u64, fps: u8) -> AppResult<Self> {
+    /// 按当前运行环境自动选择采集后端
+    pub fn new(storage_path: PathBuf, segment_duration_secs: u64, fps: u8) -> AppResult<Self> {
+        let backend = backend::detect_backend()?;
+        Self::with_backend(storage_path, segment_duration_secs, fps, backend)
+    }
+
+    /// 使用指定的采集后端
+    pub fn with_backend(
+        storage_path: PathBuf,
+        _segment_duration_secs: u64,
+        fps: u8,
+        backend: Box<dyn CaptureBackend>,
+    ) -> AppResult<Self> {
         std::fs::create_dir_all(&storage_path)
             .map_err(|e| AppError::capture(1, format!("创建存储目录失败: {}", e)))?;
 
-        let idx = find_screen_device_index();
-        info!("Screen device index: {}", idx);
+        info!("Screen capture backend: {}", backend.name());
 
         Ok(Self {
             storage_path,
             fps,
-            screen_device_index: idx,
+            backend,
             process: Mutex::new(None),
             current_path: AsyncMutex::new(None),
         })
@@ -80,24 +68,10 @@ impl ScreenRecorder {
 
         let path = dir.join(format!("{}_{}.mp4", now.format("%H-%M-%S"), Uuid::new_v4()));
 
-        let child = Command::new("ffmpeg")
-            .args([
-                "-f", "avfoundation",
-                "-framerate", &self.fps.to_string(),
-                "-i", &format!("{}:none", self.screen_device_index),
-                "-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2",
-                "-c:v", "libx264",
-                "-preset", "ultrafast",
-                "-crf", "30",
-                "-pix_fmt", "yuv420p",
-                "-y",
-                path.to_str().unwrap(),
-            ])
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
+        let child = self.backend
+            .build_command(self.fps, &path)
             .spawn()
-            .map_err(|e| AppError::capture(3, format!("启动 FFmpeg 失败: {}", e)))?;
+            .map_err(|e| AppError::capture(3, format!("启动录制进程失败 ({}): {}", self.backend.name(), e)))?;
 
         *self.process.lock().unwrap() = Some(child);
         *self.current_path.lock().await = Some(path.clone());
@@ -108,19 +82,27 @@ impl ScreenRecorder {
     pub async fn stop(&self) {
         // 使用 spawn_blocking 在阻塞线程上获取同步锁并操作子进程
         let process = &self.process;
+        let backend = self.backend.as_ref();
         tokio::task::block_in_place(|| {
             let mut guard = process.lock().unwrap();
-            Self::kill_child(guard.as_mut());
+            Self::kill_child(backend, guard.as_mut());
             *guard = None;
         });
     }
 
-    /// 同步终止子进程（先 SIGTERM，超时后 SIGKILL）
-    fn kill_child(child: Option<&mut Child>) {
+    /// 当前使用的采集后端名称
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// 同步终止子进程（先发送后端的优雅停止信号，超时后 SIGKILL）
+    fn kill_child(backend: &dyn CaptureBackend, child: Option<&mut Child>) {
         let Some(child) = child else { return };
 
         #[cfg(unix)]
-        unsafe { libc::kill(child.id() as i32, libc::SIGTERM); }
+        unsafe { libc::kill(child.id() as i32, backend.stop_signal()); }
+        #[cfg(not(unix))]
+        let _ = backend;
         #[cfg(not(unix))]
         let _ = child.kill();
 
@@ -130,7 +112,7 @@ impl ScreenRecorder {
                 Ok(Some(_)) => break,
                 Ok(None) => {
                     if std::time::Instant::now() >= deadline {
-                        warn!("FFmpeg 未在 5s 内退出，强制终止");
+                        warn!("录制进程未在 5s 内退出，强制终止");
                         let _ = child.kill();
                         let _ = child.wait();
                         break;
@@ -138,7 +120,7 @@ impl ScreenRecorder {
                     std::thread::sleep(std::time::Duration::from_millis(100));
                 }
                 Err(e) => {
-                    warn!("FFmpeg try_wait error: {}", e);
+                    warn!("录制进程 try_wait error: {}", e);
                     let _ = child.kill();
                     let _ = child.wait();
                     break;
@@ -147,6 +129,11 @@ impl ScreenRecorder {
         }
     }
 
+    /// 当前分段已完整保存，之后 delete_current_file 不再删除它
+    pub async fn release_current_file(&self) {
+        self.current_path.lock().await.take();
+    }
+
     pub async fn delete_current_file(&self) {
         let path = self.current_path.lock().await.take();
         if let Some(p) = path {
@@ -157,10 +144,10 @@ impl ScreenRecorder {
 
 impl Drop for ScreenRecorder {
     fn drop(&mut self) {
-        // std::sync::Mutex::lock() 阻塞等待，确保 FFmpeg 进程被清理
+        // std::sync::Mutex::lock() 阻塞等待，确保录制进程被清理
         // 即使 stop() 并发持锁，drop 也能在其完成后获取锁
         if let Ok(mut guard) = self.process.lock() {
-            Self::kill_child(guard.as_mut());
+            Self::kill_child(self.backend.as_ref(), guard.as_mut());
         }
     }
 }
@@ -3,12 +3,13 @@
 /// 管理 AI 提供商配置（基于新的 provider 系统）
 
 use super::ApiResponse;
-use crate::ai::{AIProviderConfig, AIConfig, AIClient, ModelInfo, get_supported_models};
-use crate::db::Database;
+use crate::ai::{AIProviderConfig, AIConfig, AIClient, AttemptRecord, ModelInfo, get_supported_models};
+use crate::ai::config_state::activate_provider;
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
 use tauri::State;
-use log::info;
+
+pub use crate::ai::config_state::AIConfigState;
 
 /// AI 配置摘要（前端展示用）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,80 +24,38 @@ pub struct AIConfigSummary {
     pub provider_count: usize,
 }
 
-/// AI 配置状态
-pub struct AIConfigState {
-    config: Arc<Mutex<AIConfig>>,
-    db: Option<Arc<Database>>,
+/// 返回前端的提供商配置：API Key 只给掩码
+#[derive(Debug, Clone, Serialize)]
+pub struct AIProviderView {
+    #[serde(flatten)]
+    pub config: AIProviderConfig,
+    /// 掩码后的 API Key
+    pub api_key: String,
+    pub has_api_key: bool,
 }
 
-impl AIConfigState {
-    pub fn new(db: Arc<Database>) -> Self {
-        let config = load_from_db(&db).unwrap_or_default();
-        info!("AI config loaded: {} providers", config.providers.len());
-        Self {
-            config: Arc::new(Mutex::new(config)),
-            db: Some(db),
-        }
-    }
-
-    pub fn get(&self) -> AIConfig {
-        self.config.lock().unwrap().clone()
-    }
-
-    pub fn update(&self, new_config: AIConfig) -> Result<(), String> {
-        if let Some(ref db) = self.db {
-            save_to_db(db, &new_config).map_err(|e| format!("保存配置失败: {}", e))?;
-        }
-        let mut config = self.config.lock().unwrap();
-        *config = new_config;
-        Ok(())
-    }
-
-    /// 获取活跃提供商的配置（用于创建AIClient）
-    pub fn get_active_provider_config(&self) -> Option<crate::ai::AIProviderConfig> {
-        let config = self.config.lock().unwrap();
-        config.get_active_provider().cloned()
-    }
+/// 返回前端的完整 AI 配置
+#[derive(Debug, Clone, Serialize)]
+pub struct AIConfigView {
+    pub providers: Vec<AIProviderView>,
+    pub active_provider_id: Option<String>,
 }
 
-impl Default for AIConfigState {
-    fn default() -> Self {
+impl From<AIConfig> for AIConfigView {
+    fn from(config: AIConfig) -> Self {
         Self {
-            config: Arc::new(Mutex::new(AIConfig::new())),
-            db: None,
+            providers: config.providers.into_iter()
+                .map(|p| AIProviderView {
+                    api_key: p.masked_api_key(),
+                    has_api_key: p.has_api_key(),
+                    config: p,
+                })
+                .collect(),
+            active_provider_id: config.active_provider_id,
         }
     }
 }
 
-/// 从数据库加载 AI 配置
-fn load_from_db(db: &Database) -> Result<AIConfig, anyhow::Error> {
-    db.with_connection(|conn| {
-        let json: Option<String> = conn
-            .query_row(
-                "SELECT value FROM ai_config WHERE key = 'ai_config'",
-                [],
-                |row| row.get(0),
-            )
-            .ok();
-        match json {
-            Some(j) => Ok(serde_json::from_str(&j)?),
-            None => Ok(AIConfig::new()),
-        }
-    })
-}
-
-/// 保存 AI 配置到数据库
-fn save_to_db(db: &Database, config: &AIConfig) -> Result<(), anyhow::Error> {
-    let json = serde_json::to_string(config)?;
-    db.with_connection(|conn| {
-        conn.execute(
-            "INSERT OR REPLACE INTO ai_config (key, value, updated_at) VALUES ('ai_config', ?1, strftime('%s', 'now'))",
-            [&json],
-        )?;
-        Ok(())
-    })
-}
-
 /// 获取 AI 配置摘要
 #[tauri::command]
 pub async fn get_ai_config_summary(
@@ -115,13 +74,12 @@ pub async fn get_ai_config_summary(
     Ok(ApiResponse::success(summary))
 }
 
-/// 获取完整的 AI 配置
+/// 获取完整的 AI 配置（API Key 已掩码）
 #[tauri::command]
 pub async fn get_ai_config(
     state: State<'_, AIConfigState>,
-) -> Result<ApiResponse<AIConfig>, String> {
-    let config = state.get();
-    Ok(ApiResponse::success(config))
+) -> Result<ApiResponse<AIConfigView>, String> {
+    Ok(ApiResponse::success(AIConfigView::from(state.get())))
 }
 
 /// 更新 AI 提供商的 API 密钥
@@ -134,7 +92,7 @@ pub async fn update_ai_api_key(
     let mut config = state.get();
 
     if let Some(provider) = config.providers.iter_mut().find(|p| p.id == provider_id) {
-        provider.api_key = api_key;
+        provider.api_key = SecretString::new(api_key);
         match state.update(config) {
             Ok(_) => Ok(ApiResponse::success(true)),
             Err(e) => Ok(ApiResponse::error(format!("更新 API 密钥失败: {}", e))),
@@ -148,10 +106,17 @@ pub async fn update_ai_api_key(
 #[tauri::command]
 pub async fn update_ai_provider_config(
     state: State<'_, AIConfigState>,
-    provider_config: AIProviderConfig,
+    mut provider_config: AIProviderConfig,
 ) -> Result<ApiResponse<bool>, String> {
     let mut config = state.get();
 
+    // 前端拿到的是掩码，原样提交回来表示不修改 Key
+    if let Some(existing) = config.get_provider(&provider_config.id) {
+        if *provider_config.api_key.expose_secret() == existing.masked_api_key() {
+            provider_config.api_key = existing.api_key.clone();
+        }
+    }
+
     // 尝试更新，如果不存在则添加
     let result = if config.get_provider(&provider_config.id).is_some() {
         config.update_provider(provider_config)
@@ -175,27 +140,9 @@ pub async fn set_active_ai_provider(
     app_state: State<'_, super::AppState>,
     provider_id: String,
 ) -> Result<ApiResponse<bool>, String> {
-    let mut config = state.get();
-
-    match config.set_active_provider(&provider_id) {
-        Ok(_) => match state.update(config) {
-            Ok(_) => {
-                // 自动连接到管道
-                if let Some(provider) = state.get_active_provider_config() {
-                    match AIClient::new(provider) {
-                        Ok(client) => {
-                            app_state.pipeline.connect_ai(client).await;
-                        }
-                        Err(e) => {
-                            log::warn!("[AIConfig] 创建 AI 客户端失败，管道未连接: {}", e);
-                        }
-                    }
-                }
-                Ok(ApiResponse::success(true))
-            },
-            Err(e) => Ok(ApiResponse::error(format!("保存配置失败: {}", e))),
-        },
-        Err(e) => Ok(ApiResponse::error(format!("设��提供商失败: {}", e))),
+    match activate_provider(&state, &app_state.pipeline, &provider_id).await {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(e)),
     }
 }
 
@@ -272,16 +219,17 @@ pub async fn connect_ai_to_pipeline(
         None => return Ok(ApiResponse::error("没有活跃的AI提供商，请先配置AI".to_string())),
     };
 
-    let client = match AIClient::new(provider.clone()) {
+    let client = match AIClient::from_ai_config(&ai_state.get()) {
         Ok(c) => c,
         Err(e) => return Ok(ApiResponse::error(format!("创建AI客户端失败: {}", e))),
     };
+    let fallbacks = client.provider_configs().len() - 1;
 
     app_state.pipeline.connect_ai(client).await;
 
     Ok(ApiResponse::success(format!(
-        "AI已连接到管道 - 提供商: {}, 模型: {}",
-        provider.name, provider.model
+        "AI已连接到管道 - 提供商: {}, 模型: {}, 备用提供商: {}",
+        provider.name, provider.model, fallbacks
     )))
 }
 
@@ -305,32 +253,31 @@ pub struct PipelineStatus {
     pub ai_connected: bool,
 }
 
+/// 获取管道 AI 客户端最近的调用尝试（重试与故障转移记录）
+#[tauri::command]
+pub async fn get_ai_call_attempts(
+    app_state: State<'_, super::AppState>,
+    limit: Option<usize>,
+) -> Result<ApiResponse<Vec<AttemptRecord>>, String> {
+    match app_state.pipeline.ai_client().await {
+        Some(client) => Ok(ApiResponse::success(client.recent_attempts(limit.unwrap_or(50)))),
+        None => Ok(ApiResponse::error("AI未连接，请先配置AI".to_string())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_ai_config_state_creation() {
-        let state = AIConfigState::default();
-        let config = state.get();
-        assert!(config.providers.is_empty());
-        assert!(config.active_provider_id.is_none());
-    }
-
-    #[test]
-    fn test_ai_config_state_update() {
-        let state = AIConfigState::default();
-        let mut new_config = AIConfig::new();
-        let provider = AIProviderConfig::new(
-            "test",
-            "Test",
-            "https://api.test.com",
-            "test-key",
-            "test-model",
-        );
-        new_config.add_provider(provider).unwrap();
-
-        assert!(state.update(new_config).is_ok());
-        assert_eq!(state.get().providers.len(), 1);
+    fn test_ai_config_view_masks_api_keys() {
+        let mut config = AIConfig::new();
+        config.add_provider(AIProviderConfig::new(
+            "openai", "OpenAI", "https://api.openai.com", "sk-live-1234567890", "gpt",
+        )).unwrap();
+
+        let view = serde_json::to_value(AIConfigView::from(config)).unwrap();
+        assert_eq!(view["providers"][0]["api_key"], "sk-l••••7890");
+        assert_eq!(view["providers"][0]["has_api_key"], true);
     }
 }
@@ -0,0 +1,85 @@
+/// AI 流式输出 Commands
+///
+/// 生成过程中通过 Tauri 事件把增量文本推送给前端：
+/// - `ai-stream:token`  { stream_id, token }
+/// - `ai-stream:done`   { stream_id, text }
+/// - `ai-stream:error`  { stream_id, error }
+///
+/// stream_id 由前端生成并传入，用于在 invoke 返回前就能按 ID 过滤事件
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use super::{ApiResponse, AppState};
+use crate::ai::{AIPurpose, TokenStream};
+
+pub const STREAM_TOKEN_EVENT: &str = "ai-stream:token";
+pub const STREAM_DONE_EVENT: &str = "ai-stream:done";
+pub const STREAM_ERROR_EVENT: &str = "ai-stream:error";
+
+#[derive(Debug, Clone, Serialize)]
+struct TokenPayload<'a> {
+    stream_id: &'a str,
+    token: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DonePayload<'a> {
+    stream_id: &'a str,
+    text: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ErrorPayload<'a> {
+    stream_id: &'a str,
+    error: &'a str,
+}
+
+/// 逐段转发到前端，返回拼接后的完整文本
+pub async fn forward_stream(app: &AppHandle, stream_id: &str, mut stream: TokenStream) -> Result<String, String> {
+    let mut text = String::new();
+
+    while let Some(token) = stream.next().await {
+        match token {
+            Ok(token) => {
+                text.push_str(&token);
+                let _ = app.emit(STREAM_TOKEN_EVENT, TokenPayload { stream_id, token: &token });
+            }
+            Err(e) => {
+                let error = e.to_string();
+                let _ = app.emit(STREAM_ERROR_EVENT, ErrorPayload { stream_id, error: &error });
+                return Err(error);
+            }
+        }
+    }
+
+    let _ = app.emit(STREAM_DONE_EVENT, DonePayload { stream_id, text: &text });
+    Ok(text)
+}
+
+/// 流式发送提示词到当前 AI，完成后同样返回完整文本
+#[tauri::command]
+pub async fn stream_ai_text(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    prompt: String,
+    stream_id: String,
+) -> Result<ApiResponse<String>, String> {
+    let Some(client) = state.pipeline.ai_client().await else {
+        return Ok(ApiResponse::error("AI未连接，请先配置AI".to_string()));
+    };
+
+    let stream = match client.for_purpose(AIPurpose::Chat).send_text_stream(&prompt).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            let error = e.to_string();
+            let _ = app.emit(STREAM_ERROR_EVENT, ErrorPayload { stream_id: &stream_id, error: &error });
+            return Ok(ApiResponse::error(format!("AI调用失败: {}", error)));
+        }
+    };
+
+    match forward_stream(&app, &stream_id, stream).await {
+        Ok(text) => Ok(ApiResponse::success(text)),
+        Err(e) => Ok(ApiResponse::error(format!("AI调用失败: {}", e))),
+    }
+}
@@ -0,0 +1,36 @@
+/// AI 用量与预算 Commands
+///
+/// 汇总 ai_usage 表中的调用记录：按日/月看趋势，按用途/模型看花费构成
+
+use tauri::State;
+
+use super::{ApiResponse, AppState};
+use crate::ai::usage::{BudgetStatus, UsageAggregate, UsageGroup};
+
+/// 获取最近 days 天（默认 30 天）的用量汇总
+#[tauri::command]
+pub async fn get_ai_usage(
+    state: State<'_, AppState>,
+    group: UsageGroup,
+    days: Option<u32>,
+) -> Result<ApiResponse<Vec<UsageAggregate>>, String> {
+    let days = days.unwrap_or(30).max(1) as i64;
+    let end = chrono::Utc::now().timestamp() + 1;
+    let start = end - days * 86400;
+
+    match state.usage.aggregate(group, start, end) {
+        Ok(rows) => Ok(ApiResponse::success(rows)),
+        Err(e) => Ok(ApiResponse::error(format!("获取 AI 用量失败: {}", e))),
+    }
+}
+
+/// 获取今日/本月花费与预算状态
+#[tauri::command]
+pub async fn get_ai_budget_status(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<BudgetStatus>, String> {
+    match state.usage.budget_status() {
+        Ok(status) => Ok(ApiResponse::success(status)),
+        Err(e) => Ok(ApiResponse::error(format!("获取预算状态失败: {}", e))),
+    }
+}
@@ -0,0 +1,170 @@
+/// 多轮对话 Commands
+///
+/// 供 asker 窗口围绕历史活动进行追问：每轮用户消息都会检索记忆作为依据，
+/// 数据库只保存用户原始输入和 AI 回答
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use super::{ApiResponse, AppState};
+use crate::ai::conversation_store::{ConversationStore, ConversationSummary};
+use crate::ai::{AIPurpose, ChatMessage, Conversation};
+use crate::memory::memory_qa::{self, Citation, MemoryQaConfig};
+
+const DEFAULT_TITLE: &str = "新对话";
+
+const SYSTEM_PROMPT: &str = "你是 Vision-Jarvis 的记忆助手，帮助用户回顾自己过去在电脑上的活动。\
+回答要基于每轮提供的记忆资料，不要编造；用户追问时结合之前的对话理解指代。";
+
+/// 一轮对话的回复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatReply {
+    pub conversation_id: String,
+    pub message: ChatMessage,
+    pub citations: Vec<Citation>,
+}
+
+/// 解析前端传入的图片：data URL 或纯 base64（默认 JPEG）
+fn parse_image(image: &str) -> (String, String) {
+    if let Some(rest) = image.strip_prefix("data:") {
+        if let Some((media_type, data)) = rest.split_once(";base64,") {
+            return (media_type.to_string(), data.to_string());
+        }
+    }
+    ("image/jpeg".to_string(), image.to_string())
+}
+
+/// 新建会话
+#[tauri::command]
+pub async fn create_conversation(
+    state: State<'_, AppState>,
+    title: Option<String>,
+) -> Result<ApiResponse<Conversation>, String> {
+    let title = title
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TITLE.to_string());
+    let mut conversation = Conversation::new(title);
+    conversation.push(ChatMessage::system(SYSTEM_PROMPT));
+
+    match ConversationStore::new(state.db.clone()).create(&conversation) {
+        Ok(()) => Ok(ApiResponse::success(conversation)),
+        Err(e) => Ok(ApiResponse::error(format!("创建会话失败: {}", e))),
+    }
+}
+
+/// 会话列表（按最近更新排序）
+#[tauri::command]
+pub async fn list_conversations(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<ApiResponse<Vec<ConversationSummary>>, String> {
+    match ConversationStore::new(state.db.clone()).list(limit.unwrap_or(50)) {
+        Ok(list) => Ok(ApiResponse::success(list)),
+        Err(e) => Ok(ApiResponse::error(format!("获取会话列表失败: {}", e))),
+    }
+}
+
+/// 获取会话详情
+#[tauri::command]
+pub async fn get_conversation(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ApiResponse<Conversation>, String> {
+    match ConversationStore::new(state.db.clone()).get(&id) {
+        Ok(Some(conversation)) => Ok(ApiResponse::success(conversation)),
+        Ok(None) => Ok(ApiResponse::error(format!("会话不存在: {}", id))),
+        Err(e) => Ok(ApiResponse::error(format!("获取会话失败: {}", e))),
+    }
+}
+
+/// 删除会话
+#[tauri::command]
+pub async fn delete_conversation(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ApiResponse<bool>, String> {
+    match ConversationStore::new(state.db.clone()).delete(&id) {
+        Ok(deleted) => Ok(ApiResponse::success(deleted)),
+        Err(e) => Ok(ApiResponse::error(format!("删除会话失败: {}", e))),
+    }
+}
+
+/// 发送一轮消息：检索记忆 → 携带历史调用 AI → 保存本轮问答
+#[tauri::command]
+pub async fn send_chat_message(
+    state: State<'_, AppState>,
+    conversation_id: String,
+    text: String,
+    images: Option<Vec<String>>,
+) -> Result<ApiResponse<ChatReply>, String> {
+    let Some(client) = state.pipeline.ai_client().await else {
+        return Ok(ApiResponse::error("AI未连接，请先配置AI".to_string()));
+    };
+
+    let store = ConversationStore::new(state.db.clone());
+    let conversation = match store.get(&conversation_id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return Ok(ApiResponse::error(format!("会话不存在: {}", conversation_id))),
+        Err(e) => return Ok(ApiResponse::error(format!("获取会话失败: {}", e))),
+    };
+
+    let embedder = state.pipeline.index_manager().embedder().await;
+    let prepared = match memory_qa::prepare_question(
+        &state.db,
+        embedder.as_ref(),
+        &text,
+        &MemoryQaConfig::default(),
+    ).await {
+        Ok(prepared) => prepared,
+        Err(e) => return Ok(ApiResponse::error(format!("检索记忆失败: {}", e))),
+    };
+
+    let with_images = |mut message: ChatMessage| {
+        for image in images.iter().flatten() {
+            let (media_type, data) = parse_image(image);
+            message = message.with_image(media_type, data);
+        }
+        message
+    };
+    let user_message = with_images(ChatMessage::user(text.trim()));
+    let grounded_message = with_images(ChatMessage::user(prepared.prompt().unwrap_or(text.trim())));
+
+    let mut request = conversation.messages.clone();
+    request.push(grounded_message);
+
+    let answer = match client.for_purpose(AIPurpose::Chat).chat(&request).await {
+        Ok(answer) => answer,
+        Err(e) => return Ok(ApiResponse::error(format!("AI调用失败: {}", e))),
+    };
+
+    let citations = if prepared.prompt().is_some() {
+        prepared.into_answer(&answer).citations
+    } else {
+        Vec::new()
+    };
+    let assistant_message = ChatMessage::assistant(answer.trim());
+
+    if let Err(e) = store.append(&conversation_id, &[user_message, assistant_message.clone()]) {
+        return Ok(ApiResponse::error(format!("保存对话失败: {}", e)));
+    }
+
+    Ok(ApiResponse::success(ChatReply {
+        conversation_id,
+        message: assistant_message,
+        citations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_image() {
+        assert_eq!(
+            parse_image("data:image/png;base64,iVBOR"),
+            ("image/png".to_string(), "iVBOR".to_string())
+        );
+        assert_eq!(parse_image("/9j/4AAQ"), ("image/jpeg".to_string(), "/9j/4AAQ".to_string()));
+    }
+}
@@ -0,0 +1,136 @@
+/// 静态加密 Commands
+///
+/// 开启/关闭加密会转换全部已有数据，期间暂停屏幕录制，避免正在写入的分段被同时改写
+
+use std::sync::Arc;
+
+use log::error;
+use tauri::State;
+
+use super::{AIConfigState, ApiResponse, AppState};
+use crate::crypto::setup::{self, EncryptionStatus, MigrationStats};
+use crate::crypto::KeySource;
+use crate::error::AppResult;
+
+/// 获取加密状态
+#[tauri::command]
+pub async fn get_encryption_status(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<EncryptionStatus>, String> {
+    match setup::status(&state.db) {
+        Ok(status) => Ok(ApiResponse::success(status)),
+        Err(e) => Ok(ApiResponse::error(format!("获取加密状态失败: {}", e))),
+    }
+}
+
+/// 开启加密并加密已有数据；source 为 passphrase 时需要 passphrase
+#[tauri::command]
+pub async fn enable_encryption(
+    state: State<'_, AppState>,
+    source: KeySource,
+    passphrase: Option<String>,
+) -> Result<ApiResponse<MigrationStats>, String> {
+    let result = with_recording_paused(&state, move |db, root| {
+        setup::enable(&db, &root, source, passphrase.as_deref())
+    })
+    .await;
+
+    match result {
+        Ok(stats) => Ok(ApiResponse::success(stats)),
+        Err(e) => Ok(ApiResponse::error(format!("开启加密失败: {}", e))),
+    }
+}
+
+/// 解锁（钥匙串来源可不传口令），并继续未完成的迁移
+#[tauri::command]
+pub async fn unlock_encryption(
+    state: State<'_, AppState>,
+    ai_state: State<'_, AIConfigState>,
+    passphrase: Option<String>,
+) -> Result<ApiResponse<MigrationStats>, String> {
+    let result = with_recording_paused(&state, move |db, root| {
+        setup::unlock(&db, &root, passphrase.as_deref())
+    })
+    .await;
+
+    match result {
+        Ok(stats) => {
+            // 启动时未解锁，AI 配置读到的是默认值
+            if let Err(e) = ai_state.reload() {
+                error!("Failed to reload AI config after unlock: {}", e);
+            }
+            Ok(ApiResponse::success(stats))
+        }
+        Err(e) => Ok(ApiResponse::error(format!("解锁失败: {}", e))),
+    }
+}
+
+/// 锁定：丢弃内存中的密钥，录制与分析暂停到再次解锁
+#[tauri::command]
+pub async fn lock_encryption(state: State<'_, AppState>) -> Result<ApiResponse<bool>, String> {
+    setup::lock(&state.db);
+    Ok(ApiResponse::success(true))
+}
+
+/// 修改口令
+#[tauri::command]
+pub async fn change_encryption_passphrase(
+    state: State<'_, AppState>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<ApiResponse<bool>, String> {
+    let db = Arc::clone(&state.db);
+    let result = tokio::task::spawn_blocking(move || {
+        setup::change_passphrase(&db, &old_passphrase, &new_passphrase)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => Ok(ApiResponse::success(true)),
+        Ok(Err(e)) => Ok(ApiResponse::error(format!("修改口令失败: {}", e))),
+        Err(e) => Ok(ApiResponse::error(format!("修改口令失败: {}", e))),
+    }
+}
+
+/// 关闭加密并解密全部数据（需已解锁）
+#[tauri::command]
+pub async fn disable_encryption(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<MigrationStats>, String> {
+    match with_recording_paused(&state, |db, root| setup::disable(&db, &root)).await {
+        Ok(stats) => Ok(ApiResponse::success(stats)),
+        Err(e) => Ok(ApiResponse::error(format!("关闭加密失败: {}", e))),
+    }
+}
+
+/// 停止录制后在阻塞线程执行迁移，结束后恢复录制
+async fn with_recording_paused<F>(state: &AppState, f: F) -> Result<MigrationStats, String>
+where
+    F: FnOnce(Arc<crate::db::Database>, std::path::PathBuf) -> AppResult<MigrationStats>
+        + Send
+        + 'static,
+{
+    let mut scheduler = state.scheduler.lock().await;
+    let was_running = scheduler.is_running().await;
+    if was_running {
+        if let Err(e) = scheduler.stop().await {
+            return Err(format!("停止录制失败: {}", e));
+        }
+    }
+
+    let db = Arc::clone(&state.db);
+    let root = state.settings.get_storage_path();
+    let result = tokio::task::spawn_blocking(move || f(db, root)).await;
+
+    if was_running {
+        if let Err(e) = scheduler.start().await {
+            error!("Failed to restart recording after encryption change: {}", e);
+        }
+    }
+
+    match result {
+        Ok(Ok(stats)) => Ok(stats),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
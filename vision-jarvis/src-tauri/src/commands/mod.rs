@@ -4,13 +4,6 @@
 
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
-use std::sync::Arc;
-use crate::db::Database;
-use crate::settings::SettingsManager;
-use crate::capture::screen_recorder::ScreenRecorder;
-use crate::capture::scheduler::CaptureScheduler;
-use crate::notification::scheduler::NotificationScheduler;
-use crate::memory::pipeline::PipelineScheduler;
 use crate::error::AppError;
 
 pub mod recording;
@@ -19,56 +12,15 @@ pub mod notification;
 pub mod settings;
 pub mod storage;
 pub mod ai_config;
+pub mod ai_stream;
+pub mod ai_usage;
+pub mod conversation;
+pub mod encryption;
+pub mod privacy;
 pub mod window;
 
 pub use ai_config::AIConfigState;
-
-/// 应用状态
-pub struct AppState {
-    pub db: Arc<Database>,
-    pub settings: Arc<SettingsManager>,
-    pub scheduler: Arc<tokio::sync::Mutex<CaptureScheduler>>,
-    pub notification_scheduler: Arc<NotificationScheduler>,
-    pub pipeline: Arc<PipelineScheduler>,
-}
-
-impl AppState {
-    pub fn new(db: Database, settings: SettingsManager) -> Self {
-        let db = Arc::new(db);
-        let settings = Arc::new(settings);
-        let storage_path = settings.get_storage_path();
-        let interval = settings.get_capture_interval() as u64;
-
-        let recorder = ScreenRecorder::new(storage_path.clone(), interval, 2)
-            .expect("Failed to create ScreenRecorder");
-
-        let (analysis_tx, analysis_rx) = tokio::sync::mpsc::channel::<(String, std::path::PathBuf)>(8);
-
-        let scheduler = CaptureScheduler::new(recorder, interval)
-            .with_db(Arc::clone(&db))
-            .with_analysis_sender(analysis_tx);
-
-        let notification_scheduler = NotificationScheduler::new(
-            Arc::clone(&db),
-            Arc::clone(&settings),
-        );
-
-        let pipeline = PipelineScheduler::new(
-            Arc::clone(&db),
-            storage_path,
-            false,
-        ).expect("Failed to create PipelineScheduler")
-            .with_analysis_receiver(analysis_rx);
-
-        Self {
-            db,
-            settings,
-            scheduler: Arc::new(tokio::sync::Mutex::new(scheduler)),
-            notification_scheduler: Arc::new(notification_scheduler),
-            pipeline: Arc::new(pipeline),
-        }
-    }
-}
+pub use crate::state::AppState;
 
 /// 通用响应结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
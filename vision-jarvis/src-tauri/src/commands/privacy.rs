@@ -0,0 +1,41 @@
+/// 隐私脱敏 Commands
+///
+/// 查看脱敏审计日志（只含规则名与计数），以及用当前规则预览一段文本的清洗结果
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::State;
+
+use super::{ApiResponse, AppState};
+use crate::privacy::redaction::RedactionLogEntry;
+
+/// 文本清洗预览
+#[derive(Debug, Serialize)]
+pub struct RedactionPreview {
+    pub text: String,
+    /// 规则名 -> 命中次数
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// 获取最近的脱敏审计记录（默认 100 条）
+#[tauri::command]
+pub async fn get_redaction_log(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<ApiResponse<Vec<RedactionLogEntry>>, String> {
+    match state.redactor.audit_log(limit.unwrap_or(100).min(1000)) {
+        Ok(entries) => Ok(ApiResponse::success(entries)),
+        Err(e) => Ok(ApiResponse::error(format!("获取脱敏日志失败: {}", e))),
+    }
+}
+
+/// 用当前脱敏规则清洗一段文本，便于调试自定义正则
+#[tauri::command]
+pub async fn preview_redaction(
+    state: State<'_, AppState>,
+    text: String,
+) -> Result<ApiResponse<RedactionPreview>, String> {
+    let (text, counts) = state.redactor.scrub_text(&text);
+    Ok(ApiResponse::success(RedactionPreview { text, counts }))
+}
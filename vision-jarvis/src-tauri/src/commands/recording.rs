@@ -3,6 +3,7 @@
 use tauri::State;
 use serde::{Deserialize, Serialize};
 use super::{ApiResponse, AppState};
+use crate::privacy::rules::ExclusionReason;
 
 /// 调度器状态信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,12 @@ pub struct SchedulerStatus {
     pub interval_seconds: u64,
     pub memory_enabled: bool,
     pub storage_path: String,
+    /// 屏幕采集后端（avfoundation / x11grab / kmsgrab / pipewire）
+    pub capture_backend: String,
+    /// 手动隐私暂停的恢复时间戳，未暂停时为 None
+    pub privacy_paused_until: Option<i64>,
+    /// 录制当前因隐私规则暂停的原因
+    pub privacy_exclusion: Option<ExclusionReason>,
 }
 
 /// 获取调度器状态
@@ -19,13 +26,45 @@ pub async fn get_scheduler_status(state: State<'_, AppState>) -> Result<ApiRespo
     let scheduler = state.scheduler.lock().await;
     let is_running = scheduler.is_running().await;
     let interval = scheduler.interval_seconds;
+    let capture_backend = scheduler.capture_backend().to_string();
     let memory_enabled = state.settings.is_memory_enabled();
     let storage_path = state.settings.get_storage_path().to_string_lossy().to_string();
 
+    let now = chrono::Local::now().timestamp();
+    let privacy_paused_until = match state.privacy_guard.pause_state(now) {
+        Ok(pause) => pause.map(|p| p.paused_until),
+        Err(e) => return Ok(ApiResponse::error(format!("读取隐私暂停状态失败: {}", e))),
+    };
+    let privacy_exclusion = if is_running { state.privacy_guard.active() } else { None };
+
     Ok(ApiResponse::success(SchedulerStatus {
         is_running,
         interval_seconds: interval,
         memory_enabled,
         storage_path,
+        capture_backend,
+        privacy_paused_until,
+        privacy_exclusion,
     }))
 }
+
+/// 暂停录制 minutes 分钟（隐私暂停，重启后仍然有效），返回恢复时间戳
+#[tauri::command]
+pub async fn pause_recording_for(
+    state: State<'_, AppState>,
+    minutes: u32,
+) -> Result<ApiResponse<i64>, String> {
+    match state.privacy_guard.pause_for(minutes) {
+        Ok(until) => Ok(ApiResponse::success(until)),
+        Err(e) => Ok(ApiResponse::error(format!("暂停录制失败: {}", e))),
+    }
+}
+
+/// 提前结束隐私暂停
+#[tauri::command]
+pub async fn resume_recording(state: State<'_, AppState>) -> Result<ApiResponse<bool>, String> {
+    match state.privacy_guard.resume() {
+        Ok(()) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("恢复录制失败: {}", e))),
+    }
+}
@@ -1,9 +1,9 @@
 /// 设置相关 Commands
 
 use tauri::State;
-use log::{info, error};
-use super::{ApiResponse, AppState};
+use super::{AIConfigState, ApiResponse, AppState};
 use crate::settings::AppSettings;
+use crate::settings::profiles::{ProfileState, SettingsProfile};
 
 /// 获取设置
 #[tauri::command]
@@ -16,9 +16,8 @@ pub async fn get_settings(
 
 /// 更新设置
 ///
-/// 自动联动调度器：
-/// - memory_enabled 变化时启动/停止调度器
-/// - capture_interval_seconds 变化时重启调度器
+/// 运行中的子系统订阅了设置变更（见 `AppState::watch_settings`），
+/// 录制启停、分段时长、记忆管道、Idle 检测与各共享配置随之更新
 #[tauri::command]
 pub async fn update_settings(
     state: State<'_, AppState>,
@@ -27,50 +26,10 @@ pub async fn update_settings(
     // 强制 clamp 录制分段时长到 30-300 秒
     settings.capture_interval_seconds = settings.capture_interval_seconds.clamp(30, 300);
 
-    let old_settings = (*state.settings).get();
-
-    let result = (*state.settings).update(settings.clone());
-
-    if let Err(e) = result {
-        return Ok(ApiResponse::error(format!("更新设置失败: {}", e)));
-    }
-
-    // 联动调度器
-    let memory_changed = old_settings.memory_enabled != settings.memory_enabled;
-    let interval_changed = old_settings.capture_interval_seconds != settings.capture_interval_seconds;
-
-    if memory_changed || interval_changed {
-        let mut scheduler = state.scheduler.lock().await;
-
-        if memory_changed {
-            if settings.memory_enabled {
-                if !scheduler.is_running().await {
-                    scheduler.interval_seconds = settings.capture_interval_seconds as u64;
-                    if let Err(e) = scheduler.start().await {
-                        error!("Failed to start scheduler: {}", e);
-                    } else {
-                        info!("Scheduler started (segment: {}s)", settings.capture_interval_seconds);
-                    }
-                }
-            } else {
-                if scheduler.is_running().await {
-                    if let Err(e) = scheduler.stop().await {
-                        error!("Failed to stop scheduler: {}", e);
-                    } else {
-                        info!("Scheduler stopped");
-                    }
-                }
-            }
-        } else if interval_changed && settings.memory_enabled {
-            let was_running = scheduler.is_running().await;
-            if was_running { let _ = scheduler.stop().await; }
-            scheduler.interval_seconds = settings.capture_interval_seconds as u64;
-            if was_running { let _ = scheduler.start().await; }
-            info!("Segment duration updated: {}s", settings.capture_interval_seconds);
-        }
+    match (*state.settings).update(settings) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("更新设置失败: {}", e))),
     }
-
-    Ok(ApiResponse::success(true))
 }
 
 /// 重置设置为默认值
@@ -86,3 +45,79 @@ pub async fn reset_settings(
         Err(e) => Ok(ApiResponse::error(format!("重置设置失败: {}", e))),
     }
 }
+
+/// 获取配置方案列表与当前状态
+#[tauri::command]
+pub async fn get_settings_profiles(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<ProfileState>, String> {
+    Ok(ApiResponse::success(state.profiles.state()))
+}
+
+/// 新增或修改配置方案
+#[tauri::command]
+pub async fn save_settings_profile(
+    state: State<'_, AppState>,
+    ai_state: State<'_, AIConfigState>,
+    profile: SettingsProfile,
+) -> Result<ApiResponse<bool>, String> {
+    if let Some(provider_id) = &profile.ai_provider_id {
+        if ai_state.get().get_provider(provider_id).is_none() {
+            return Ok(ApiResponse::error(format!("保存配置方案失败: AI 提供商不存在: {}", provider_id)));
+        }
+    }
+
+    match state.profiles.save_profile(profile) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("保存配置方案失败: {}", e))),
+    }
+}
+
+/// 删除配置方案
+#[tauri::command]
+pub async fn delete_settings_profile(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ApiResponse<bool>, String> {
+    match state.profiles.delete_profile(&id) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("删除配置方案失败: {}", e))),
+    }
+}
+
+/// 手动切换配置方案，返回切换后的设置
+///
+/// 方案的 AI 提供商由 runtime.rs 中订阅方案切换的任务切换
+#[tauri::command]
+pub async fn activate_settings_profile(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ApiResponse<AppSettings>, String> {
+    match state.profiles.activate(&id) {
+        Ok(_) => Ok(ApiResponse::success(state.settings.get())),
+        Err(e) => Ok(ApiResponse::error(format!("切换配置方案失败: {}", e))),
+    }
+}
+
+/// 取消当前配置方案并恢复基础设置，返回恢复后的设置
+#[tauri::command]
+pub async fn deactivate_settings_profile(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<AppSettings>, String> {
+    match state.profiles.deactivate() {
+        Ok(_) => Ok(ApiResponse::success(state.settings.get())),
+        Err(e) => Ok(ApiResponse::error(format!("取消配置方案失败: {}", e))),
+    }
+}
+
+/// 开启或关闭按时间段 / 前台应用自动切换配置方案
+#[tauri::command]
+pub async fn set_profile_auto_switch(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<ApiResponse<bool>, String> {
+    match state.profiles.set_auto_switch(enabled) {
+        Ok(_) => Ok(ApiResponse::success(true)),
+        Err(e) => Ok(ApiResponse::error(format!("设置自动切换失败: {}", e))),
+    }
+}
@@ -3,6 +3,9 @@
 use tauri::State;
 use super::{ApiResponse, AppState};
 use crate::storage::{FolderType, StorageInfo, FileInfo};
+use crate::storage::archive::{self, ArchiveReport};
+use crate::storage::export::{self, ConflictPolicy, ExportManifest, ImportReport};
+use crate::storage::retention::RetentionReport;
 
 /// 获取存储信息
 #[tauri::command]
@@ -65,6 +68,127 @@ pub async fn cleanup_old_files(
     }
 }
 
+/// 预览保留策略清理（dry run）：列出将被删除的文件，不做任何修改
+#[tauri::command]
+pub async fn preview_retention(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<RetentionReport>, String> {
+    match state.retention.run(true) {
+        Ok(report) => Ok(ApiResponse::success(report)),
+        Err(e) => Ok(ApiResponse::error(format!("预览清理失败: {}", e))),
+    }
+}
+
+/// 立即按保留策略清理
+#[tauri::command]
+pub async fn run_retention(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<RetentionReport>, String> {
+    match state.retention.run(false) {
+        Ok(report) => {
+            log::info!("保留策略清理了 {} 个文件", report.items.len() - report.failed);
+            Ok(ApiResponse::success(report))
+        }
+        Err(e) => Ok(ApiResponse::error(format!("清理失败: {}", e))),
+    }
+}
+
+/// 立即把到期的录制归档为关键帧
+#[tauri::command]
+pub async fn run_keyframe_archive(
+    state: State<'_, AppState>,
+) -> Result<ApiResponse<ArchiveReport>, String> {
+    let archiver = state.archiver.clone();
+    match tokio::task::spawn_blocking(move || archiver.run()).await {
+        Ok(Ok(report)) => Ok(ApiResponse::success(report)),
+        Ok(Err(e)) => Ok(ApiResponse::error(format!("归档失败: {}", e))),
+        Err(e) => Ok(ApiResponse::error(format!("归档失败: {}", e))),
+    }
+}
+
+/// 获取已归档录制的关键帧（JPEG data URL）
+#[tauri::command]
+pub async fn get_recording_keyframes(
+    state: State<'_, AppState>,
+    recording_id: String,
+) -> Result<ApiResponse<Vec<String>>, String> {
+    use base64::Engine;
+
+    match archive::load_keyframes(&state.db, &recording_id) {
+        Ok(frames) => Ok(ApiResponse::success(
+            frames
+                .iter()
+                .map(|data| {
+                    format!(
+                        "data:image/jpeg;base64,{}",
+                        base64::engine::general_purpose::STANDARD.encode(data)
+                    )
+                })
+                .collect(),
+        )),
+        Err(e) => Ok(ApiResponse::error(format!("读取关键帧失败: {}", e))),
+    }
+}
+
+/// 导出记忆库（数据库记录与 Markdown 树）到单个 zip 文件
+#[tauri::command]
+pub async fn export_vault(
+    state: State<'_, AppState>,
+    dest_path: String,
+) -> Result<ApiResponse<ExportManifest>, String> {
+    let db = state.db.clone();
+    let root = state.settings.get_storage_path();
+    let result = tokio::task::spawn_blocking(move || {
+        export::export_vault(&db, &root, std::path::Path::new(&dest_path))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(manifest)) => Ok(ApiResponse::success(manifest)),
+        Ok(Err(e)) => Ok(ApiResponse::error(format!("导出失败: {}", e))),
+        Err(e) => Ok(ApiResponse::error(format!("导出失败: {}", e))),
+    }
+}
+
+/// 读取导出包清单（导入前确认来源与版本）
+#[tauri::command]
+pub async fn preview_vault_import(
+    src_path: String,
+) -> Result<ApiResponse<ExportManifest>, String> {
+    match export::read_manifest(std::path::Path::new(&src_path)) {
+        Ok(manifest) => Ok(ApiResponse::success(manifest)),
+        Err(e) => Ok(ApiResponse::error(format!("读取导出包失败: {}", e))),
+    }
+}
+
+/// 导入记忆库并按 id 合并；on_conflict 默认保留本地数据
+#[tauri::command]
+pub async fn import_vault(
+    state: State<'_, AppState>,
+    src_path: String,
+    on_conflict: Option<ConflictPolicy>,
+) -> Result<ApiResponse<ImportReport>, String> {
+    let db = state.db.clone();
+    let root = state.settings.get_storage_path();
+    let policy = on_conflict.unwrap_or_default();
+    let result = tokio::task::spawn_blocking(move || {
+        export::import_vault(&db, &root, std::path::Path::new(&src_path), policy)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(report)) => {
+            // 导入的 Markdown 需要进入检索索引
+            if let Err(e) = state.pipeline.index_manager().sync().await {
+                log::warn!("导入后同步索引失败: {}", e);
+            }
+            Ok(ApiResponse::success(report))
+        }
+        Ok(Err(e)) => Ok(ApiResponse::error(format!("导入失败: {}", e))),
+        Err(e) => Ok(ApiResponse::error(format!("导入失败: {}", e))),
+    }
+}
+
 /// 删除单个文件
 #[tauri::command]
 pub async fn delete_file(
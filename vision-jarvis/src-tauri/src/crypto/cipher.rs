@@ -0,0 +1,137 @@
+/// 对称加密原语
+///
+/// XChaCha20-Poly1305（24 字节随机 nonce，可安全地对大量数据使用随机 nonce）。
+/// 二进制格式：`VJENC01\0` + nonce(24) + 密文与认证标签；
+/// 文本格式：`vjenc1:` + base64(二进制格式)，便于存入 TEXT 列
+
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// 数据密钥长度（字节）
+pub const KEY_LEN: usize = 32;
+
+const MAGIC: &[u8; 8] = b"VJENC01\0";
+const NONCE_LEN: usize = 24;
+const TEXT_PREFIX: &str = "vjenc1:";
+
+/// 数据密钥，释放时清零
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DataKey([u8; KEY_LEN]);
+
+impl DataKey {
+    /// 随机生成
+    pub fn generate() -> Self {
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        Self(key.into())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        <[u8; KEY_LEN]>::try_from(bytes).ok().map(Self)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new((&self.0).into())
+    }
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DataKey(..)")
+    }
+}
+
+/// 是否为本模块加密的二进制数据
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 是否为本模块加密的文本
+pub fn is_sealed_text(text: &str) -> bool {
+    text.starts_with(TEXT_PREFIX)
+}
+
+/// 加密
+pub fn seal(key: &DataKey, plaintext: &[u8]) -> Vec<u8> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = key.cipher()
+        .encrypt(&nonce, plaintext)
+        .expect("XChaCha20-Poly1305 加密不会失败");
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// 解密；密钥错误或数据被篡改时返回错误
+pub fn open(key: &DataKey, data: &[u8]) -> Result<Vec<u8>> {
+    let body = data.strip_prefix(MAGIC.as_slice())
+        .ok_or_else(|| anyhow!("不是加密数据"))?;
+    if body.len() < NONCE_LEN {
+        return Err(anyhow!("加密数据已截断"));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    key.cipher()
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("解密失败：密钥错误或数据已损坏"))
+}
+
+/// 加密文本
+pub fn seal_text(key: &DataKey, plaintext: &str) -> String {
+    format!("{}{}", TEXT_PREFIX, BASE64.encode(seal(key, plaintext.as_bytes())))
+}
+
+/// 解密文本
+pub fn open_text(key: &DataKey, text: &str) -> Result<String> {
+    let encoded = text.strip_prefix(TEXT_PREFIX)
+        .ok_or_else(|| anyhow!("不是加密文本"))?;
+    let data = BASE64.decode(encoded)?;
+    Ok(String::from_utf8(open(key, &data)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let key = DataKey::generate();
+        let sealed = seal(&key, b"screen recording");
+        assert!(is_sealed(&sealed));
+        assert_ne!(&sealed[MAGIC.len() + NONCE_LEN..], b"screen recording");
+        assert_eq!(open(&key, &sealed).unwrap(), b"screen recording");
+
+        // 同一明文两次加密结果不同（随机 nonce）
+        assert_ne!(seal(&key, b"x"), seal(&key, b"x"));
+    }
+
+    #[test]
+    fn test_open_rejects_wrong_key_and_tampering() {
+        let key = DataKey::generate();
+        let mut sealed = seal(&key, b"secret");
+        assert!(open(&DataKey::generate(), &sealed).is_err());
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(open(&key, &sealed).is_err());
+        assert!(open(&key, b"plain").is_err());
+    }
+
+    #[test]
+    fn test_text_roundtrip() {
+        let key = DataKey::generate();
+        let sealed = seal_text(&key, "OCR: 卡号 6222");
+        assert!(is_sealed_text(&sealed));
+        assert!(!sealed.contains("6222"));
+        assert_eq!(open_text(&key, &sealed).unwrap(), "OCR: 卡号 6222");
+    }
+}
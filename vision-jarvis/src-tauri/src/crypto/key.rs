@@ -0,0 +1,148 @@
+/// 数据密钥的来源
+///
+/// - 口令：Argon2id 从用户口令派生密钥加密密钥（KEK），用它包裹随机数据密钥；
+///   改口令只需重新包裹，不必重加密全部数据
+/// - 系统钥匙串：数据密钥直接存入 macOS Keychain / Windows 凭据管理器 / Secret Service，
+///   启动时自动解锁
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{OsRng, rand_core::RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::cipher::{DataKey, KEY_LEN};
+use crate::error::{AppError, AppResult};
+
+/// 口令最短长度
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+/// 盐长度
+pub const SALT_LEN: usize = 16;
+
+const KEYRING_SERVICE: &str = "vision-jarvis";
+const KEYRING_DATA_KEY: &str = "data-key";
+
+/// 密钥来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    Passphrase,
+    Keyring,
+}
+
+impl KeySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Passphrase => "passphrase",
+            Self::Keyring => "keyring",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "passphrase" => Some(Self::Passphrase),
+            "keyring" => Some(Self::Keyring),
+            _ => None,
+        }
+    }
+}
+
+/// Argon2id 参数（随元数据保存，日后调高默认值不影响已有数据）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// 内存开销（KiB）
+    pub m_cost: u32,
+    /// 迭代次数
+    pub t_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        // 单元测试用低成本参数，避免 debug 构建下每次派生耗时数秒
+        if cfg!(test) {
+            Self { m_cost: 1024, t_cost: 1 }
+        } else {
+            Self { m_cost: 19 * 1024, t_cost: 2 }
+        }
+    }
+}
+
+/// 随机盐
+pub fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// 检查口令强度
+pub fn validate_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::validation(26, format!("口令至少 {} 个字符", MIN_PASSPHRASE_LEN)));
+    }
+    Ok(())
+}
+
+/// 从口令派生密钥加密密钥
+pub fn derive_kek(passphrase: &str, salt: &[u8], params: KdfParams) -> AppResult<DataKey> {
+    let params = Params::new(params.m_cost, params.t_cost, 1, Some(KEY_LEN))
+        .map_err(|e| AppError::storage(13, format!("密钥派生参数无效: {}", e)))?;
+    let mut out = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut out)
+        .map_err(|e| AppError::storage(13, format!("密钥派生失败: {}", e)))?;
+    let key = DataKey::from_bytes(&out).expect("长度固定为 KEY_LEN");
+    zeroize::Zeroize::zeroize(&mut out);
+    Ok(key)
+}
+
+pub(super) fn keyring_entry(user: &str) -> AppResult<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, user)
+        .map_err(|e| AppError::storage(12, format!("系统钥匙串不可用: {}", e)))
+}
+
+/// 把数据密钥存入系统钥匙串
+pub fn keyring_store(key: &DataKey) -> AppResult<()> {
+    keyring_entry(KEYRING_DATA_KEY)?
+        .set_secret(key.as_bytes())
+        .map_err(|e| AppError::storage(12, format!("写入系统钥匙串失败: {}", e)))
+}
+
+/// 从系统钥匙串读取数据密钥
+pub fn keyring_load() -> AppResult<DataKey> {
+    let secret = keyring_entry(KEYRING_DATA_KEY)?
+        .get_secret()
+        .map_err(|e| AppError::storage(12, format!("读取系统钥匙串失败: {}", e)))?;
+    DataKey::from_bytes(&secret)
+        .ok_or_else(|| AppError::storage(12, "系统钥匙串中的密钥长度不正确"))
+}
+
+/// 删除系统钥匙串中的数据密钥（不存在时视为成功）
+pub fn keyring_delete() -> AppResult<()> {
+    match keyring_entry(KEYRING_DATA_KEY)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::storage(12, format!("删除系统钥匙串条目失败: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cipher;
+
+    #[test]
+    fn test_derive_kek_is_deterministic_per_salt() {
+        let salt = random_salt();
+        let params = KdfParams::default();
+        let a = derive_kek("correct horse", &salt, params).unwrap();
+        let b = derive_kek("correct horse", &salt, params).unwrap();
+        let other = derive_kek("correct horse", &random_salt(), params).unwrap();
+
+        let sealed = cipher::seal(&a, b"data key");
+        assert_eq!(cipher::open(&b, &sealed).unwrap(), b"data key");
+        assert!(cipher::open(&other, &sealed).is_err());
+    }
+
+    #[test]
+    fn test_validate_passphrase() {
+        assert!(validate_passphrase("short").is_err());
+        assert!(validate_passphrase("长一点的中文口令").is_ok());
+    }
+}
@@ -0,0 +1,172 @@
+/// 静态加密
+///
+/// 开启后录制分段、分析 JSON、Markdown 文件与数据库敏感列以密文落盘。
+/// `Vault` 挂在 `Database` 上随之共享，读写这些数据的组件通过 `db.vault()` 加解密。
+/// 未开启时所有操作原样透传；解密时遇到明文也直接返回，因此迁移进行中新旧数据可以混存
+
+pub mod cipher;
+pub mod key;
+pub mod secrets;
+pub mod setup;
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::RwLock;
+
+use cipher::DataKey;
+use crate::error::AppError;
+
+pub use key::KeySource;
+
+#[derive(Default)]
+struct VaultState {
+    /// 新写入的数据是否加密
+    encrypt_writes: bool,
+    /// 已解锁的数据密钥
+    key: Option<DataKey>,
+}
+
+/// 加解密入口：持有当前数据密钥与加密开关
+#[derive(Default)]
+pub struct Vault {
+    state: RwLock<VaultState>,
+}
+
+impl Vault {
+    /// 新写入的数据是否加密
+    pub fn is_encrypting(&self) -> bool {
+        self.state.read().unwrap().encrypt_writes
+    }
+
+    /// 已开启加密但尚未解锁：此时不能写入敏感数据
+    pub fn is_locked(&self) -> bool {
+        let state = self.state.read().unwrap();
+        state.encrypt_writes && state.key.is_none()
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.state.read().unwrap().key.is_some()
+    }
+
+    pub(crate) fn set(&self, encrypt_writes: bool, key: Option<DataKey>) {
+        *self.state.write().unwrap() = VaultState { encrypt_writes, key };
+    }
+
+    pub(crate) fn key(&self) -> Option<DataKey> {
+        self.state.read().unwrap().key.clone()
+    }
+
+    /// 写入用的密钥：未开启加密时为 None，开启但未解锁时报错
+    fn write_key(&self) -> Result<Option<DataKey>> {
+        let state = self.state.read().unwrap();
+        if !state.encrypt_writes {
+            return Ok(None);
+        }
+        state.key.clone().map(Some).ok_or_else(locked_error)
+    }
+
+    fn read_key(&self) -> Result<DataKey> {
+        self.key().ok_or_else(locked_error)
+    }
+
+    pub fn encrypt_bytes(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self.write_key()? {
+            Some(key) if !cipher::is_sealed(&data) => Ok(cipher::seal(&key, &data)),
+            _ => Ok(data),
+        }
+    }
+
+    pub fn decrypt_bytes(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if !cipher::is_sealed(&data) {
+            return Ok(data);
+        }
+        cipher::open(&self.read_key()?, &data)
+    }
+
+    pub fn encrypt_text(&self, text: &str) -> Result<String> {
+        match self.write_key()? {
+            Some(key) if !cipher::is_sealed_text(text) => Ok(cipher::seal_text(&key, text)),
+            _ => Ok(text.to_string()),
+        }
+    }
+
+    pub fn decrypt_text(&self, text: String) -> Result<String> {
+        if !cipher::is_sealed_text(&text) {
+            return Ok(text);
+        }
+        cipher::open_text(&self.read_key()?, &text)
+    }
+
+    pub fn encrypt_opt_text(&self, text: Option<&str>) -> Result<Option<String>> {
+        text.map(|t| self.encrypt_text(t)).transpose()
+    }
+
+    pub fn decrypt_opt_text(&self, text: Option<String>) -> Result<Option<String>> {
+        text.map(|t| self.decrypt_text(t)).transpose()
+    }
+
+    /// 写文件（按需加密）
+    pub fn write_file(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let data = self.encrypt_bytes(data.to_vec())?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// 读文件（按需解密）
+    pub fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        self.decrypt_bytes(std::fs::read(path)?)
+    }
+
+    pub fn read_to_string(&self, path: &Path) -> Result<String> {
+        Ok(String::from_utf8(self.read_file(path)?)?)
+    }
+}
+
+fn locked_error() -> anyhow::Error {
+    AppError::storage(10, "数据已加密，请先解锁").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_vault_passes_through() {
+        let vault = Vault::default();
+        assert!(!vault.is_locked());
+        assert_eq!(vault.encrypt_text("hello").unwrap(), "hello");
+        assert_eq!(vault.encrypt_bytes(b"mp4".to_vec()).unwrap(), b"mp4");
+    }
+
+    #[test]
+    fn test_locked_vault_refuses_writes_but_reads_plaintext() {
+        let key = DataKey::generate();
+        let sealed = cipher::seal_text(&key, "ocr");
+
+        let vault = Vault::default();
+        vault.set(true, None);
+        assert!(vault.is_locked());
+        assert!(vault.encrypt_text("ocr").is_err());
+        assert!(vault.decrypt_text(sealed.clone()).is_err());
+        assert_eq!(vault.decrypt_text("legacy".to_string()).unwrap(), "legacy");
+
+        vault.set(true, Some(key));
+        assert_eq!(vault.decrypt_text(sealed).unwrap(), "ocr");
+        let written = vault.encrypt_text("new").unwrap();
+        assert!(cipher::is_sealed_text(&written));
+        // 已是密文时不重复加密
+        assert_eq!(vault.encrypt_text(&written).unwrap(), written);
+    }
+
+    #[test]
+    fn test_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.md");
+        let vault = Vault::default();
+        vault.set(true, Some(DataKey::generate()));
+
+        vault.write_file(&path, "# 今日活动".as_bytes()).unwrap();
+        assert!(cipher::is_sealed(&std::fs::read(&path).unwrap()));
+        assert_eq!(vault.read_to_string(&path).unwrap(), "# 今日活动");
+    }
+}
@@ -0,0 +1,223 @@
+/// 凭据存储（AI 提供商 API Key 等）
+///
+/// 优先存入系统钥匙串；没有 Secret Service 的无桌面 Linux 退回到数据目录下的加密文件。
+/// 加密文件的密钥与之同目录保存（权限 0600），只能防止凭据随数据库、备份或同步盘泄露，
+/// 不能防御可读取用户目录的本地进程
+
+use log::{info, warn};
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+use super::cipher::{self, DataKey};
+use super::key;
+use crate::error::{AppError, AppResult};
+
+/// 回退文件名
+const SECRETS_FILE: &str = "secrets.enc";
+const SECRETS_KEY_FILE: &str = "secrets.key";
+/// 探测钥匙串可用性时读取的条目（不会被写入）
+const PROBE_USER: &str = "probe";
+
+/// 凭据存储接口
+pub trait SecretStore: Send + Sync {
+    /// 存储后端名称（日志用）
+    fn backend(&self) -> &'static str;
+
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>>;
+
+    fn set(&self, name: &str, value: &SecretString) -> AppResult<()>;
+
+    /// 删除（不存在时视为成功）
+    fn delete(&self, name: &str) -> AppResult<()>;
+}
+
+/// 打开凭据存储：系统钥匙串可用时用钥匙串，否则用 data_dir 下的加密文件
+pub fn open(data_dir: &Path) -> AppResult<Arc<dyn SecretStore>> {
+    if KeyringSecretStore::is_available() {
+        return Ok(Arc::new(KeyringSecretStore));
+    }
+    warn!("[Crypto] 系统钥匙串不可用，凭据改存加密文件");
+    Ok(Arc::new(FileSecretStore::open(data_dir)?))
+}
+
+/// 系统钥匙串
+pub struct KeyringSecretStore;
+
+impl KeyringSecretStore {
+    /// 能正常访问钥匙串（条目不存在也算可用）
+    pub fn is_available() -> bool {
+        let Ok(entry) = key::keyring_entry(PROBE_USER) else {
+            return false;
+        };
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => true,
+            Err(e) => {
+                info!("[Crypto] 系统钥匙串探测失败: {}", e);
+                false
+            }
+        }
+    }
+}
+
+impl SecretStore for KeyringSecretStore {
+    fn backend(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>> {
+        match key::keyring_entry(name)?.get_password() {
+            Ok(value) => Ok(Some(SecretString::new(value))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(AppError::storage(12, format!("读取系统钥匙串失败: {}", e))),
+        }
+    }
+
+    fn set(&self, name: &str, value: &SecretString) -> AppResult<()> {
+        key::keyring_entry(name)?
+            .set_password(value.expose_secret())
+            .map_err(|e| AppError::storage(12, format!("写入系统钥匙串失败: {}", e)))
+    }
+
+    fn delete(&self, name: &str) -> AppResult<()> {
+        match key::keyring_entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(AppError::storage(12, format!("删除系统钥匙串条目失败: {}", e))),
+        }
+    }
+}
+
+/// 加密文件：全部凭据序列化为一个 JSON 对象后整体加密
+pub struct FileSecretStore {
+    path: PathBuf,
+    key: DataKey,
+    /// 串行化读-改-写
+    lock: Mutex<()>,
+}
+
+impl FileSecretStore {
+    /// 打开 dir 下的凭据文件，首次使用时生成文件密钥
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        std::fs::create_dir_all(dir).map_err(file_error)?;
+        let key_path = dir.join(SECRETS_KEY_FILE);
+        let key = if key_path.exists() {
+            let bytes = Zeroizing::new(std::fs::read(&key_path).map_err(file_error)?);
+            DataKey::from_bytes(&bytes)
+                .ok_or_else(|| AppError::storage(14, "凭据文件密钥长度不正确"))?
+        } else {
+            let key = DataKey::generate();
+            write_private(&key_path, key.as_bytes())?;
+            key
+        };
+
+        Ok(Self {
+            path: dir.join(SECRETS_FILE),
+            key,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn load(&self) -> AppResult<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let data = std::fs::read(&self.path).map_err(file_error)?;
+        let json = Zeroizing::new(
+            cipher::open(&self.key, &data)
+                .map_err(|e| AppError::storage(14, format!("解密凭据文件失败: {}", e)))?,
+        );
+        serde_json::from_slice(&json)
+            .map_err(|e| AppError::storage(14, format!("凭据文件格式错误: {}", e)))
+    }
+
+    fn save(&self, secrets: &BTreeMap<String, String>) -> AppResult<()> {
+        let json = Zeroizing::new(serde_json::to_vec(secrets)?);
+        let tmp = self.path.with_extension("tmp");
+        write_private(&tmp, &cipher::seal(&self.key, &json))?;
+        std::fs::rename(&tmp, &self.path).map_err(file_error)
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn backend(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>> {
+        let _guard = self.lock.lock().unwrap();
+        let mut secrets = self.load()?;
+        Ok(secrets.remove(name).map(SecretString::new))
+    }
+
+    fn set(&self, name: &str, value: &SecretString) -> AppResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut secrets = self.load()?;
+        secrets.insert(name.to_string(), value.expose_secret().clone());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, name: &str) -> AppResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut secrets = self.load()?;
+        if secrets.remove(name).is_some() {
+            self.save(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+fn file_error(e: std::io::Error) -> AppError {
+    AppError::storage(14, format!("读写凭据文件失败: {}", e))
+}
+
+/// 写入仅当前用户可读写的文件
+fn write_private(path: &Path, data: &[u8]) -> AppResult<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(file_error)?;
+    file.write_all(data).map_err(file_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileSecretStore::open(dir.path()).unwrap();
+        assert!(store.get("ai-api-key:openai").unwrap().is_none());
+
+        store.set("ai-api-key:openai", &SecretString::new("sk-secret-value".into())).unwrap();
+        store.set("ai-api-key:claude", &SecretString::new("sk-ant".into())).unwrap();
+        store.delete("ai-api-key:claude").unwrap();
+        store.delete("missing").unwrap();
+
+        // 文件中没有明文，重新打开后仍可读取
+        let raw = std::fs::read(dir.path().join(SECRETS_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("sk-secret-value"));
+
+        let reopened = FileSecretStore::open(dir.path()).unwrap();
+        let value = reopened.get("ai-api-key:openai").unwrap().unwrap();
+        assert_eq!(value.expose_secret(), "sk-secret-value");
+        assert!(reopened.get("ai-api-key:claude").unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_store_key_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        FileSecretStore::open(dir.path()).unwrap();
+        let mode = std::fs::metadata(dir.path().join(SECRETS_KEY_FILE)).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
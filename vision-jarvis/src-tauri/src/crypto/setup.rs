@@ -0,0 +1,563 @@
+/// 开启/解锁/关闭静态加密，以及已有数据的迁移
+///
+/// 元数据存于 encryption_meta 表（单行）。迁移分两步：先写入元数据并标记
+/// `encrypting` / `decrypting`，再逐个转换文件与数据库列；中途退出后，
+/// 下次启动或解锁时按标记继续，已转换的数据会被跳过
+
+use anyhow::Result;
+use log::{info, warn};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::cipher::{self, DataKey};
+use super::key::{self, KdfParams, KeySource};
+use crate::db::{migrations, Database};
+use crate::error::{AppError, AppResult};
+
+/// 用于校验钥匙串中密钥是否匹配的固定明文
+const KEY_CHECK_PLAINTEXT: &[u8] = b"vision-jarvis";
+
+/// 迁移方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Migration {
+    Encrypt,
+    Decrypt,
+}
+
+/// 元数据中的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MetaState {
+    /// 已开启，已有数据可能尚未全部加密
+    Encrypting,
+    Enabled,
+    /// 正在关闭，已有数据可能尚未全部解密
+    Decrypting,
+}
+
+impl MetaState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Encrypting => "encrypting",
+            Self::Enabled => "enabled",
+            Self::Decrypting => "decrypting",
+        }
+    }
+
+    /// 无法识别的状态报错而不是当作已完成，避免跳过未完成的迁移
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "encrypting" => Ok(Self::Encrypting),
+            "enabled" => Ok(Self::Enabled),
+            "decrypting" => Ok(Self::Decrypting),
+            other => Err(anyhow::anyhow!("未知的加密状态: {}", other)),
+        }
+    }
+
+    fn pending(&self) -> Option<Migration> {
+        match self {
+            Self::Encrypting => Some(Migration::Encrypt),
+            Self::Enabled => None,
+            Self::Decrypting => Some(Migration::Decrypt),
+        }
+    }
+}
+
+struct EncryptionMeta {
+    key_source: KeySource,
+    state: MetaState,
+    salt: Option<Vec<u8>>,
+    /// 口令来源：被 KEK 加密的数据密钥
+    wrapped_key: Option<Vec<u8>>,
+    /// 数据密钥加密的固定明文，用于验证密钥
+    key_check: Vec<u8>,
+    kdf: KdfParams,
+}
+
+/// 加密状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub key_source: Option<KeySource>,
+    /// 未完成的迁移（解锁后自动继续）
+    pub pending_migration: Option<Migration>,
+    /// 检索索引（memory_chunks、FTS、向量）不在加密范围内：加密开启且已有索引内容时为 true
+    #[serde(default)]
+    pub plaintext_search_index: bool,
+}
+
+/// 迁移统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationStats {
+    pub files: usize,
+    pub rows: usize,
+}
+
+fn load_meta(db: &Database) -> Result<Option<EncryptionMeta>> {
+    let row = db.with_connection(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT key_source, state, salt, wrapped_key, key_check, kdf_m_cost, kdf_t_cost
+                 FROM encryption_meta WHERE id = 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        EncryptionMeta {
+                            key_source: KeySource::Passphrase,
+                            state: MetaState::Enabled,
+                            salt: row.get(2)?,
+                            wrapped_key: row.get(3)?,
+                            key_check: row.get(4)?,
+                            kdf: KdfParams { m_cost: row.get(5)?, t_cost: row.get(6)? },
+                        },
+                    ))
+                },
+            )
+            .optional()?)
+    })?;
+
+    let Some((source, state, mut meta)) = row else {
+        return Ok(None);
+    };
+    meta.key_source = KeySource::parse(&source)
+        .ok_or_else(|| anyhow::anyhow!("未知的密钥来源: {}", source))?;
+    meta.state = MetaState::parse(&state)?;
+    Ok(Some(meta))
+}
+
+fn save_meta(db: &Database, meta: &EncryptionMeta) -> Result<()> {
+    db.with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO encryption_meta
+                (id, key_source, state, salt, wrapped_key, key_check, kdf_m_cost, kdf_t_cost, created_at)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                meta.key_source.as_str(),
+                meta.state.as_str(),
+                meta.salt,
+                meta.wrapped_key,
+                meta.key_check,
+                meta.kdf.m_cost,
+                meta.kdf.t_cost,
+                chrono::Utc::now().timestamp(),
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+fn set_state(db: &Database, state: MetaState) -> Result<()> {
+    db.with_connection(|conn| {
+        conn.execute("UPDATE encryption_meta SET state = ?1 WHERE id = 1", [state.as_str()])?;
+        Ok(())
+    })
+}
+
+fn delete_meta(db: &Database) -> Result<()> {
+    db.with_connection(|conn| {
+        conn.execute("DELETE FROM encryption_meta", [])?;
+        Ok(())
+    })
+}
+
+fn db_error(e: anyhow::Error) -> AppError {
+    AppError::database(2, format!("读写加密元数据失败: {}", e))
+}
+
+/// 用元数据解出数据密钥（口令来源需要 passphrase）
+fn unwrap_key(meta: &EncryptionMeta, passphrase: Option<&str>) -> AppResult<DataKey> {
+    let key = match meta.key_source {
+        KeySource::Passphrase => {
+            let passphrase = passphrase
+                .ok_or_else(|| AppError::validation(26, "需要输入口令"))?;
+            let salt = meta.salt.as_deref().unwrap_or_default();
+            let kek = key::derive_kek(passphrase, salt, meta.kdf)?;
+            let wrapped = meta.wrapped_key.as_deref().unwrap_or_default();
+            let raw = cipher::open(&kek, wrapped)
+                .map_err(|_| AppError::storage(11, "口令错误"))?;
+            DataKey::from_bytes(&raw).ok_or_else(|| AppError::storage(11, "口令错误"))?
+        }
+        KeySource::Keyring => key::keyring_load()?,
+    };
+
+    if cipher::open(&key, &meta.key_check).is_err() {
+        return Err(AppError::storage(11, "密钥与已加密数据不匹配"));
+    }
+    Ok(key)
+}
+
+/// 启动时加载加密状态；钥匙串来源自动解锁并继续未完成的迁移
+pub fn load(db: &Database, storage_root: &Path) -> AppResult<()> {
+    match unlock_from_keyring(db)? {
+        Some(meta) => resume(db, storage_root, &meta).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// 只加载加密状态并从钥匙串解锁，不继续未完成的迁移（命令行客户端等只读访问使用）
+pub fn load_key(db: &Database) -> AppResult<()> {
+    unlock_from_keyring(db).map(|_| ())
+}
+
+/// 加载元数据，钥匙串来源时解锁；返回已解锁的元数据（未开启或需要口令时为 None）
+fn unlock_from_keyring(db: &Database) -> AppResult<Option<EncryptionMeta>> {
+    let Some(meta) = load_meta(db).map_err(db_error)? else {
+        db.vault().set(false, None);
+        return Ok(None);
+    };
+
+    let encrypt_writes = meta.state != MetaState::Decrypting;
+    db.vault().set(encrypt_writes, None);
+
+    if meta.key_source == KeySource::Keyring {
+        let key = unwrap_key(&meta, None)?;
+        db.vault().set(encrypt_writes, Some(key));
+        Ok(Some(meta))
+    } else {
+        info!("[Crypto] 数据已加密，等待输入口令解锁");
+        Ok(None)
+    }
+}
+
+/// 当前加密状态
+pub fn status(db: &Database) -> AppResult<EncryptionStatus> {
+    let meta = load_meta(db).map_err(db_error)?;
+    let enabled = db.vault().is_encrypting();
+    let plaintext_search_index = enabled && has_search_index(db).map_err(db_error)?;
+    Ok(EncryptionStatus {
+        enabled,
+        unlocked: db.vault().is_unlocked(),
+        key_source: meta.as_ref().map(|m| m.key_source),
+        pending_migration: meta.and_then(|m| m.state.pending()),
+        plaintext_search_index,
+    })
+}
+
+/// 检索索引是否有内容（索引需要明文才能匹配，不随静态加密转换）
+fn has_search_index(db: &Database) -> Result<bool> {
+    db.with_connection(|conn| {
+        Ok(conn.query_row("SELECT EXISTS(SELECT 1 FROM memory_chunks)", [], |row| row.get(0))?)
+    })
+}
+
+/// 开启加密并加密已有数据
+pub fn enable(
+    db: &Database,
+    storage_root: &Path,
+    source: KeySource,
+    passphrase: Option<&str>,
+) -> AppResult<MigrationStats> {
+    if load_meta(db).map_err(db_error)?.is_some() {
+        return Err(AppError::validation(27, "加密已开启"));
+    }
+
+    let data_key = DataKey::generate();
+    let kdf = KdfParams::default();
+    let (salt, wrapped_key) = match source {
+        KeySource::Passphrase => {
+            let passphrase = passphrase.unwrap_or_default();
+            key::validate_passphrase(passphrase)?;
+            let salt = key::random_salt().to_vec();
+            let kek = key::derive_kek(passphrase, &salt, kdf)?;
+            (Some(salt), Some(cipher::seal(&kek, data_key.as_bytes())))
+        }
+        KeySource::Keyring => {
+            key::keyring_store(&data_key)?;
+            (None, None)
+        }
+    };
+
+    let meta = EncryptionMeta {
+        key_source: source,
+        state: MetaState::Encrypting,
+        salt,
+        wrapped_key,
+        key_check: cipher::seal(&data_key, KEY_CHECK_PLAINTEXT),
+        kdf,
+    };
+    save_meta(db, &meta).map_err(db_error)?;
+    db.vault().set(true, Some(data_key));
+    info!("[Crypto] 已开启静态加密（{}）", source.as_str());
+    warn!("[Crypto] 检索索引（memory_chunks、全文与向量索引）保持明文，不在静态加密范围内");
+
+    let stats = migrate(db, storage_root, Migration::Encrypt)?;
+    set_state(db, MetaState::Enabled).map_err(db_error)?;
+    Ok(stats)
+}
+
+/// 用口令解锁（钥匙串来源无需口令），并继续未完成的迁移
+pub fn unlock(db: &Database, storage_root: &Path, passphrase: Option<&str>) -> AppResult<MigrationStats> {
+    let meta = load_meta(db).map_err(db_error)?
+        .ok_or_else(|| AppError::validation(27, "加密未开启"))?;
+    let key = unwrap_key(&meta, passphrase)?;
+    db.vault().set(meta.state != MetaState::Decrypting, Some(key));
+    resume(db, storage_root, &meta)
+}
+
+/// 丢弃内存中的密钥；之后需重新解锁才能读写加密数据
+pub fn lock(db: &Database) {
+    let encrypting = db.vault().is_encrypting();
+    db.vault().set(encrypting, None);
+}
+
+/// 修改口令：只重新包裹数据密钥，已加密数据不变
+pub fn change_passphrase(db: &Database, old: &str, new: &str) -> AppResult<()> {
+    let mut meta = load_meta(db).map_err(db_error)?
+        .filter(|m| m.key_source == KeySource::Passphrase)
+        .ok_or_else(|| AppError::validation(27, "未使用口令加密"))?;
+    let data_key = unwrap_key(&meta, Some(old))?;
+    key::validate_passphrase(new)?;
+
+    let salt = key::random_salt().to_vec();
+    let kek = key::derive_kek(new, &salt, meta.kdf)?;
+    meta.wrapped_key = Some(cipher::seal(&kek, data_key.as_bytes()));
+    meta.salt = Some(salt);
+    save_meta(db, &meta).map_err(db_error)
+}
+
+/// 关闭加密：解密全部数据后删除元数据与钥匙串条目
+pub fn disable(db: &Database, storage_root: &Path) -> AppResult<MigrationStats> {
+    let meta = load_meta(db).map_err(db_error)?
+        .ok_or_else(|| AppError::validation(27, "加密未开启"))?;
+    let key = db.vault().key()
+        .ok_or_else(|| AppError::storage(10, "数据已加密，请先解锁"))?;
+
+    set_state(db, MetaState::Decrypting).map_err(db_error)?;
+    db.vault().set(false, Some(key));
+    finish_disable(db, storage_root, &meta)
+}
+
+fn finish_disable(db: &Database, storage_root: &Path, meta: &EncryptionMeta) -> AppResult<MigrationStats> {
+    let stats = migrate(db, storage_root, Migration::Decrypt)?;
+    delete_meta(db).map_err(db_error)?;
+    if meta.key_source == KeySource::Keyring {
+        if let Err(e) = key::keyring_delete() {
+            warn!("[Crypto] {}", e);
+        }
+    }
+    db.vault().set(false, None);
+    info!("[Crypto] 已关闭静态加密");
+    Ok(stats)
+}
+
+/// 继续中断的迁移
+fn resume(db: &Database, storage_root: &Path, meta: &EncryptionMeta) -> AppResult<MigrationStats> {
+    match meta.state {
+        MetaState::Enabled => Ok(MigrationStats::default()),
+        MetaState::Encrypting => {
+            info!("[Crypto] 继续未完成的加密迁移");
+            let stats = migrate(db, storage_root, Migration::Encrypt)?;
+            set_state(db, MetaState::Enabled).map_err(db_error)?;
+            Ok(stats)
+        }
+        MetaState::Decrypting => {
+            info!("[Crypto] 继续未完成的解密迁移");
+            finish_disable(db, storage_root, meta)
+        }
+    }
+}
+
+/// 转换存储目录中的文件与数据库敏感列
+fn migrate(db: &Database, storage_root: &Path, direction: Migration) -> AppResult<MigrationStats> {
+    let key = db.vault().key()
+        .ok_or_else(|| AppError::storage(10, "数据已加密，请先解锁"))?;
+
+    let mut stats = MigrationStats::default();
+    for path in sensitive_files(storage_root) {
+        match convert_file(&key, &path, direction) {
+            Ok(true) => stats.files += 1,
+            Ok(false) => {}
+            Err(e) => {
+                return Err(AppError::io(3, format!("转换文件失败 {}: {}", path.display(), e)));
+            }
+        }
+    }
+
+    stats.rows = db
+        .with_connection(|conn| {
+            migrations::transform_encrypted_columns(conn, |value| match direction {
+                Migration::Encrypt if !cipher::is_sealed_text(value) => {
+                    Ok(Some(cipher::seal_text(&key, value)))
+                }
+                Migration::Decrypt if cipher::is_sealed_text(value) => {
+                    Ok(Some(cipher::open_text(&key, value)?))
+                }
+                _ => Ok(None),
+            })
+        })
+        .map_err(|e| AppError::database(3, format!("转换加密列失败: {}", e)))?;
+
+    info!("[Crypto] {:?} 迁移完成: {} 个文件, {} 行", direction, stats.files, stats.rows);
+    Ok(stats)
+}
+
+/// 需要加密的文件：recordings 下的视频、关键帧与分析 JSON，以及任意位置的 Markdown
+fn sensitive_files(storage_root: &Path) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    collect_files(storage_root, &mut |path| {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        let in_recordings = path
+            .strip_prefix(storage_root)
+            .map(|rel| rel.starts_with("recordings"))
+            .unwrap_or(false);
+        if ext == "md" || (in_recordings && matches!(ext, "mp4" | "jpg" | "json")) {
+            files.push(path.to_path_buf());
+        }
+    });
+    files
+}
+
+fn collect_files(dir: &Path, visit: &mut dyn FnMut(&Path)) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => collect_files(&path, visit),
+            Ok(t) if t.is_file() => visit(&path),
+            _ => {}
+        }
+    }
+}
+
+/// 原地转换单个文件（先写临时文件再重命名）；已是目标格式时返回 false
+fn convert_file(key: &DataKey, path: &Path, direction: Migration) -> Result<bool> {
+    let data = std::fs::read(path)?;
+    let converted = match direction {
+        Migration::Encrypt if !cipher::is_sealed(&data) => cipher::seal(key, &data),
+        Migration::Decrypt if cipher::is_sealed(&data) => cipher::open(key, &data)?,
+        _ => return Ok(false),
+    };
+
+    let tmp = path.with_extension("vjtmp");
+    std::fs::write(&tmp, converted)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(db: &Database, root: &Path) {
+        let day = root.join("recordings/20261016/afternoon");
+        std::fs::create_dir_all(&day).unwrap();
+        std::fs::write(day.join("14-00-00_a.mp4"), b"fake mp4").unwrap();
+        std::fs::write(day.join("14-00-00_a.json"), br#"{"ocr_text":"secret"}"#).unwrap();
+        std::fs::create_dir_all(root.join("activity")).unwrap();
+        std::fs::write(root.join("activity/a.md"), "# 写代码").unwrap();
+        std::fs::write(root.join("notes.txt"), "untouched").unwrap();
+
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO screenshot_analyses (screenshot_id, application, activity_type,
+                    activity_description, ocr_text, analysis_json, analyzed_at)
+                 VALUES ('r1', 'Code', 'work', '写代码', '密码 hunter2', '{}', 0)",
+                [],
+            )?;
+            conn.execute("INSERT INTO ai_config (key, value) VALUES ('ai_config', '{\"api_key\":\"sk-1\"}')", [])?;
+            Ok(())
+        }).unwrap();
+    }
+
+    fn ocr(db: &Database) -> String {
+        db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT ocr_text FROM screenshot_analyses WHERE screenshot_id = 'r1'", [], |r| r.get(0))?)
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_enable_lock_unlock_disable() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        seed(&db, root);
+        let video = root.join("recordings/20261016/afternoon/14-00-00_a.mp4");
+
+        let stats = enable(&db, root, KeySource::Passphrase, Some("correct horse")).unwrap();
+        assert_eq!(stats, MigrationStats { files: 3, rows: 8 });
+        assert!(cipher::is_sealed(&std::fs::read(&video).unwrap()));
+        assert_eq!(std::fs::read(root.join("notes.txt")).unwrap(), b"untouched");
+        assert!(cipher::is_sealed_text(&ocr(&db)));
+        assert_eq!(db.vault().decrypt_text(ocr(&db)).unwrap(), "密码 hunter2");
+        let description: String = db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT activity_description FROM screenshot_analyses", [], |r| r.get(0))?)
+        }).unwrap();
+        assert!(cipher::is_sealed_text(&description));
+        assert!(enable(&db, root, KeySource::Passphrase, Some("correct horse")).is_err());
+
+        // 模拟重启：口令来源需要手动解锁
+        lock(&db);
+        load(&db, root).unwrap();
+        assert!(db.vault().is_locked());
+        assert!(db.vault().read_file(&video).is_err());
+        assert!(matches!(unlock(&db, root, Some("wrong passphrase")), Err(AppError::Storage(11, _))));
+        unlock(&db, root, Some("correct horse")).unwrap();
+        assert_eq!(db.vault().read_file(&video).unwrap(), b"fake mp4");
+
+        change_passphrase(&db, "correct horse", "battery staple").unwrap();
+        lock(&db);
+        assert!(unlock(&db, root, Some("correct horse")).is_err());
+        unlock(&db, root, Some("battery staple")).unwrap();
+
+        let stats = disable(&db, root).unwrap();
+        assert_eq!(stats, MigrationStats { files: 3, rows: 8 });
+        assert_eq!(std::fs::read(&video).unwrap(), b"fake mp4");
+        assert_eq!(ocr(&db), "密码 hunter2");
+        assert!(!status(&db).unwrap().enabled);
+    }
+
+    #[test]
+    fn test_interrupted_encryption_resumes_on_unlock() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        enable(&db, root, KeySource::Passphrase, Some("correct horse")).unwrap();
+        // 模拟迁移中途退出：标记回 encrypting，并出现一个未加密的旧文件
+        set_state(&db, MetaState::Encrypting).unwrap();
+        seed(&db, root);
+        lock(&db);
+
+        assert_eq!(status(&db).unwrap().pending_migration, Some(Migration::Encrypt));
+        let stats = unlock(&db, root, Some("correct horse")).unwrap();
+        assert_eq!(stats.files, 3);
+        assert!(cipher::is_sealed_text(&ocr(&db)));
+        assert_eq!(status(&db).unwrap().pending_migration, None);
+    }
+
+    #[test]
+    fn test_unknown_meta_state_is_an_error() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        enable(&db, dir.path(), KeySource::Passphrase, Some("correct horse")).unwrap();
+
+        db.with_connection(|conn| {
+            conn.execute("UPDATE encryption_meta SET state = 'bogus' WHERE id = 1", [])?;
+            Ok(())
+        }).unwrap();
+        assert!(status(&db).is_err());
+        assert!(load(&db, dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_status_reports_plaintext_search_index() {
+        let db = Database::open_in_memory().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        enable(&db, dir.path(), KeySource::Passphrase, Some("correct horse")).unwrap();
+        assert!(!status(&db).unwrap().plaintext_search_index);
+
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO memory_files (path, hash, mtime, size) VALUES ('activity/a.md', 'h', 0, 9);
+                 INSERT INTO memory_chunks (id, file_path, start_line, end_line, hash, model, text, embedding, updated_at)
+                 VALUES ('c1', 'activity/a.md', 1, 1, 'h', 'm', '写代码', x'', 0);",
+            )?;
+            Ok(())
+        }).unwrap();
+        assert!(status(&db).unwrap().plaintext_search_index);
+    }
+}
@@ -87,11 +87,91 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
         tx.commit()?;
     }
 
+    // V9: memory_chunks 全文索引（FTS5）
+    if version < 9 {
+        let tx = conn.unchecked_transaction()?;
+        create_memory_chunks_fts_table(&tx)?;
+        set_schema_version(&tx, 9)?;
+        tx.commit()?;
+    }
+
+    // V10: 多轮对话会话
+    if version < 10 {
+        let tx = conn.unchecked_transaction()?;
+        create_conversations_tables(&tx)?;
+        set_schema_version(&tx, 10)?;
+        tx.commit()?;
+    }
+
+    // V11: AI 调用用量
+    if version < 11 {
+        let tx = conn.unchecked_transaction()?;
+        create_ai_usage_table(&tx)?;
+        set_schema_version(&tx, 11)?;
+        tx.commit()?;
+    }
+
+    // V12: 脱敏（录制窗口采样 + 审计日志）
+    if version < 12 {
+        let tx = conn.unchecked_transaction()?;
+        migrate_v12(&tx)?;
+        set_schema_version(&tx, 12)?;
+        tx.commit()?;
+    }
+
+    // V13: 隐私暂停状态
+    if version < 13 {
+        let tx = conn.unchecked_transaction()?;
+        create_privacy_pause_table(&tx)?;
+        set_schema_version(&tx, 13)?;
+        tx.commit()?;
+    }
+
+    // V14: 静态加密元数据
+    if version < 14 {
+        let tx = conn.unchecked_transaction()?;
+        create_encryption_meta_table(&tx)?;
+        set_schema_version(&tx, 14)?;
+        tx.commit()?;
+    }
+
+    // V15: 保留策略清理的录制标记
+    if version < 15 {
+        let tx = conn.unchecked_transaction()?;
+        migrate_v15(&tx)?;
+        set_schema_version(&tx, 15)?;
+        tx.commit()?;
+    }
+
+    // V16: 关键帧归档
+    if version < 16 {
+        let tx = conn.unchecked_transaction()?;
+        migrate_v16(&tx)?;
+        set_schema_version(&tx, 16)?;
+        tx.commit()?;
+    }
+
+    // V17: 通知记录
+    if version < 17 {
+        let tx = conn.unchecked_transaction()?;
+        create_notifications_table(&tx)?;
+        set_schema_version(&tx, 17)?;
+        tx.commit()?;
+    }
+
+    // V18: 后台任务台账
+    if version < 18 {
+        let tx = conn.unchecked_transaction()?;
+        create_job_ledger_table(&tx)?;
+        set_schema_version(&tx, 18)?;
+        tx.commit()?;
+    }
+
     Ok(())
 }
 
 /// 获取当前schema版本
-fn get_schema_version(conn: &Connection) -> Result<i32> {
+pub(crate) fn get_schema_version(conn: &Connection) -> Result<i32> {
     // 先创建meta表(如果不存在)
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_meta (
@@ -337,6 +417,12 @@ fn create_memory_chunks_table(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chunks_model_updated
+         ON memory_chunks(model, updated_at)",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -759,6 +845,326 @@ fn migrate_v8(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// V9: memory_chunks FTS5
+// ============================================================================
+
+/// 创建 memory_chunks_fts 全文索引表
+///
+/// body 存储经过 CJK 分字处理的文本（见 memory::fts），由 IndexManager 维护同步；
+/// rowid 与 memory_chunks 的 rowid 一致，已有分块在下次索引同步时补齐
+fn create_memory_chunks_fts_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS memory_chunks_fts USING fts5(
+            chunk_id UNINDEXED,
+            file_path UNINDEXED,
+            body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// ============================================================================
+// V10: Conversations
+// ============================================================================
+
+/// 创建 conversations / conversation_messages 表
+///
+/// content 为 ChatContent 数组的 JSON（文本与 base64 图片混合）
+fn create_conversations_tables(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS conversations (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS conversation_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversation_messages_conversation
+         ON conversation_messages(conversation_id, id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_conversations_updated
+         ON conversations(updated_at DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V11: AI Usage
+// ============================================================================
+
+/// 创建 ai_usage 表（每次 AI 调用尝试一行）
+///
+/// cost_usd 按调用时的供应商单价估算，未配置单价时为 0
+fn create_ai_usage_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ai_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            provider_id TEXT NOT NULL,
+            model TEXT NOT NULL,
+            purpose TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            latency_ms INTEGER NOT NULL DEFAULT 0,
+            success INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_ai_usage_timestamp ON ai_usage(timestamp)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V12: Redaction
+// ============================================================================
+
+/// V12 迁移：recordings 增加 window_samples（JSON 数组），创建 redaction_log 审计表
+///
+/// 审计表只记录命中的规则与次数，不保存被脱敏的原文
+fn migrate_v12(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn
+        .prepare("SELECT COUNT(*) FROM pragma_table_info('recordings') WHERE name='window_samples'")?
+        .query_row([], |row| row.get::<_, i64>(0))
+        .map(|count| count > 0)?;
+
+    if !has_column {
+        conn.execute("ALTER TABLE recordings ADD COLUMN window_samples TEXT", [])?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS redaction_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recording_id TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            detail TEXT NOT NULL DEFAULT ''
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_redaction_log_timestamp ON redaction_log(timestamp DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V13: Privacy pause
+// ============================================================================
+
+/// 手动暂停录制的状态（单行表，重启后仍然有效）
+fn create_privacy_pause_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS privacy_pause (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            paused_until INTEGER NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V14: Encryption at rest
+// ============================================================================
+
+/// 静态加密元数据（单行）：密钥来源、迁移状态、口令派生参数与包裹后的数据密钥
+fn create_encryption_meta_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS encryption_meta (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            key_source TEXT NOT NULL,
+            state TEXT NOT NULL,
+            salt BLOB,
+            wrapped_key BLOB,
+            key_check BLOB NOT NULL,
+            kdf_m_cost INTEGER NOT NULL DEFAULT 0,
+            kdf_t_cost INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// 静态加密覆盖的列：(表, 主键列, 列)
+pub const ENCRYPTED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("screenshot_analyses", "screenshot_id", "ocr_text"),
+    ("screenshot_analyses", "screenshot_id", "analysis_json"),
+    ("screenshot_analyses", "screenshot_id", "activity_description"),
+    ("screenshot_analyses", "screenshot_id", "activity_summary"),
+    ("screenshot_analyses", "screenshot_id", "key_elements"),
+    ("screenshot_analyses", "screenshot_id", "context_tags"),
+    ("screenshot_analyses", "screenshot_id", "accomplishments"),
+    ("activities", "id", "summary"),
+    ("summaries", "id", "content"),
+    ("ai_config", "key", "value"),
+];
+
+/// 已有数据的加密/解密迁移：逐行转换 ENCRYPTED_COLUMNS，transform 返回 None 表示保持原值。
+/// 单个事务内完成，可重复执行；返回改动的行数
+pub fn transform_encrypted_columns<F>(conn: &Connection, transform: F) -> Result<usize>
+where
+    F: Fn(&str) -> Result<Option<String>>,
+{
+    let tx = conn.unchecked_transaction()?;
+    let mut changed = 0;
+
+    for (table, pk, column) in ENCRYPTED_COLUMNS {
+        let rows: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {pk}, {column} FROM {table} WHERE {column} IS NOT NULL"
+            ))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        for (id, value) in rows {
+            if let Some(new_value) = transform(&value)? {
+                tx.execute(
+                    &format!("UPDATE {table} SET {column} = ?1 WHERE {pk} = ?2"),
+                    rusqlite::params![new_value, id],
+                )?;
+                changed += 1;
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(changed)
+}
+
+// ============================================================================
+// V15: Retention
+// ============================================================================
+
+/// V15 迁移：recordings 增加 purged_at，视频被保留策略删除后记录时间而不删行，
+/// 分析结果与活动关联保持完整
+fn migrate_v15(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn
+        .prepare("SELECT COUNT(*) FROM pragma_table_info('recordings') WHERE name='purged_at'")?
+        .query_row([], |row| row.get::<_, i64>(0))
+        .map(|count| count > 0)?;
+
+    if !has_column {
+        conn.execute("ALTER TABLE recordings ADD COLUMN purged_at INTEGER", [])?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_recordings_path ON recordings(path)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V16: Keyframe Archive
+// ============================================================================
+
+/// V16 迁移：recordings 增加 archived_at 与 keyframes（关键帧路径 JSON 数组），
+/// 旧视频归档为关键帧后删除原文件
+fn migrate_v16(conn: &Connection) -> Result<()> {
+    for (column, ty) in [("archived_at", "INTEGER"), ("keyframes", "TEXT")] {
+        let exists: bool = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('recordings') WHERE name = ?1")?
+            .query_row([column], |row| row.get::<_, i64>(0))
+            .map(|count| count > 0)?;
+        if !exists {
+            conn.execute(&format!("ALTER TABLE recordings ADD COLUMN {} {}", column, ty), [])?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// V17: Notifications
+// ============================================================================
+
+/// 创建 notifications 表 - 通知调度器生成的提醒与主动建议
+fn create_notifications_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notifications (
+            id TEXT PRIMARY KEY,
+            type TEXT NOT NULL,
+            priority INTEGER NOT NULL DEFAULT 1,
+            title TEXT NOT NULL,
+            message TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            scheduled_at INTEGER,
+            sent_at INTEGER,
+            dismissed INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_notifications_created_at
+         ON notifications(created_at DESC)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// ============================================================================
+// V18: Job ledger
+// ============================================================================
+
+/// 创建 job_ledger 表 - 日/周/月总结与录制分析的执行记录，供启动补跑判断缺口与限制重试
+fn create_job_ledger_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS job_ledger (
+            job TEXT NOT NULL,
+            target TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (job, target)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -804,9 +1210,54 @@ mod tests {
         // 验证V4表创建
         assert!(tables.contains(&"recordings".to_string()));
 
+        // 验证V9全文索引
+        assert!(tables.contains(&"memory_chunks_fts".to_string()));
+
+        // 验证V10对话表
+        assert!(tables.contains(&"conversations".to_string()));
+        assert!(tables.contains(&"conversation_messages".to_string()));
+
+        // 验证V11用量表
+        assert!(tables.contains(&"ai_usage".to_string()));
+
+        // 验证V12脱敏审计表
+        assert!(tables.contains(&"redaction_log".to_string()));
+
+        // 验证V13隐私暂停表
+        assert!(tables.contains(&"privacy_pause".to_string()));
+
+        // 验证V14加密元数据表
+        assert!(tables.contains(&"encryption_meta".to_string()));
+
+        // 验证V15录制清理标记
+        let has_purged_at: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('recordings') WHERE name='purged_at'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(has_purged_at, 1);
+
+        // 验证V16关键帧归档列
+        let archive_columns: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('recordings') WHERE name IN ('archived_at', 'keyframes')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(archive_columns, 2);
+
+        // 验证V17通知表
+        assert!(tables.contains(&"notifications".to_string()));
+
+        // 验证V18任务台账
+        assert!(tables.contains(&"job_ledger".to_string()));
+
         // 验证版本号
         let version = get_schema_version(&conn).unwrap();
-        assert_eq!(version, 7);
+        assert_eq!(version, 18);
     }
 
     #[test]
@@ -871,7 +1322,7 @@ mod tests {
         assert!(run_migrations(&conn).is_ok());
 
         let version = get_schema_version(&conn).unwrap();
-        assert_eq!(version, 7);
+        assert_eq!(version, 18);
     }
 
     #[test]
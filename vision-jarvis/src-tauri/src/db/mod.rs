@@ -2,14 +2,22 @@ use anyhow::Result;
 use rusqlite::Connection;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::crypto::Vault;
 
 pub mod schema;
 pub mod migrations;
 
+/// 写锁被其他进程占用时的最长等待时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// 数据库管理器
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// 静态加密（敏感列与文件的加解密）
+    vault: Arc<Vault>,
 }
 
 impl Database {
@@ -19,9 +27,14 @@ impl Database {
 
         // 启用外键约束
         conn.execute("PRAGMA foreign_keys = ON", [])?;
+        // 桌面应用、守护进程与 vjctl 可能同时打开同一数据库：
+        // WAL 让读写互不阻塞，写锁被占用时等待而不是立即返回 SQLITE_BUSY
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            vault: Arc::new(Vault::default()),
         })
     }
 
@@ -33,6 +46,7 @@ impl Database {
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            vault: Arc::new(Vault::default()),
         };
 
         // 运行迁移
@@ -48,6 +62,11 @@ impl Database {
         Ok(())
     }
 
+    /// 静态加密入口
+    pub fn vault(&self) -> &Arc<Vault> {
+        &self.vault
+    }
+
     /// 获取数据库连接的引用（需要锁定）
     pub fn with_connection<F, R>(&self, f: F) -> Result<R>
     where
@@ -72,4 +91,17 @@ mod tests {
         let db = Database::new(db_path).unwrap();
         assert!(db.initialize().is_ok());
     }
+
+    #[test]
+    fn test_wal_and_busy_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db")).unwrap();
+        let (mode, timeout): (String, i64) = db.with_connection(|conn| {
+            let mode = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+            let timeout = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0))?;
+            Ok((mode, timeout))
+        }).unwrap();
+        assert_eq!(mode, "wal");
+        assert_eq!(timeout, BUSY_TIMEOUT.as_millis() as i64);
+    }
 }
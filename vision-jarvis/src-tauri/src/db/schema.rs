@@ -160,6 +160,8 @@ pub enum SummaryType {
     Daily,
     Weekly,
     Monthly,
+    /// 任意时段
+    Range,
 }
 
 impl SummaryType {
@@ -168,6 +170,7 @@ impl SummaryType {
             SummaryType::Daily => "daily",
             SummaryType::Weekly => "weekly",
             SummaryType::Monthly => "monthly",
+            SummaryType::Range => "range",
         }
     }
 }
@@ -31,6 +31,14 @@ pub enum AppError {
     #[error("[ERR_NETWORK_{0:03}] {1}")]
     Network(u16, String),
 
+    /// AI 服务返回的非 2xx HTTP 响应（保留真实状态码与 Retry-After 供重试策略使用）
+    #[error("[ERR_AI_{status:03}] {message}")]
+    Http {
+        status: u16,
+        retry_after: Option<std::time::Duration>,
+        message: String,
+    },
+
     /// IO 相关错误
     #[error("[ERR_IO_{0:03}] {1}")]
     IO(u16, String),
@@ -83,6 +91,11 @@ impl AppError {
         Self::Network(code, msg.into())
     }
 
+    /// 创建 HTTP 响应错误
+    pub fn http(status: u16, retry_after: Option<std::time::Duration>, msg: impl Into<String>) -> Self {
+        Self::Http { status, retry_after, message: msg.into() }
+    }
+
     /// 创建 IO 错误
     pub fn io(code: u16, msg: impl Into<String>) -> Self {
         Self::IO(code, msg.into())
@@ -131,14 +144,15 @@ impl From<reqwest::Error> for AppError {
         } else if err.is_connect() {
             Self::network(2, "网络连接失败")
         } else if let Some(status) = err.status() {
-            match status.as_u16() {
-                401 => Self::network(401, "API Key 无效或未授权"),
-                403 => Self::network(403, "访问被拒绝"),
-                404 => Self::network(404, "API 端点不存在"),
-                429 => Self::network(429, "请求过于频繁，请稍后重试"),
-                500..=599 => Self::network(500, "服务器错误"),
-                _ => Self::network(999, format!("HTTP 错误: {}", status)),
-            }
+            let message = match status.as_u16() {
+                401 => "API Key 无效或未授权".to_string(),
+                403 => "访问被拒绝".to_string(),
+                404 => "API 端点不存在".to_string(),
+                429 => "请求过于频繁，请稍后重试".to_string(),
+                500..=599 => format!("服务器错误 {}", status),
+                _ => format!("HTTP 错误: {}", status),
+            };
+            Self::http(status.as_u16(), None, message)
         } else {
             Self::network(999, format!("网络错误: {}", err))
         }
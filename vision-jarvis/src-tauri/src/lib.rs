@@ -1,3 +1,6 @@
+// 无界面构建（不开 desktop feature）时，只被 Tauri 命令使用的功能（问答、导入导出、加密设置等）未被引用
+#![cfg_attr(not(feature = "desktop"), allow(dead_code, unused_imports))]
+
 // 模块声明
 mod error;
 mod db;
@@ -6,22 +9,37 @@ mod capture;
 pub mod ai;
 mod memory;
 mod notification;
+#[cfg(feature = "desktop")]
 mod commands;
+mod state;
 mod storage;
+mod privacy;
+mod crypto;
+pub mod runtime;
 
 // 导出错误类型供其他模块使用
 pub use error::{AppError, AppResult};
 
+// 导出记忆查询与存储信息供命令行客户端（vjctl）使用
+pub use memory::queries;
+pub use storage::{StorageInfo, StorageManager};
+
+#[cfg(feature = "desktop")]
 use commands::{AppState, AIConfigState};
+#[cfg(feature = "desktop")]
 use tauri::{Manager, LogicalPosition};
-use log::{info, error};
+#[cfg(feature = "desktop")]
+use log::info;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+#[cfg(feature = "desktop")]
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+/// 桌面应用入口（`desktop` feature，默认开启）
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // 初始化日志
@@ -29,25 +47,9 @@ pub fn run() {
         .format_timestamp_secs()
         .init();
 
-    // 初始化数据库
-    let db_path = dirs::data_local_dir()
-        .unwrap()
-        .join("vision-jarvis")
-        .join("vision-jarvis.db");
-
-    std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
-
-    let db = db::Database::new(db_path).expect("Failed to create database");
-    db.initialize().expect("Failed to initialize database");
-
-    // 初始化设置管理器
-    let settings_manager = settings::SettingsManager::new();
-
-    // 创建应用状态
-    let app_state = AppState::new(db, settings_manager);
-
-    // 创建 AI 配置状态（从数据库加载已保存的配置）
-    let ai_config_state = AIConfigState::new(app_state.db.clone());
+    // 打开数据库，加载设置与 AI 配置（API Key 来自凭据存储）
+    let runtime = runtime::Runtime::open(&runtime::RuntimeConfig::default())
+        .expect("Failed to initialize runtime");
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -58,8 +60,8 @@ pub fn run() {
         ))
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_store::Builder::default().build())
-        .manage(app_state)
-        .manage(ai_config_state)
+        .manage(runtime.state)
+        .manage(runtime.ai_config)
         .setup(|app| {
             // 设置悬浮球窗口位置到右上角
             if let Some(window) = app.get_webview_window("floating-ball") {
@@ -85,124 +87,36 @@ pub fn run() {
                 }
             }
 
-            // 启动录制调度器（如果记忆功能启用）
-            let state = app.state::<AppState>();
-            let memory_enabled = state.settings.is_memory_enabled();
-            let storage_path = state.settings.get_storage_path();
-            info!("memory_enabled={}, storage={}", memory_enabled, storage_path.display());
-
-            // 确保所有标准文件夹存在
-            if let Ok(manager) = crate::storage::StorageManager::new(storage_path.clone()) {
-                if let Err(e) = manager.ensure_all_folders() {
-                    error!("创建标准文件夹失败: {}", e);
-                }
-            }
-
-            if memory_enabled {
-                let scheduler = state.scheduler.clone();
-
-                tauri::async_runtime::spawn(async move {
-                    let mut scheduler = scheduler.lock().await;
-                    info!("Starting recorder (segment: {}s)", scheduler.interval_seconds);
-                    match scheduler.start().await {
-                        Ok(_) => info!("Recorder started"),
-                        Err(e) => error!("Failed to start recorder: {}", e),
-                    }
-                });
-            } else {
-                info!("Memory disabled, recorder skipped");
-            }
+            // 启动录制、记忆管道、保留策略、归档、设置订阅与配置方案自动切换
+            let app_handle_runtime = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle_runtime.state::<AppState>();
+                let ai_state = app_handle_runtime.state::<AIConfigState>();
+                runtime::start(&state, &ai_state);
+            });
 
             // 启动通知调度器
+            let state = app.state::<AppState>();
             let notif_scheduler = state.notification_scheduler.clone();
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                notif_scheduler.start(app_handle);
+                notif_scheduler.start(move |notif| {
+                    crate::notification::delivery::deliver(&app_handle, notif);
+                });
                 info!("Notification scheduler started");
             });
 
-            // 启动记忆管道调度器，并尝试自动连接 AI
-            if memory_enabled {
-                let pipeline = state.pipeline.clone();
-                let ai_state = app.state::<AIConfigState>();
-                let ai_provider = ai_state.get_active_provider_config();
-
-                tauri::async_runtime::spawn(async move {
-                    // 如果已有 AI 配置，自动连接到管道
-                    if let Some(provider) = ai_provider {
-                        match crate::ai::AIClient::new(provider.clone()) {
-                            Ok(client) => {
-                                pipeline.connect_ai(client).await;
-                                pipeline.start();
-                                info!("Pipeline started (AI: {} / {})", provider.name, provider.model);
-                            }
-                            Err(e) => {
-                                error!("Failed to create AI client: {}", e);
-                                pipeline.start();
-                                info!("Pipeline started (AI connection failed)");
-                            }
-                        }
-                    } else {
-                        pipeline.start();
-                        info!("Pipeline started (no AI configured)");
-                    }
-                });
-            }
-
-            // 启动鼠标 Idle 检测 + 回归提醒
-            {
-                let idle_settings = state.settings.get();
-                if idle_settings.idle_reminder_enabled {
-                    let db = state.db.clone();
-                    let ai_state = app.state::<AIConfigState>();
-                    let provider_config = ai_state.get_active_provider_config();
-                    let app_handle_idle = app.handle().clone();
-                    let threshold = idle_settings.idle_threshold_secs;
-                    let min_trigger = idle_settings.idle_min_trigger_secs;
-
-                    std::thread::spawn(move || {
-                        let rt = tokio::runtime::Runtime::new()
-                            .expect("Failed to create tokio runtime for IdleWatcher");
-
-                        let advisor = std::sync::Arc::new(
-                            crate::notification::return_advisor::ReturnAdvisor::new(
-                                db,
-                                provider_config,
-                            )
-                        );
-
-                        let watcher = crate::capture::idle_watcher::IdleWatcher::new(
-                            threshold,
-                            min_trigger,
-                        );
-
-                        watcher.start(move |idle_secs| {
-                            let advisor = advisor.clone();
-                            let app = app_handle_idle.clone();
-                            rt.spawn(async move {
-                                if let Some(msg) = advisor.generate_return_hint(idle_secs).await {
-                                    let notif = crate::notification::Notification::new(
-                                        crate::notification::NotificationType::ReturnReminder,
-                                        crate::notification::NotificationPriority::Normal,
-                                        "欢迎回来".to_string(),
-                                        msg,
-                                    );
-                                    let _ = crate::notification::delivery::send_system_notification(
-                                        &app, &notif,
-                                    );
-                                    let _ = crate::notification::delivery::emit_notification_event(
-                                        &app, &notif,
-                                    );
-                                }
-                            });
-                        });
-                    });
-
-                    info!("IdleWatcher started (threshold={}s, min_trigger={}s)", threshold, min_trigger);
-                } else {
-                    info!("IdleWatcher disabled in settings");
-                }
-            }
+            // 启动鼠标 Idle 检测 + 回归提醒（关闭时线程空转，设置中开启后立即生效）
+            let app_handle_idle = app.handle().clone();
+            runtime::start_idle_watcher(&state, &app.state::<AIConfigState>(), move |msg| {
+                let notif = crate::notification::Notification::new(
+                    crate::notification::NotificationType::ReturnReminder,
+                    crate::notification::NotificationPriority::Normal,
+                    "欢迎回来".to_string(),
+                    msg,
+                );
+                crate::notification::delivery::deliver(&app_handle_idle, &notif);
+            });
 
             Ok(())
         })
@@ -211,6 +125,8 @@ pub fn run() {
             commands::health_check,
             // 录制相关
             commands::recording::get_scheduler_status,
+            commands::recording::pause_recording_for,
+            commands::recording::resume_recording,
             // 记忆相关
             commands::memory::get_activities,
             commands::memory::get_activity_detail,
@@ -219,7 +135,14 @@ pub fn run() {
             commands::memory::get_summary,
             commands::memory::get_recording_stats,
             commands::memory::search_memories,
+            commands::memory::semantic_search_memories,
+            commands::memory::fulltext_search_memories,
+            commands::memory::ask_memory,
+            commands::memory::ask_memory_stream,
             commands::memory::trigger_daily_summary,
+            commands::memory::generate_range_summary,
+            commands::memory::rebuild_knowledge_base,
+            commands::memory::run_catch_up,
             // 通知相关
             commands::notification::get_pending_notifications,
             commands::notification::dismiss_notification,
@@ -230,10 +153,23 @@ pub fn run() {
             commands::settings::get_settings,
             commands::settings::update_settings,
             commands::settings::reset_settings,
+            commands::settings::get_settings_profiles,
+            commands::settings::save_settings_profile,
+            commands::settings::delete_settings_profile,
+            commands::settings::activate_settings_profile,
+            commands::settings::deactivate_settings_profile,
+            commands::settings::set_profile_auto_switch,
             // 存储管理相关
             commands::storage::get_storage_info,
             commands::storage::list_files,
             commands::storage::cleanup_old_files,
+            commands::storage::preview_retention,
+            commands::storage::run_retention,
+            commands::storage::run_keyframe_archive,
+            commands::storage::get_recording_keyframes,
+            commands::storage::export_vault,
+            commands::storage::preview_vault_import,
+            commands::storage::import_vault,
             commands::storage::delete_file,
             commands::storage::open_folder,
             // AI 配置相关
@@ -248,6 +184,24 @@ pub fn run() {
             commands::ai_config::reset_ai_config,
             commands::ai_config::connect_ai_to_pipeline,
             commands::ai_config::get_pipeline_status,
+            commands::ai_config::get_ai_call_attempts,
+            commands::ai_usage::get_ai_usage,
+            commands::ai_usage::get_ai_budget_status,
+            commands::privacy::get_redaction_log,
+            commands::privacy::preview_redaction,
+            commands::encryption::get_encryption_status,
+            commands::encryption::enable_encryption,
+            commands::encryption::unlock_encryption,
+            commands::encryption::lock_encryption,
+            commands::encryption::change_encryption_passphrase,
+            commands::encryption::disable_encryption,
+            commands::ai_stream::stream_ai_text,
+            // 多轮对话
+            commands::conversation::create_conversation,
+            commands::conversation::list_conversations,
+            commands::conversation::get_conversation,
+            commands::conversation::delete_conversation,
+            commands::conversation::send_chat_message,
             // 窗口管理相关
             commands::window::open_memory_window,
             commands::window::open_popup_setting_window,
@@ -259,16 +213,9 @@ pub fn run() {
         .expect("error while building tauri application")
         .run(|app_handle, event| {
             if let tauri::RunEvent::Exit = event {
+                info!("App exiting, stopping recorder and pipeline...");
                 let state = app_handle.state::<AppState>();
-                let scheduler = state.scheduler.clone();
-                tauri::async_runtime::block_on(async {
-                    let mut s = scheduler.lock().await;
-                    if s.is_running().await {
-                        info!("App exiting, stopping recorder...");
-                        let _ = s.stop().await;
-                        info!("Recorder stopped");
-                    }
-                });
+                tauri::async_runtime::block_on(runtime::shutdown(&state));
             }
         });
 }
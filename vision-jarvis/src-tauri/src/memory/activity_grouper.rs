@@ -9,7 +9,6 @@
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -126,7 +125,9 @@ impl ActivityGroup {
     }
 
     /// 转换为ActivitySession
-    fn finalize(&self, markdown_path: String) -> ActivitySession {
+    ///
+    /// Markdown 文件名取自活动 ID，多次分组不会互相覆盖，知识库中的链接也保持稳定
+    fn finalize(&self) -> ActivitySession {
         let duration_minutes = (self.duration_seconds() / 60).max(1);
 
         let title = if !self.merged_key_elements.is_empty() {
@@ -162,8 +163,15 @@ impl ActivityGroup {
             }
         }
 
+        let id = generate_activity_id(self.start_time);
+        let markdown_path = format!(
+            "activities/{}/{}.md",
+            format_timestamp_as_date(self.start_time),
+            id
+        );
+
         ActivitySession {
-            id: generate_activity_id(self.start_time),
+            id,
             title,
             start_time: self.start_time,
             end_time: self.last_time,
@@ -202,30 +210,39 @@ impl ActivityGrouper {
                  ORDER BY r.start_time ASC"
             )?;
 
-            let recordings = stmt.query_map([], |row| {
-                let key_elements_json: String = row.get(8)?;
-                let context_tags_json: String = row.get(9)?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    AnalyzedRecording {
+                        id: row.get(0)?,
+                        path: row.get(1)?,
+                        start_time: row.get(2)?,
+                        application: row.get(3)?,
+                        activity_type: row.get(4)?,
+                        activity_description: row.get(5)?,
+                        activity_category: row.get::<_, Option<String>>(6)?.unwrap_or_else(|| "other".to_string()),
+                        activity_summary: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+                        key_elements: Vec::new(),
+                        context_tags: Vec::new(),
+                        productivity_score: row.get(10)?,
+                        project_name: row.get(11)?,
+                    },
+                    row.get::<_, String>(8)?,
+                    row.get::<_, String>(9)?,
+                ))
+            })?.collect::<rusqlite::Result<Vec<_>>>()?;
 
-                let key_elements: Vec<String> = serde_json::from_str(&key_elements_json)
+            // 描述类字段可能是密文，取出后再解密、解析
+            let vault = self.db.vault();
+            let mut recordings = Vec::with_capacity(rows.len());
+            for (mut recording, key_elements_json, context_tags_json) in rows {
+                recording.activity_description = vault.decrypt_text(recording.activity_description)?;
+                recording.activity_summary = vault.decrypt_text(recording.activity_summary)?;
+                recording.key_elements = serde_json::from_str(&vault.decrypt_text(key_elements_json)?)
                     .unwrap_or_default();
-                let context_tags: Vec<String> = serde_json::from_str(&context_tags_json)
+                recording.context_tags = serde_json::from_str(&vault.decrypt_text(context_tags_json)?)
                     .unwrap_or_default();
-
-                Ok(AnalyzedRecording {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    start_time: row.get(2)?,
-                    application: row.get(3)?,
-                    activity_type: row.get(4)?,
-                    activity_description: row.get(5)?,
-                    activity_category: row.get::<_, Option<String>>(6)?.unwrap_or_else(|| "other".to_string()),
-                    activity_summary: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
-                    key_elements,
-                    context_tags,
-                    productivity_score: row.get(10)?,
-                    project_name: row.get(11)?,
-                })
-            })?.collect::<rusqlite::Result<Vec<_>>>()?;
+                recordings.push(recording);
+            }
 
             Ok(recordings)
         })
@@ -239,7 +256,6 @@ impl ActivityGrouper {
 
         let mut groups = Vec::new();
         let mut current_group: Option<ActivityGroup> = None;
-        let mut date_counters: HashMap<String, usize> = HashMap::new();
 
         for recording in recordings {
             if let Some(ref mut group) = current_group {
@@ -265,11 +281,7 @@ impl ActivityGrouper {
                     group.add(recording.clone());
                 } else {
                     if group.meets_minimum_criteria(&self.config) {
-                        let date_str = format_timestamp_as_date(group.start_time);
-                        let counter = date_counters.entry(date_str.clone()).or_insert(0);
-                        *counter += 1;
-                        let markdown_path = format!("activities/{}/activity-{:03}.md", date_str, counter);
-                        groups.push(group.finalize(markdown_path));
+                        groups.push(group.finalize());
                     }
 
                     current_group = Some(ActivityGroup::new(recording.clone()));
@@ -281,11 +293,7 @@ impl ActivityGrouper {
 
         if let Some(group) = current_group {
             if group.meets_minimum_criteria(&self.config) {
-                let date_str = format_timestamp_as_date(group.start_time);
-                let counter = date_counters.entry(date_str.clone()).or_insert(0);
-                *counter += 1;
-                let markdown_path = format!("activities/{}/activity-{:03}.md", date_str, counter);
-                groups.push(group.finalize(markdown_path));
+                groups.push(group.finalize());
             }
         }
 
@@ -294,6 +302,7 @@ impl ActivityGrouper {
 
     /// 保存活动会话到数据库并关联录制分段
     pub fn save_activity(&self, activity: &ActivitySession) -> Result<()> {
+        let summary = self.db.vault().encrypt_opt_text(activity.summary.as_deref())?;
         self.db.with_connection(|conn| {
             let tx = conn.unchecked_transaction()?;
 
@@ -314,7 +323,7 @@ impl ActivityGrouper {
                     serde_json::to_string(&activity.screenshot_ids)?,
                     serde_json::to_string(&activity.tags)?,
                     &activity.markdown_path,
-                    &activity.summary,
+                    summary,
                     if activity.indexed { 1 } else { 0 },
                     activity.created_at,
                 ],
@@ -444,6 +453,10 @@ mod tests {
         let groups = grouper.group_recordings(&recordings).unwrap();
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].screenshot_ids.len(), 3);
+        assert_eq!(
+            groups[0].markdown_path,
+            format!("activities/1970-01-01/{}.md", groups[0].id)
+        );
     }
 
     #[test]
@@ -587,4 +600,41 @@ mod tests {
         assert_eq!(groups.len(), 1);
         assert!(groups[0].title.contains("pipeline.rs"));
     }
+
+    #[test]
+    fn test_encrypted_analyses_are_decrypted() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        db.vault().set(true, Some(crate::crypto::cipher::DataKey::generate()));
+        let vault = db.vault();
+        let sealed = |text: &str| vault.encrypt_text(text).unwrap();
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO recordings (id, path, start_time, end_time, fps, analyzed)
+                 VALUES ('r1', '/tmp/r1.mp4', 1000, 1060, 2, 1)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO screenshot_analyses (screenshot_id, application, activity_type,
+                    activity_description, key_elements, context_tags, analysis_json,
+                    activity_summary, accomplishments)
+                 VALUES ('r1', 'VSCode', 'work', ?1, ?2, ?3, '{}', ?4, ?5)",
+                rusqlite::params![
+                    sealed("编写代码"),
+                    sealed(r#"["pipeline.rs"]"#),
+                    sealed(r#"["rust"]"#),
+                    sealed("在 VSCode 中编写代码"),
+                    sealed("[]"),
+                ],
+            )?;
+            Ok(())
+        }).unwrap();
+
+        let grouper = ActivityGrouper::new(Arc::clone(&db), GroupingConfig::default());
+        let recordings = grouper.get_ungrouped_recordings().unwrap();
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].activity_description, "编写代码");
+        assert_eq!(recordings[0].activity_summary, "在 VSCode 中编写代码");
+        assert_eq!(recordings[0].key_elements, vec!["pipeline.rs"]);
+        assert_eq!(recordings[0].context_tags, vec!["rust"]);
+    }
 }
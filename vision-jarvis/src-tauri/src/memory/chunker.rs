@@ -200,7 +200,7 @@ pub fn estimate_tokens(text: &str) -> usize {
 }
 
 /// 判断是否为CJK字符
-fn is_cjk(ch: char) -> bool {
+pub fn is_cjk(ch: char) -> bool {
     matches!(ch,
         '\u{4E00}'..='\u{9FFF}' |  // CJK统一表意文字
         '\u{3400}'..='\u{4DBF}' |  // CJK扩展A
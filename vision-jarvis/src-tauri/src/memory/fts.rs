@@ -0,0 +1,306 @@
+/// 全文索引 - memory_chunks_fts（FTS5）的分词与同步
+///
+/// FTS5 内置的 unicode61 分词器会把连续的中文当作一个词，无法检索其中的片段。
+/// 因此入库前把 CJK 字符逐字用零宽空格隔开，查询时同样分字并组成短语（phrase），
+/// 利用短语的位置相邻约束实现任意中文子串匹配；ASCII 词保持原样，支持前缀查询。
+/// 分隔符与原文中的空格区分开，还原片段时只去掉分隔符。
+///
+/// 全文索引的 rowid 与 memory_chunks 的 rowid 一致，补齐与删除都按 rowid 进行。
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::chunker::is_cjk;
+
+/// 高亮起始标记
+pub const MARK_START: &str = "<mark>";
+/// 高亮结束标记
+pub const MARK_END: &str = "</mark>";
+
+/// 分字插入的分隔符（零宽空格，unicode61 视为分隔字符）
+const SEPARATOR: char = '\u{200B}';
+
+/// 入库前分字：CJK 字符两侧插入分隔符
+pub fn segment(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut prev_cjk = false;
+
+    for ch in text.chars() {
+        let cjk = is_cjk(ch);
+        if (cjk || prev_cjk) && !out.is_empty() && !out.ends_with(char::is_whitespace) && !ch.is_whitespace() {
+            out.push(SEPARATOR);
+        }
+        out.push(ch);
+        prev_cjk = cjk;
+    }
+
+    out
+}
+
+/// 把分字后的文本（含高亮标记）还原为原始排版
+pub fn desegment(text: &str) -> String {
+    let out: String = text.chars().filter(|&ch| ch != SEPARATOR).collect();
+
+    // 合并相邻的高亮片段
+    out.replace(&format!("{}{}", MARK_END, MARK_START), "")
+}
+
+/// 切分查询词为 FTS 词元（ASCII 词、单个 CJK 字）
+fn query_tokens(term: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+
+    for ch in term.chars() {
+        if is_cjk(ch) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            tokens.push(ch.to_string());
+        } else if ch.is_alphanumeric() {
+            word.push(ch);
+        } else if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+
+    tokens
+}
+
+/// 把用户查询转换为 FTS5 MATCH 表达式
+///
+/// - 空格分隔的多个词按 AND 组合
+/// - `"..."` 作为短语精确匹配
+/// - 以 `*` 结尾的词做前缀匹配（如 `migra*`）
+/// - 中文词自动转换为逐字短语
+///
+/// 查询中没有可检索内容时返回 None
+pub fn build_match_query(query: &str) -> Option<String> {
+    let mut terms: Vec<(String, bool)> = Vec::new();
+    let mut rest = query.trim();
+
+    while !rest.is_empty() {
+        if let Some(stripped) = rest.strip_prefix('"') {
+            let (phrase, remaining) = match stripped.find('"') {
+                Some(end) => (&stripped[..end], &stripped[end + 1..]),
+                None => (stripped, ""),
+            };
+            terms.push((phrase.to_string(), false));
+            rest = remaining.trim_start();
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+            let word = &rest[..end];
+            let prefix = word.ends_with('*');
+            terms.push((word.trim_end_matches('*').to_string(), prefix));
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    let parts: Vec<String> = terms
+        .into_iter()
+        .filter_map(|(term, prefix)| {
+            let tokens = query_tokens(&term);
+            if tokens.is_empty() {
+                return None;
+            }
+            let phrase = format!("\"{}\"", tokens.join(" "));
+            Some(if prefix { format!("{} *", phrase) } else { phrase })
+        })
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" AND "))
+    }
+}
+
+/// 问答检索忽略的中文虚词、疑问词与时间词（时间描述已由问答转为时间范围过滤）
+const QUESTION_STOPWORDS_CJK: &[&str] = &[
+    "为什么", "是不是", "有没有", "什么", "哪些", "哪个", "哪里", "怎么", "怎样", "如何", "多少",
+    "一下", "时候", "之前", "我们", "你们", "他们", "自己",
+    "星期一", "星期二", "星期三", "星期四", "星期五", "星期六", "星期日", "星期天",
+    "礼拜一", "礼拜二", "礼拜三", "礼拜四", "礼拜五", "礼拜六", "礼拜天",
+    "周一", "周二", "周三", "周四", "周五", "周六", "周日", "周天",
+    "上周", "本周", "这周", "上个月", "这个月", "今天", "昨天", "前天", "今晚", "昨晚",
+    "上午", "下午", "中午", "早上", "晚上",
+    "我", "你", "他", "她", "的", "了", "在", "是", "吗", "呢", "吧", "啊", "和", "与", "做", "有", "过", "都",
+];
+
+/// 问答检索忽略的英文停用词与时间词
+const QUESTION_STOPWORDS_EN: &[&str] = &[
+    "a", "an", "the", "i", "me", "my", "we", "you", "it", "is", "am", "are", "was", "were", "be",
+    "do", "did", "does", "doing", "what", "which", "who", "when", "where", "why", "how",
+    "on", "in", "at", "of", "to", "for", "with", "about", "and", "or", "this", "that", "last",
+    "today", "yesterday", "tonight", "morning", "afternoon", "evening", "noon", "night", "week",
+    "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+];
+
+/// 把自然语言问题转换为 FTS5 MATCH 表达式（任意词命中即可，按 BM25 排序）
+///
+/// 问题里的词很少全部出现在同一分块中，因此去掉停用词与时间词后，
+/// 英文词各自成词、连续中文拆成相邻两字的短语，以 OR 组合。
+/// 没有可检索内容时返回 None
+pub fn build_question_query(question: &str) -> Option<String> {
+    let mut text = question.to_lowercase();
+    for word in QUESTION_STOPWORDS_CJK {
+        text = text.replace(word, " ");
+    }
+
+    let mut terms: Vec<String> = Vec::new();
+    let mut add = |term: String| {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    };
+
+    let mut run: Vec<char> = Vec::new();
+    let mut word = String::new();
+    for ch in text.chars().chain(std::iter::once(' ')) {
+        let cjk = is_cjk(ch);
+        if cjk {
+            run.push(ch);
+        } else if !run.is_empty() {
+            // 单个汉字多为去掉虚词后的残留，不单独检索
+            for pair in run.windows(2) {
+                add(format!("\"{} {}\"", pair[0], pair[1]));
+            }
+            run.clear();
+        }
+
+        if !cjk && ch.is_alphanumeric() {
+            word.push(ch);
+        } else if !word.is_empty() {
+            if word.chars().count() > 1 && !QUESTION_STOPWORDS_EN.contains(&word.as_str()) {
+                add(format!("\"{}\"", word));
+            }
+            word.clear();
+        }
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+/// 写入单个分块的全文索引（分块须已写入 memory_chunks）
+pub fn index_chunk(conn: &Connection, chunk_id: &str, text: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO memory_chunks_fts (rowid, chunk_id, file_path, body)
+         SELECT rowid, id, file_path, ?2 FROM memory_chunks WHERE id = ?1",
+        rusqlite::params![chunk_id, segment(text)],
+    )?;
+    Ok(())
+}
+
+/// 删除文件对应的全文索引（须在删除 memory_chunks 之前调用）
+pub fn delete_file(conn: &Connection, file_path: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM memory_chunks_fts
+         WHERE rowid IN (SELECT rowid FROM memory_chunks WHERE file_path = ?1)",
+        [file_path],
+    )?;
+    Ok(())
+}
+
+/// 为尚未建立全文索引的分块补齐索引，返回补齐数量
+///
+/// 新分块写入时同步建立索引，rowid 递增，因此只需补齐全文索引最大 rowid 之后的分块；
+/// 全文索引为空（如刚升级到 V9）时全部补齐
+pub fn backfill(conn: &Connection) -> Result<usize> {
+    let watermark: i64 = conn
+        .query_row(
+            "SELECT rowid FROM memory_chunks_fts ORDER BY rowid DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+
+    let missing: Vec<(String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, text FROM memory_chunks WHERE rowid > ?1 ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map([watermark], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows
+    };
+
+    for (id, text) in &missing {
+        index_chunk(conn, id, text)?;
+    }
+
+    Ok(missing.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment() {
+        assert_eq!(segment("调试Rust错误"), "调\u{200B}试\u{200B}Rust\u{200B}错\u{200B}误");
+        assert_eq!(segment("hello world"), "hello world");
+        assert_eq!(segment("数据库 migration"), "数\u{200B}据\u{200B}库 migration");
+    }
+
+    #[test]
+    fn test_desegment_round_trip() {
+        for original in ["在VSCode中调试Rust错误", "hello world", "用 Rust 写 数据库 迁移", "数据库 migration"] {
+            assert_eq!(desegment(&segment(original)), original);
+        }
+    }
+
+    #[test]
+    fn test_desegment_merges_highlights() {
+        let snippet = "在\u{200B}<mark>数</mark>\u{200B}<mark>据</mark>\u{200B}<mark>库</mark>\u{200B}中";
+        assert_eq!(desegment(snippet), "在<mark>数据库</mark>中");
+
+        let snippet = "fix <mark>rust</mark>\u{200B}编\u{200B}译 成功";
+        assert_eq!(desegment(snippet), "fix <mark>rust</mark>编译 成功");
+    }
+
+    #[test]
+    fn test_build_match_query() {
+        assert_eq!(build_match_query("rust"), Some("\"rust\"".to_string()));
+        assert_eq!(build_match_query("数据库"), Some("\"数 据 库\"".to_string()));
+        assert_eq!(
+            build_match_query("rust 编译"),
+            Some("\"rust\" AND \"编 译\"".to_string())
+        );
+        assert_eq!(build_match_query("migra*"), Some("\"migra\" *".to_string()));
+        assert_eq!(
+            build_match_query("\"compile error\" vscode"),
+            Some("\"compile error\" AND \"vscode\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_question_query() {
+        assert_eq!(
+            build_question_query("我上周三在调试什么 Rust 错误"),
+            Some("\"调 试\" OR \"rust\" OR \"错 误\"".to_string())
+        );
+        assert_eq!(
+            build_question_query("What was I debugging on Tuesday afternoon?"),
+            Some("\"debugging\"".to_string())
+        );
+        assert_eq!(
+            build_question_query("数据库迁移"),
+            Some("\"数 据\" OR \"据 库\" OR \"库 迁\" OR \"迁 移\"".to_string())
+        );
+        assert_eq!(build_question_query("我昨天在做什么？"), None);
+    }
+
+    #[test]
+    fn test_build_match_query_escapes_operators() {
+        // FTS5 运算符和特殊字符不会被原样传入
+        assert_eq!(build_match_query("NOT OR"), Some("\"NOT\" AND \"OR\"".to_string()));
+        assert_eq!(build_match_query("a:b (c)"), Some("\"a b\" AND \"c\"".to_string()));
+        assert_eq!(build_match_query("  *** \"\" "), None);
+    }
+}
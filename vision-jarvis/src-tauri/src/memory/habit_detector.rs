@@ -600,7 +600,7 @@ impl HabitDetector {
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&full_path, content)?;
+        self.db.vault().write_file(&full_path, content.as_bytes())?;
         Ok(full_path)
     }
 }
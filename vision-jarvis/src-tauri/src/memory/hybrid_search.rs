@@ -0,0 +1,359 @@
+/// 混合搜索 - 融合关键词（BM25）与语义（向量）检索结果
+///
+/// 1. 两路检索各取 `limit × candidate_multiplier` 个候选（共享同一组过滤条件）；
+///    问答检索时关键词按任意词匹配（`question`）
+/// 2. 倒数排名融合（RRF）：score = Σ 1 / (rrf_k + rank)
+/// 3. 时间衰减：按 updated_at 的半衰期衰减，与 RRF 分数按 recency_weight 混合
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::ai::embedding::EmbeddingProvider;
+use crate::db::Database;
+use super::fts;
+use super::search::{self, SearchFilters};
+
+/// 混合搜索配置
+#[derive(Debug, Clone)]
+pub struct HybridSearchConfig {
+    /// 候选池放大倍数
+    pub candidate_multiplier: usize,
+    /// RRF 平滑常数
+    pub rrf_k: f64,
+    /// 时间衰减半衰期（天）
+    pub recency_half_life_days: f64,
+    /// 时间衰减权重 [0, 1]，0 表示不考虑时间
+    pub recency_weight: f64,
+    /// 把查询当作自然语言问题：关键词检索去掉停用词与时间词，任意词命中即可（问答用）
+    pub question: bool,
+}
+
+impl Default for HybridSearchConfig {
+    fn default() -> Self {
+        Self {
+            candidate_multiplier: 4,
+            rrf_k: 60.0,
+            recency_half_life_days: 30.0,
+            recency_weight: 0.3,
+            question: false,
+        }
+    }
+}
+
+/// 混合搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub file_path: String,
+    pub start_line: i32,
+    pub end_line: i32,
+    pub text: String,
+    /// 关键词命中时的高亮片段
+    pub snippet: Option<String>,
+    pub source: String,
+    pub activity_id: Option<String>,
+    pub application: Option<String>,
+    pub updated_at: i64,
+    /// 融合后的最终分数
+    pub score: f64,
+    /// 关键词检索中的排名（1-based）
+    pub keyword_rank: Option<usize>,
+    /// 语义检索中的排名（1-based）
+    pub semantic_rank: Option<usize>,
+}
+
+/// 混合搜索入口
+pub async fn hybrid_search(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+    config: &HybridSearchConfig,
+) -> Result<Vec<SearchHit>> {
+    if query.trim().is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let candidates = limit * config.candidate_multiplier.max(1);
+
+    let match_query = if config.question {
+        fts::build_question_query(query)
+    } else {
+        fts::build_match_query(query)
+    };
+    let keyword = match match_query {
+        Some(match_query) => search::fulltext_match(db, match_query, filters, candidates)?,
+        None => Vec::new(),
+    };
+    // 语义检索失败（如远程 embedding 不可用）时退化为纯关键词检索
+    let semantic = match search::semantic_search(db, embedder, query, filters, candidates).await {
+        Ok(hits) => hits,
+        Err(e) => {
+            log::warn!("Semantic search failed, falling back to keyword only: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut fused: HashMap<String, Fused> = HashMap::new();
+    for (i, hit) in keyword.into_iter().enumerate() {
+        let entry = fused.entry(hit.id.clone()).or_default();
+        entry.keyword_rank = Some(i + 1);
+        entry.snippet = Some(hit.snippet);
+    }
+    for (i, hit) in semantic.into_iter().enumerate() {
+        fused.entry(hit.id).or_default().semantic_rank = Some(i + 1);
+    }
+
+    if fused.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids: Vec<String> = fused.keys().cloned().collect();
+    let rows = load_chunks(db, &ids)?;
+    let now = Utc::now().timestamp();
+
+    let mut hits: Vec<SearchHit> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let entry = fused.remove(&row.id)?;
+            let rrf = rrf_score(entry.keyword_rank, config.rrf_k) + rrf_score(entry.semantic_rank, config.rrf_k);
+            let decay = recency_decay(now - row.updated_at, config.recency_half_life_days);
+            let weight = config.recency_weight.clamp(0.0, 1.0);
+
+            Some(SearchHit {
+                score: rrf * (1.0 - weight + weight * decay),
+                snippet: entry.snippet,
+                keyword_rank: entry.keyword_rank,
+                semantic_rank: entry.semantic_rank,
+                id: row.id,
+                file_path: row.file_path,
+                start_line: row.start_line,
+                end_line: row.end_line,
+                text: row.text,
+                source: row.source,
+                activity_id: row.activity_id,
+                application: row.application,
+                updated_at: row.updated_at,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+
+    Ok(hits)
+}
+
+#[derive(Debug, Default)]
+struct Fused {
+    keyword_rank: Option<usize>,
+    semantic_rank: Option<usize>,
+    snippet: Option<String>,
+}
+
+struct ChunkRow {
+    id: String,
+    file_path: String,
+    start_line: i32,
+    end_line: i32,
+    text: String,
+    source: String,
+    activity_id: Option<String>,
+    application: Option<String>,
+    updated_at: i64,
+}
+
+fn rrf_score(rank: Option<usize>, k: f64) -> f64 {
+    rank.map(|r| 1.0 / (k + r as f64)).unwrap_or(0.0)
+}
+
+/// 指数时间衰减：经过一个半衰期后为 0.5
+fn recency_decay(age_secs: i64, half_life_days: f64) -> f64 {
+    if half_life_days <= 0.0 {
+        return 1.0;
+    }
+    let age_days = age_secs.max(0) as f64 / 86400.0;
+    0.5f64.powf(age_days / half_life_days)
+}
+
+fn load_chunks(db: &Database, ids: &[String]) -> Result<Vec<ChunkRow>> {
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let params: Vec<Value> = ids.iter().map(|id| Value::Text(id.clone())).collect();
+
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT c.id, c.file_path, c.start_line, c.end_line, c.text, c.source,
+                    c.activity_id, a.application, c.updated_at
+             FROM memory_chunks c
+             LEFT JOIN activities a ON a.id = c.activity_id
+             WHERE c.id IN ({})",
+            placeholders
+        ))?;
+
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok(ChunkRow {
+                id: row.get(0)?,
+                file_path: row.get(1)?,
+                start_line: row.get(2)?,
+                end_line: row.get(3)?,
+                text: row.get(4)?,
+                source: row.get(5)?,
+                activity_id: row.get(6)?,
+                application: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::index_manager::{IndexConfig, IndexManager};
+    use std::sync::Arc;
+
+    async fn setup(dir: &std::path::Path) -> (Arc<Database>, IndexManager) {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.to_path_buf(),
+            ..Default::default()
+        });
+
+        let day = dir.join("activities/2024-01-15");
+        std::fs::create_dir_all(&day).unwrap();
+        std::fs::create_dir_all(dir.join("project")).unwrap();
+        std::fs::write(day.join("activity-001.md"), "在 VSCode 中调试 Rust 编译错误\n").unwrap();
+        std::fs::write(day.join("activity-002.md"), "在 Chrome 查阅 Rust 文档\n").unwrap();
+        std::fs::write(dir.join("project/vision-jarvis.md"), "Rust 项目进展\n").unwrap();
+
+        db.with_connection(|conn| {
+            for (id, app, start, path) in [
+                ("act-1", "VSCode", 1705305600, "activities/2024-01-15/activity-001.md"),
+                ("act-2", "Chrome", 1705312800, "activities/2024-01-15/activity-002.md"),
+            ] {
+                conn.execute(
+                    "INSERT INTO activities (id, title, start_time, end_time, duration_minutes,
+                        application, category, screenshot_ids, markdown_path)
+                     VALUES (?1, 'x', ?2, ?2, 1, ?3, 'work', '[]', ?4)",
+                    rusqlite::params![id, start, app, path],
+                )?;
+            }
+            Ok(())
+        }).unwrap();
+
+        manager.sync().await.unwrap();
+        (db, manager)
+    }
+
+    #[test]
+    fn test_rrf_score() {
+        assert!((rrf_score(Some(1), 60.0) - 1.0 / 61.0).abs() < 1e-12);
+        assert_eq!(rrf_score(None, 60.0), 0.0);
+        assert!(rrf_score(Some(1), 60.0) > rrf_score(Some(2), 60.0));
+    }
+
+    #[test]
+    fn test_recency_decay() {
+        assert!((recency_decay(0, 30.0) - 1.0).abs() < 1e-12);
+        assert!((recency_decay(30 * 86400, 30.0) - 0.5).abs() < 1e-9);
+        assert_eq!(recency_decay(30 * 86400, 0.0), 1.0);
+        // 未来时间视为刚更新
+        assert!((recency_decay(-100, 30.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_fuses_both_lists() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (db, manager) = setup(dir.path()).await;
+        let embedder = manager.embedder().await;
+
+        let hits = hybrid_search(
+            &db, embedder.as_ref(), "Rust 编译", &SearchFilters::default(), 10,
+            &HybridSearchConfig::default(),
+        ).await.unwrap();
+
+        assert!(!hits.is_empty());
+        let top = &hits[0];
+        assert_eq!(top.activity_id.as_deref(), Some("act-1"));
+        assert_eq!(top.application.as_deref(), Some("VSCode"));
+        assert_eq!(top.keyword_rank, Some(1));
+        assert!(top.semantic_rank.is_some());
+        assert!(top.snippet.as_deref().unwrap().contains("<mark>"));
+        assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_filters() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (db, manager) = setup(dir.path()).await;
+        let embedder = manager.embedder().await;
+        let config = HybridSearchConfig::default();
+
+        let by_app = SearchFilters { application: Some("chrome".to_string()), ..Default::default() };
+        let hits = hybrid_search(&db, embedder.as_ref(), "Rust", &by_app, 10, &config).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].activity_id.as_deref(), Some("act-2"));
+
+        let by_source = SearchFilters { source: Some("project".to_string()), ..Default::default() };
+        let hits = hybrid_search(&db, embedder.as_ref(), "Rust", &by_source, 10, &config).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "project/vision-jarvis.md");
+
+        let by_activity = SearchFilters { activity_id: Some("act-1".to_string()), ..Default::default() };
+        let hits = hybrid_search(&db, embedder.as_ref(), "Rust", &by_activity, 10, &config).await.unwrap();
+        assert_eq!(hits.len(), 1);
+
+        // 按活动开始时间过滤：只保留 act-2
+        let by_time = SearchFilters {
+            source: Some("activity".to_string()),
+            start_time: Some(1705309200),
+            end_time: Some(1705316400),
+            ..Default::default()
+        };
+        let hits = hybrid_search(&db, embedder.as_ref(), "Rust", &by_time, 10, &config).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].activity_id.as_deref(), Some("act-2"));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_recency_decay() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (db, manager) = setup(dir.path()).await;
+        let embedder = manager.embedder().await;
+
+        // 把 act-1 的分块设为一年前更新
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE memory_chunks SET updated_at = updated_at - 365 * 86400 WHERE activity_id = 'act-1'",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+
+        let filters = SearchFilters { source: Some("activity".to_string()), ..Default::default() };
+        let config = HybridSearchConfig { recency_weight: 1.0, ..Default::default() };
+        let hits = hybrid_search(&db, embedder.as_ref(), "Rust", &filters, 10, &config).await.unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].activity_id.as_deref(), Some("act-2"));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_no_match() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (db, manager) = setup(dir.path()).await;
+        let embedder = manager.embedder().await;
+
+        let hits = hybrid_search(
+            &db, embedder.as_ref(), "", &SearchFilters::default(), 10,
+            &HybridSearchConfig::default(),
+        ).await.unwrap();
+        assert!(hits.is_empty());
+    }
+}
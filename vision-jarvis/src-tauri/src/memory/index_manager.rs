@@ -1,23 +1,51 @@
 /// 索引管理器 - 负责文件扫描、增量索引和存储
 ///
-/// NOTE: Embedding 生成将在记忆系统重新设计时实现
-/// 当前仅支持文本分块和存储（无向量索引）
-///
 /// 核心功能：
 /// 1. 递归扫描Markdown文件
 /// 2. 文件变更检测（基于哈希）
 /// 3. 文本分块存储
+/// 4. 分块向量化（embedding_cache 按分块哈希去重）
+/// 5. 全文索引同步（memory_chunks_fts）
 
 use anyhow::{Result, Context};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::fs;
+use std::collections::HashMap;
 use chrono::Utc;
 use sha2::{Sha256, Digest};
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::ai::embedding::{
+    EmbeddingProvider, HashEmbeddingProvider, blob_to_embedding, embedding_to_blob,
+};
 use crate::db::Database;
 use super::chunker::{Chunker, ChunkConfig, TextChunk};
+use super::fts;
+
+/// 单次请求 embedding 的最大分块数
+const EMBED_BATCH_SIZE: usize = 64;
+/// 每次同步最多重新向量化的旧分块数（切换模型后逐步补齐）
+const REEMBED_LIMIT: usize = 256;
+
+/// 相对路径前缀 → 分块来源
+const SOURCE_PREFIXES: [(&str, &str); 4] = [
+    ("activities/", "activity"),
+    ("project/", "project"),
+    ("habits/", "habit"),
+    ("long_term_memory/", "summary"),
+];
+
+/// 根据文件相对路径判断分块来源（activity / project / habit / summary / other）
+pub fn source_for_path(relative_path: &str) -> &'static str {
+    let normalized = relative_path.replace('\\', "/");
+    SOURCE_PREFIXES
+        .iter()
+        .find(|(prefix, _)| normalized.starts_with(prefix))
+        .map(|(_, source)| *source)
+        .unwrap_or("other")
+}
 
 /// 索引管理器配置
 #[derive(Debug, Clone)]
@@ -42,6 +70,8 @@ pub struct IndexManager {
     db: Arc<Database>,
     chunker: Chunker,
     config: IndexConfig,
+    /// 动态可替换的向量化提供者（默认本地哈希向量）
+    embedder: RwLock<Arc<dyn EmbeddingProvider>>,
 }
 
 /// 文件元数据
@@ -63,9 +93,21 @@ impl IndexManager {
             db,
             chunker,
             config,
+            embedder: RwLock::new(Arc::new(HashEmbeddingProvider::default())),
         }
     }
 
+    /// 替换向量化提供者（旧模型生成的分块会在后续同步中逐步重新向量化）
+    pub async fn set_embedder(&self, embedder: Arc<dyn EmbeddingProvider>) {
+        log::info!("Embedding provider: {}/{}", embedder.provider_id(), embedder.model());
+        *self.embedder.write().await = embedder;
+    }
+
+    /// 当前的向量化提供者
+    pub async fn embedder(&self) -> Arc<dyn EmbeddingProvider> {
+        Arc::clone(&*self.embedder.read().await)
+    }
+
     /// 执行增量同步
     pub async fn sync(&self) -> Result<SyncStats> {
         let mut stats = SyncStats::default();
@@ -86,6 +128,22 @@ impl IndexManager {
             }
         }
 
+        if let Err(e) = self.db.with_connection(link_chunk_metadata) {
+            log::warn!("Linking chunk metadata failed: {}", e);
+        }
+
+        // 补齐升级前已有分块的全文索引
+        match self.db.with_connection(fts::backfill) {
+            Ok(0) => {}
+            Ok(count) => log::info!("Backfilled full-text index for {} chunks", count),
+            Err(e) => log::warn!("Full-text index backfill failed: {}", e),
+        }
+
+        match self.reembed_stale_chunks().await {
+            Ok(count) => stats.reembedded_chunks = count,
+            Err(e) => log::warn!("Re-embedding stale chunks failed: {}", e),
+        }
+
         Ok(stats)
     }
 
@@ -94,7 +152,7 @@ impl IndexManager {
         let mut stats = SyncStats::default();
 
         // 读取文件内容
-        let content = fs::read_to_string(file_path)
+        let content = self.db.vault().read_to_string(file_path)
             .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
 
         // 计算文件元数据
@@ -106,23 +164,27 @@ impl IndexManager {
             return Ok(stats);
         }
 
-        // 删除旧的chunks
-        self.delete_chunks_for_file(&metadata.path)?;
-
         // 分块
         let chunks = self.chunker.chunk_markdown(&content)?;
+
+        // 先完成向量化，失败时保留旧chunks，下次同步重试
+        let embedder = self.embedder().await;
+        let texts: Vec<(String, String)> = chunks.iter()
+            .map(|c| (c.hash.clone(), c.text.clone()))
+            .collect();
+        let embeddings = self.embed_with_cache(embedder.as_ref(), &texts).await?;
+
+        // 文件元数据需先于chunks写入（memory_chunks.file_path 外键）
+        self.save_file_metadata(&metadata)?;
+        self.delete_chunks_for_file(&metadata.path)?;
+
         if chunks.is_empty() {
             log::warn!("No chunks generated for file: {}", file_path.display());
             stats.indexed_files += 1;
-            self.save_file_metadata(&metadata)?;
             return Ok(stats);
         }
 
-        // 保存chunks（不含 embedding，待记忆系统重新设计时实现）
-        self.save_chunks(&metadata.path, &chunks)?;
-
-        // 更新文件元数据
-        self.save_file_metadata(&metadata)?;
+        self.save_chunks(&metadata.path, &chunks, embedder.model(), &embeddings)?;
 
         stats.indexed_files += 1;
         stats.new_chunks += chunks.len();
@@ -140,6 +202,10 @@ impl IndexManager {
 
         self.scan_directory(&self.config.memory_root, &mut files)?;
 
+        // 知识库索引只是链接列表，不参与检索
+        let index_dir = self.config.memory_root.join(super::knowledge_base::INDEX_DIR);
+        files.retain(|f| !f.starts_with(&index_dir));
+
         Ok(files)
     }
 
@@ -201,12 +267,17 @@ impl IndexManager {
     /// 保存文件元数据
     fn save_file_metadata(&self, metadata: &FileMetadata) -> Result<()> {
         self.db.with_connection(|conn| {
+            // 不能用 INSERT OR REPLACE：REPLACE 会先删除被 memory_chunks 引用的行
             conn.execute(
-                "INSERT OR REPLACE INTO memory_files
+                "INSERT INTO memory_files
                  (path, source, hash, mtime, size)
-                 VALUES (?1, 'activity', ?2, ?3, ?4)",
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(path) DO UPDATE SET
+                    source = excluded.source, hash = excluded.hash,
+                    mtime = excluded.mtime, size = excluded.size",
                 rusqlite::params![
                     &metadata.path,
+                    source_for_path(&metadata.path),
                     &metadata.hash,
                     metadata.mtime,
                     metadata.size,
@@ -216,9 +287,10 @@ impl IndexManager {
         })
     }
 
-    /// 删除文件的所有chunks
+    /// 删除文件的所有chunks（含全文索引）
     fn delete_chunks_for_file(&self, file_path: &str) -> Result<()> {
         self.db.with_connection(|conn| {
+            fts::delete_file(conn, file_path)?;
             conn.execute(
                 "DELETE FROM memory_chunks WHERE file_path = ?1",
                 [file_path],
@@ -227,38 +299,166 @@ impl IndexManager {
         })
     }
 
-    /// 保存chunks（不含 embedding）
+    /// 保存chunks及其向量，同时写入全文索引
     fn save_chunks(
         &self,
         file_path: &str,
         chunks: &[TextChunk],
+        model: &str,
+        embeddings: &[Vec<f32>],
     ) -> Result<()> {
         let now = Utc::now().timestamp();
-        let empty_blob: Vec<u8> = Vec::new();
+        let source = source_for_path(file_path);
 
         self.db.with_connection(|conn| {
-            for chunk in chunks {
+            // 活动Markdown通过 activities.markdown_path 关联活动ID
+            let activity_id: Option<String> = conn
+                .query_row(
+                    "SELECT id FROM activities WHERE markdown_path = ?1",
+                    [file_path],
+                    |row| row.get(0),
+                )
+                .ok();
+
+            for (chunk, embedding) in chunks.iter().zip(embeddings) {
                 let id = Uuid::new_v4().to_string();
 
                 conn.execute(
                     "INSERT INTO memory_chunks
-                     (id, file_path, source, start_line, end_line, hash, model, text, embedding, updated_at)
-                     VALUES (?1, ?2, 'activity', ?3, ?4, ?5, '', ?6, ?7, ?8)",
+                     (id, file_path, source, start_line, end_line, hash, model, text, embedding,
+                      activity_id, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                     rusqlite::params![
                         id,
                         file_path,
+                        source,
                         chunk.start_line,
                         chunk.end_line,
                         &chunk.hash,
+                        model,
                         &chunk.text,
-                        &empty_blob,
+                        embedding_to_blob(embedding),
+                        &activity_id,
                         now,
                     ],
                 )?;
+                fts::index_chunk(conn, &id, &chunk.text)?;
             }
             Ok(())
         })
     }
+
+    /// 向量化 (hash, text) 列表，优先命中 embedding_cache，返回顺序与输入一致
+    async fn embed_with_cache(
+        &self,
+        embedder: &dyn EmbeddingProvider,
+        items: &[(String, String)],
+    ) -> Result<Vec<Vec<f32>>> {
+        let provider = embedder.provider_id().to_string();
+        let model = embedder.model().to_string();
+
+        let mut resolved: HashMap<String, Vec<f32>> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT embedding FROM embedding_cache
+                 WHERE provider = ?1 AND model = ?2 AND hash = ?3",
+            )?;
+            let mut found = HashMap::new();
+            for (hash, _) in items {
+                if found.contains_key(hash) {
+                    continue;
+                }
+                let blob: Option<Vec<u8>> = stmt
+                    .query_row(rusqlite::params![&provider, &model, hash], |row| row.get(0))
+                    .ok();
+                if let Some(blob) = blob {
+                    found.insert(hash.clone(), blob_to_embedding(&blob));
+                }
+            }
+            Ok(found)
+        })?;
+
+        // 未命中的分块（同一哈希只请求一次）
+        let mut missing: Vec<&(String, String)> = Vec::new();
+        for item in items {
+            if !resolved.contains_key(&item.0) && !missing.iter().any(|m| m.0 == item.0) {
+                missing.push(item);
+            }
+        }
+
+        for batch in missing.chunks(EMBED_BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+            let vectors = embedder.embed(&texts).await?;
+            let now = Utc::now().timestamp();
+
+            self.db.with_connection(|conn| {
+                for ((hash, _), vector) in batch.iter().zip(&vectors) {
+                    conn.execute(
+                        "INSERT OR REPLACE INTO embedding_cache
+                         (provider, model, hash, embedding, dims, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        rusqlite::params![
+                            &provider,
+                            &model,
+                            hash,
+                            embedding_to_blob(vector),
+                            vector.len() as i64,
+                            now,
+                        ],
+                    )?;
+                }
+                Ok(())
+            })?;
+
+            for ((hash, _), vector) in batch.iter().zip(vectors) {
+                resolved.insert(hash.clone(), vector);
+            }
+        }
+
+        items.iter()
+            .map(|(hash, _)| resolved.get(hash).cloned()
+                .with_context(|| format!("Missing embedding for chunk {}", hash)))
+            .collect()
+    }
+
+    /// 重新向量化由其他模型（或未向量化）生成的分块
+    async fn reembed_stale_chunks(&self) -> Result<usize> {
+        let embedder = self.embedder().await;
+        let model = embedder.model().to_string();
+
+        let stale: Vec<(String, String, String)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, hash, text FROM memory_chunks
+                 WHERE model != ?1 OR length(embedding) = 0
+                 LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(
+                rusqlite::params![&model, REEMBED_LIMIT as i64],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let items: Vec<(String, String)> = stale.iter()
+            .map(|(_, hash, text)| (hash.clone(), text.clone()))
+            .collect();
+        let embeddings = self.embed_with_cache(embedder.as_ref(), &items).await?;
+
+        self.db.with_connection(|conn| {
+            for ((id, _, _), embedding) in stale.iter().zip(&embeddings) {
+                conn.execute(
+                    "UPDATE memory_chunks SET model = ?1, embedding = ?2 WHERE id = ?3",
+                    rusqlite::params![&model, embedding_to_blob(embedding), id],
+                )?;
+            }
+            Ok(())
+        })?;
+
+        Ok(stale.len())
+    }
 }
 
 /// 同步统计
@@ -269,6 +469,7 @@ pub struct SyncStats {
     pub skipped_files: usize,
     pub failed_files: usize,
     pub new_chunks: usize,
+    pub reembedded_chunks: usize,
 }
 
 impl SyncStats {
@@ -280,6 +481,25 @@ impl SyncStats {
     }
 }
 
+/// 修正分块来源，并为索引时尚未入库的活动补齐 activity_id
+fn link_chunk_metadata(conn: &rusqlite::Connection) -> Result<()> {
+    for (prefix, source) in SOURCE_PREFIXES {
+        conn.execute(
+            "UPDATE memory_chunks SET source = ?1
+             WHERE source != ?1 AND substr(file_path, 1, length(?2)) = ?2",
+            rusqlite::params![source, prefix],
+        )?;
+    }
+
+    conn.execute(
+        "UPDATE memory_chunks
+         SET activity_id = (SELECT a.id FROM activities a WHERE a.markdown_path = memory_chunks.file_path)
+         WHERE activity_id IS NULL AND source = 'activity'",
+        [],
+    )?;
+    Ok(())
+}
+
 /// 计算文件哈希
 fn compute_file_hash(content: &str) -> String {
     let mut hasher = Sha256::new();
@@ -301,4 +521,134 @@ mod tests {
         assert_ne!(hash1, hash3);
         assert_eq!(hash1.len(), 16);
     }
+
+    #[test]
+    fn test_source_for_path() {
+        assert_eq!(source_for_path("activities/2024-01-15/activity-001.md"), "activity");
+        assert_eq!(source_for_path("project/vision-jarvis.md"), "project");
+        assert_eq!(source_for_path("habits/morning.md"), "habit");
+        assert_eq!(source_for_path("long_term_memory/daily_summary/2024-01-15.md"), "summary");
+        assert_eq!(source_for_path("notes.md"), "other");
+    }
+
+    #[tokio::test]
+    async fn test_sync_links_activity_chunks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        fs::create_dir_all(dir.path().join("activities/2024-01-15")).unwrap();
+        fs::write(dir.path().join("activities/2024-01-15/activity-001.md"), "编写代码\n").unwrap();
+        manager.sync().await.unwrap();
+
+        // 活动在索引之后才入库，下次同步时补齐关联
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes,
+                    application, category, screenshot_ids, markdown_path)
+                 VALUES ('act-1', '编写代码', 0, 60, 1, 'VSCode', 'work', '[]',
+                    'activities/2024-01-15/activity-001.md')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        manager.sync().await.unwrap();
+
+        let (source, activity_id): (String, Option<String>) = db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT source, activity_id FROM memory_chunks", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })?)
+        }).unwrap();
+        assert_eq!(source, "activity");
+        assert_eq!(activity_id.as_deref(), Some("act-1"));
+    }
+
+    fn write_md(root: &Path, name: &str, body: &str) {
+        fs::write(root.join(name), format!("---\nid: {}\n---\n{}\n", name, body)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync_generates_embeddings_with_cache() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        write_md(dir.path(), "a.md", "调试 Rust 编译错误");
+        write_md(dir.path(), "b.md", "调试 Rust 编译错误");
+
+        let stats = manager.sync().await.unwrap();
+        assert_eq!(stats.indexed_files, 2);
+        assert_eq!(stats.new_chunks, 2);
+
+        let (chunks, empty, cached): (i64, i64, i64) = db.with_connection(|conn| {
+            Ok((
+                conn.query_row("SELECT COUNT(*) FROM memory_chunks", [], |r| r.get(0))?,
+                conn.query_row("SELECT COUNT(*) FROM memory_chunks WHERE length(embedding) = 0", [], |r| r.get(0))?,
+                conn.query_row("SELECT COUNT(*) FROM embedding_cache", [], |r| r.get(0))?,
+            ))
+        }).unwrap();
+        assert_eq!(chunks, 2);
+        assert_eq!(empty, 0);
+        // 相同内容的分块共享缓存条目
+        assert_eq!(cached, 1);
+
+        // 未变化的文件跳过
+        let stats = manager.sync().await.unwrap();
+        assert_eq!(stats.skipped_files, 2);
+        assert_eq!(stats.reembedded_chunks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sync_reindexes_changed_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        write_md(dir.path(), "a.md", "first version");
+        manager.sync().await.unwrap();
+        write_md(dir.path(), "a.md", "second version");
+        let stats = manager.sync().await.unwrap();
+        assert_eq!(stats.indexed_files, 1);
+
+        let texts: Vec<String> = db.with_connection(|conn| {
+            let mut stmt = conn.prepare("SELECT text FROM memory_chunks")?;
+            let rows = stmt.query_map([], |r| r.get(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        }).unwrap();
+        assert_eq!(texts, vec!["second version".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_set_embedder_reembeds_stale_chunks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        write_md(dir.path(), "a.md", "some content");
+        manager.sync().await.unwrap();
+
+        manager.set_embedder(Arc::new(HashEmbeddingProvider::new(64))).await;
+        let stats = manager.sync().await.unwrap();
+        assert_eq!(stats.reembedded_chunks, 1);
+
+        let (model, len): (String, i64) = db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT model, length(embedding) FROM memory_chunks", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })?)
+        }).unwrap();
+        assert_eq!(model, "local-hash-64");
+        assert_eq!(len, 64 * 4);
+    }
 }
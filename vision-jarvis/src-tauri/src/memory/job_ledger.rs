@@ -0,0 +1,524 @@
+/// 后台任务台账与补跑
+///
+/// `job_ledger` 表记录日/周/月总结与录制分析的执行结果。应用没有在 23:00 运行、
+/// 或录制没来得及即时分析就退出时，调度器启动后按台账与现有数据找出缺口并分批补跑；
+/// 失败后按指数退避等待再重试，连续失败 `MAX_ATTEMPTS` 次后放弃，避免每轮都重复消耗 AI 调用。
+/// 网络、限流、服务端错误与预算用尽属于暂时性失败，只推迟不计入尝试次数
+
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::ai::retry::is_retryable;
+use crate::ai::usage::is_budget_exceeded;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::db::schema::SummaryType;
+use super::summary_generator::{complete_after, month_bounds, summary_id, week_bounds};
+
+/// 同一目标最多尝试次数
+pub const MAX_ATTEMPTS: i64 = 5;
+
+/// 第 n 次失败后等待 `RETRY_BASE_SECS * 2^(n-1)` 秒再重试（1h、2h、4h、8h）
+pub const RETRY_BASE_SECS: i64 = 3600;
+
+/// 台账中的任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    DailySummary,
+    WeeklySummary,
+    MonthlySummary,
+    RecordingAnalysis,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DailySummary => "daily_summary",
+            Self::WeeklySummary => "weekly_summary",
+            Self::MonthlySummary => "monthly_summary",
+            Self::RecordingAnalysis => "recording_analysis",
+        }
+    }
+}
+
+/// 待补跑的总结（target 为日期；周总结取周一，月总结取当月 1 日）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingJob {
+    pub kind: JobKind,
+    pub target: String,
+}
+
+/// 待补分析的录制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRecording {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+/// 一轮补跑的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CatchUpReport {
+    /// 补分析成功的录制数
+    pub analyzed: usize,
+    /// 补分析失败的录制数
+    pub analysis_failed: usize,
+    /// 补生成的总结数
+    pub summaries: usize,
+    /// 补生成失败的总结数
+    pub summary_failed: usize,
+    /// 因 AI 预算用尽而提前结束
+    pub budget_exhausted: bool,
+}
+
+impl CatchUpReport {
+    pub fn is_empty(&self) -> bool {
+        self.analyzed + self.analysis_failed + self.summaries + self.summary_failed == 0
+    }
+}
+
+/// 任务台账
+pub struct JobLedger {
+    db: Arc<Database>,
+}
+
+impl JobLedger {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 记录成功
+    pub fn record_success(&self, kind: JobKind, target: &str) -> Result<()> {
+        self.upsert(kind, target, "done", None, 1)
+    }
+
+    /// 记录失败（累加尝试次数）
+    pub fn record_failure(&self, kind: JobKind, target: &str, error: &str) -> Result<()> {
+        self.upsert(kind, target, "failed", Some(error), 1)
+    }
+
+    /// 记录暂时性失败（不累加尝试次数，下一轮照常重试）
+    pub fn record_deferral(&self, kind: JobKind, target: &str, error: &str) -> Result<()> {
+        self.upsert(kind, target, "deferred", Some(error), 0)
+    }
+
+    /// 删除记录（录制分析成功后以 `analyzed` 列为准，不再保留台账行）
+    pub fn clear(&self, kind: JobKind, target: &str) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "DELETE FROM job_ledger WHERE job = ?1 AND target = ?2",
+                params![kind.as_str(), target],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// 是否还需要执行：未成功过、失败次数未达上限且已过退避时间
+    pub fn should_run(&self, kind: JobKind, target: &str) -> Result<bool> {
+        let row: Option<(String, i64, i64)> = self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT status, attempts, updated_at FROM job_ledger WHERE job = ?1 AND target = ?2",
+                    params![kind.as_str(), target],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?)
+        })?;
+
+        Ok(match row {
+            Some((status, attempts, updated_at)) => {
+                status != "done"
+                    && attempts < MAX_ATTEMPTS
+                    && Utc::now().timestamp() >= updated_at + retry_delay_secs(attempts)
+            }
+            None => true,
+        })
+    }
+
+    /// 找出 today（UTC）之前 lookback_days 天内缺失的总结
+    ///
+    /// 有活动但没有日总结的日期、已结束但没有总结的周与月，按日 → 周 → 月排列，
+    /// 周/月总结会复用先补出的日总结。周期结束前生成的总结（手动生成、月总结顺带生成的跨月周总结）
+    /// 缺少之后的活动，同样视为缺失。仍有未分析录制的日期暂缓，等录制补分析后再总结
+    pub fn missing_summaries(&self, today: NaiveDate, lookback_days: i64) -> Result<Vec<PendingJob>> {
+        let from = start_of_day(today - Duration::days(lookback_days));
+        let to = start_of_day(today);
+
+        let (active_days, pending_days, existing) = self.db.with_connection(|conn| {
+            let days = |sql: &str| -> Result<BTreeSet<NaiveDate>> {
+                let mut stmt = conn.prepare(sql)?;
+                let days = stmt
+                    .query_map(params![from, to], |row| row.get::<_, String>(0))?
+                    .filter_map(|d| d.ok())
+                    .filter_map(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+                    .collect();
+                Ok(days)
+            };
+
+            let active = days(
+                "SELECT DISTINCT date(start_time, 'unixepoch') FROM activities
+                 WHERE start_time >= ?1 AND start_time < ?2",
+            )?;
+            let pending = days(
+                "SELECT DISTINCT date(start_time, 'unixepoch') FROM recordings
+                 WHERE analyzed = 0 AND purged_at IS NULL AND archived_at IS NULL
+                   AND start_time >= ?1 AND start_time < ?2",
+            )?;
+
+            let mut stmt = conn.prepare("SELECT id, created_at FROM summaries")?;
+            let existing: HashMap<String, i64> = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .filter_map(|row| row.ok())
+                .collect();
+
+            Ok((active, pending, existing))
+        })?;
+
+        let mut jobs = Vec::new();
+        let mut push = |kind: JobKind, summary_type: SummaryType, start: NaiveDate, end: NaiveDate| -> Result<()> {
+            let target = start.to_string();
+            let complete = existing
+                .get(&summary_id(&summary_type, start, end))
+                .is_some_and(|&created_at| created_at >= complete_after(end));
+            if !complete && self.should_run(kind, &target)?
+            {
+                jobs.push(PendingJob { kind, target });
+            }
+            Ok(())
+        };
+
+        for &day in active_days.difference(&pending_days) {
+            push(JobKind::DailySummary, SummaryType::Daily, day, day)?;
+        }
+
+        let periods = |bounds: fn(NaiveDate) -> (NaiveDate, NaiveDate)| -> BTreeSet<(NaiveDate, NaiveDate)> {
+            active_days.iter()
+                .map(|&day| bounds(day))
+                .filter(|&(start, end)| {
+                    end < today && !pending_days.iter().any(|d| (start..=end).contains(d))
+                })
+                .collect()
+        };
+
+        for (start, end) in periods(week_bounds) {
+            push(JobKind::WeeklySummary, SummaryType::Weekly, start, end)?;
+        }
+        for (start, end) in periods(month_bounds) {
+            push(JobKind::MonthlySummary, SummaryType::Monthly, start, end)?;
+        }
+
+        Ok(jobs)
+    }
+
+    /// 结束超过 min_age_secs 仍未分析的录制（按时间先后，跳过已放弃与退避中的）
+    ///
+    /// 新录制交给即时分析，留出间隔以免两边重复分析同一段
+    pub fn pending_recordings(&self, min_age_secs: i64, limit: usize) -> Result<Vec<PendingRecording>> {
+        let now = Utc::now().timestamp();
+        let cutoff = now - min_age_secs;
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT r.id, r.path FROM recordings r
+                 WHERE r.analyzed = 0 AND r.purged_at IS NULL AND r.archived_at IS NULL
+                   AND COALESCE(r.end_time, r.start_time) < ?1
+                   AND NOT EXISTS (
+                       SELECT 1 FROM job_ledger j
+                       WHERE j.job = ?2 AND j.target = r.id
+                         AND (j.attempts >= ?3 OR j.updated_at + ?5 * ((1 << j.attempts) >> 1) > ?6)
+                   )
+                 ORDER BY r.start_time ASC
+                 LIMIT ?4",
+            )?;
+            let recordings = stmt
+                .query_map(
+                    params![
+                        cutoff,
+                        JobKind::RecordingAnalysis.as_str(),
+                        MAX_ATTEMPTS,
+                        limit as i64,
+                        RETRY_BASE_SECS,
+                        now,
+                    ],
+                    |row| Ok(PendingRecording {
+                        id: row.get(0)?,
+                        path: PathBuf::from(row.get::<_, String>(1)?),
+                    }),
+                )?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(recordings)
+        })
+    }
+
+    /// 仍等待分析的录制文件路径（未分析、未清理，台账也没有放弃）
+    ///
+    /// 保留策略不删除这些文件，否则录制在补分析之前就被清理，这段记忆再也找不回来
+    pub fn awaiting_analysis_paths(&self) -> Result<HashSet<String>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT r.path FROM recordings r
+                 WHERE r.analyzed = 0 AND r.purged_at IS NULL AND r.archived_at IS NULL
+                   AND NOT EXISTS (
+                       SELECT 1 FROM job_ledger j
+                       WHERE j.job = ?1 AND j.target = r.id AND j.attempts >= ?2
+                   )",
+            )?;
+            let paths = stmt
+                .query_map(
+                    params![JobKind::RecordingAnalysis.as_str(), MAX_ATTEMPTS],
+                    |row| row.get(0),
+                )?
+                .collect::<std::result::Result<HashSet<String>, _>>()?;
+            Ok(paths)
+        })
+    }
+
+    fn upsert(&self, kind: JobKind, target: &str, status: &str, error: Option<&str>, attempts: i64) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO job_ledger (job, target, status, attempts, last_error, updated_at)
+                 VALUES (?1, ?2, ?3, ?6, ?4, ?5)
+                 ON CONFLICT(job, target) DO UPDATE SET
+                     status = excluded.status,
+                     attempts = job_ledger.attempts + ?6,
+                     last_error = excluded.last_error,
+                     updated_at = excluded.updated_at",
+                params![kind.as_str(), target, status, error, Utc::now().timestamp(), attempts],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+/// 失败 attempts 次后的重试等待时间（未失败过为 0）
+fn retry_delay_secs(attempts: i64) -> i64 {
+    RETRY_BASE_SECS * ((1_i64 << attempts.clamp(0, 32)) >> 1)
+}
+
+/// 是否为暂时性失败：网络、限流、服务端错误或超出 AI 预算
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<AppError>())
+        .any(|e| is_retryable(e) || is_budget_exceeded(e))
+}
+
+/// 是否因超出 AI 预算而失败
+pub fn is_budget_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<AppError>())
+        .any(is_budget_exceeded)
+}
+
+fn start_of_day(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn insert_activity(db: &Database, id: &str, day: &str) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                        category, screenshot_ids, markdown_path)
+                 VALUES (?1, 't', ?2, ?2 + 600, 10, 'VSCode', '\"work\"', '[]', ?1 || '.md')",
+                params![id, start_of_day(date(day)) + 3600],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn insert_recording(db: &Database, id: &str, start: i64) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO recordings (id, path, start_time, end_time) VALUES (?1, ?2, ?3, ?3 + 60)",
+                params![id, format!("/tmp/{}.mp4", id), start],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn insert_summary(db: &Database, id: &str, created_at: i64) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO summaries (id, summary_type, date_start, date_end, content, markdown_path, created_at)
+                 VALUES (?1, 'daily', '', '', '', '', ?2)",
+                params![id, created_at],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// 把台账记录的更新时间往前拨，模拟退避时间已过
+    fn backdate(db: &Database, target: &str, secs: i64) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE job_ledger SET updated_at = updated_at - ?2 WHERE target = ?1",
+                params![target, secs],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn targets(jobs: &[PendingJob], kind: JobKind) -> Vec<&str> {
+        jobs.iter().filter(|j| j.kind == kind).map(|j| j.target.as_str()).collect()
+    }
+
+    #[test]
+    fn test_attempts_and_status() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let ledger = JobLedger::new(Arc::clone(&db));
+        let kind = JobKind::DailySummary;
+
+        assert!(ledger.should_run(kind, "2026-10-12").unwrap());
+        for attempts in 1..MAX_ATTEMPTS {
+            ledger.record_failure(kind, "2026-10-12", "timeout").unwrap();
+            // 退避期间不执行
+            assert!(!ledger.should_run(kind, "2026-10-12").unwrap());
+            backdate(&db, "2026-10-12", retry_delay_secs(attempts));
+            assert!(ledger.should_run(kind, "2026-10-12").unwrap());
+        }
+        ledger.record_failure(kind, "2026-10-12", "timeout").unwrap();
+        backdate(&db, "2026-10-12", retry_delay_secs(MAX_ATTEMPTS));
+        assert!(!ledger.should_run(kind, "2026-10-12").unwrap());
+
+        // 暂时性失败不计入尝试次数，也不退避
+        ledger.record_deferral(kind, "2026-10-14", "rate limited").unwrap();
+        ledger.record_deferral(kind, "2026-10-14", "rate limited").unwrap();
+        assert!(ledger.should_run(kind, "2026-10-14").unwrap());
+
+        ledger.record_success(kind, "2026-10-13").unwrap();
+        assert!(!ledger.should_run(kind, "2026-10-13").unwrap());
+
+        ledger.clear(kind, "2026-10-12").unwrap();
+        assert!(ledger.should_run(kind, "2026-10-12").unwrap());
+    }
+
+    #[test]
+    fn test_missing_summaries() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        // 10-05 ~ 10-11 为完整的一周，10-12 起的一周尚未结束
+        insert_activity(&db, "a1", "2026-10-06");
+        insert_activity(&db, "a2", "2026-10-08");
+        insert_activity(&db, "a3", "2026-10-13");
+        insert_activity(&db, "a4", "2026-10-16");
+        insert_activity(&db, "old", "2026-08-01");
+        insert_summary(&db, "summary-daily-2026-10-08", complete_after(date("2026-10-08")));
+
+        let ledger = JobLedger::new(Arc::clone(&db));
+        let jobs = ledger.missing_summaries(date("2026-10-16"), 30).unwrap();
+
+        assert_eq!(targets(&jobs, JobKind::DailySummary), vec!["2026-10-06", "2026-10-13"]);
+        assert_eq!(targets(&jobs, JobKind::WeeklySummary), vec!["2026-10-05"]);
+        assert!(targets(&jobs, JobKind::MonthlySummary).is_empty());
+
+        // 9 月已结束，有活动即补月总结
+        let jobs = ledger.missing_summaries(date("2026-11-02"), 40).unwrap();
+        assert_eq!(targets(&jobs, JobKind::MonthlySummary), vec!["2026-10-01"]);
+
+        // 放弃的目标不再出现
+        for _ in 0..MAX_ATTEMPTS {
+            ledger.record_failure(JobKind::DailySummary, "2026-10-06", "err").unwrap();
+        }
+        let jobs = ledger.missing_summaries(date("2026-10-16"), 30).unwrap();
+        assert_eq!(targets(&jobs, JobKind::DailySummary), vec!["2026-10-13"]);
+    }
+
+    #[test]
+    fn test_partial_summaries_are_missing() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        insert_activity(&db, "a1", "2026-10-06");
+        insert_activity(&db, "a2", "2026-10-08");
+        // 10-06 的日总结在当天上午手动生成；10-05 周的周总结在周日中午随月总结生成
+        insert_summary(&db, "summary-daily-2026-10-06", start_of_day(date("2026-10-06")) + 10 * 3600);
+        insert_summary(&db, "summary-daily-2026-10-08", complete_after(date("2026-10-08")));
+        insert_summary(&db, "summary-weekly-2026-10-05", start_of_day(date("2026-10-11")) + 12 * 3600);
+
+        let ledger = JobLedger::new(Arc::clone(&db));
+        let jobs = ledger.missing_summaries(date("2026-10-16"), 30).unwrap();
+        assert_eq!(targets(&jobs, JobKind::DailySummary), vec!["2026-10-06"]);
+        assert_eq!(targets(&jobs, JobKind::WeeklySummary), vec!["2026-10-05"]);
+    }
+
+    #[test]
+    fn test_days_with_pending_recordings_wait() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        insert_activity(&db, "a1", "2026-10-06");
+        insert_recording(&db, "r1", start_of_day(date("2026-10-06")) + 7200);
+
+        let ledger = JobLedger::new(Arc::clone(&db));
+        let jobs = ledger.missing_summaries(date("2026-10-16"), 30).unwrap();
+        assert!(jobs.is_empty());
+
+        db.with_connection(|conn| {
+            conn.execute("UPDATE recordings SET analyzed = 1 WHERE id = 'r1'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let jobs = ledger.missing_summaries(date("2026-10-16"), 30).unwrap();
+        assert_eq!(targets(&jobs, JobKind::DailySummary), vec!["2026-10-06"]);
+        assert_eq!(targets(&jobs, JobKind::WeeklySummary), vec!["2026-10-05"]);
+    }
+
+    #[test]
+    fn test_pending_recordings() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let now = Utc::now().timestamp();
+        insert_recording(&db, "old", now - 7200);
+        insert_recording(&db, "older", now - 9000);
+        insert_recording(&db, "fresh", now - 30);
+        insert_recording(&db, "purged", now - 7200);
+        insert_recording(&db, "given-up", now - 7200);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE recordings SET purged_at = 1 WHERE id = 'purged'", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        insert_recording(&db, "backing-off", now - 7200);
+        insert_recording(&db, "retry-due", now - 7100);
+        insert_recording(&db, "deferred", now - 7000);
+
+        let ledger = JobLedger::new(Arc::clone(&db));
+        let kind = JobKind::RecordingAnalysis;
+        for _ in 0..MAX_ATTEMPTS {
+            ledger.record_failure(kind, "given-up", "err").unwrap();
+        }
+        backdate(&db, "given-up", retry_delay_secs(MAX_ATTEMPTS));
+        ledger.record_failure(kind, "backing-off", "err").unwrap();
+        ledger.record_failure(kind, "retry-due", "err").unwrap();
+        ledger.record_failure(kind, "retry-due", "err").unwrap();
+        backdate(&db, "retry-due", retry_delay_secs(2));
+        ledger.record_deferral(kind, "deferred", "timeout").unwrap();
+
+        let ids: Vec<String> = ledger.pending_recordings(600, 10).unwrap()
+            .into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["older", "old", "retry-due", "deferred"]);
+
+        assert_eq!(ledger.pending_recordings(600, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_classifies_transient_errors() {
+        let wrap = |e: AppError| anyhow::Error::new(e).context("AI视频分析失败");
+        assert!(is_transient(&wrap(AppError::network(1, "请求超时"))));
+        assert!(is_transient(&wrap(crate::ai::retry::status_error(503, "", None))));
+        assert!(is_transient(&wrap(AppError::ai(30, "预算"))));
+        assert!(is_budget_error(&wrap(AppError::ai(30, "预算"))));
+        assert!(!is_budget_error(&wrap(AppError::network(1, "请求超时"))));
+        assert!(!is_transient(&wrap(crate::ai::retry::status_error(401, "", None))));
+        assert!(!is_transient(&anyhow::anyhow!("解析响应失败")));
+    }
+}
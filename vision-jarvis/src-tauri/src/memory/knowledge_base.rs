@@ -0,0 +1,746 @@
+/// 知识库模式（Obsidian / Logseq）
+///
+/// 开启后存储目录可直接作为知识库打开：
+/// - 日总结、周/月/时段总结、活动、项目、习惯笔记之间以 `[[文件名]]` 互相链接（两个工具都按文件名解析）
+/// - frontmatter 补齐 `type` / `date` / `aliases`，`tags` 规范为不含空格的 YAML 列表
+/// - `index/` 下重建日记、项目、习惯与反向链接索引
+///
+/// 链接写在笔记末尾的托管区块内，每次重建整体替换；生成器写出的正文保持不变
+
+use anyhow::Result;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::settings::AppSettings;
+
+/// 索引笔记目录（相对存储根目录，不参与检索索引）
+pub const INDEX_DIR: &str = "index";
+
+/// 托管区块标记
+const LINKS_BEGIN: &str = "<!-- vision-jarvis:links -->";
+const LINKS_END: &str = "<!-- /vision-jarvis:links -->";
+
+/// 习惯笔记最多链接的日总结数
+const HABIT_DAY_LINKS: usize = 30;
+
+/// 知识库配置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnowledgeBaseConfig {
+    /// 是否开启知识库模式
+    pub enabled: bool,
+}
+
+impl From<&AppSettings> for KnowledgeBaseConfig {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            enabled: settings.knowledge_base_mode,
+        }
+    }
+}
+
+/// 重建报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeBaseReport {
+    /// 参与链接的笔记数
+    pub notes: usize,
+    /// 内容有变化、重新写入的笔记数
+    pub updated: usize,
+    /// 数据库中有记录但文件已不存在的笔记数
+    pub missing: usize,
+    /// 写入的链接总数
+    pub links: usize,
+    /// 重建的索引笔记数
+    pub index_files: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum NoteKind {
+    Daily,
+    /// 周/月/时段总结
+    Period,
+    Activity,
+    Project,
+    Habit,
+}
+
+impl NoteKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NoteKind::Daily => "daily",
+            NoteKind::Period => "summary",
+            NoteKind::Activity => "activity",
+            NoteKind::Project => "project",
+            NoteKind::Habit => "habit",
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            NoteKind::Daily => "日总结",
+            NoteKind::Period => "周期总结",
+            NoteKind::Activity => "活动",
+            NoteKind::Project => "项目",
+            NoteKind::Habit => "习惯",
+        }
+    }
+}
+
+/// 知识库中的一篇笔记
+#[derive(Debug)]
+struct Note {
+    kind: NoteKind,
+    /// 相对存储根目录的路径；没有日总结的日期为 None（链接仍然指向该日期）
+    path: Option<String>,
+    /// 链接名（文件名去掉扩展名）
+    stem: String,
+    title: String,
+    date: Option<String>,
+    tags: Vec<String>,
+    /// 出链（笔记下标）
+    links: BTreeSet<usize>,
+}
+
+#[derive(Default)]
+struct Graph {
+    notes: Vec<Note>,
+    dailies: HashMap<String, usize>,
+}
+
+impl Graph {
+    fn add(&mut self, note: Note) -> usize {
+        self.notes.push(note);
+        self.notes.len() - 1
+    }
+
+    /// 日期对应的日总结，不存在时建一个只用于链接的占位
+    fn daily(&mut self, date: &str) -> usize {
+        if let Some(&idx) = self.dailies.get(date) {
+            return idx;
+        }
+        let idx = self.add(Note {
+            kind: NoteKind::Daily,
+            path: None,
+            stem: date.to_string(),
+            title: format!("{} 日总结", date),
+            date: Some(date.to_string()),
+            tags: Vec::new(),
+            links: BTreeSet::new(),
+        });
+        self.dailies.insert(date.to_string(), idx);
+        idx
+    }
+
+    fn link(&mut self, a: usize, b: usize) {
+        if a != b {
+            self.notes[a].links.insert(b);
+            self.notes[b].links.insert(a);
+        }
+    }
+
+    fn backlinks(&self) -> Vec<Vec<usize>> {
+        let mut result = vec![Vec::new(); self.notes.len()];
+        for (source, note) in self.notes.iter().enumerate() {
+            for &target in &note.links {
+                result[target].push(source);
+            }
+        }
+        result
+    }
+}
+
+/// 知识库维护器
+pub struct KnowledgeBase {
+    db: Arc<Database>,
+    root: PathBuf,
+    config: RwLock<KnowledgeBaseConfig>,
+}
+
+impl KnowledgeBase {
+    pub fn new(db: Arc<Database>, root: PathBuf, config: KnowledgeBaseConfig) -> Self {
+        Self {
+            db,
+            root,
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn config(&self) -> KnowledgeBaseConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// 设置变更后更新配置，下次重建生效
+    pub fn set_config(&self, config: KnowledgeBaseConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 按数据库中的关联重写各笔记的链接区块与 frontmatter，并重建索引；未开启时直接返回
+    pub fn rebuild(&self) -> Result<KnowledgeBaseReport> {
+        let mut report = KnowledgeBaseReport::default();
+        if !self.config().enabled {
+            return Ok(report);
+        }
+        if self.db.vault().is_locked() {
+            return Err(AppError::storage(10, "数据已加密，请先解锁").into());
+        }
+
+        let graph = self.load_graph()?;
+        report.notes = graph.notes.iter().filter(|n| n.path.is_some()).count();
+
+        for note in &graph.notes {
+            let Some(path) = &note.path else { continue };
+            let full_path = self.root.join(path);
+            if !full_path.exists() {
+                report.missing += 1;
+                continue;
+            }
+            let original = self.db.vault().read_to_string(&full_path)?;
+            let rendered = render_note(&original, note, &graph);
+            report.links += note.links.len();
+            if rendered != original {
+                self.db.vault().write_file(&full_path, rendered.as_bytes())?;
+                report.updated += 1;
+            }
+        }
+
+        for (name, content) in render_indexes(&graph) {
+            self.write_index(name, &content)?;
+            report.index_files += 1;
+        }
+
+        Ok(report)
+    }
+
+    fn write_index(&self, name: &str, content: &str) -> Result<()> {
+        let dir = self.root.join(INDEX_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.md", name));
+        let unchanged = path.exists()
+            && self.db.vault().read_to_string(&path).ok().as_deref() == Some(content);
+        if !unchanged {
+            self.db.vault().write_file(&path, content.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// 从数据库构建笔记关联图
+    fn load_graph(&self) -> Result<Graph> {
+        let mut graph = Graph::default();
+
+        let summaries: Vec<(String, String)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT date_start, markdown_path FROM summaries
+                 WHERE summary_type = 'daily' ORDER BY date_start",
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        for (date, path) in summaries {
+            let idx = graph.daily(&date);
+            graph.notes[idx].stem = note_stem(&path);
+            graph.notes[idx].path = Some(path);
+        }
+
+        let mut projects = HashMap::new();
+        let project_rows: Vec<(String, String, String, String)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, title, tags, markdown_path FROM projects ORDER BY start_date",
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        for (id, title, tags, path) in project_rows {
+            let idx = graph.add(Note {
+                kind: NoteKind::Project,
+                stem: note_stem(&path),
+                path: Some(path),
+                title,
+                date: None,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                links: BTreeSet::new(),
+            });
+            projects.insert(id, idx);
+        }
+
+        // 习惯按涉及的应用与活动关联
+        let mut habits_by_app: HashMap<String, Vec<usize>> = HashMap::new();
+        let habit_rows: Vec<(String, Option<String>, String)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT pattern_name, trigger_conditions, markdown_path FROM habits
+                 ORDER BY confidence DESC",
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        for (pattern_name, trigger_conditions, path) in habit_rows {
+            let apps = habit_apps(&pattern_name, trigger_conditions.as_deref());
+            let idx = graph.add(Note {
+                kind: NoteKind::Habit,
+                stem: note_stem(&path),
+                path: Some(path),
+                title: pattern_name,
+                date: None,
+                tags: apps.clone(),
+                links: BTreeSet::new(),
+            });
+            for app in apps {
+                habits_by_app.entry(app).or_default().push(idx);
+            }
+        }
+
+        let activity_rows: Vec<(String, i64, String, String, String, Option<String>)> =
+            self.db.with_connection(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT title, start_time, application, tags, markdown_path, project_id
+                     FROM activities ORDER BY start_time",
+                )?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(rows)
+            })?;
+        // 习惯 → 出现过的日期（新到旧）
+        let mut habit_days: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (title, start_time, application, tags, path, project_id) in activity_rows {
+            let date = format_date(start_time);
+            let idx = graph.add(Note {
+                kind: NoteKind::Activity,
+                stem: note_stem(&path),
+                path: Some(path),
+                title,
+                date: Some(date.clone()),
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                links: BTreeSet::new(),
+            });
+
+            let daily = graph.daily(&date);
+            graph.link(idx, daily);
+            if let Some(&project) = project_id.as_ref().and_then(|id| projects.get(id)) {
+                graph.link(idx, project);
+                graph.link(daily, project);
+            }
+            for &habit in habits_by_app.get(&application).into_iter().flatten() {
+                graph.link(idx, habit);
+                let days = habit_days.entry(habit).or_default();
+                if days.last() != Some(&date) {
+                    days.push(date.clone());
+                }
+            }
+        }
+        for (habit, days) in habit_days {
+            for date in days.iter().rev().take(HABIT_DAY_LINKS) {
+                let daily = graph.dailies[date];
+                graph.link(habit, daily);
+            }
+        }
+
+        self.load_periods(&mut graph)?;
+
+        Ok(graph)
+    }
+
+    /// 周/月/时段总结：链接范围内的日总结，月/时段总结再链接与之重叠的周总结
+    fn load_periods(&self, graph: &mut Graph) -> Result<()> {
+        let rows: Vec<(String, String, String, String)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT summary_type, date_start, date_end, markdown_path FROM summaries
+                 WHERE summary_type != 'daily' ORDER BY date_start, summary_type",
+            )?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+
+        let mut periods = Vec::new();
+        for (summary_type, start, end, path) in rows {
+            let stem = note_stem(&path);
+            let title = match summary_type.as_str() {
+                "weekly" => format!("{} 周总结", stem),
+                "monthly" => format!("{} 月总结", stem),
+                _ => format!("{} ~ {} 时段总结", start, end),
+            };
+            let idx = graph.add(Note {
+                kind: NoteKind::Period,
+                stem,
+                path: Some(path),
+                title,
+                date: None,
+                tags: vec![summary_type.clone()],
+                links: BTreeSet::new(),
+            });
+
+            let days: Vec<usize> = graph.dailies.iter()
+                .filter(|(date, _)| date.as_str() >= start.as_str() && date.as_str() <= end.as_str())
+                .map(|(_, &daily)| daily)
+                .collect();
+            for daily in days {
+                graph.link(idx, daily);
+            }
+            periods.push((idx, summary_type, start, end));
+        }
+
+        for (idx, summary_type, start, end) in &periods {
+            if summary_type == "weekly" {
+                continue;
+            }
+            for (week, week_type, week_start, week_end) in &periods {
+                if week_type == "weekly" && week_start <= end && week_end >= start {
+                    graph.link(*idx, *week);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 链接名：文件名去掉扩展名
+fn note_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// 时间戳对应的日期（与活动目录、日总结一致使用 UTC）
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// 习惯涉及的应用：触发/序列模式取自 trigger_conditions，时间模式取自名称
+fn habit_apps(pattern_name: &str, trigger_conditions: Option<&str>) -> Vec<String> {
+    if let Some(json) = trigger_conditions.and_then(|t| serde_json::from_str::<serde_json::Value>(t).ok()) {
+        let mut apps: Vec<String> = ["from_app", "to_app"]
+            .iter()
+            .filter_map(|key| json.get(key).and_then(|v| v.as_str()).map(str::to_string))
+            .collect();
+        if let Some(sequence) = json.get("sequence").and_then(|v| v.as_array()) {
+            apps.extend(sequence.iter().filter_map(|v| v.as_str().map(str::to_string)));
+        }
+        apps.dedup();
+        return apps;
+    }
+    pattern_name
+        .split_once("使用 ")
+        .map(|(_, app)| vec![app.trim().to_string()])
+        .unwrap_or_default()
+}
+
+/// Obsidian 标签：不含空格与标点，不能是纯数字
+fn normalize_tag(tag: &str) -> Option<String> {
+    let normalized: String = tag
+        .trim()
+        .trim_start_matches('#')
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '-' | '/') { c } else { '-' })
+        .collect();
+    let normalized = normalized.trim_matches('-').to_string();
+    if normalized.is_empty() || normalized.chars().all(|c| c.is_ascii_digit()) {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+fn wiki_link(note: &Note) -> String {
+    if note.title == note.stem {
+        format!("[[{}]]", note.stem)
+    } else {
+        format!("[[{}]] {}", note.stem, note.title)
+    }
+}
+
+/// 拆分 frontmatter 与正文
+fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    if let Some(rest) = content.strip_prefix("---\n") {
+        if let Some(end) = rest.find("\n---") {
+            let body = &rest[end + 4..];
+            return (Some(&rest[..end]), body.strip_prefix('\n').unwrap_or(body));
+        }
+    }
+    (None, content)
+}
+
+/// 去掉旧的托管区块
+fn strip_links_block(body: &str) -> String {
+    match (body.find(LINKS_BEGIN), body.find(LINKS_END)) {
+        (Some(start), Some(end)) if end > start => {
+            let mut result = body[..start].trim_end().to_string();
+            let rest = body[end + LINKS_END.len()..].trim();
+            if !rest.is_empty() {
+                result.push_str("\n\n");
+                result.push_str(rest);
+            }
+            result
+        }
+        _ => body.trim_end().to_string(),
+    }
+}
+
+fn render_frontmatter(existing: Option<&str>, note: &Note) -> String {
+    // 手写的 frontmatter 可能不是合法 YAML（如标题含冒号），此时只保留我们能确定的字段
+    let mut map = existing
+        .and_then(|yaml| serde_yaml::from_str::<Mapping>(yaml).ok())
+        .unwrap_or_default();
+
+    let key = |k: &str| Value::String(k.to_string());
+    if !map.contains_key("title") {
+        map.insert(key("title"), Value::String(note.title.clone()));
+    }
+    // 周/月/时段总结保留生成器写入的 weekly / monthly / range
+    if note.kind != NoteKind::Period || !map.contains_key("type") {
+        map.insert(key("type"), Value::String(note.kind.as_str().to_string()));
+    }
+    if let Some(date) = &note.date {
+        map.insert(key("date"), Value::String(date.clone()));
+    }
+
+    let mut tags = vec![note.kind.as_str().to_string()];
+    for tag in note.tags.iter().filter_map(|t| normalize_tag(t)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    map.insert(key("tags"), Value::Sequence(tags.into_iter().map(Value::String).collect()));
+    if note.title != note.stem {
+        map.insert(key("aliases"), Value::Sequence(vec![Value::String(note.title.clone())]));
+    }
+
+    let yaml = serde_yaml::to_string(&map).unwrap_or_default();
+    format!("---\n{}---\n", yaml)
+}
+
+fn render_links_block(note: &Note, graph: &Graph) -> String {
+    let mut grouped: BTreeMap<NoteKind, Vec<&Note>> = BTreeMap::new();
+    for &idx in &note.links {
+        grouped.entry(graph.notes[idx].kind).or_default().push(&graph.notes[idx]);
+    }
+
+    let mut block = format!("{}\n## 关联\n", LINKS_BEGIN);
+    for (kind, notes) in grouped {
+        block.push_str(&format!("\n### {}\n\n", kind.heading()));
+        for linked in notes {
+            block.push_str(&format!("- {}\n", wiki_link(linked)));
+        }
+    }
+    block.push_str(LINKS_END);
+    block.push('\n');
+    block
+}
+
+/// 生成带 frontmatter 与链接区块的笔记内容
+fn render_note(content: &str, note: &Note, graph: &Graph) -> String {
+    let (frontmatter, body) = split_frontmatter(content);
+    let body = strip_links_block(body);
+    let mut result = render_frontmatter(frontmatter, note);
+    result.push('\n');
+    if !body.trim().is_empty() {
+        result.push_str(body.trim_start_matches('\n'));
+        result.push_str("\n\n");
+    }
+    result.push_str(&render_links_block(note, graph));
+    result
+}
+
+/// 索引笔记：日记与周期总结、项目、习惯与反向链接
+fn render_indexes(graph: &Graph) -> Vec<(&'static str, String)> {
+    let list = |kind: NoteKind, reverse: bool| {
+        let mut notes: Vec<&Note> = graph.notes.iter()
+            .filter(|n| n.kind == kind && n.path.is_some())
+            .collect();
+        if reverse {
+            notes.reverse();
+        }
+        notes.iter().map(|n| format!("- {}\n", wiki_link(n))).collect::<String>()
+    };
+
+    let mut dailies: Vec<&Note> = graph.notes.iter().filter(|n| n.kind == NoteKind::Daily).collect();
+    dailies.sort_by(|a, b| b.stem.cmp(&a.stem));
+    let daily_list: String = dailies.iter().map(|n| format!("- {}\n", wiki_link(n))).collect();
+
+    let backlinks = graph.backlinks();
+    let mut backlink_index = String::from("---\ntype: index\n---\n\n# 反向链接\n");
+    let mut targets: Vec<usize> = (0..graph.notes.len()).filter(|&i| !backlinks[i].is_empty()).collect();
+    targets.sort_by(|&a, &b| {
+        (graph.notes[a].kind, &graph.notes[a].stem).cmp(&(graph.notes[b].kind, &graph.notes[b].stem))
+    });
+    for target in targets {
+        backlink_index.push_str(&format!("\n## {}\n\n", wiki_link(&graph.notes[target])));
+        for &source in &backlinks[target] {
+            backlink_index.push_str(&format!("- {}\n", wiki_link(&graph.notes[source])));
+        }
+    }
+
+    vec![
+        ("daily", format!(
+            "---\ntype: index\n---\n\n# 日记\n\n{}\n## 周期总结\n\n{}",
+            daily_list,
+            list(NoteKind::Period, true)
+        )),
+        ("projects", format!("---\ntype: index\n---\n\n# 项目\n\n{}", list(NoteKind::Project, true))),
+        ("habits", format!("---\ntype: index\n---\n\n# 习惯\n\n{}", list(NoteKind::Habit, false))),
+        ("backlinks", backlink_index),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(db: &Database, root: &Path) {
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO projects (id, title, start_date, last_activity_date, tags, markdown_path)
+                 VALUES ('proj-1', 'Vision Jarvis', 1760572800, 1760572800, '[\"Rust\"]', 'project/Vision-Jarvis.md');
+                 INSERT INTO habits (id, pattern_name, pattern_type, trigger_conditions, markdown_path)
+                 VALUES ('habit-1', '使用VSCode后通常会使用Chrome', 'trigger-based',
+                         '{\"from_app\":\"VSCode\",\"to_app\":\"Chrome\"}', 'habits/trigger-VSCode-Chrome.md');
+                 INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                         category, screenshot_ids, tags, markdown_path, project_id, created_at)
+                 VALUES ('activity-2025-10-16-aaaa', 'VSCode中写代码', 1760608800, 1760612400, 60, 'VSCode',
+                         '\"work\"', '[]', '[\"VSCode\",\"machine learning\",\"2025\"]',
+                         'activities/2025-10-16/activity-2025-10-16-aaaa.md', 'proj-1', 1760612400);
+                 INSERT INTO summaries (id, summary_type, date_start, date_end, content, activity_ids, markdown_path)
+                 VALUES ('summary-daily-2025-10-16', 'daily', '2025-10-16', '2025-10-16', '写代码',
+                         '[]', 'long_term_memory/daily_summary/2025-10-16.md'),
+                        ('summary-weekly-2025-10-13', 'weekly', '2025-10-13', '2025-10-19', '本周',
+                         '[]', 'long_term_memory/range_summary/2025-W42.md');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        for (path, content) in [
+            ("activities/2025-10-16/activity-2025-10-16-aaaa.md",
+             "---\nid: activity-2025-10-16-aaaa\ntitle: VSCode中写代码\ntags:\n- VSCode\n---\n\n# VSCode中写代码\n"),
+            ("project/Vision-Jarvis.md", "---\nid: proj-1\ntitle: Vision Jarvis\n---\n\n# Vision Jarvis\n"),
+            ("habits/trigger-VSCode-Chrome.md", "---\nid: habit-1\n---\n\n# 使用VSCode后通常会使用Chrome\n"),
+            ("long_term_memory/daily_summary/2025-10-16.md", "---\nid: summary-daily-2025-10-16\n---\n\n# 2025-10-16 日总结\n"),
+            ("long_term_memory/range_summary/2025-W42.md", "---\nid: summary-weekly-2025-10-13\ntype: weekly\n---\n\n# 2025-W42 周总结\n"),
+        ] {
+            let full = root.join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(full, content).unwrap();
+        }
+    }
+
+    fn knowledge_base(root: &Path, enabled: bool) -> (Arc<Database>, KnowledgeBase) {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        seed(&db, root);
+        let kb = KnowledgeBase::new(Arc::clone(&db), root.to_path_buf(), KnowledgeBaseConfig { enabled });
+        (db, kb)
+    }
+
+    #[test]
+    fn test_rebuild_links_notes_and_writes_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, kb) = knowledge_base(dir.path(), true);
+
+        let report = kb.rebuild().unwrap();
+        assert_eq!(report.notes, 5);
+        assert_eq!(report.updated, 5);
+        assert_eq!(report.index_files, 4);
+
+        let activity = fs::read_to_string(dir.path().join("activities/2025-10-16/activity-2025-10-16-aaaa.md")).unwrap();
+        assert!(activity.contains("type: activity"));
+        assert!(activity.contains("- machine-learning"));
+        assert!(!activity.contains("- '2025'"));
+        assert!(activity.contains("aliases:\n- VSCode中写代码"));
+        assert!(activity.contains("# VSCode中写代码"));
+        assert!(activity.contains("[[2025-10-16]] 2025-10-16 日总结"));
+        assert!(activity.contains("[[Vision-Jarvis]] Vision Jarvis"));
+        assert!(activity.contains("[[trigger-VSCode-Chrome]]"));
+
+        let daily = fs::read_to_string(dir.path().join("long_term_memory/daily_summary/2025-10-16.md")).unwrap();
+        assert!(daily.contains("[[activity-2025-10-16-aaaa]] VSCode中写代码"));
+        assert!(daily.contains("[[Vision-Jarvis]]"));
+        assert!(daily.contains("[[trigger-VSCode-Chrome]]"));
+
+        assert!(daily.contains("[[2025-W42]] 2025-W42 周总结"));
+        let weekly = fs::read_to_string(dir.path().join("long_term_memory/range_summary/2025-W42.md")).unwrap();
+        assert!(weekly.contains("type: weekly"));
+        assert!(weekly.contains("[[2025-10-16]]"));
+
+        let habit = fs::read_to_string(dir.path().join("habits/trigger-VSCode-Chrome.md")).unwrap();
+        assert!(habit.contains("[[2025-10-16]]"));
+
+        let backlinks = fs::read_to_string(dir.path().join("index/backlinks.md")).unwrap();
+        assert!(backlinks.contains("## [[Vision-Jarvis]] Vision Jarvis"));
+        let projects = fs::read_to_string(dir.path().join("index/projects.md")).unwrap();
+        assert!(projects.contains("[[Vision-Jarvis]]"));
+    }
+
+    #[test]
+    fn test_rebuild_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, kb) = knowledge_base(dir.path(), true);
+
+        kb.rebuild().unwrap();
+        let path = dir.path().join("project/Vision-Jarvis.md");
+        let first = fs::read_to_string(&path).unwrap();
+
+        let report = kb.rebuild().unwrap();
+        assert_eq!(report.updated, 0);
+        let second = fs::read_to_string(&path).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second.matches(LINKS_BEGIN).count(), 1);
+    }
+
+    #[test]
+    fn test_disabled_leaves_notes_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, kb) = knowledge_base(dir.path(), false);
+
+        let report = kb.rebuild().unwrap();
+        assert_eq!(report.notes, 0);
+        assert!(!dir.path().join(INDEX_DIR).exists());
+        let project = fs::read_to_string(dir.path().join("project/Vision-Jarvis.md")).unwrap();
+        assert!(!project.contains(LINKS_BEGIN));
+    }
+
+    #[test]
+    fn test_missing_files_are_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_db, kb) = knowledge_base(dir.path(), true);
+        fs::remove_file(dir.path().join("habits/trigger-VSCode-Chrome.md")).unwrap();
+
+        let report = kb.rebuild().unwrap();
+        assert_eq!(report.missing, 1);
+        assert_eq!(report.updated, 4);
+    }
+
+    #[test]
+    fn test_habit_apps() {
+        assert_eq!(habit_apps("每天 08:00 使用 微信", None), vec!["微信"]);
+        assert_eq!(
+            habit_apps("A→B→C", Some(r#"{"sequence":["A","B","C"],"count":3}"#)),
+            vec!["A", "B", "C"]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("machine learning").as_deref(), Some("machine-learning"));
+        assert_eq!(normalize_tag("#Rust").as_deref(), Some("Rust"));
+        assert_eq!(normalize_tag("编程").as_deref(), Some("编程"));
+        assert_eq!(normalize_tag("2025"), None);
+        assert_eq!(normalize_tag("  "), None);
+    }
+}
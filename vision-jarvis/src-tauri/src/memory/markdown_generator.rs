@@ -14,7 +14,8 @@ use std::sync::Arc;
 use std::fs;
 use tokio::sync::RwLock;
 
-use crate::ai::AIClient;
+use crate::ai::{AIClient, AIPurpose};
+use crate::crypto::Vault;
 use crate::db::schema::{ActivitySession, ScreenshotAnalysisSummary, ActivityCategory};
 
 /// Markdown生成器配置
@@ -39,6 +40,8 @@ impl Default for GeneratorConfig {
 pub struct MarkdownGenerator {
     config: GeneratorConfig,
     ai_client: Arc<RwLock<Option<Arc<AIClient>>>>,
+    /// 静态加密（默认不加密）
+    vault: Arc<Vault>,
 }
 
 /// YAML frontmatter结构
@@ -69,9 +72,15 @@ impl MarkdownGenerator {
         Self {
             config,
             ai_client: Arc::new(RwLock::new(None)),
+            vault: Arc::new(Vault::default()),
         }
     }
 
+    /// 写入文件时按数据库的加密设置加密
+    pub fn with_vault(self, vault: Arc<Vault>) -> Self {
+        Self { vault, ..self }
+    }
+
     /// 动态注入 AI 客户端
     pub async fn set_ai_client(&self, client: Arc<AIClient>) {
         let mut guard = self.ai_client.write().await;
@@ -88,7 +97,7 @@ impl MarkdownGenerator {
             let ai_guard = self.ai_client.read().await;
             if let Some(ref client) = *ai_guard {
                 let prompt = self.build_summary_prompt(activity);
-                match client.send_text(&prompt).await {
+                match client.for_purpose(AIPurpose::MarkdownSummary).send_text(&prompt).await {
                     Ok(s) => s,
                     Err(e) => {
                         log::warn!("AI summary generation failed: {}, using template", e);
@@ -219,7 +228,7 @@ impl MarkdownGenerator {
         }
 
         // 写入文件
-        fs::write(&full_path, content)?;
+        self.vault.write_file(&full_path, content.as_bytes())?;
 
         Ok(full_path)
     }
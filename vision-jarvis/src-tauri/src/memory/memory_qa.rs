@@ -0,0 +1,719 @@
+/// 记忆问答 - 基于检索结果生成带引用的回答
+///
+/// 流程：解析问题中的时间描述 → 混合检索 memory_chunks + 时间范围内的活动和日总结
+/// → 组装编号资料的提示词 → AI 回答并以 [n] 标注引用 → 解析引用编号映射回资料
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::ai::{AIClient, AIPurpose, EmbeddingProvider};
+use crate::db::Database;
+use super::hybrid_search::{self, HybridSearchConfig};
+use super::search::SearchFilters;
+
+/// 问答检索配置
+#[derive(Debug, Clone)]
+pub struct MemoryQaConfig {
+    /// 最多引用的记忆分块数
+    pub max_chunks: usize,
+    /// 最多引用的活动数
+    pub max_activities: usize,
+    /// 最多引用的日总结数
+    pub max_summaries: usize,
+    /// 单条资料写入提示词的最大字符数
+    pub max_source_chars: usize,
+}
+
+impl Default for MemoryQaConfig {
+    fn default() -> Self {
+        Self {
+            max_chunks: 8,
+            max_activities: 10,
+            max_summaries: 2,
+            max_source_chars: 600,
+        }
+    }
+}
+
+/// 资料类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Chunk,
+    Activity,
+    Summary,
+}
+
+/// 提供给 AI 的一条资料，被回答引用时即为引用来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    /// 提示词中的编号（1-based）
+    pub index: usize,
+    pub kind: SourceKind,
+    /// 分块或活动对应的 Markdown 文件
+    pub file_path: Option<String>,
+    pub start_line: Option<i32>,
+    pub end_line: Option<i32>,
+    pub activity_id: Option<String>,
+    pub summary_id: Option<String>,
+    /// 资料的一行描述（活动标题、总结日期等）
+    pub title: String,
+    pub excerpt: String,
+}
+
+/// 问答结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryAnswer {
+    pub answer: String,
+    /// 回答中引用到的资料；AI 未标注编号时为全部资料
+    pub citations: Vec<Citation>,
+    /// 从问题中识别出的时间范围（Unix 秒）
+    pub time_range: Option<(i64, i64)>,
+}
+
+/// 检索阶段的资料（尚未编号）
+struct Source {
+    citation: Citation,
+    /// 写入提示词的正文
+    body: String,
+}
+
+/// 检索完成、等待 AI 回答的问题
+pub struct PreparedQuestion {
+    /// 没有检索到任何资料时为 None，此时无需调用 AI
+    prompt: Option<String>,
+    sources: Vec<Source>,
+    time_range: Option<(i64, i64)>,
+}
+
+impl PreparedQuestion {
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    /// 没有资料时的固定回答
+    pub fn empty_answer(self) -> MemoryAnswer {
+        MemoryAnswer {
+            answer: "没有找到与这个问题相关的记忆。".to_string(),
+            citations: Vec::new(),
+            time_range: self.time_range,
+        }
+    }
+
+    /// 根据 AI 回答中的编号整理引用
+    pub fn into_answer(self, answer: &str) -> MemoryAnswer {
+        let cited = parse_citations(answer, self.sources.len());
+        let citations = if cited.is_empty() {
+            self.sources.into_iter().map(|s| s.citation).collect()
+        } else {
+            let mut by_index: Vec<Option<Citation>> = self.sources.into_iter().map(|s| Some(s.citation)).collect();
+            cited.iter().filter_map(|i| by_index[i - 1].take()).collect()
+        };
+
+        MemoryAnswer {
+            answer: answer.trim().to_string(),
+            citations,
+            time_range: self.time_range,
+        }
+    }
+}
+
+/// 检索资料并组装提示词（流式与非流式回答共用）
+pub async fn prepare_question(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    question: &str,
+    config: &MemoryQaConfig,
+) -> Result<PreparedQuestion> {
+    let question = question.trim();
+    if question.is_empty() {
+        anyhow::bail!("问题不能为空");
+    }
+
+    let now = Local::now().naive_local();
+    let time_range = parse_time_hint(question, now)
+        .map(|(start, end)| (local_timestamp(start), local_timestamp(end)));
+
+    let sources = retrieve(db, embedder, question, time_range, config).await?;
+    let prompt = (!sources.is_empty()).then(|| build_prompt(question, now, &sources));
+
+    Ok(PreparedQuestion { prompt, sources, time_range })
+}
+
+/// 回答记忆相关的问题
+pub async fn ask_memory(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    client: &AIClient,
+    question: &str,
+    config: &MemoryQaConfig,
+) -> Result<MemoryAnswer> {
+    let prepared = prepare_question(db, embedder, question, config).await?;
+    let Some(prompt) = prepared.prompt() else {
+        return Ok(prepared.empty_answer());
+    };
+
+    let answer = client
+        .for_purpose(AIPurpose::MemoryQa)
+        .send_text(prompt)
+        .await
+        .map_err(|e| anyhow::anyhow!("AI调用失败: {}", e))?;
+
+    Ok(prepared.into_answer(&answer))
+}
+
+/// 检索分块、活动和日总结，按提示词中的顺序编号
+async fn retrieve(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    question: &str,
+    time_range: Option<(i64, i64)>,
+    config: &MemoryQaConfig,
+) -> Result<Vec<Source>> {
+    let filters = SearchFilters {
+        start_time: time_range.map(|(start, _)| start),
+        end_time: time_range.map(|(_, end)| end),
+        ..Default::default()
+    };
+    let hits = hybrid_search::hybrid_search(
+        db,
+        embedder,
+        question,
+        &filters,
+        config.max_chunks,
+        &HybridSearchConfig { question: true, ..Default::default() },
+    ).await?;
+
+    // 时间范围内的活动 + 命中分块关联的活动
+    let mut activity_ids: Vec<String> = Vec::new();
+    if let Some((start, end)) = time_range {
+        activity_ids.extend(activity_ids_in_range(db, start, end, config.max_activities)?);
+    }
+    for id in hits.iter().filter_map(|h| h.activity_id.clone()) {
+        if activity_ids.len() >= config.max_activities {
+            break;
+        }
+        if !activity_ids.contains(&id) {
+            activity_ids.push(id);
+        }
+    }
+
+    let mut sources = Vec::new();
+    for id in &activity_ids {
+        if let Some(source) = load_activity(db, id, config.max_source_chars)? {
+            sources.push(source);
+        }
+    }
+    if let Some((start, end)) = time_range {
+        sources.extend(load_daily_summaries(db, start, end, config)?);
+    }
+    for hit in hits {
+        let title = match (&hit.application, hit.start_line == hit.end_line) {
+            (Some(app), _) => format!("{} 第{}-{}行（{}）", hit.file_path, hit.start_line, hit.end_line, app),
+            (None, true) => format!("{} 第{}行", hit.file_path, hit.start_line),
+            (None, false) => format!("{} 第{}-{}行", hit.file_path, hit.start_line, hit.end_line),
+        };
+        let body = truncate_chars(&hit.text, config.max_source_chars);
+        sources.push(Source {
+            citation: Citation {
+                index: 0,
+                kind: SourceKind::Chunk,
+                file_path: Some(hit.file_path),
+                start_line: Some(hit.start_line),
+                end_line: Some(hit.end_line),
+                activity_id: hit.activity_id,
+                summary_id: None,
+                title,
+                excerpt: truncate_chars(&body, 120),
+            },
+            body,
+        });
+    }
+
+    for (i, source) in sources.iter_mut().enumerate() {
+        source.citation.index = i + 1;
+    }
+    Ok(sources)
+}
+
+fn activity_ids_in_range(db: &Database, start: i64, end: i64, limit: usize) -> Result<Vec<String>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM activities
+             WHERE start_time <= ?2 AND end_time >= ?1
+             ORDER BY start_time ASC
+             LIMIT ?3",
+        )?;
+        let ids = stmt
+            .query_map(rusqlite::params![start, end, limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    })
+}
+
+fn load_activity(db: &Database, id: &str, max_chars: usize) -> Result<Option<Source>> {
+    db.with_connection(|conn| {
+        let row = conn.query_row(
+            "SELECT title, start_time, end_time, application, summary, markdown_path
+             FROM activities WHERE id = ?1",
+            [id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        );
+
+        let (title, start, end, application, summary, markdown_path) = match row {
+            Ok(r) => r,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let summary = db.vault().decrypt_opt_text(summary)?;
+
+        let heading = format!("{} {}", format_time_span(start, end), application);
+        let body = match summary.filter(|s| !s.trim().is_empty()) {
+            Some(summary) => truncate_chars(&format!("{}：{}", title, summary), max_chars),
+            None => truncate_chars(&title, max_chars),
+        };
+
+        Ok(Some(Source {
+            citation: Citation {
+                index: 0,
+                kind: SourceKind::Activity,
+                file_path: Some(markdown_path).filter(|p| !p.is_empty()),
+                start_line: None,
+                end_line: None,
+                activity_id: Some(id.to_string()),
+                summary_id: None,
+                title: format!("{} {}", heading, title),
+                excerpt: truncate_chars(&body, 120),
+            },
+            body: format!("{}\n{}", heading, body),
+        }))
+    })
+}
+
+fn load_daily_summaries(db: &Database, start: i64, end: i64, config: &MemoryQaConfig) -> Result<Vec<Source>> {
+    let day = |ts: i64| {
+        Local
+            .timestamp_opt(ts, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let (first, last) = (day(start), day(end));
+
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, date_start, content, markdown_path FROM summaries
+             WHERE summary_type = 'daily' AND date_start >= ?1 AND date_start <= ?2
+             ORDER BY date_start DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![first, last, config.max_summaries as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut sources = Vec::with_capacity(rows.len());
+        for (id, date, content, markdown_path) in rows {
+            let content = db.vault().decrypt_text(content)?;
+            let body = truncate_chars(&content, config.max_source_chars);
+            sources.push(Source {
+                citation: Citation {
+                    index: 0,
+                    kind: SourceKind::Summary,
+                    file_path: Some(markdown_path).filter(|p| !p.is_empty()),
+                    start_line: None,
+                    end_line: None,
+                    activity_id: None,
+                    summary_id: Some(id),
+                    title: format!("{} 日总结", date),
+                    excerpt: truncate_chars(&body, 120),
+                },
+                body,
+            });
+        }
+        Ok(sources)
+    })
+}
+
+/// 组装带编号资料的提示词
+fn build_prompt(question: &str, now: NaiveDateTime, sources: &[Source]) -> String {
+    let mut materials = String::new();
+    for source in sources {
+        let kind = match source.citation.kind {
+            SourceKind::Chunk => "记忆片段",
+            SourceKind::Activity => "活动",
+            SourceKind::Summary => "日总结",
+        };
+        materials.push_str(&format!(
+            "[{}] {} | {}\n{}\n\n",
+            source.citation.index, kind, source.citation.title, source.body.trim()
+        ));
+    }
+
+    format!(
+        r#"你是用户的个人记忆助手。下面是从用户的屏幕活动记录中检索到的资料，请只根据这些资料回答问题。
+
+要求：
+- 每个结论后用方括号标注所依据的资料编号，如 [1] 或 [2][3]
+- 资料不足以回答时直接说明，不要编造
+- 使用与问题相同的语言，简洁回答
+
+当前时间：{}（{}）
+
+## 资料
+
+{}## 问题
+
+{}"#,
+        now.format("%Y-%m-%d %H:%M"),
+        weekday_name(now.weekday()),
+        materials,
+        question
+    )
+}
+
+/// 解析回答中引用的资料编号（去重、保持出现顺序，忽略越界编号）
+///
+/// 支持 `[1]`、`[1, 3]`、`[1][2]` 以及全角 `【1】`
+pub fn parse_citations(answer: &str, source_count: usize) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    let mut indices = Vec::new();
+    let mut rest = answer;
+
+    while let Some(open) = rest.find(['[', '【']) {
+        let after_open = &rest[open + rest[open..].chars().next().map_or(1, char::len_utf8)..];
+        let Some(close) = after_open.find([']', '】']) else {
+            break;
+        };
+        let inner = &after_open[..close];
+        let valid = !inner.trim().is_empty()
+            && inner.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '，' || c.is_whitespace());
+        if valid {
+            for n in inner.split([',', '，']).filter_map(|s| s.trim().parse::<usize>().ok()) {
+                if n >= 1 && n <= source_count && seen.insert(n) {
+                    indices.push(n);
+                }
+            }
+        }
+        rest = &after_open[close..];
+    }
+
+    indices
+}
+
+/// 从问题中识别时间描述，返回本地时间范围 [start, end)
+///
+/// 日期：今天/昨天/前天、周一~周日（最近一次，含今天）、today/yesterday/monday…；
+/// 时段：上午/中午/下午/晚上、morning/noon/afternoon/evening/tonight。
+/// 只出现时段时默认为今天
+pub fn parse_time_hint(question: &str, now: NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let lower = question.to_lowercase();
+    let today = now.date();
+    let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+
+    let day = if has(&["前天", "day before yesterday"]) {
+        Some(today - chrono::Duration::days(2))
+    } else if has(&["昨天", "昨晚", "yesterday", "last night"]) {
+        Some(today - chrono::Duration::days(1))
+    } else if has(&["今天", "今晚", "today", "tonight"]) {
+        Some(today)
+    } else {
+        mentioned_weekday(&lower).map(|weekday| most_recent(today, weekday))
+    };
+
+    let hours = if has(&["上午", "早上", "morning"]) {
+        Some((6, 12))
+    } else if has(&["下午", "afternoon"]) {
+        Some((12, 18))
+    } else if has(&["中午", "noon"]) {
+        Some((11, 14))
+    } else if has(&["晚上", "今晚", "昨晚", "evening", "tonight", "last night"]) {
+        Some((18, 24))
+    } else {
+        None
+    };
+
+    if day.is_none() && hours.is_none() {
+        return None;
+    }
+
+    let day = day.unwrap_or(today);
+    let (start_hour, end_hour) = hours.unwrap_or((0, 24));
+    let at = |date: NaiveDate, hour: u32| {
+        if hour >= 24 {
+            (date + chrono::Duration::days(1)).and_time(NaiveTime::MIN)
+        } else {
+            date.and_hms_opt(hour, 0, 0).unwrap()
+        }
+    };
+
+    Some((at(day, start_hour), at(day, end_hour)))
+}
+
+fn mentioned_weekday(lower: &str) -> Option<Weekday> {
+    const WEEKDAYS: [(Weekday, &[&str]); 7] = [
+        (Weekday::Mon, &["周一", "星期一", "礼拜一", "monday"]),
+        (Weekday::Tue, &["周二", "星期二", "礼拜二", "tuesday"]),
+        (Weekday::Wed, &["周三", "星期三", "礼拜三", "wednesday"]),
+        (Weekday::Thu, &["周四", "星期四", "礼拜四", "thursday"]),
+        (Weekday::Fri, &["周五", "星期五", "礼拜五", "friday"]),
+        (Weekday::Sat, &["周六", "星期六", "礼拜六", "saturday"]),
+        (Weekday::Sun, &["周日", "周天", "星期日", "星期天", "礼拜天", "sunday"]),
+    ];
+
+    WEEKDAYS
+        .iter()
+        .find(|(_, words)| words.iter().any(|w| lower.contains(w)))
+        .map(|(weekday, _)| *weekday)
+}
+
+/// 不晚于 today 的最近一个指定星期几
+fn most_recent(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    today - chrono::Duration::days(back as i64)
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "星期一",
+        Weekday::Tue => "星期二",
+        Weekday::Wed => "星期三",
+        Weekday::Thu => "星期四",
+        Weekday::Fri => "星期五",
+        Weekday::Sat => "星期六",
+        Weekday::Sun => "星期日",
+    }
+}
+
+fn local_timestamp(dt: NaiveDateTime) -> i64 {
+    Local
+        .from_local_datetime(&dt)
+        .earliest()
+        .map(|t| t.timestamp())
+        .unwrap_or_else(|| dt.and_utc().timestamp())
+}
+
+fn format_time_span(start: i64, end: i64) -> String {
+    let fmt = |ts: i64, pattern: &str| {
+        Local
+            .timestamp_opt(ts, 0)
+            .single()
+            .map(|t| t.format(pattern).to_string())
+            .unwrap_or_default()
+    };
+    format!("{}-{}", fmt(start, "%Y-%m-%d %H:%M"), fmt(end, "%H:%M"))
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::provider::AIProviderConfig;
+    use crate::ai::traits::AIProvider;
+    use crate::ai::usage::Completion;
+    use crate::error::AppResult;
+    use crate::memory::index_manager::{IndexConfig, IndexManager};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// 记录提示词并返回固定回答的 Provider
+    struct StubProvider {
+        config: AIProviderConfig,
+        answer: String,
+        prompts: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AIProvider for StubProvider {
+        async fn send_text(&self, prompt: &str) -> AppResult<Completion> {
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            Ok(Completion::new(self.answer.clone()))
+        }
+        async fn analyze_video(&self, _: &str, _: &str) -> AppResult<Completion> {
+            unreachable!()
+        }
+        async fn analyze_image(&self, _: &str, _: &str) -> AppResult<Completion> {
+            unreachable!()
+        }
+        async fn test_connection(&self) -> AppResult<String> {
+            Ok("ok".to_string())
+        }
+        fn config(&self) -> &AIProviderConfig {
+            &self.config
+        }
+    }
+
+    fn stub_client(answer: &str) -> (AIClient, Arc<Mutex<Vec<String>>>) {
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let provider = StubProvider {
+            config: AIProviderConfig::new("stub", "Stub", "http://localhost", "key", "model"),
+            answer: answer.to_string(),
+            prompts: Arc::clone(&prompts),
+        };
+        (AIClient::from_provider(Box::new(provider)), prompts)
+    }
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_citations() {
+        assert_eq!(parse_citations("在调试 Rust [2]，随后查文档[1][2]。", 3), vec![2, 1]);
+        assert_eq!(parse_citations("见 [1, 3] 和【2】", 3), vec![1, 3, 2]);
+        // 越界编号和非编号方括号被忽略
+        assert_eq!(parse_citations("[5] [a] [] vec[0] [3]", 3), vec![3]);
+        assert!(parse_citations("没有引用", 3).is_empty());
+    }
+
+    #[test]
+    fn test_parse_time_hint_weekday_afternoon() {
+        // 2024-01-18 是星期四
+        let now = dt("2024-01-18 10:00");
+        assert_eq!(
+            parse_time_hint("what was I debugging on Tuesday afternoon?", now),
+            Some((dt("2024-01-16 12:00"), dt("2024-01-16 18:00")))
+        );
+        assert_eq!(
+            parse_time_hint("我周四上午在做什么", now),
+            Some((dt("2024-01-18 06:00"), dt("2024-01-18 12:00")))
+        );
+        // 星期五尚未到来，取上周五
+        assert_eq!(
+            parse_time_hint("星期五晚上看了什么", now),
+            Some((dt("2024-01-12 18:00"), dt("2024-01-13 00:00")))
+        );
+    }
+
+    #[test]
+    fn test_parse_time_hint_relative_days() {
+        let now = dt("2024-01-18 10:00");
+        assert_eq!(
+            parse_time_hint("昨天做了哪些事", now),
+            Some((dt("2024-01-17 00:00"), dt("2024-01-18 00:00")))
+        );
+        assert_eq!(
+            parse_time_hint("前天下午", now),
+            Some((dt("2024-01-16 12:00"), dt("2024-01-16 18:00")))
+        );
+        assert_eq!(
+            parse_time_hint("this morning", now),
+            Some((dt("2024-01-18 06:00"), dt("2024-01-18 12:00")))
+        );
+        assert_eq!(parse_time_hint("rust 编译错误", now), None);
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("调试Rust", 2), "调试…");
+        assert_eq!(truncate_chars(" short ", 10), "short");
+    }
+
+    #[tokio::test]
+    async fn test_ask_memory_returns_cited_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        let day = dir.path().join("activities/2024-01-16");
+        std::fs::create_dir_all(&day).unwrap();
+        std::fs::write(day.join("activity-001.md"), "在 VSCode 中调试 Rust 编译错误\n").unwrap();
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes,
+                    application, category, screenshot_ids, markdown_path, summary)
+                 VALUES ('act-1', '调试 Rust 编译错误', 1705384800, 1705388400, 60,
+                    'VSCode', 'work', '[]', 'activities/2024-01-16/activity-001.md', '修复借用检查报错')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        manager.sync().await.unwrap();
+
+        let (client, prompts) = stub_client("你在 VSCode 里调试 Rust 编译错误 [2]。");
+        let embedder = manager.embedder().await;
+        let answer = ask_memory(&db, embedder.as_ref(), &client, "调试 Rust", &MemoryQaConfig::default())
+            .await
+            .unwrap();
+
+        let prompt = prompts.lock().unwrap()[0].clone();
+        assert!(prompt.contains("[1] 活动"));
+        assert!(prompt.contains("[2] 记忆片段 | activities/2024-01-16/activity-001.md"));
+        assert!(prompt.contains("修复借用检查报错"));
+        assert!(prompt.ends_with("调试 Rust"));
+
+        assert_eq!(answer.citations.len(), 1);
+        let citation = &answer.citations[0];
+        assert_eq!(citation.index, 2);
+        assert_eq!(citation.kind, SourceKind::Chunk);
+        assert_eq!(citation.file_path.as_deref(), Some("activities/2024-01-16/activity-001.md"));
+        assert_eq!(citation.start_line, Some(1));
+        assert_eq!(citation.activity_id.as_deref(), Some("act-1"));
+        assert!(answer.time_range.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ask_memory_matches_question_keywords() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+        std::fs::write(dir.path().join("notes.md"), "修复 Rust 借用检查错误\n").unwrap();
+        std::fs::write(dir.path().join("video.md"), "在浏览器观看烹饪视频\n").unwrap();
+        manager.sync().await.unwrap();
+
+        // 向量模型与索引不同，语义检索没有结果，只能靠关键词命中
+        let embedder = crate::ai::HashEmbeddingProvider::new(32);
+        let (client, prompts) = stub_client("在修复借用检查错误 [1]。");
+        let answer = ask_memory(&db, &embedder, &client, "我之前在调试什么 Rust 错误？", &MemoryQaConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(prompts.lock().unwrap().len(), 1);
+        assert_eq!(answer.citations.len(), 1);
+        assert_eq!(answer.citations[0].file_path.as_deref(), Some("notes.md"));
+    }
+
+    #[tokio::test]
+    async fn test_ask_memory_without_sources_skips_ai() {
+        let db = Database::open_in_memory().unwrap();
+        let embedder = crate::ai::HashEmbeddingProvider::default();
+        let (client, prompts) = stub_client("不应被调用");
+
+        let answer = ask_memory(&db, &embedder, &client, "上周的会议", &MemoryQaConfig::default())
+            .await
+            .unwrap();
+
+        assert!(answer.citations.is_empty());
+        assert!(prompts.lock().unwrap().is_empty());
+    }
+}
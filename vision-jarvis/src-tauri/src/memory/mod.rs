@@ -7,8 +7,15 @@ pub mod activity_grouper;
 pub mod markdown_generator;
 pub mod chunker;
 pub mod index_manager;
+pub mod fts;
+pub mod search;
+pub mod hybrid_search;
+pub mod memory_qa;
 pub mod pipeline;
 pub mod screenshot_analyzer;
 pub mod summary_generator;
 pub mod project_extractor;
 pub mod habit_detector;
+pub mod knowledge_base;
+pub mod job_ledger;
+pub mod queries;
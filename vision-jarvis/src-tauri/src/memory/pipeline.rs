@@ -5,19 +5,23 @@
 /// 2. 活动分组 (30分钟) - 聚合录制分段为活动会话
 /// 3. 索引同步 (10分钟) - 增量文件索引
 /// 4. 习惯检测 (每日) - 识别行为模式
-/// 5. 日总结 (每日23:00) - 生成日总结
+/// 5. 日总结 (每日23:00) - 生成日总结；周日生成周总结，月末生成月总结
+/// 6. 知识库链接 - 以上任务产出新笔记后重建 wiki-link 与索引（开启知识库模式时）
+/// 7. 补跑 (启动后及每10分钟) - 按任务台账补分析遗留录制、补生成错过的日/周/月总结
 
 use anyhow::Result;
 use std::sync::Arc;
 use std::path::PathBuf;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, Mutex, RwLock};
 use tokio::time::{interval, Duration};
 use tokio::task::JoinHandle;
-use chrono::{Local, Timelike};
+use chrono::{Datelike, NaiveDate, Timelike, Utc, Weekday};
 use log::{info, error, warn};
 
-use crate::ai::AIClient;
+use crate::ai::{AIClient, AIPurpose, UsageTracker, create_embedding_provider};
 use crate::db::Database;
+use crate::privacy::Redactor;
+use crate::settings::AppSettings;
 use super::{
     activity_grouper::{ActivityGrouper, GroupingConfig},
     markdown_generator::{MarkdownGenerator, GeneratorConfig},
@@ -26,8 +30,24 @@ use super::{
     summary_generator::{SummaryGenerator, SummaryConfig},
     project_extractor::{ProjectExtractor, ProjectExtractorConfig},
     habit_detector::{HabitDetector, HabitDetectorConfig},
+    knowledge_base::KnowledgeBase,
+    job_ledger::{is_budget_error, is_transient, CatchUpReport, JobKind, JobLedger, PendingJob},
+    summary_generator::{month_bounds, week_bounds, SUMMARY_HOUR},
 };
 
+/// 即时分析请求（录制 ID 与文件路径）的接收端
+type AnalysisReceiver = tokio::sync::mpsc::Receiver<(String, PathBuf)>;
+
+/// 补跑节奏：启动后稍等再开始，之后定期检查；每轮限量，避免集中消耗 AI 调用
+const CATCH_UP_STARTUP_DELAY: Duration = Duration::from_secs(60);
+const CATCH_UP_INTERVAL: Duration = Duration::from_secs(600);
+const CATCH_UP_RECORDINGS: usize = 10;
+const CATCH_UP_RECORDING_GAP: Duration = Duration::from_secs(6);
+const CATCH_UP_SUMMARIES: usize = 3;
+const CATCH_UP_LOOKBACK_DAYS: i64 = 30;
+/// 结束超过该时长仍未分析的录制才补分析，更新的交给即时分析
+const CATCH_UP_MIN_AGE_SECS: i64 = 600;
+
 /// 管道调度器
 pub struct PipelineScheduler {
     db: Arc<Database>,
@@ -37,10 +57,24 @@ pub struct PipelineScheduler {
     /// 动态可更新的录制分析器（支持运行时接入AI）
     screenshot_analyzer: Arc<RwLock<Option<Arc<ScreenshotAnalyzer>>>>,
     summary_generator: Arc<SummaryGenerator>,
+    /// 已连接的 AI 客户端（供记忆问答等按需调用）
+    ai_client: Arc<RwLock<Option<Arc<AIClient>>>>,
     project_extractor: Arc<ProjectExtractor>,
     habit_detector: Arc<HabitDetector>,
-    /// 即时分析 channel receiver（录制完成后立刻触发）
-    analysis_rx: std::sync::Mutex<Option<tokio::sync::mpsc::Receiver<(String, std::path::PathBuf)>>>,
+    /// 用量记录（连接 AI 时挂到客户端上）
+    usage: Option<Arc<UsageTracker>>,
+    /// 录制分析前的脱敏器
+    redactor: Option<Arc<Redactor>>,
+    /// 知识库维护器（笔记生成后重建链接）
+    knowledge_base: Option<Arc<KnowledgeBase>>,
+    /// 任务台账（总结与补分析的执行记录）
+    ledger: Arc<JobLedger>,
+    /// 定时分组与补跑分组互斥，避免同一批录制被分组两次
+    grouping_lock: Arc<Mutex<()>>,
+    /// 即时分析 channel receiver（录制完成后立刻触发；停止后重新启动时继续使用）
+    analysis_rx: Arc<Mutex<Option<AnalysisReceiver>>>,
+    /// 运行中时持有停止信号的发送端，drop 后调度任务在当前任务完成后退出
+    stop_tx: std::sync::Mutex<Option<watch::Sender<()>>>,
 }
 
 impl PipelineScheduler {
@@ -58,7 +92,7 @@ impl PipelineScheduler {
         let markdown_gen = Arc::new(MarkdownGenerator::new(GeneratorConfig {
             storage_root: storage_root.clone(),
             enable_ai_summary,
-        }));
+        }).with_vault(Arc::clone(db.vault())));
 
         let index_manager = Arc::new(IndexManager::new(
             Arc::clone(&db),
@@ -93,6 +127,8 @@ impl PipelineScheduler {
             },
         ));
 
+        let ledger = Arc::new(JobLedger::new(Arc::clone(&db)));
+
         Ok(Self {
             db,
             grouper,
@@ -100,30 +136,57 @@ impl PipelineScheduler {
             index_manager,
             screenshot_analyzer: Arc::new(RwLock::new(None)),
             summary_generator,
+            ai_client: Arc::new(RwLock::new(None)),
             project_extractor,
             habit_detector,
-            analysis_rx: std::sync::Mutex::new(None),
+            usage: None,
+            redactor: None,
+            knowledge_base: None,
+            ledger,
+            grouping_lock: Arc::new(Mutex::new(())),
+            analysis_rx: Arc::new(Mutex::new(None)),
+            stop_tx: std::sync::Mutex::new(None),
         })
     }
 
     /// 设置即时分析 channel receiver
     pub fn with_analysis_receiver(
         self,
-        rx: tokio::sync::mpsc::Receiver<(String, std::path::PathBuf)>,
+        rx: AnalysisReceiver,
     ) -> Self {
-        *self.analysis_rx.lock().unwrap() = Some(rx);
-        self
+        Self { analysis_rx: Arc::new(Mutex::new(Some(rx))), ..self }
+    }
+
+    /// 设置用量记录器，之后连接的 AI 客户端都会记录用量并受预算约束
+    pub fn with_usage_tracker(self, tracker: Arc<UsageTracker>) -> Self {
+        Self { usage: Some(tracker), ..self }
+    }
+
+    /// 设置脱敏器，之后连接 AI 时录制分析会先经过脱敏
+    pub fn with_redactor(self, redactor: Arc<Redactor>) -> Self {
+        Self { redactor: Some(redactor), ..self }
+    }
+
+    /// 设置知识库维护器，分组、习惯检测与日总结之后重建笔记链接
+    pub fn with_knowledge_base(self, knowledge_base: Arc<KnowledgeBase>) -> Self {
+        Self { knowledge_base: Some(knowledge_base), ..self }
     }
 
     /// 动态连接AI客户端（可在管道运行中调用）
     pub async fn connect_ai(&self, ai_client: AIClient) {
-        let ai_client = Arc::new(ai_client);
+        let ai_client = Arc::new(match &self.usage {
+            Some(tracker) => ai_client.with_usage_tracker(Arc::clone(tracker)),
+            None => ai_client,
+        });
 
-        let analyzer = ScreenshotAnalyzer::new(
+        let mut analyzer = ScreenshotAnalyzer::new(
             Arc::clone(&ai_client),
             Arc::clone(&self.db),
             AnalyzerConfig::default(),
         );
+        if let Some(redactor) = &self.redactor {
+            analyzer = analyzer.with_redactor(Arc::clone(redactor));
+        }
 
         let mut guard = self.screenshot_analyzer.write().await;
         *guard = Some(Arc::new(analyzer));
@@ -132,6 +195,13 @@ impl PipelineScheduler {
         self.summary_generator.set_ai_client(Arc::clone(&ai_client)).await;
         self.markdown_gen.set_ai_client(Arc::clone(&ai_client)).await;
 
+        // 供应商支持 embeddings 接口时切换到远程向量化
+        self.index_manager
+            .set_embedder(create_embedding_provider(ai_client.config()))
+            .await;
+
+        *self.ai_client.write().await = Some(ai_client);
+
         info!("[Pipeline] AI客户端已连接，录制分析/总结/Markdown生成已启用");
     }
 
@@ -140,8 +210,75 @@ impl PipelineScheduler {
         self.screenshot_analyzer.read().await.is_some()
     }
 
-    /// 启动管道调度
-    pub fn start(&self) -> JoinHandle<()> {
+    /// 当前连接的 AI 客户端
+    pub async fn ai_client(&self) -> Option<Arc<AIClient>> {
+        self.ai_client.read().await.clone()
+    }
+
+    /// 索引管理器（供检索命令获取当前的向量化提供者）
+    pub fn index_manager(&self) -> Arc<IndexManager> {
+        Arc::clone(&self.index_manager)
+    }
+
+    /// 立即执行一轮补跑
+    pub async fn catch_up(&self) -> CatchUpReport {
+        self.catch_up_context().run_once().await
+    }
+
+    fn catch_up_context(&self) -> CatchUp {
+        CatchUp {
+            ledger: Arc::clone(&self.ledger),
+            screenshot_analyzer: Arc::clone(&self.screenshot_analyzer),
+            grouper: Arc::clone(&self.grouper),
+            markdown_gen: Arc::clone(&self.markdown_gen),
+            project_extractor: Arc::clone(&self.project_extractor),
+            summary_generator: Arc::clone(&self.summary_generator),
+            usage: self.usage.clone(),
+            knowledge_base: self.knowledge_base.clone(),
+            grouping_lock: Arc::clone(&self.grouping_lock),
+        }
+    }
+
+    /// 是否正在调度
+    pub fn is_running(&self) -> bool {
+        self.stop_tx.lock().unwrap().is_some()
+    }
+
+    /// 停止管道调度（正在执行的任务完成后退出）
+    pub fn stop(&self) {
+        if self.stop_tx.lock().unwrap().take().is_some() {
+            info!("[Pipeline] Stopping");
+        }
+    }
+
+    /// 订阅设置变更：开启记忆功能时启动调度，关闭时停止
+    pub fn watch_settings(self: &Arc<Self>, mut settings: watch::Receiver<AppSettings>) -> JoinHandle<()> {
+        let pipeline = Arc::clone(self);
+        tokio::spawn(async move {
+            while settings.changed().await.is_ok() {
+                let enabled = settings.borrow_and_update().memory_enabled;
+                if enabled && !pipeline.is_running() {
+                    pipeline.start();
+                    info!("[Pipeline] Started (memory enabled)");
+                } else if !enabled {
+                    pipeline.stop();
+                }
+            }
+        })
+    }
+
+    /// 启动管道调度（已在运行时不做任何事）
+    pub fn start(&self) {
+        let stop_rx = {
+            let mut stop_tx = self.stop_tx.lock().unwrap();
+            if stop_tx.is_some() {
+                return;
+            }
+            let (tx, rx) = watch::channel(());
+            *stop_tx = Some(tx);
+            rx
+        };
+
         let grouping_interval = Duration::from_secs(1800);    // 30分钟 - 分组活动
         let indexing_interval = Duration::from_secs(600);     // 10分钟 - 同步索引
         let habit_interval = Duration::from_secs(86400);      // 24小时 - 习惯检测
@@ -154,23 +291,52 @@ impl PipelineScheduler {
         let habit_detector = Arc::clone(&self.habit_detector);
         let project_extractor = Arc::clone(&self.project_extractor);
         let summary_generator = Arc::clone(&self.summary_generator);
-        // 将 analysis_rx 移动到 spawn 闭包中（取出所有权）
-        let analysis_rx = self.analysis_rx.lock().unwrap().take();
+        let knowledge_base = self.knowledge_base.clone();
+        let ledger = Arc::clone(&self.ledger);
+        let grouping_lock = Arc::clone(&self.grouping_lock);
+
+        // 补跑在独立任务中限速执行，不阻塞即时分析
+        let catch_up = self.catch_up_context();
+        let mut catch_up_stop = stop_rx.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval_at(
+                tokio::time::Instant::now() + CATCH_UP_STARTUP_DELAY,
+                CATCH_UP_INTERVAL,
+            );
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {
+                        let report = catch_up.run_once().await;
+                        if !report.is_empty() {
+                            info!("[Pipeline] Catch-up: {} recordings analyzed ({} failed), {} summaries ({} failed){}",
+                                report.analyzed, report.analysis_failed, report.summaries, report.summary_failed,
+                                if report.budget_exhausted { ", AI budget exhausted" } else { "" });
+                        }
+                    }
+                    _ = catch_up_stop.changed() => break,
+                }
+            }
+        });
+
+        let analysis_rx = Arc::clone(&self.analysis_rx);
+        let mut stop = stop_rx;
 
         tokio::spawn(async move {
             let mut grouping_tick = interval(grouping_interval);
             let mut indexing_tick = interval(indexing_interval);
             let mut habit_tick = interval(habit_interval);
             let mut summary_tick = interval(summary_check_interval);
-            // 记录上次生成日总结的日期，避免重复生成
-            let mut last_summary_date: Option<String> = None;
-            // 即时分析 receiver（从 channel 中取出，放入本地 mut 变量）
-            let mut instant_rx = analysis_rx;
+            // 即时分析 receiver（任务运行期间独占，停止后释放给下一次启动）
+            let mut instant_rx = analysis_rx.lock().await;
 
             loop {
                 tokio::select! {
+                    _ = stop.changed() => {
+                        info!("[Pipeline] Stopped");
+                        break;
+                    }
                     msg = async {
-                        if let Some(ref mut rx) = instant_rx {
+                        if let Some(rx) = instant_rx.as_mut() {
                             rx.recv().await
                         } else {
                             std::future::pending::<Option<(String, std::path::PathBuf)>>().await
@@ -190,6 +356,7 @@ impl PipelineScheduler {
                         }
                     }
                     _ = grouping_tick.tick() => {
+                        let _guard = grouping_lock.lock().await;
                         if let Err(e) = Self::group_and_generate(&grouper, &markdown_gen).await {
                             error!("Activity grouping failed: {}", e);
                         }
@@ -197,6 +364,7 @@ impl PipelineScheduler {
                         if let Err(e) = project_extractor.process_unlinked_activities().await {
                             error!("Project extraction failed: {}", e);
                         }
+                        Self::refresh_knowledge_base(knowledge_base.as_ref()).await;
                     }
                     _ = indexing_tick.tick() => {
                         if let Err(e) = Self::sync_index(&index_manager).await {
@@ -212,30 +380,25 @@ impl PipelineScheduler {
                             }
                             Err(e) => error!("Habit detection failed: {}", e),
                         }
+                        Self::refresh_knowledge_base(knowledge_base.as_ref()).await;
                     }
                     _ = summary_tick.tick() => {
-                        // 每10分钟检查：本地时间23点且今天未生成过 → 触发日总结
-                        let now = Local::now();
-                        let today = now.format("%Y-%m-%d").to_string();
-                        let already_done = last_summary_date.as_ref() == Some(&today);
-
-                        if now.hour() == 23 && !already_done {
-                            info!("[Pipeline] Triggering daily summary for {}", today);
-                            match summary_generator.generate_daily(&today).await {
-                                Ok(summary) => {
-                                    info!("[Pipeline] Daily summary generated for {} ({} activities)",
-                                        summary.date_start, summary.activity_ids.len());
-                                    last_summary_date = Some(today);
-                                }
-                                Err(e) => {
-                                    warn!("[Pipeline] Daily summary failed for {}: {}", today, e);
-                                }
+                        // 每10分钟检查：23点且台账中今天未完成 → 触发日总结
+                        // 与活动归档、补跑一致按 UTC 日期；台账持久化，重启后不会重复生成；错过的日期由补跑处理
+                        let now = summary_clock();
+                        if now.hour() == SUMMARY_HOUR {
+                            let today = now.date_naive();
+                            if Self::run_summary_job(&summary_generator, &ledger, JobKind::DailySummary, today).await.is_generated() {
+                                Self::refresh_knowledge_base(knowledge_base.as_ref()).await;
+                            }
+                            if Self::generate_rollups(&summary_generator, &ledger, today).await {
+                                Self::refresh_knowledge_base(knowledge_base.as_ref()).await;
                             }
                         }
                     }
                 }
             }
-        })
+        });
     }
 
     /// Task: 分组活动并生成Markdown
@@ -279,6 +442,94 @@ impl PipelineScheduler {
         Ok(())
     }
 
+    /// Task: 周日生成周总结，月末最后一天生成月总结（返回是否生成了新总结）
+    async fn generate_rollups(summary_generator: &SummaryGenerator, ledger: &JobLedger, today: NaiveDate) -> bool {
+        let mut generated = false;
+
+        if today.weekday() == Weekday::Sun {
+            generated |= Self::run_summary_job(summary_generator, ledger, JobKind::WeeklySummary, today).await.is_generated();
+        }
+
+        if today.succ_opt().is_some_and(|next| next.day() == 1) {
+            generated |= Self::run_summary_job(summary_generator, ledger, JobKind::MonthlySummary, today).await.is_generated();
+        }
+
+        generated
+    }
+
+    /// 生成 date 所在日/周/月的总结并记入台账（台账中已完成、已放弃或退避中时跳过；
+    /// 暂时性失败与预算用尽只推迟，不计入尝试次数）
+    async fn run_summary_job(
+        summary_generator: &SummaryGenerator,
+        ledger: &JobLedger,
+        kind: JobKind,
+        date: NaiveDate,
+    ) -> JobOutcome {
+        let target = match kind {
+            JobKind::WeeklySummary => week_bounds(date).0,
+            JobKind::MonthlySummary => month_bounds(date).0,
+            _ => date,
+        }
+        .to_string();
+
+        match ledger.should_run(kind, &target) {
+            Ok(true) => {}
+            Ok(false) => return JobOutcome::Skipped,
+            Err(e) => {
+                warn!("[Pipeline] Job ledger read failed: {}", e);
+                return JobOutcome::Skipped;
+            }
+        }
+
+        let result = match kind {
+            JobKind::WeeklySummary => summary_generator.generate_weekly(&target).await,
+            JobKind::MonthlySummary => summary_generator.generate_monthly(&target).await,
+            _ => summary_generator.generate_daily(&target).await,
+        };
+
+        let recorded = match &result {
+            Ok(summary) => {
+                info!("[Pipeline] {} generated for {} ~ {} ({} activities)",
+                    kind.as_str(), summary.date_start, summary.date_end, summary.activity_ids.len());
+                ledger.record_success(kind, &target)
+            }
+            Err(e) if is_transient(e) || is_budget_error(e) => {
+                info!("[Pipeline] {} deferred for {}: {}", kind.as_str(), target, e);
+                ledger.record_deferral(kind, &target, &e.to_string())
+            }
+            Err(e) => {
+                warn!("[Pipeline] {} failed for {}: {}", kind.as_str(), target, e);
+                ledger.record_failure(kind, &target, &e.to_string())
+            }
+        };
+        if let Err(e) = recorded {
+            warn!("[Pipeline] Job ledger write failed: {}", e);
+        }
+
+        match result {
+            Ok(_) => JobOutcome::Generated,
+            Err(e) if is_budget_error(&e) => JobOutcome::BudgetExhausted,
+            Err(e) if is_transient(&e) => JobOutcome::Deferred,
+            Err(_) => JobOutcome::Failed,
+        }
+    }
+
+    /// Task: 重建知识库链接（未设置或未开启时跳过）
+    ///
+    /// 重建会遍历并改写 Markdown 文件，放到阻塞线程池执行，不占用调度循环
+    async fn refresh_knowledge_base(knowledge_base: Option<&Arc<KnowledgeBase>>) {
+        let Some(knowledge_base) = knowledge_base.cloned() else { return };
+        match tokio::task::spawn_blocking(move || knowledge_base.rebuild()).await {
+            Ok(Ok(report)) if report.notes > 0 => {
+                info!("Knowledge base rebuilt - notes: {}, updated: {}, missing: {}, links: {}",
+                    report.notes, report.updated, report.missing, report.links);
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Knowledge base rebuild failed: {}", e),
+            Err(e) => error!("Knowledge base rebuild task panicked: {}", e),
+        }
+    }
+
     /// Task: 同步索引
     async fn sync_index(index_manager: &IndexManager) -> Result<()> {
         info!("Starting index sync...");
@@ -286,15 +537,222 @@ impl PipelineScheduler {
         let stats = index_manager.sync().await?;
 
         info!(
-            "Index sync completed - total: {}, indexed: {}, skipped: {}, failed: {}, chunks: {}",
+            "Index sync completed - total: {}, indexed: {}, skipped: {}, failed: {}, chunks: {}, re-embedded: {}",
             stats.total_files,
             stats.indexed_files,
             stats.skipped_files,
             stats.failed_files,
-            stats.new_chunks
+            stats.new_chunks,
+            stats.reembedded_chunks
         );
 
         Ok(())
     }
 
 }
+
+/// 总结任务的日期基准：日总结与补跑统一用 UTC，与活动归档、总结的日期边界一致
+fn summary_clock() -> chrono::DateTime<Utc> {
+    Utc::now()
+}
+
+/// 单个总结任务的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobOutcome {
+    Generated,
+    Failed,
+    /// 暂时性失败（网络、限流、服务端错误），稍后重试
+    Deferred,
+    /// 预算用尽，稍后重试
+    BudgetExhausted,
+    /// 台账中已完成、已放弃或退避中，未执行
+    Skipped,
+}
+
+impl JobOutcome {
+    fn is_generated(self) -> bool {
+        self == Self::Generated
+    }
+}
+
+/// 补跑所需的组件（后台定时任务与手动触发共用）
+struct CatchUp {
+    ledger: Arc<JobLedger>,
+    screenshot_analyzer: Arc<RwLock<Option<Arc<ScreenshotAnalyzer>>>>,
+    grouper: Arc<ActivityGrouper>,
+    markdown_gen: Arc<MarkdownGenerator>,
+    project_extractor: Arc<ProjectExtractor>,
+    summary_generator: Arc<SummaryGenerator>,
+    usage: Option<Arc<UsageTracker>>,
+    knowledge_base: Option<Arc<KnowledgeBase>>,
+    grouping_lock: Arc<Mutex<()>>,
+}
+
+impl CatchUp {
+    /// 一轮补跑：先补分析录制并分组，再补总结，最后重建知识库链接
+    async fn run_once(&self) -> CatchUpReport {
+        let mut report = CatchUpReport::default();
+
+        self.analyze_backlog(&mut report).await;
+
+        if report.analyzed > 0 {
+            let _guard = self.grouping_lock.lock().await;
+            if let Err(e) = PipelineScheduler::group_and_generate(&self.grouper, &self.markdown_gen).await {
+                error!("Catch-up grouping failed: {}", e);
+            }
+            if let Err(e) = self.project_extractor.process_unlinked_activities().await {
+                error!("Catch-up project extraction failed: {}", e);
+            }
+        }
+
+        self.backfill_summaries(&mut report).await;
+
+        if !report.is_empty() {
+            PipelineScheduler::refresh_knowledge_base(self.knowledge_base.as_ref()).await;
+        }
+
+        report
+    }
+
+    /// 补分析遗留录制（AI 未连接时跳过；暂时性失败只推迟、不计入尝试次数，预算用尽时停止）
+    async fn analyze_backlog(&self, report: &mut CatchUpReport) {
+        let Some(analyzer) = self.screenshot_analyzer.read().await.clone() else { return };
+
+        let pending = match self.ledger.pending_recordings(CATCH_UP_MIN_AGE_SECS, CATCH_UP_RECORDINGS) {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("[Pipeline] Failed to load pending recordings: {}", e);
+                return;
+            }
+        };
+
+        for (i, recording) in pending.iter().enumerate() {
+            if let Some(Err(e)) = self.usage.as_ref().map(|u| u.check(AIPurpose::RecordingAnalysis)) {
+                info!("[Pipeline] Catch-up analysis paused: {}", e);
+                report.budget_exhausted = true;
+                return;
+            }
+            if i > 0 {
+                tokio::time::sleep(CATCH_UP_RECORDING_GAP).await;
+            }
+
+            let kind = JobKind::RecordingAnalysis;
+            let recorded = match analyzer.analyze_single_direct(&recording.id, &recording.path).await {
+                Ok(()) => {
+                    report.analyzed += 1;
+                    self.ledger.clear(kind, &recording.id)
+                }
+                Err(e) if is_budget_error(&e) => {
+                    info!("[Pipeline] Catch-up analysis paused: {}", e);
+                    report.budget_exhausted = true;
+                    if let Err(e) = self.ledger.record_deferral(kind, &recording.id, &e.to_string()) {
+                        warn!("[Pipeline] Job ledger write failed: {}", e);
+                    }
+                    return;
+                }
+                Err(e) if is_transient(&e) => {
+                    report.analysis_failed += 1;
+                    self.ledger.record_deferral(kind, &recording.id, &e.to_string())
+                }
+                Err(e) => {
+                    report.analysis_failed += 1;
+                    self.ledger.record_failure(kind, &recording.id, &e.to_string())
+                }
+            };
+            if let Err(e) = recorded {
+                warn!("[Pipeline] Job ledger write failed: {}", e);
+            }
+        }
+    }
+
+    /// 补生成错过的日/周/月总结（按 UTC 日期，与活动归档日期一致）
+    async fn backfill_summaries(&self, report: &mut CatchUpReport) {
+        let today = summary_clock().date_naive();
+        let jobs = match self.ledger.missing_summaries(today, CATCH_UP_LOOKBACK_DAYS) {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                warn!("[Pipeline] Failed to detect missing summaries: {}", e);
+                return;
+            }
+        };
+
+        for PendingJob { kind, target } in jobs.into_iter().take(CATCH_UP_SUMMARIES) {
+            let Ok(date) = NaiveDate::parse_from_str(&target, "%Y-%m-%d") else { continue };
+            match PipelineScheduler::run_summary_job(&self.summary_generator, &self.ledger, kind, date).await {
+                JobOutcome::Generated => report.summaries += 1,
+                JobOutcome::Failed | JobOutcome::Deferred => report.summary_failed += 1,
+                JobOutcome::BudgetExhausted => {
+                    report.budget_exhausted = true;
+                    return;
+                }
+                JobOutcome::Skipped => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    #[tokio::test]
+    async fn test_start_stop_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let (_tx, rx) = tokio::sync::mpsc::channel(1);
+        let scheduler = PipelineScheduler::new(db, dir.path().to_path_buf(), false)
+            .unwrap()
+            .with_analysis_receiver(rx);
+
+        scheduler.start();
+        scheduler.start();
+        assert!(scheduler.is_running());
+
+        scheduler.stop();
+        assert!(!scheduler.is_running());
+        // 调度任务退出后释放即时分析 receiver，重新启动时可继续使用
+        let released = tokio::time::timeout(Duration::from_secs(5), scheduler.analysis_rx.lock()).await;
+        assert!(released.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_backfills_missed_summaries_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let start = chrono::Utc::now().timestamp() - 3 * 86400;
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                        category, screenshot_ids, markdown_path)
+                 VALUES ('a1', '在VSCode中工作', ?1, ?1 + 3600, 60, 'VSCode', '\"work\"', '[]', '')",
+                [start],
+            )?;
+            Ok(())
+        }).unwrap();
+
+        let scheduler = PipelineScheduler::new(Arc::clone(&db), dir.path().to_path_buf(), false).unwrap();
+        let report = scheduler.catch_up().await;
+        assert!(report.summaries >= 1);
+        assert_eq!(report.summary_failed, 0);
+
+        let date = DateTime::from_timestamp(start, 0).unwrap().date_naive();
+        let daily: i64 = db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT COUNT(*) FROM summaries WHERE id = ?1",
+                [format!("summary-daily-{}", date)],
+                |row| row.get(0),
+            )?)
+        }).unwrap();
+        assert_eq!(daily, 1);
+
+        // 已补过的不再重复生成
+        assert!(scheduler.catch_up().await.is_empty());
+
+        // 台账中已完成的任务跳过，不算失败
+        let outcome = PipelineScheduler::run_summary_job(
+            &scheduler.summary_generator, &scheduler.ledger, JobKind::DailySummary, date,
+        ).await;
+        assert_eq!(outcome, JobOutcome::Skipped);
+    }
+}
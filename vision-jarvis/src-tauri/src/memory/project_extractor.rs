@@ -259,7 +259,12 @@ impl ProjectExtractor {
                 })
             })?.collect::<rusqlite::Result<Vec<_>>>()?;
 
-            Ok(activities)
+            activities.into_iter()
+                .map(|mut a| {
+                    a.summary = self.db.vault().decrypt_opt_text(a.summary)?;
+                    Ok(a)
+                })
+                .collect()
         })
     }
 
@@ -368,7 +373,7 @@ impl ProjectExtractor {
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&full_path, content)?;
+        self.db.vault().write_file(&full_path, content.as_bytes())?;
         Ok(full_path)
     }
 }
@@ -0,0 +1,438 @@
+/// 记忆查询
+///
+/// 查询 activities / projects / habits / summaries / memory_chunks，
+/// 供 Tauri 命令（`commands::memory`）与命令行客户端 `vjctl` 共用
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ai::{AIClient, EmbeddingProvider};
+use crate::db::schema::Summary;
+use crate::db::Database;
+use crate::error::AppError;
+use super::hybrid_search::{self, HybridSearchConfig, SearchHit};
+use super::knowledge_base::KnowledgeBase;
+use super::summary_generator::{parse_date, SummaryConfig, SummaryGenerator};
+
+pub use super::search::SearchFilters;
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityInfo {
+    pub id: String,
+    pub title: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub duration_minutes: i64,
+    pub application: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub summary: Option<String>,
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityDetail {
+    pub activity: ActivityInfo,
+    pub screenshot_analyses: Vec<ScreenshotAnalysisInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotAnalysisInfo {
+    pub screenshot_id: String,
+    pub application: String,
+    pub activity_type: String,
+    pub activity_description: String,
+    pub analyzed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub start_date: i64,
+    pub last_activity_date: i64,
+    pub activity_count: i32,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitInfo {
+    pub id: String,
+    pub pattern_name: String,
+    pub pattern_type: String,
+    pub confidence: f32,
+    pub frequency: String,
+    pub occurrence_count: i32,
+    pub typical_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryInfo {
+    pub id: String,
+    pub summary_type: String,
+    pub date_start: String,
+    pub date_end: String,
+    pub content: String,
+    pub activity_count: usize,
+}
+
+impl From<Summary> for SummaryInfo {
+    fn from(summary: Summary) -> Self {
+        Self {
+            id: summary.id,
+            summary_type: summary.summary_type.as_str().to_string(),
+            date_start: summary.date_start,
+            date_end: summary.date_end,
+            content: summary.content,
+            activity_count: summary.activity_ids.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingStatsInfo {
+    pub total_recordings: i64,
+    pub analyzed_recordings: i64,
+    pub total_activities: i64,
+    pub total_projects: i64,
+    pub total_habits: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Queries
+// ---------------------------------------------------------------------------
+
+const ACTIVITY_COLUMNS: &str = "id, title, start_time, end_time, duration_minutes,
+                                application, category, tags, summary, project_id";
+
+fn activity_from_row(row: &rusqlite::Row) -> rusqlite::Result<ActivityInfo> {
+    let tags_json: String = row.get(7)?;
+    Ok(ActivityInfo {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        start_time: row.get(2)?,
+        end_time: row.get(3)?,
+        duration_minutes: row.get(4)?,
+        application: row.get(5)?,
+        category: row.get(6)?,
+        tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        summary: row.get(8)?,
+        project_id: row.get(9)?,
+    })
+}
+
+/// activities.summary 可能是密文
+fn decrypt_activity(db: &Database, mut activity: ActivityInfo) -> Result<ActivityInfo> {
+    activity.summary = db.vault().decrypt_opt_text(activity.summary)?;
+    Ok(activity)
+}
+
+/// 按日期（YYYY-MM-DD）查询活动
+pub fn activities(db: &Database, date: &str) -> Result<Vec<ActivityInfo>> {
+    let parsed = parse_date(date)?;
+    let start_ts = parsed.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+    let end_ts = parsed.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
+
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM activities
+             WHERE start_time >= ?1 AND start_time <= ?2
+             ORDER BY start_time ASC",
+            ACTIVITY_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map(rusqlite::params![start_ts, end_ts], activity_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter().map(|a| decrypt_activity(db, a)).collect()
+    })
+}
+
+/// 活动详情（含关联的 screenshot_analyses）
+pub fn activity_detail(db: &Database, id: &str) -> Result<ActivityDetail> {
+    db.with_connection(|conn| {
+        let activity = conn
+            .prepare(&format!("SELECT {} FROM activities WHERE id = ?1", ACTIVITY_COLUMNS))?
+            .query_row([id], activity_from_row)?;
+        let activity = decrypt_activity(db, activity)?;
+
+        // 查关联的 screenshot_ids (JSON array)
+        let screenshot_ids_json: String = conn.prepare(
+            "SELECT screenshot_ids FROM activities WHERE id = ?1"
+        )?.query_row([id], |row| row.get(0))?;
+
+        let screenshot_ids: Vec<String> =
+            serde_json::from_str(&screenshot_ids_json).unwrap_or_default();
+
+        // 查 screenshot_analyses
+        let analyses = if screenshot_ids.is_empty() {
+            Vec::new()
+        } else {
+            let placeholders: Vec<String> = screenshot_ids.iter().enumerate()
+                .map(|(i, _)| format!("?{}", i + 1))
+                .collect();
+            let sql = format!(
+                "SELECT screenshot_id, application, activity_type, activity_description, analyzed_at
+                 FROM screenshot_analyses
+                 WHERE screenshot_id IN ({})
+                 ORDER BY analyzed_at ASC",
+                placeholders.join(", ")
+            );
+
+            let mut sa_stmt = conn.prepare(&sql)?;
+            let params: Vec<&dyn rusqlite::types::ToSql> = screenshot_ids.iter()
+                .map(|s| s as &dyn rusqlite::types::ToSql)
+                .collect();
+
+            let result = sa_stmt.query_map(params.as_slice(), |row| {
+                Ok(ScreenshotAnalysisInfo {
+                    screenshot_id: row.get(0)?,
+                    application: row.get(1)?,
+                    activity_type: row.get(2)?,
+                    activity_description: row.get(3)?,
+                    analyzed_at: row.get(4)?,
+                })
+            })?.collect::<rusqlite::Result<Vec<_>>>()?;
+            result.into_iter()
+                .map(|mut info| {
+                    info.activity_description = db.vault().decrypt_text(info.activity_description)?;
+                    Ok(info)
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        Ok(ActivityDetail {
+            activity,
+            screenshot_analyses: analyses,
+        })
+    })
+}
+
+/// 活动的 Markdown 笔记内容（路径相对存储根目录，已加密时解密）
+pub fn activity_markdown(db: &Database, storage_root: &Path, id: &str) -> Result<String> {
+    let markdown_path: String = db.with_connection(|conn| {
+        Ok(conn
+            .prepare("SELECT markdown_path FROM activities WHERE id = ?1")?
+            .query_row([id], |row| row.get(0))?)
+    })?;
+    if markdown_path.is_empty() {
+        return Err(AppError::storage(16, format!("活动 {} 没有 Markdown 笔记", id)).into());
+    }
+    db.vault().read_to_string(&storage_root.join(markdown_path))
+}
+
+/// 项目列表（按最近活动时间倒序）
+pub fn projects(db: &Database) -> Result<Vec<ProjectInfo>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, title, description, start_date, last_activity_date,
+                    activity_count, status
+             FROM projects
+             ORDER BY last_activity_date DESC"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(ProjectInfo {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                description: row.get(2)?,
+                start_date: row.get(3)?,
+                last_activity_date: row.get(4)?,
+                activity_count: row.get(5)?,
+                status: row.get(6)?,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+}
+
+/// 习惯列表（按置信度倒序）
+pub fn habits(db: &Database) -> Result<Vec<HabitInfo>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, pattern_name, pattern_type, confidence, frequency,
+                    occurrence_count, typical_time
+             FROM habits
+             ORDER BY confidence DESC"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(HabitInfo {
+                id: row.get(0)?,
+                pattern_name: row.get(1)?,
+                pattern_type: row.get(2)?,
+                confidence: row.get(3)?,
+                frequency: row.get(4)?,
+                occurrence_count: row.get(5)?,
+                typical_time: row.get(6)?,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+}
+
+/// 日总结（未生成时为 None）
+pub fn daily_summary(db: &Database, date: &str) -> Result<Option<SummaryInfo>> {
+    parse_date(date)?;
+
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, summary_type, date_start, date_end, content, activity_ids
+             FROM summaries
+             WHERE summary_type = 'daily' AND date_start = ?1"
+        )?;
+
+        let row = stmt.query_row([date], |row| {
+            let activity_ids_json: String = row.get(5)?;
+            let activity_ids: Vec<String> =
+                serde_json::from_str(&activity_ids_json).unwrap_or_default();
+
+            Ok(SummaryInfo {
+                id: row.get(0)?,
+                summary_type: row.get(1)?,
+                date_start: row.get(2)?,
+                date_end: row.get(3)?,
+                content: row.get(4)?,
+                activity_count: activity_ids.len(),
+            })
+        });
+
+        match row {
+            Ok(mut s) => {
+                s.content = db.vault().decrypt_text(s.content)?;
+                Ok(Some(s))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    })
+}
+
+/// 录制/分析状态统计
+pub fn recording_stats(db: &Database) -> Result<RecordingStatsInfo> {
+    db.with_connection(|conn| {
+        let count = |sql: &str| -> rusqlite::Result<i64> {
+            conn.prepare(sql)?.query_row([], |row| row.get(0))
+        };
+
+        Ok(RecordingStatsInfo {
+            total_recordings: count("SELECT COUNT(*) FROM recordings")?,
+            analyzed_recordings: count("SELECT COUNT(*) FROM recordings WHERE analyzed = 1")?,
+            total_activities: count("SELECT COUNT(*) FROM activities")?,
+            total_projects: count("SELECT COUNT(*) FROM projects")?,
+            total_habits: count("SELECT COUNT(*) FROM habits")?,
+        })
+    })
+}
+
+/// 搜索记忆（混合检索：关键词 BM25 + 语义向量，RRF 融合并按更新时间衰减）
+pub async fn search(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<SearchHit>> {
+    hybrid_search::hybrid_search(
+        db,
+        embedder,
+        query,
+        filters,
+        limit,
+        &HybridSearchConfig::default(),
+    ).await
+}
+
+/// 按需构建总结生成器（pipeline 内的实例不对外暴露），有 AI 客户端时使用 AI 总结
+pub fn summary_generator(
+    db: Arc<Database>,
+    ai_client: Option<Arc<AIClient>>,
+    storage_root: PathBuf,
+) -> SummaryGenerator {
+    let enable_ai = ai_client.is_some();
+    SummaryGenerator::new(
+        ai_client,
+        db,
+        SummaryConfig {
+            storage_root,
+            enable_ai,
+        },
+    )
+}
+
+/// 新总结写入后补齐知识库链接（未开启时为空操作）
+pub async fn refresh_knowledge_base(knowledge_base: Arc<KnowledgeBase>) {
+    if let Ok(Err(e)) = tokio::task::spawn_blocking(move || knowledge_base.rebuild()).await {
+        log::warn!("重建知识库链接失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(db: &Database) {
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                        category, screenshot_ids, tags, markdown_path)
+                 VALUES ('a1', '写代码', 1718013600, 1718017200, 60, 'VSCode', 'work', '[]', '[\"rust\"]',
+                         'activities/2024-06-10/a1.md'),
+                        ('a2', '开会', 1718100000, 1718101800, 30, 'Zoom', 'meeting', '[]', '[]', '');
+                 INSERT INTO recordings (id, path, start_time, end_time, fps, analyzed)
+                 VALUES ('r1', '/tmp/r1.mp4', 0, 60, 2, 1), ('r2', '/tmp/r2.mp4', 60, 120, 2, 0);",
+            )?;
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_activities_by_date() {
+        let db = Database::open_in_memory().unwrap();
+        seed(&db);
+
+        let found = activities(&db, "2024-06-10").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "a1");
+        assert_eq!(found[0].tags, vec!["rust".to_string()]);
+        assert!(activities(&db, "2024-06-12").unwrap().is_empty());
+
+        let err = activities(&db, "06/10/2024").unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Validation(31, _))));
+    }
+
+    #[test]
+    fn test_activity_markdown() {
+        let db = Database::open_in_memory().unwrap();
+        seed(&db);
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("activities/2024-06-10/a1.md");
+        std::fs::create_dir_all(note.parent().unwrap()).unwrap();
+        std::fs::write(&note, "# 写代码\n").unwrap();
+
+        assert_eq!(activity_markdown(&db, dir.path(), "a1").unwrap(), "# 写代码\n");
+        assert!(activity_markdown(&db, dir.path(), "a2").is_err());
+        assert!(activity_markdown(&db, dir.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn test_recording_stats() {
+        let db = Database::open_in_memory().unwrap();
+        seed(&db);
+
+        let stats = recording_stats(&db).unwrap();
+        assert_eq!(stats.total_recordings, 2);
+        assert_eq!(stats.analyzed_recordings, 1);
+        assert_eq!(stats.total_activities, 2);
+        assert_eq!(stats.total_projects, 0);
+        assert!(daily_summary(&db, "2024-06-10").unwrap().is_none());
+    }
+}
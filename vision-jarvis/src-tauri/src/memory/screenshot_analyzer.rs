@@ -10,9 +10,11 @@ use log::{info, warn, error};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
 
-use crate::ai::AIClient;
+use crate::ai::{AIClient, AIPurpose};
+use crate::capture::active_window::WindowSample;
 use crate::db::Database;
 use crate::db::schema::ScreenshotAnalysis;
+use crate::privacy::redaction::{self, RedactionAction, Redactor};
 
 /// AI返回的分析结果（用于JSON解析）
 /// 一次性提取所有下游组件需要的信息
@@ -50,13 +52,15 @@ fn default_activity_category() -> String {
 #[derive(Debug, Clone)]
 pub struct AnalyzerConfig {
     /// 分析失败后的最大重试次数
+    ///
+    /// 网络与限流错误已由 AIClient 退避重试，这里主要兜底响应解析失败
     pub max_retries: u32,
 }
 
 impl Default for AnalyzerConfig {
     fn default() -> Self {
         Self {
-            max_retries: 2,
+            max_retries: 1,
         }
     }
 }
@@ -66,11 +70,18 @@ pub struct ScreenshotAnalyzer {
     ai_client: Arc<AIClient>,
     db: Arc<Database>,
     config: AnalyzerConfig,
+    /// 发送前脱敏（未设置时原样发送）
+    redactor: Option<Arc<Redactor>>,
 }
 
 impl ScreenshotAnalyzer {
     pub fn new(ai_client: Arc<AIClient>, db: Arc<Database>, config: AnalyzerConfig) -> Self {
-        Self { ai_client, db, config }
+        Self { ai_client, db, config, redactor: None }
+    }
+
+    /// 设置脱敏器（builder 模式）
+    pub fn with_redactor(self, redactor: Arc<Redactor>) -> Self {
+        Self { redactor: Some(redactor), ..self }
     }
 
     /// 分析单个录制分段
@@ -80,18 +91,31 @@ impl ScreenshotAnalyzer {
         video_path: &Path,
     ) -> Result<ScreenshotAnalysis> {
         info!("读取视频文件: {} (exists={})", video_path.display(), video_path.exists());
-        let video_data = tokio::fs::read(video_path).await?;
+        let video_data = match &self.redactor {
+            Some(redactor) => match self.redacted_video(redactor, recording_id, video_path).await? {
+                Some(data) => data,
+                None => return self.save_withheld(recording_id, video_path),
+            },
+            None => self.read_video(video_path).await?,
+        };
         info!("视频文件大小: {} bytes, base64约: {} bytes", video_data.len(), video_data.len() * 4 / 3);
         let video_base64 = BASE64.encode(&video_data);
 
         let prompt = recording_understanding_prompt();
-        let response = self.ai_client.analyze_video(&video_base64, &prompt).await
-            .map_err(|e| anyhow::anyhow!("AI视频分析失败: {}", e))?;
+        let response = self.ai_client
+            .for_purpose(AIPurpose::RecordingAnalysis)
+            .analyze_video(&video_base64, &prompt)
+            .await
+            .map_err(|e| {
+                // 保留 AppError 供补跑区分暂时性失败
+                let message = format!("AI视频分析失败: {}", e);
+                anyhow::Error::new(e).context(message)
+            })?;
 
         let ai_result = parse_ai_response(&response)?;
         let now = chrono::Utc::now().timestamp();
 
-        let analysis = ScreenshotAnalysis {
+        let mut analysis = ScreenshotAnalysis {
             screenshot_id: recording_id.to_string(),
             application: ai_result.application,
             activity_type: ai_result.activity_type,
@@ -108,12 +132,115 @@ impl ScreenshotAnalyzer {
             accomplishments: ai_result.accomplishments,
         };
 
+        if let Some(redactor) = &self.redactor {
+            let counts = redactor.scrub_analysis(&mut analysis);
+            if !counts.is_empty() {
+                redactor.audit(recording_id, RedactionAction::PiiScrubbed, &redaction::format_counts(&counts));
+            }
+        }
+
         self.save_analysis(&analysis)?;
         self.write_analysis_json(&analysis, video_path)?;
         self.mark_recording_analyzed(recording_id)?;
         Ok(analysis)
     }
 
+    /// 按脱敏配置准备要发送的视频；返回 None 表示整段不能离开本机
+    async fn redacted_video(
+        &self,
+        redactor: &Redactor,
+        recording_id: &str,
+        video_path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        let samples = self.load_window_samples(recording_id)?;
+        if samples.is_empty() && redactor.has_window_blocklist() {
+            // 取不到前台窗口时无法确认分段里没有黑名单应用，按命中处理
+            redactor.audit(recording_id, RedactionAction::WindowUnknown, "没有前台窗口采样，无法核对窗口黑名单");
+            return Ok(None);
+        }
+        if let Some(blocked) = redactor.blocked_window(&samples) {
+            redactor.audit(
+                recording_id,
+                RedactionAction::BlockedWindow,
+                &format!("应用 {} 命中规则 '{}'（第 {}s）", blocked.app_name, blocked.rule, blocked.offset_secs),
+            );
+            return Ok(None);
+        }
+
+        let video = self.read_video(video_path).await?;
+        let regions = redactor.blur_regions();
+        if regions.is_empty() {
+            return Ok(Some(video));
+        }
+
+        let count = regions.len();
+        let temp_dir = redactor.temp_dir();
+        match tokio::task::spawn_blocking(move || redaction::blur_video(&video, &regions, &temp_dir)).await? {
+            Ok(data) => {
+                redactor.audit(recording_id, RedactionAction::RegionsBlurred, &format!("{} 个区域", count));
+                Ok(Some(data))
+            }
+            Err(e) => {
+                // 模糊失败时不退回原视频，避免未脱敏内容外发
+                redactor.audit(recording_id, RedactionAction::BlurFailed, &e.to_string());
+                Ok(None)
+            }
+        }
+    }
+
+    /// 读取录制文件（静态加密开启时解密）
+    async fn read_video(&self, video_path: &Path) -> Result<Vec<u8>> {
+        let data = tokio::fs::read(video_path).await?;
+        self.db.vault().decrypt_bytes(data)
+    }
+
+    /// 保存不经 AI 的占位分析，使分段仍出现在时间线中且不会被反复重试
+    fn save_withheld(&self, recording_id: &str, video_path: &Path) -> Result<ScreenshotAnalysis> {
+        let analysis = ScreenshotAnalysis {
+            screenshot_id: recording_id.to_string(),
+            application: "已屏蔽".to_string(),
+            activity_type: "other".to_string(),
+            activity_description: "该分段包含受保护的内容，未发送 AI 分析".to_string(),
+            key_elements: Vec::new(),
+            ocr_text: None,
+            context_tags: vec!["redacted".to_string()],
+            productivity_score: 5,
+            analysis_json: "{}".to_string(),
+            analyzed_at: chrono::Utc::now().timestamp(),
+            activity_category: "other".to_string(),
+            activity_summary: "隐私屏蔽".to_string(),
+            project_name: None,
+            accomplishments: Vec::new(),
+        };
+
+        self.save_analysis(&analysis)?;
+        self.write_analysis_json(&analysis, video_path)?;
+        self.mark_recording_analyzed(recording_id)?;
+        info!("录制 {} 已按隐私规则屏蔽", &recording_id[..8.min(recording_id.len())]);
+        Ok(analysis)
+    }
+
+    /// 读取录制期间的前台窗口采样
+    fn load_window_samples(&self, recording_id: &str) -> Result<Vec<WindowSample>> {
+        let raw: Option<String> = self.db.with_connection(|conn| {
+            let raw = conn
+                .query_row(
+                    "SELECT window_samples FROM recordings WHERE id = ?1",
+                    [recording_id],
+                    |row| row.get(0),
+                )
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })?;
+            Ok(raw)
+        })?;
+
+        Ok(raw
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
     /// 即时分析单条录制（由 channel 事件驱动，跳过 DB 查询）
     pub async fn analyze_single_direct(&self, id: &str, path: &std::path::Path) -> Result<()> {
         if !path.exists() {
@@ -155,6 +282,14 @@ impl ScreenshotAnalyzer {
 
     /// 保存分析结果到数据库
     fn save_analysis(&self, analysis: &ScreenshotAnalysis) -> Result<()> {
+        let vault = self.db.vault();
+        let ocr_text = vault.encrypt_opt_text(analysis.ocr_text.as_deref())?;
+        let analysis_json = vault.encrypt_text(&analysis.analysis_json)?;
+        let description = vault.encrypt_text(&analysis.activity_description)?;
+        let summary = vault.encrypt_text(&analysis.activity_summary)?;
+        let key_elements = vault.encrypt_text(&serde_json::to_string(&analysis.key_elements)?)?;
+        let context_tags = vault.encrypt_text(&serde_json::to_string(&analysis.context_tags)?)?;
+        let accomplishments = vault.encrypt_text(&serde_json::to_string(&analysis.accomplishments)?)?;
         self.db.with_connection(|conn| {
             conn.execute(
                 "INSERT OR REPLACE INTO screenshot_analyses (
@@ -167,17 +302,17 @@ impl ScreenshotAnalyzer {
                     &analysis.screenshot_id,
                     &analysis.application,
                     &analysis.activity_type,
-                    &analysis.activity_description,
-                    serde_json::to_string(&analysis.key_elements)?,
-                    &analysis.ocr_text,
-                    serde_json::to_string(&analysis.context_tags)?,
+                    description,
+                    key_elements,
+                    ocr_text,
+                    context_tags,
                     analysis.productivity_score,
-                    &analysis.analysis_json,
+                    analysis_json,
                     analysis.analyzed_at,
                     &analysis.activity_category,
-                    &analysis.activity_summary,
+                    summary,
                     &analysis.project_name,
-                    serde_json::to_string(&analysis.accomplishments)?,
+                    accomplishments,
                 ],
             )?;
             Ok(())
@@ -189,7 +324,7 @@ impl ScreenshotAnalyzer {
         let json_path = video_path.with_extension("json");
         let json_content = serde_json::to_string_pretty(analysis)
             .map_err(|e| anyhow::anyhow!("序列化分析结果失败: {}", e))?;
-        std::fs::write(&json_path, json_content)
+        self.db.vault().write_file(&json_path, json_content.as_bytes())
             .map_err(|e| anyhow::anyhow!("写入分析JSON失败: {} - {}", json_path.display(), e))?;
         info!("分析JSON已写入: {}", json_path.display());
         Ok(())
@@ -274,6 +409,136 @@ fn extract_json_from_response(response: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::ai::{AIProvider, AIProviderConfig, Completion};
+    use crate::error::AppResult;
+    use crate::privacy::RedactionConfig;
+
+    struct StubProvider {
+        config: AIProviderConfig,
+        answer: String,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl AIProvider for StubProvider {
+        async fn send_text(&self, _: &str) -> AppResult<Completion> {
+            unreachable!()
+        }
+        async fn analyze_video(&self, _: &str, _: &str) -> AppResult<Completion> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Completion::new(self.answer.clone()))
+        }
+        async fn analyze_image(&self, _: &str, _: &str) -> AppResult<Completion> {
+            unreachable!()
+        }
+        async fn test_connection(&self) -> AppResult<String> {
+            Ok("ok".to_string())
+        }
+        fn config(&self) -> &AIProviderConfig {
+            &self.config
+        }
+    }
+
+    /// 返回 (分析器, AI 调用次数, 数据库, 临时目录, 录制文件路径)
+    fn redacting_analyzer(
+        answer: &str,
+        samples: &str,
+    ) -> (ScreenshotAnalyzer, Arc<AtomicUsize>, Arc<Database>, tempfile::TempDir, std::path::PathBuf) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = StubProvider {
+            config: AIProviderConfig::new("stub", "Stub", "http://localhost", "key", "model"),
+            answer: answer.to_string(),
+            calls: Arc::clone(&calls),
+        };
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("seg.mp4");
+        std::fs::write(&video, b"fake mp4").unwrap();
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO recordings (id, path, start_time, analyzed, created_at, window_samples)
+                 VALUES ('rec-1', ?1, 0, 0, 0, ?2)",
+                rusqlite::params![video.to_string_lossy(), samples],
+            )?;
+            Ok(())
+        }).unwrap();
+
+        let redactor = Arc::new(Redactor::new(Arc::clone(&db), RedactionConfig {
+            windows: crate::privacy::WindowRules {
+                apps: vec!["1Password".to_string()],
+                ..Default::default()
+            },
+            scrub_pii: true,
+            ..Default::default()
+        }));
+        let analyzer = ScreenshotAnalyzer::new(
+            Arc::new(AIClient::from_provider(Box::new(provider))),
+            Arc::clone(&db),
+            AnalyzerConfig::default(),
+        ).with_redactor(redactor);
+        (analyzer, calls, db, dir, video)
+    }
+
+    #[tokio::test]
+    async fn test_blocklisted_segment_never_sent() {
+        let samples = r#"[{"offset_secs":0,"app_name":"Code","title":"main.rs"},{"offset_secs":15,"app_name":"1Password","title":"Vault"}]"#;
+        let (analyzer, calls, db, _dir, video) = redacting_analyzer("{}", samples);
+
+        let analysis = analyzer.analyze_recording("rec-1", &video).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(analysis.application, "已屏蔽");
+
+        let (analyzed, action): (i64, String) = db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT r.analyzed, l.action FROM recordings r JOIN redaction_log l ON l.recording_id = r.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?)
+        }).unwrap();
+        assert_eq!((analyzed, action.as_str()), (1, "blocked_window"));
+    }
+
+    #[tokio::test]
+    async fn test_segment_without_window_samples_never_sent() {
+        for samples in ["[]", "not json"] {
+            let (analyzer, calls, db, _dir, video) = redacting_analyzer("{}", samples);
+
+            let analysis = analyzer.analyze_recording("rec-1", &video).await.unwrap();
+            assert_eq!(calls.load(Ordering::SeqCst), 0);
+            assert_eq!(analysis.application, "已屏蔽");
+
+            let action: String = db.with_connection(|conn| {
+                Ok(conn.query_row("SELECT action FROM redaction_log WHERE recording_id = 'rec-1'", [], |row| row.get(0))?)
+            }).unwrap();
+            assert_eq!(action, "window_unknown");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pii_scrubbed_before_persistence() {
+        let answer = r#"{"application":"Mail","activity_type":"communication","activity_description":"回复 carol@example.com","ocr_text":"手机 13700001111","context_tags":[],"key_elements":[]}"#;
+        let samples = r#"[{"offset_secs":0,"app_name":"Mail","title":"收件箱"}]"#;
+        let (analyzer, calls, db, _dir, video) = redacting_analyzer(answer, samples);
+
+        analyzer.analyze_recording("rec-1", &video).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (description, ocr, raw): (String, String, String) = db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT activity_description, ocr_text, analysis_json FROM screenshot_analyses WHERE screenshot_id = 'rec-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?)
+        }).unwrap();
+        assert_eq!(description, "回复 [已脱敏:email]");
+        assert_eq!(ocr, "手机 [已脱敏:phone]");
+        assert!(!raw.contains("carol@example.com") && !raw.contains("13700001111"));
+
+        let json = std::fs::read_to_string(video.with_extension("json")).unwrap();
+        assert!(!json.contains("carol@example.com"));
+    }
 
     #[test]
     fn test_parse_clean_json() {
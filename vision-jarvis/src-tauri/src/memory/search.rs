@@ -0,0 +1,404 @@
+/// 记忆检索 - 基于 memory_chunks 的语义搜索与全文搜索
+///
+/// - 语义搜索：查询文本与分块使用同一 embedding 模型向量化，按余弦相似度排序。
+///   只比较由当前模型生成的分块，切换模型后未重新向量化的分块暂不参与；
+///   过滤条件在 SQL 中生效，每次最多比较最近索引的 `SEMANTIC_CANDIDATES` 个分块，
+///   打分时只读取向量，命中后再读取正文。
+/// - 全文搜索：memory_chunks_fts（FTS5）按 BM25 排序，返回高亮片段。
+
+use anyhow::Result;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::embedding::{EmbeddingProvider, blob_to_embedding, cosine_similarity};
+use crate::db::Database;
+use super::fts;
+
+/// 全文检索片段的最大词元数
+const SNIPPET_TOKENS: i64 = 24;
+
+/// 语义检索每次最多比较的分块数（按索引时间取最新的）
+const SEMANTIC_CANDIDATES: i64 = 5000;
+
+/// 检索过滤条件（均为可选，多个条件同时满足）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// 分块来源：activity / project / habit / summary / other
+    pub source: Option<String>,
+    pub activity_id: Option<String>,
+    /// 时间范围起点（Unix 秒，含）；有关联活动时按活动开始时间，否则按索引时间
+    pub start_time: Option<i64>,
+    /// 时间范围终点（Unix 秒，含）
+    pub end_time: Option<i64>,
+    /// 关联活动的应用名
+    pub application: Option<String>,
+}
+
+impl SearchFilters {
+    /// 生成 WHERE 子句片段（以 AND 开头）及参数，要求查询中 memory_chunks 别名为 c、activities 别名为 a
+    pub(crate) fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut sql = String::new();
+        let mut params = Vec::new();
+
+        if let Some(source) = &self.source {
+            sql.push_str(" AND c.source = ?");
+            params.push(Value::Text(source.clone()));
+        }
+        if let Some(activity_id) = &self.activity_id {
+            sql.push_str(" AND c.activity_id = ?");
+            params.push(Value::Text(activity_id.clone()));
+        }
+        if let Some(start) = self.start_time {
+            sql.push_str(" AND COALESCE(a.start_time, c.updated_at) >= ?");
+            params.push(Value::Integer(start));
+        }
+        if let Some(end) = self.end_time {
+            sql.push_str(" AND COALESCE(a.start_time, c.updated_at) <= ?");
+            params.push(Value::Integer(end));
+        }
+        if let Some(application) = &self.application {
+            sql.push_str(" AND a.application = ? COLLATE NOCASE");
+            params.push(Value::Text(application.clone()));
+        }
+
+        (sql, params)
+    }
+}
+
+/// 语义检索命中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticHit {
+    pub id: String,
+    pub file_path: String,
+    pub start_line: i32,
+    pub end_line: i32,
+    pub text: String,
+    pub activity_id: Option<String>,
+    pub updated_at: i64,
+    /// 余弦相似度 [-1, 1]
+    pub score: f32,
+}
+
+/// 语义检索：返回与查询最相近的 `limit` 个分块
+pub async fn semantic_search(
+    db: &Database,
+    embedder: &dyn EmbeddingProvider,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<SemanticHit>> {
+    let query = query.trim();
+    if query.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let query_vec = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+
+    let (filter_sql, mut params) = filters.to_sql();
+    params.insert(0, Value::Text(embedder.model().to_string()));
+    params.push(Value::Integer(SEMANTIC_CANDIDATES));
+
+    db.with_connection(|conn| {
+        // 先只读取向量打分，保留得分最高的 limit 个
+        let mut scored: Vec<(i64, f32)> = {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT c.rowid, c.embedding
+                 FROM memory_chunks c
+                 LEFT JOIN activities a ON a.id = c.activity_id
+                 WHERE c.model = ? AND length(c.embedding) > 0{}
+                 ORDER BY c.updated_at DESC
+                 LIMIT ?",
+                filter_sql
+            ))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+                let blob: Vec<u8> = row.get(1)?;
+                Ok((row.get(0)?, cosine_similarity(&query_vec, &blob_to_embedding(&blob))))
+            })?.collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        scored.retain(|(_, score)| *score > 0.0);
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+
+        // 再读取命中分块的正文
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path, start_line, end_line, text, activity_id, updated_at
+             FROM memory_chunks WHERE rowid = ?1",
+        )?;
+        let mut hits = Vec::with_capacity(scored.len());
+        for (rowid, score) in scored {
+            hits.push(stmt.query_row([rowid], |row| {
+                Ok(SemanticHit {
+                    id: row.get(0)?,
+                    file_path: row.get(1)?,
+                    start_line: row.get(2)?,
+                    end_line: row.get(3)?,
+                    text: row.get(4)?,
+                    activity_id: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    score,
+                })
+            })?);
+        }
+
+        Ok(hits)
+    })
+}
+
+/// 全文检索命中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordHit {
+    pub id: String,
+    pub file_path: String,
+    pub start_line: i32,
+    pub end_line: i32,
+    pub activity_id: Option<String>,
+    pub updated_at: i64,
+    /// 命中片段，匹配词用 `<mark>` 包裹
+    pub snippet: String,
+    /// BM25 相关度（越大越相关）
+    pub score: f64,
+}
+
+/// 全文检索：支持中文子串、`"短语"` 与 `前缀*` 查询，按 BM25 排序
+pub fn fulltext_search(
+    db: &Database,
+    query: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<KeywordHit>> {
+    match fts::build_match_query(query) {
+        Some(match_query) => fulltext_match(db, match_query, filters, limit),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 按已构造的 FTS5 MATCH 表达式检索（见 `fts::build_match_query` / `fts::build_question_query`）
+pub(crate) fn fulltext_match(
+    db: &Database,
+    match_query: String,
+    filters: &SearchFilters,
+    limit: usize,
+) -> Result<Vec<KeywordHit>> {
+    let (filter_sql, filter_params) = filters.to_sql();
+    let mut params = vec![
+        Value::Text(fts::MARK_START.to_string()),
+        Value::Text(fts::MARK_END.to_string()),
+        Value::Integer(SNIPPET_TOKENS),
+        Value::Text(match_query),
+    ];
+    params.extend(filter_params);
+    params.push(Value::Integer(limit as i64));
+
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT c.id, c.file_path, c.start_line, c.end_line, c.activity_id, c.updated_at,
+                    snippet(memory_chunks_fts, 2, ?, ?, '…', ?),
+                    bm25(memory_chunks_fts)
+             FROM memory_chunks_fts f
+             JOIN memory_chunks c ON c.id = f.chunk_id
+             LEFT JOIN activities a ON a.id = c.activity_id
+             WHERE memory_chunks_fts MATCH ?{}
+             ORDER BY bm25(memory_chunks_fts)
+             LIMIT ?",
+            filter_sql
+        ))?;
+
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(params),
+            |row| {
+                let snippet: String = row.get(6)?;
+                let bm25: f64 = row.get(7)?;
+                Ok(KeywordHit {
+                    id: row.get(0)?,
+                    file_path: row.get(1)?,
+                    start_line: row.get(2)?,
+                    end_line: row.get(3)?,
+                    activity_id: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    snippet: fts::desegment(&snippet),
+                    // SQLite 的 bm25() 越小越相关，取反便于统一为“越大越好”
+                    score: -bm25,
+                })
+            },
+        )?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::embedding::HashEmbeddingProvider;
+    use crate::memory::index_manager::{IndexConfig, IndexManager};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_semantic_search_ranks_by_similarity() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        std::fs::write(dir.path().join("rust.md"), "在 VSCode 中调试 Rust 编译错误\n").unwrap();
+        std::fs::write(dir.path().join("video.md"), "在浏览器观看烹饪视频\n").unwrap();
+        manager.sync().await.unwrap();
+
+        let embedder = manager.embedder().await;
+        let hits = semantic_search(&db, embedder.as_ref(), "Rust 编译", &SearchFilters::default(), 10).await.unwrap();
+
+        assert!(!hits.is_empty());
+        assert_eq!(hits[0].file_path, "rust.md");
+        assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_ignores_other_models() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        std::fs::write(dir.path().join("a.md"), "rust debugging\n").unwrap();
+        manager.sync().await.unwrap();
+
+        let other = HashEmbeddingProvider::new(32);
+        let hits = semantic_search(&db, &other, "rust", &SearchFilters::default(), 10).await.unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_applies_filters_and_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        std::fs::create_dir_all(dir.path().join("activities")).unwrap();
+        std::fs::write(dir.path().join("activities/rust.md"), "调试 Rust 编译错误\n").unwrap();
+        std::fs::write(dir.path().join("rust.md"), "调试 Rust 编译错误\n").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "Rust 编译很慢\n").unwrap();
+        manager.sync().await.unwrap();
+        let embedder = manager.embedder().await;
+
+        let filters = SearchFilters { source: Some("activity".to_string()), ..Default::default() };
+        let hits = semantic_search(&db, embedder.as_ref(), "Rust 编译", &filters, 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "activities/rust.md");
+        assert!(hits[0].text.contains("Rust"));
+
+        let hits = semantic_search(&db, embedder.as_ref(), "Rust 编译", &SearchFilters::default(), 2).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].score >= hits[1].score);
+    }
+
+    async fn index_fixture(dir: &std::path::Path) -> Arc<Database> {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.to_path_buf(),
+            ..Default::default()
+        });
+
+        std::fs::write(dir.join("db.md"), "今天完成了数据库迁移脚本，修复 migration 失败\n").unwrap();
+        std::fs::write(dir.join("rust.md"), "在 VSCode 中调试 Rust compile error\n").unwrap();
+        std::fs::write(dir.join("video.md"), "在浏览器观看烹饪视频\n").unwrap();
+        manager.sync().await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_fulltext_search_cjk_substring() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = index_fixture(dir.path()).await;
+
+        let hits = fulltext_search(&db, "数据库迁移", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "db.md");
+        assert!(hits[0].snippet.contains("<mark>数据库迁移</mark>"));
+        assert!(hits[0].snippet.contains("脚本，修复 migration"));
+        assert!(hits[0].score > 0.0);
+
+        // 单字也可检索
+        let hits = fulltext_search(&db, "视", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "video.md");
+    }
+
+    #[tokio::test]
+    async fn test_fulltext_search_prefix_and_phrase() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = index_fixture(dir.path()).await;
+
+        let hits = fulltext_search(&db, "migra*", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "db.md");
+
+        let hits = fulltext_search(&db, "\"compile error\"", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "rust.md");
+
+        assert!(fulltext_search(&db, "\"error compile\"", &SearchFilters::default(), 10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fulltext_search_bm25_ordering() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = index_fixture(dir.path()).await;
+
+        // 多个词按 AND 组合
+        let hits = fulltext_search(&db, "在 VSCode", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_path, "rust.md");
+
+        let hits = fulltext_search(&db, "在", &SearchFilters::default(), 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].score >= hits[1].score);
+    }
+
+    #[tokio::test]
+    async fn test_fulltext_index_follows_file_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let manager = IndexManager::new(Arc::clone(&db), IndexConfig {
+            memory_root: dir.path().to_path_buf(),
+            ..Default::default()
+        });
+
+        std::fs::write(dir.path().join("a.md"), "alpha content\n").unwrap();
+        manager.sync().await.unwrap();
+        std::fs::write(dir.path().join("a.md"), "beta content\n").unwrap();
+        manager.sync().await.unwrap();
+
+        assert!(fulltext_search(&db, "alpha", &SearchFilters::default(), 10).unwrap().is_empty());
+        assert_eq!(fulltext_search(&db, "beta", &SearchFilters::default(), 10).unwrap().len(), 1);
+
+        // 手动清空全文索引后，同步时自动补齐
+        db.with_connection(|conn| {
+            conn.execute("DELETE FROM memory_chunks_fts", [])?;
+            Ok(())
+        }).unwrap();
+        manager.sync().await.unwrap();
+        assert_eq!(fulltext_search(&db, "beta", &SearchFilters::default(), 10).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_empty_query() {
+        let db = Database::open_in_memory().unwrap();
+        let embedder = HashEmbeddingProvider::default();
+        assert!(semantic_search(&db, &embedder, "  ", &SearchFilters::default(), 10).await.unwrap().is_empty());
+    }
+}
@@ -4,16 +4,27 @@
 /// 输出存入 summaries 表和 Markdown 文件
 
 use anyhow::Result;
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use log::{info, warn};
 use tokio::sync::RwLock;
 
-use crate::ai::AIClient;
+use crate::ai::{AIClient, AIPurpose};
 use crate::db::Database;
 use crate::db::schema::{ActivitySession, Summary, SummaryType};
+use crate::error::AppError;
+
+/// 周/月总结中引用的下级总结正文最多字符数（避免提示词过长）
+const CHILD_SUMMARY_MAX_CHARS: usize = 1500;
+/// 任意时段总结不超过该天数时由日总结汇总，更长的由周总结汇总
+const RANGE_DAILY_MAX_DAYS: i64 = 31;
+/// 任意时段总结最长天数
+pub const MAX_RANGE_DAYS: i64 = 366;
+/// 每天生成日总结（以及周日的周总结、月末的月总结）的时刻（UTC 小时）
+pub const SUMMARY_HOUR: u32 = 23;
 
 /// 总结生成器配置
 #[derive(Debug, Clone)]
@@ -114,6 +125,212 @@ impl SummaryGenerator {
         Ok(summary)
     }
 
+    /// 生成周总结：`date` 所在的周（周一至周日），由当周的日总结汇总，缺失的日总结先补生成
+    pub async fn generate_weekly(&self, date: &str) -> Result<Summary> {
+        let (start, end) = week_bounds(parse_date(date)?);
+        let children = self.ensure_daily_summaries(start, end).await?;
+        self.roll_up(SummaryType::Weekly, start, end, children).await
+    }
+
+    /// 生成月总结：`date` 所在的自然月，由与该月重叠的周总结汇总
+    pub async fn generate_monthly(&self, date: &str) -> Result<Summary> {
+        let (start, end) = month_bounds(parse_date(date)?);
+        let children = self.ensure_weekly_summaries(start, end).await?;
+        self.roll_up(SummaryType::Monthly, start, end, children).await
+    }
+
+    /// 生成任意时段总结（含首尾两天）：31 天以内由日总结汇总，更长的由周总结汇总
+    pub async fn generate_range(&self, start: &str, end: &str) -> Result<Summary> {
+        let (start, end) = (parse_date(start)?, parse_date(end)?);
+        let days = (end - start).num_days() + 1;
+        if !(1..=MAX_RANGE_DAYS).contains(&days) {
+            return Err(AppError::validation(
+                31,
+                format!("时段必须在 1-{} 天之间，且结束日期不能早于开始日期", MAX_RANGE_DAYS),
+            ).into());
+        }
+
+        let children = if days <= RANGE_DAILY_MAX_DAYS {
+            self.ensure_daily_summaries(start, end).await?
+        } else {
+            self.ensure_weekly_summaries(start, end).await?
+        };
+        self.roll_up(SummaryType::Range, start, end, children).await
+    }
+
+    /// 范围内有活动的各天的日总结：已有的直接复用，缺失的先生成
+    async fn ensure_daily_summaries(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Summary>> {
+        let active_days: BTreeSet<String> = self.get_activities_between(start, end)?
+            .iter()
+            .map(|a| format_date(a.start_time))
+            .collect();
+
+        let mut summaries = Vec::new();
+        for date in active_days {
+            let summary = match self.load_summary(&format!("summary-daily-{}", date))? {
+                Some(summary) => summary,
+                None => self.generate_daily(&date).await?,
+            };
+            summaries.push(summary);
+        }
+        Ok(summaries)
+    }
+
+    /// 与范围重叠的各周的周总结：在该周最后一天之后生成的直接复用，其余重新生成（没有活动的周跳过）
+    async fn ensure_weekly_summaries(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<Summary>> {
+        let mut summaries = Vec::new();
+        let mut week_start = week_bounds(start).0;
+        while week_start <= end {
+            let week_end = week_start + Duration::days(6);
+            let existing = self
+                .load_summary(&summary_id(&SummaryType::Weekly, week_start, week_end))?
+                .filter(|s| s.created_at >= complete_after(week_end));
+
+            match existing {
+                Some(summary) => summaries.push(summary),
+                None if !self.get_activities_between(week_start, week_end)?.is_empty() => {
+                    summaries.push(self.generate_weekly(&week_start.to_string()).await?);
+                }
+                None => {}
+            }
+            week_start += Duration::days(7);
+        }
+        Ok(summaries)
+    }
+
+    /// 汇总下级总结，生成周/月/时段总结
+    async fn roll_up(
+        &self,
+        summary_type: SummaryType,
+        start: NaiveDate,
+        end: NaiveDate,
+        children: Vec<Summary>,
+    ) -> Result<Summary> {
+        let title = summary_title(&summary_type, start, end);
+        if children.is_empty() {
+            return Err(anyhow::anyhow!("{} 没有活动记录", title));
+        }
+
+        let activities = self.get_activities_between(start, end)?;
+        info!("生成{}，共 {} 篇下级总结、{} 个活动", title, children.len(), activities.len());
+
+        let content = if self.config.enable_ai {
+            let ai_guard = self.ai_client.read().await;
+            if let Some(ref client) = *ai_guard {
+                match self.generate_ai_rollup(client, &title, &children, &activities).await {
+                    Ok(c) => c,
+                    Err(e) => {
+                        warn!("AI{}生成失败: {}，使用模板", title, e);
+                        generate_template_rollup(start, end, &children, &activities)
+                    }
+                }
+            } else {
+                generate_template_rollup(start, end, &children, &activities)
+            }
+        } else {
+            generate_template_rollup(start, end, &children, &activities)
+        };
+
+        let activity_ids: Vec<String> = activities.iter().map(|a| a.id.clone()).collect();
+        let project_ids = self.get_related_project_ids(&activity_ids)?;
+        let markdown_path = summary_path(&summary_type, start, end);
+
+        let summary = Summary {
+            id: summary_id(&summary_type, start, end),
+            summary_type,
+            date_start: start.to_string(),
+            date_end: end.to_string(),
+            content,
+            activity_ids,
+            project_ids: if project_ids.is_empty() { None } else { Some(project_ids) },
+            markdown_path: markdown_path.clone(),
+            created_at: Utc::now().timestamp(),
+        };
+
+        let full_content = format_rollup_markdown(&summary, &title, &children);
+        self.write_file(&markdown_path, &full_content)?;
+        self.save_summary(&summary)?;
+
+        Ok(summary)
+    }
+
+    /// AI生成周/月/时段总结
+    async fn generate_ai_rollup(
+        &self,
+        client: &AIClient,
+        title: &str,
+        children: &[Summary],
+        activities: &[ActivitySession],
+    ) -> Result<String> {
+        let total_minutes: i64 = activities.iter().map(|a| a.duration_minutes).sum();
+        let children_desc = children.iter()
+            .map(|s| format!("### {}\n{}", child_title(s), truncate_chars(&s.content, CHILD_SUMMARY_MAX_CHARS)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let prompt = format!(
+            r#"基于以下分期总结生成{}。
+
+## 统计（共{}个活动，总计{}分钟）
+{}
+
+## 分期总结
+{}
+
+请生成简洁的总结，包含：
+1. 时间分配与主要投入方向
+2. 关键成果（3-5条）
+3. 趋势与效率变化
+4. 下一阶段建议
+
+要求简洁专业，数据驱动。直接输出总结内容，不要包含标题。"#,
+            title, activities.len(), total_minutes, format_app_usage(activities), children_desc
+        );
+
+        let response = client.for_purpose(AIPurpose::DailySummary).send_text(&prompt).await
+            .map_err(|e| anyhow::anyhow!("AI调用失败: {}", e))?;
+
+        Ok(response)
+    }
+
+    /// 按 ID 读取已有总结
+    fn load_summary(&self, id: &str) -> Result<Option<Summary>> {
+        self.db.with_connection(|conn| {
+            let result = conn.query_row(
+                "SELECT id, summary_type, date_start, date_end, content,
+                        activity_ids, project_ids, markdown_path, created_at
+                 FROM summaries WHERE id = ?1",
+                [id],
+                |row| {
+                    let summary_type: String = row.get(1)?;
+                    let activity_ids: String = row.get(5)?;
+                    let project_ids: Option<String> = row.get(6)?;
+                    Ok(Summary {
+                        id: row.get(0)?,
+                        summary_type: serde_json::from_value(serde_json::Value::String(summary_type))
+                            .unwrap_or(SummaryType::Daily),
+                        date_start: row.get(2)?,
+                        date_end: row.get(3)?,
+                        content: row.get(4)?,
+                        activity_ids: serde_json::from_str(&activity_ids).unwrap_or_default(),
+                        project_ids: project_ids.and_then(|p| serde_json::from_str(&p).ok()),
+                        markdown_path: row.get(7)?,
+                        created_at: row.get(8)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(mut summary) => {
+                    summary.content = self.db.vault().decrypt_text(summary.content)?;
+                    Ok(Some(summary))
+                }
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
     /// AI生成日总结
     async fn generate_ai_daily_summary(
         &self,
@@ -166,7 +383,7 @@ impl SummaryGenerator {
             activities.len(), total_minutes, activities_desc, accomplishments_section
         );
 
-        let response = client.send_text(&prompt).await
+        let response = client.for_purpose(AIPurpose::DailySummary).send_text(&prompt).await
             .map_err(|e| anyhow::anyhow!("AI调用失败: {}", e))?;
 
         Ok(response)
@@ -217,8 +434,13 @@ impl SummaryGenerator {
     /// 获取指定日期的活动
     fn get_activities_for_date(&self, date: &str) -> Result<Vec<ActivitySession>> {
         let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
-        let start_ts = parsed.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
-        let end_ts = parsed.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
+        self.get_activities_between(parsed, parsed)
+    }
+
+    /// 获取日期范围内（含首尾两天）的活动
+    fn get_activities_between(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<ActivitySession>> {
+        let start_ts = start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+        let end_ts = end.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp();
 
         self.db.with_connection(|conn| {
             let mut stmt = conn.prepare(
@@ -256,18 +478,40 @@ impl SummaryGenerator {
                 },
             )?.collect::<rusqlite::Result<Vec<_>>>()?;
 
-            Ok(activities)
+            activities.into_iter()
+                .map(|mut a| {
+                    a.summary = self.db.vault().decrypt_opt_text(a.summary)?;
+                    Ok(a)
+                })
+                .collect()
         })
     }
 
     /// 获取关联的项目ID
-    fn get_related_project_ids(&self, _activity_ids: &[String]) -> Result<Vec<String>> {
-        // TODO: 实现项目关联查询
-        Ok(Vec::new())
+    fn get_related_project_ids(&self, activity_ids: &[String]) -> Result<Vec<String>> {
+        if activity_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.db.with_connection(|conn| {
+            let placeholders = vec!["?"; activity_ids.len()].join(", ");
+            let sql = format!(
+                "SELECT DISTINCT project_id FROM activities
+                 WHERE project_id IS NOT NULL AND id IN ({})
+                 ORDER BY project_id",
+                placeholders
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let ids = stmt
+                .query_map(rusqlite::params_from_iter(activity_ids), |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(ids)
+        })
     }
 
     /// 保存总结到数据库
     fn save_summary(&self, summary: &Summary) -> Result<()> {
+        let content = self.db.vault().encrypt_text(&summary.content)?;
         self.db.with_connection(|conn| {
             conn.execute(
                 "INSERT OR REPLACE INTO summaries (
@@ -279,7 +523,7 @@ impl SummaryGenerator {
                     summary.summary_type.as_str(),
                     &summary.date_start,
                     &summary.date_end,
-                    &summary.content,
+                    content,
                     serde_json::to_string(&summary.activity_ids)?,
                     summary.project_ids.as_ref().map(|p| serde_json::to_string(p).ok()).flatten(),
                     &summary.markdown_path,
@@ -302,7 +546,7 @@ impl SummaryGenerator {
                 .collect();
             let sql = format!(
                 "SELECT accomplishments FROM screenshot_analyses \
-                 WHERE screenshot_id IN ({}) AND accomplishments IS NOT NULL AND accomplishments != '[]'",
+                 WHERE screenshot_id IN ({}) AND accomplishments IS NOT NULL",
                 placeholders.join(", ")
             );
 
@@ -316,8 +560,9 @@ impl SummaryGenerator {
             })?.collect::<rusqlite::Result<Vec<_>>>()?;
 
             let mut all: Vec<String> = Vec::new();
-            for json_str in &rows {
-                if let Ok(items) = serde_json::from_str::<Vec<String>>(json_str) {
+            for json_str in rows {
+                let json_str = self.db.vault().decrypt_text(json_str)?;
+                if let Ok(items) = serde_json::from_str::<Vec<String>>(&json_str) {
                     for item in items {
                         if !all.contains(&item) {
                             all.push(item);
@@ -336,7 +581,7 @@ impl SummaryGenerator {
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&full_path, content)?;
+        self.db.vault().write_file(&full_path, content.as_bytes())?;
         Ok(full_path)
     }
 }
@@ -365,6 +610,150 @@ fn format_time(timestamp: i64) -> String {
         .to_string()
 }
 
+/// 格式化时间戳为 YYYY-MM-DD
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_else(Utc::now)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+pub(crate) fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| AppError::validation(31, format!("日期格式错误: {} ({})", date, e)).into())
+}
+
+/// 所在周的周一与周日
+pub(crate) fn week_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = date - Duration::days(i64::from(date.weekday().num_days_from_monday()));
+    (start, start + Duration::days(6))
+}
+
+/// 所在月的第一天与最后一天
+pub(crate) fn month_bounds(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start = date.with_day(1).unwrap();
+    let next = if start.month() == 12 {
+        NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+    };
+    (start, next.unwrap() - Duration::days(1))
+}
+
+/// 截止 end 的总结须在该时间（Unix 秒）之后生成才算完整，更早生成的缺少最后一天的活动
+pub(crate) fn complete_after(end: NaiveDate) -> i64 {
+    end.and_hms_opt(SUMMARY_HOUR, 0, 0).unwrap().and_utc().timestamp()
+}
+
+pub(crate) fn summary_id(summary_type: &SummaryType, start: NaiveDate, end: NaiveDate) -> String {
+    match summary_type {
+        SummaryType::Daily => format!("summary-daily-{}", start),
+        SummaryType::Weekly => format!("summary-weekly-{}", start),
+        SummaryType::Monthly => format!("summary-monthly-{}", start.format("%Y-%m")),
+        SummaryType::Range => format!("summary-range-{}-{}", start, end),
+    }
+}
+
+/// 周/月/时段总结写入 long_term_memory/range_summary/
+fn summary_path(summary_type: &SummaryType, start: NaiveDate, end: NaiveDate) -> String {
+    let name = match summary_type {
+        SummaryType::Daily => return format!("long_term_memory/daily_summary/{}.md", start),
+        SummaryType::Weekly => {
+            let week = start.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        SummaryType::Monthly => start.format("%Y-%m").to_string(),
+        SummaryType::Range => format!("{}_{}", start, end),
+    };
+    format!("long_term_memory/range_summary/{}.md", name)
+}
+
+fn summary_title(summary_type: &SummaryType, start: NaiveDate, end: NaiveDate) -> String {
+    match summary_type {
+        SummaryType::Daily => format!("{} 日总结", start),
+        SummaryType::Weekly => {
+            let week = start.iso_week();
+            format!("{}-W{:02} 周总结", week.year(), week.week())
+        }
+        SummaryType::Monthly => format!("{} 月总结", start.format("%Y-%m")),
+        SummaryType::Range => format!("{} ~ {} 时段总结", start, end),
+    }
+}
+
+fn child_title(summary: &Summary) -> String {
+    match (parse_date(&summary.date_start), parse_date(&summary.date_end)) {
+        (Ok(start), Ok(end)) => summary_title(&summary.summary_type, start, end),
+        _ => summary.id.clone(),
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// 按应用统计使用时长（降序）
+fn format_app_usage(activities: &[ActivitySession]) -> String {
+    let mut app_time: std::collections::HashMap<&str, i64> = std::collections::HashMap::new();
+    for a in activities {
+        *app_time.entry(a.application.as_str()).or_default() += a.duration_minutes;
+    }
+
+    let mut app_stats: Vec<_> = app_time.into_iter().collect();
+    app_stats.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    app_stats.iter()
+        .map(|(app, mins)| format!("- {}: {}分钟", app, mins))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 模板周/月/时段总结
+fn generate_template_rollup(
+    start: NaiveDate,
+    end: NaiveDate,
+    children: &[Summary],
+    activities: &[ActivitySession],
+) -> String {
+    let total_minutes: i64 = activities.iter().map(|a| a.duration_minutes).sum();
+    let active_days = activities.iter()
+        .map(|a| format_date(a.start_time))
+        .collect::<BTreeSet<_>>()
+        .len();
+
+    let child_list = children.iter()
+        .map(|s| format!("- {}: {}个活动", child_title(s), s.activity_ids.len()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "时段: {} ~ {}\n总活动时间: {}分钟\n活动数: {}\n活跃天数: {}\n\n### 应用使用\n{}\n\n### 分期回顾\n{}",
+        start, end, total_minutes, activities.len(), active_days,
+        format_app_usage(activities), child_list
+    )
+}
+
+/// 格式化周/月/时段总结Markdown
+fn format_rollup_markdown(summary: &Summary, title: &str, children: &[Summary]) -> String {
+    let frontmatter = format!(
+        "---\nid: {}\ntype: {}\ndate_start: {}\ndate_end: {}\nactivity_count: {}\ncreated_at: {}\n---",
+        summary.id, summary.summary_type.as_str(), summary.date_start, summary.date_end,
+        summary.activity_ids.len(), summary.created_at
+    );
+
+    let child_list = children.iter()
+        .map(|s| format!("- {}", child_title(s)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "{}\n\n# {}\n\n{}\n\n## 分期总结\n\n{}\n",
+        frontmatter, title, summary.content, child_list
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,6 +794,116 @@ mod tests {
         assert!(summary.contains("总活动时间: 90分钟"));
     }
 
+    #[test]
+    fn test_related_project_ids() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                        category, screenshot_ids, markdown_path, project_id)
+                 VALUES ('a1', 't', 0, 60, 1, 'VSCode', '\"work\"', '[]', 'a1.md', 'proj-1'),
+                        ('a2', 't', 0, 60, 1, 'VSCode', '\"work\"', '[]', 'a2.md', 'proj-1'),
+                        ('a3', 't', 0, 60, 1, 'Chrome', '\"work\"', '[]', 'a3.md', NULL);",
+            )?;
+            Ok(())
+        }).unwrap();
+        let gen = SummaryGenerator::new(None, db, SummaryConfig::default());
+
+        let ids = ["a1", "a2", "a3"].map(String::from);
+        assert_eq!(gen.get_related_project_ids(&ids).unwrap(), vec!["proj-1".to_string()]);
+        assert!(gen.get_related_project_ids(&[]).unwrap().is_empty());
+    }
+
+    fn seed_activity(db: &Database, id: &str, app: &str, start: i64, minutes: i64) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                        category, screenshot_ids, markdown_path)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, '\"work\"', '[]', '')",
+                rusqlite::params![id, format!("在{}中工作", app), start, start + minutes * 60, minutes, app],
+            )?;
+            Ok(())
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_period_bounds() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(); // 周五
+        let (start, end) = week_bounds(date);
+        assert_eq!(start.to_string(), "2026-10-12");
+        assert_eq!(end.to_string(), "2026-10-18");
+        assert_eq!(summary_path(&SummaryType::Weekly, start, end), "long_term_memory/range_summary/2026-W42.md");
+
+        let (start, end) = month_bounds(NaiveDate::from_ymd_opt(2026, 12, 5).unwrap());
+        assert_eq!((start.to_string(), end.to_string()), ("2026-12-01".into(), "2026-12-31".into()));
+        let (_, end) = month_bounds(NaiveDate::from_ymd_opt(2028, 2, 10).unwrap());
+        assert_eq!(end.to_string(), "2028-02-29");
+    }
+
+    #[tokio::test]
+    async fn test_weekly_rolls_up_daily_summaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        // 2026-10-12 (周一) 与 2026-10-14 各有活动，2026-10-19 属于下一周
+        seed_activity(&db, "a1", "VSCode", 1791799200, 60);
+        seed_activity(&db, "a2", "Chrome", 1791972000, 30);
+        seed_activity(&db, "a3", "VSCode", 1792404000, 45);
+        let gen = SummaryGenerator::new(None, Arc::clone(&db), SummaryConfig {
+            storage_root: dir.path().to_path_buf(),
+            enable_ai: false,
+        });
+
+        let weekly = gen.generate_weekly("2026-10-16").await.unwrap();
+        assert_eq!(weekly.id, "summary-weekly-2026-10-12");
+        assert_eq!(weekly.date_end, "2026-10-18");
+        assert_eq!(weekly.activity_ids, vec!["a1".to_string(), "a2".to_string()]);
+        assert!(weekly.content.contains("总活动时间: 90分钟"));
+        assert!(weekly.content.contains("活跃天数: 2"));
+        assert!(weekly.content.contains("- 2026-10-14 日总结: 1个活动"));
+
+        // 缺失的日总结已补生成
+        assert!(gen.load_summary("summary-daily-2026-10-12").unwrap().is_some());
+        assert!(gen.load_summary("summary-daily-2026-10-14").unwrap().is_some());
+        let markdown = fs::read_to_string(dir.path().join(&weekly.markdown_path)).unwrap();
+        assert!(markdown.contains("type: weekly"));
+        assert!(markdown.contains("# 2026-W42 周总结"));
+    }
+
+    #[tokio::test]
+    async fn test_monthly_rolls_up_weekly_summaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        seed_activity(&db, "a1", "VSCode", 1791799200, 60); // 2026-10-12
+        seed_activity(&db, "a2", "VSCode", 1792404000, 45); // 2026-10-19
+        seed_activity(&db, "a3", "VSCode", 1793700000, 20); // 2026-11-03
+        let gen = SummaryGenerator::new(None, Arc::clone(&db), SummaryConfig {
+            storage_root: dir.path().to_path_buf(),
+            enable_ai: false,
+        });
+
+        let monthly = gen.generate_monthly("2026-10-02").await.unwrap();
+        assert_eq!(monthly.id, "summary-monthly-2026-10");
+        assert_eq!(monthly.markdown_path, "long_term_memory/range_summary/2026-10.md");
+        assert_eq!(monthly.activity_ids.len(), 2);
+        assert!(monthly.content.contains("2026-W42 周总结"));
+        assert!(monthly.content.contains("2026-W43 周总结"));
+        assert!(gen.load_summary("summary-weekly-2026-10-19").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_range_validation_and_empty() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let gen = SummaryGenerator::new(None, db, SummaryConfig {
+            storage_root: dir.path().to_path_buf(),
+            enable_ai: false,
+        });
+
+        assert!(gen.generate_range("2026-10-16", "2026-10-01").await.is_err());
+        assert!(gen.generate_range("2024-01-01", "2026-01-01").await.is_err());
+        assert!(gen.generate_range("2026-10-01", "2026-10-16").await.is_err());
+    }
+
     #[test]
     fn test_format_time() {
         let ts = 1705300800; // 2024-01-15 10:00:00 UTC
@@ -4,6 +4,16 @@
 
 use super::Notification;
 
+/// 双通道投递：系统通知 + 前端事件，失败只记录日志
+pub fn deliver(app: &tauri::AppHandle, notification: &Notification) {
+    if let Err(e) = send_system_notification(app, notification) {
+        log::warn!("[Notification] System notification failed: {}", e);
+    }
+    if let Err(e) = emit_notification_event(app, notification) {
+        log::warn!("[Notification] Event emission failed: {}", e);
+    }
+}
+
 /// 通过 tauri-plugin-notification 发送系统通知
 pub fn send_system_notification(
     app: &tauri::AppHandle,
@@ -8,6 +8,7 @@ use chrono::Utc;
 pub mod scheduler;
 pub mod rules;
 pub mod context;
+#[cfg(feature = "desktop")]
 pub mod delivery;
 pub mod smart;
 pub mod return_advisor;
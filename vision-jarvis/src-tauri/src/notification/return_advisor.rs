@@ -9,7 +9,7 @@ use log::{info, warn};
 use serde_json;
 
 use crate::db::Database;
-use crate::ai::AIClient;
+use crate::ai::{AIClient, AIPurpose, UsageTracker};
 use crate::ai::provider::AIProviderConfig;
 
 /// 从 screenshot_analyses 查到的精简上下文
@@ -24,11 +24,18 @@ pub struct ReturnAdvisor {
     db: Arc<Database>,
     /// 当前活跃的 AI provider 配置（用于临时创建 AIClient）
     provider_config: Option<AIProviderConfig>,
+    /// 用量记录（超出预算时跳过生成）
+    usage: Option<Arc<UsageTracker>>,
 }
 
 impl ReturnAdvisor {
     pub fn new(db: Arc<Database>, provider_config: Option<AIProviderConfig>) -> Self {
-        Self { db, provider_config }
+        Self { db, provider_config, usage: None }
+    }
+
+    /// 设置用量记录器（builder 模式）
+    pub fn with_usage_tracker(self, tracker: Arc<UsageTracker>) -> Self {
+        Self { usage: Some(tracker), ..self }
     }
 
     /// 当用户从 idle 返回时调用，生成欢迎提醒文案
@@ -49,14 +56,17 @@ impl ReturnAdvisor {
         );
 
         let client = match AIClient::new(provider_config.clone()) {
-            Ok(c) => c,
+            Ok(c) => match &self.usage {
+                Some(tracker) => c.with_usage_tracker(Arc::clone(tracker)),
+                None => c,
+            },
             Err(e) => {
                 warn!("[ReturnAdvisor] Failed to create AI client: {}", e);
                 return None;
             }
         };
 
-        match client.send_text(&prompt).await {
+        match client.for_purpose(AIPurpose::Notification).send_text(&prompt).await {
             Ok(response) => {
                 let hint = response.trim().to_string();
                 info!("[ReturnAdvisor] Generated hint: {}", hint);
@@ -89,10 +99,11 @@ impl ReturnAdvisor {
 
                 match result {
                     Ok((summary, category, acc_json)) => {
+                        let vault = self.db.vault();
                         let accomplishments: Vec<String> =
-                            serde_json::from_str(&acc_json).unwrap_or_default();
+                            serde_json::from_str(&vault.decrypt_text(acc_json)?).unwrap_or_default();
                         Ok(Some(AnalysisContext {
-                            activity_summary: summary,
+                            activity_summary: vault.decrypt_text(summary)?,
                             activity_category: category,
                             accomplishments,
                         }))
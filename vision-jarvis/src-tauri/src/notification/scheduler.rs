@@ -9,7 +9,6 @@ use tokio::task::JoinHandle;
 use super::{Notification, NotificationType, NotificationPriority};
 use super::rules::{RuleEngine, CooldownTracker};
 use super::context;
-use super::delivery;
 use crate::db::Database;
 use crate::settings::SettingsManager;
 
@@ -33,8 +32,11 @@ impl NotificationScheduler {
         }
     }
 
-    /// 启动调度器（需要 AppHandle 用于发送通知）
-    pub fn start(&self, app_handle: tauri::AppHandle) -> JoinHandle<()> {
+    /// 启动调度器，生成的通知入库后交给 `deliver` 投递（桌面应用发送系统通知与前端事件）
+    pub fn start<F>(&self, deliver: F) -> JoinHandle<()>
+    where
+        F: Fn(&Notification) + Send + Sync + 'static,
+    {
         let check_interval = Duration::from_secs(60); // 每分钟检查
 
         let db = Arc::clone(&self.db);
@@ -95,15 +97,7 @@ impl NotificationScheduler {
                     // 标记已发送
                     notification.mark_sent();
 
-                    // 系统通知
-                    if let Err(e) = delivery::send_system_notification(&app_handle, &notification) {
-                        eprintln!("[NotificationScheduler] System notification failed: {}", e);
-                    }
-
-                    // 前端事件
-                    if let Err(e) = delivery::emit_notification_event(&app_handle, &notification) {
-                        eprintln!("[NotificationScheduler] Event emission failed: {}", e);
-                    }
+                    deliver(&notification);
 
                     eprintln!(
                         "[NotificationScheduler] Sent: {} - {}",
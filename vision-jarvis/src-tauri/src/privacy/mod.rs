@@ -0,0 +1,9 @@
+/// 隐私保护模块
+///
+/// 录制前的排除规则（暂停/丢弃分段），以及录制内容离开本机前的脱敏处理与审计
+
+pub mod redaction;
+pub mod rules;
+
+pub use redaction::{RedactionConfig, Redactor, ScreenRegion};
+pub use rules::{ExclusionRules, ExclusionSchedule, PrivacyGuard, WindowRules};
@@ -0,0 +1,655 @@
+/// 敏感内容脱敏
+///
+/// 录制分段在离开本机之前、分析结果在写入数据库之前依次经过：
+/// 1. 窗口黑名单：与录制排除共用排除应用/标题规则（`WindowRules`）。采样间隙漏录进分段的
+///    排除窗口出现时整段不发送，只保存占位分析；配置了规则但分段没有前台窗口采样
+///    （Wayland、Windows 等取不到窗口）时同样不发送
+/// 2. 区域模糊：用 ffmpeg 把配置的屏幕区域做高斯模糊后再发送
+/// 3. PII 清洗：替换分析文本中的邮箱、手机号、证件号、银行卡号、API 密钥及自定义正则
+///
+/// 每次处理写入 redaction_log 审计表，只记录规则与次数，不记录原文
+
+use anyhow::Result;
+use log::{info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, RwLock};
+
+use crate::ai::frame_extractor::find_ffmpeg;
+use crate::capture::active_window::WindowSample;
+use crate::db::Database;
+use crate::db::schema::ScreenshotAnalysis;
+use crate::error::{AppError, AppResult};
+use crate::settings::AppSettings;
+use crate::storage::FolderType;
+use super::rules::{WindowMatch, WindowMatcher, WindowRules};
+
+/// 屏幕区域（相对坐标 0.0-1.0，与分辨率无关）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScreenRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ScreenRegion {
+    /// 区域非空且完全位于屏幕内
+    pub fn is_valid(&self) -> bool {
+        let in_unit = |v: f64| (0.0..=1.0).contains(&v);
+        in_unit(self.x)
+            && in_unit(self.y)
+            && self.width > 0.0
+            && self.height > 0.0
+            && self.x + self.width <= 1.0 + f64::EPSILON
+            && self.y + self.height <= 1.0 + f64::EPSILON
+    }
+}
+
+/// 脱敏配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RedactionConfig {
+    /// 窗口黑名单（即隐私排除的应用与标题规则）
+    pub windows: WindowRules,
+    /// 是否启用内置 PII 规则
+    pub scrub_pii: bool,
+    /// 自定义清洗正则
+    pub custom_patterns: Vec<String>,
+    /// 发送前模糊的屏幕区域
+    pub blur_regions: Vec<ScreenRegion>,
+    /// 模糊处理的临时目录（存储根目录下的 temp），解密后的视频不落到系统临时目录
+    pub temp_dir: PathBuf,
+}
+
+impl From<&AppSettings> for RedactionConfig {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            windows: WindowRules::from(settings),
+            scrub_pii: settings.redaction_pii_enabled,
+            custom_patterns: settings.redaction_custom_patterns.clone(),
+            blur_regions: settings.redaction_blur_regions.clone(),
+            temp_dir: PathBuf::from(&settings.storage_path).join(FolderType::Temp.folder_name()),
+        }
+    }
+}
+
+/// 校验自定义正则（供设置校验使用）
+pub fn validate_patterns(patterns: &[String]) -> AppResult<()> {
+    for pattern in patterns {
+        Regex::new(pattern)
+            .map_err(|e| AppError::validation(21, format!("脱敏正则 '{}' 无效: {}", pattern, e)))?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// PII 清洗
+// ============================================================================
+
+struct PiiRule {
+    label: String,
+    regex: Regex,
+    /// 命中后还需通过 Luhn 校验（银行卡号）
+    luhn: bool,
+}
+
+/// 基于正则的文本清洗器，命中内容替换为 `[已脱敏:类型]`
+pub struct PiiScrubber {
+    rules: Vec<PiiRule>,
+}
+
+impl PiiScrubber {
+    /// 数字类规则使用 ASCII 单词边界，使紧贴中文的号码也能命中
+    const BUILTIN: [(&'static str, &'static str, bool); 5] = [
+        (
+            "secret",
+            r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}|\bgh[pousr]_[A-Za-z0-9]{20,}|\bAKIA[0-9A-Z]{16}\b|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+            false,
+        ),
+        ("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}", false),
+        (
+            "id_card",
+            r"(?-u:\b)[1-9]\d{5}(?:19|20)\d{2}(?:0[1-9]|1[0-2])(?:0[1-9]|[12]\d|3[01])\d{3}[\dXx](?-u:\b)",
+            false,
+        ),
+        ("bank_card", r"(?-u:\b)\d{4}(?:[ -]?\d{4}){2,3}(?:[ -]?\d{1,3})?(?-u:\b)", true),
+        ("phone", r"(?:\+?86[ -]?)?(?-u:\b)1[3-9]\d{9}(?-u:\b)|\+\d{1,3}[ -]?\d{2,4}[ -]?\d{3,4}[ -]?\d{3,4}(?-u:\b)", false),
+    ];
+
+    pub fn new(builtin: bool, custom_patterns: &[String]) -> AppResult<Self> {
+        let mut rules = Vec::new();
+        if builtin {
+            for (label, pattern, luhn) in Self::BUILTIN {
+                rules.push(PiiRule {
+                    label: label.to_string(),
+                    regex: Regex::new(pattern).expect("内置脱敏正则无效"),
+                    luhn,
+                });
+            }
+        }
+        for pattern in custom_patterns {
+            let regex = Regex::new(pattern)
+                .map_err(|e| AppError::validation(21, format!("脱敏正则 '{}' 无效: {}", pattern, e)))?;
+            rules.push(PiiRule {
+                label: "custom".to_string(),
+                regex,
+                luhn: false,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 清洗一段文本，命中次数按类型累加到 counts
+    pub fn scrub(&self, text: &str, counts: &mut BTreeMap<String, usize>) -> String {
+        let mut text = text.to_string();
+        for rule in &self.rules {
+            let mut hits = 0;
+            let replaced = rule.regex.replace_all(&text, |caps: &regex::Captures| {
+                let matched = &caps[0];
+                if rule.luhn && !luhn_valid(matched) {
+                    return matched.to_string();
+                }
+                hits += 1;
+                format!("[已脱敏:{}]", rule.label)
+            });
+            if hits > 0 {
+                text = replaced.into_owned();
+                *counts.entry(rule.label.clone()).or_insert(0) += hits;
+            }
+        }
+        text
+    }
+}
+
+/// Luhn 校验（忽略空格与连字符）
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// ============================================================================
+// 区域模糊
+// ============================================================================
+
+/// 构造 ffmpeg filter_complex：每个区域裁剪、模糊后叠加回原位置，输出标签为 `[out]`
+pub fn blur_filter_graph(regions: &[ScreenRegion]) -> String {
+    let n = regions.len();
+    let mut graph = format!("[0:v]split={}[base]", n + 1);
+    for i in 0..n {
+        graph.push_str(&format!("[r{}]", i));
+    }
+    graph.push(';');
+
+    for (i, r) in regions.iter().enumerate() {
+        graph.push_str(&format!(
+            "[r{i}]crop=iw*{w:.4}:ih*{h:.4}:iw*{x:.4}:ih*{y:.4},gblur=sigma=30[b{i}];",
+            i = i, w = r.width, h = r.height, x = r.x, y = r.y
+        ));
+    }
+
+    let mut prev = "base".to_string();
+    for (i, r) in regions.iter().enumerate() {
+        let out = if i + 1 == n { "out".to_string() } else { format!("v{}", i) };
+        graph.push_str(&format!(
+            "[{prev}][b{i}]overlay=W*{x:.4}:H*{y:.4}[{out}]",
+            prev = prev, i = i, x = r.x, y = r.y, out = out
+        ));
+        if i + 1 < n {
+            graph.push(';');
+        }
+        prev = out;
+    }
+    graph
+}
+
+/// 生成模糊后的视频字节（阻塞调用 ffmpeg；输入为已解密的视频，在 temp_root 下的临时目录处理）
+pub fn blur_video(video: &[u8], regions: &[ScreenRegion], temp_root: &Path) -> AppResult<Vec<u8>> {
+    let ffmpeg = find_ffmpeg()?;
+    std::fs::create_dir_all(temp_root)
+        .map_err(|e| AppError::io(1, format!("创建临时目录失败: {}", e)))?;
+    let tmp_dir = tempfile::TempDir::new_in(temp_root)
+        .map_err(|e| AppError::io(1, format!("创建临时目录失败: {}", e)))?;
+    let input_path = tmp_dir.path().join("input.mp4");
+    let output_path = tmp_dir.path().join("redacted.mp4");
+    std::fs::write(&input_path, video)
+        .map_err(|e| AppError::io(2, format!("写入临时视频文件失败: {}", e)))?;
+
+    let output = Command::new(&ffmpeg)
+        .arg("-i")
+        .arg(&input_path)
+        .args([
+            "-filter_complex", &blur_filter_graph(regions),
+            "-map", "[out]",
+            "-an",
+            "-c:v", "libx264",
+            "-preset", "veryfast",
+            "-pix_fmt", "yuv420p",
+            "-y",
+            "-v", "error",
+        ])
+        .arg(&output_path)
+        .output()
+        .map_err(|e| AppError::capture(30, format!("执行 ffmpeg 失败: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::capture(30, format!(
+            "区域模糊失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    std::fs::read(&output_path)
+        .map_err(|e| AppError::io(2, format!("读取模糊后视频失败: {}", e)))
+}
+
+// ============================================================================
+// Redactor
+// ============================================================================
+
+/// 审计动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionAction {
+    /// 命中窗口黑名单，整段未发送
+    BlockedWindow,
+    /// 配置了窗口黑名单但没有前台窗口采样，无法核对，整段未发送
+    WindowUnknown,
+    /// 已模糊配置区域后发送
+    RegionsBlurred,
+    /// 模糊失败，整段未发送
+    BlurFailed,
+    /// 分析结果中的 PII 已替换
+    PiiScrubbed,
+}
+
+impl RedactionAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedactionAction::BlockedWindow => "blocked_window",
+            RedactionAction::WindowUnknown => "window_unknown",
+            RedactionAction::RegionsBlurred => "regions_blurred",
+            RedactionAction::BlurFailed => "blur_failed",
+            RedactionAction::PiiScrubbed => "pii_scrubbed",
+        }
+    }
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionLogEntry {
+    pub id: i64,
+    pub recording_id: String,
+    pub timestamp: i64,
+    pub action: String,
+    pub detail: String,
+}
+
+/// 命中的黑名单窗口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedWindow {
+    /// 命中的规则（黑名单中的原始条目）
+    pub rule: String,
+    pub app_name: String,
+    pub offset_secs: u32,
+}
+
+struct RedactorState {
+    config: RedactionConfig,
+    windows: WindowMatcher,
+    scrubber: PiiScrubber,
+}
+
+impl RedactorState {
+    fn build(config: RedactionConfig) -> Self {
+        // 设置保存前已校验正则；这里兜底跳过无效条目，保证内置规则仍然生效
+        let valid: Vec<String> = config.custom_patterns
+            .iter()
+            .filter(|p| match Regex::new(p) {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("[Redaction] 跳过无效正则 '{}': {}", p, e);
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        let scrubber = PiiScrubber::new(config.scrub_pii, &valid)
+            .expect("正则已预先校验");
+        let windows = WindowMatcher::new(&config.windows);
+        Self { config, windows, scrubber }
+    }
+}
+
+/// 脱敏器：持有当前配置，负责判定、清洗与审计
+pub struct Redactor {
+    db: Arc<Database>,
+    state: RwLock<RedactorState>,
+}
+
+impl Redactor {
+    pub fn new(db: Arc<Database>, config: RedactionConfig) -> Self {
+        Self {
+            db,
+            state: RwLock::new(RedactorState::build(config)),
+        }
+    }
+
+    pub fn config(&self) -> RedactionConfig {
+        self.state.read().unwrap().config.clone()
+    }
+
+    pub fn set_config(&self, config: RedactionConfig) {
+        *self.state.write().unwrap() = RedactorState::build(config);
+    }
+
+    /// 是否配置了应用或标题黑名单
+    pub fn has_window_blocklist(&self) -> bool {
+        !self.state.read().unwrap().windows.is_empty()
+    }
+
+    /// 找出第一个命中黑名单的窗口采样
+    pub fn blocked_window(&self, samples: &[WindowSample]) -> Option<BlockedWindow> {
+        let state = self.state.read().unwrap();
+        samples.iter().find_map(|sample| {
+            let rule = match state.windows.find(&sample.app_name, &sample.title)? {
+                WindowMatch::App(rule) | WindowMatch::Title(rule) => rule,
+            };
+            Some(BlockedWindow {
+                rule,
+                app_name: sample.app_name.clone(),
+                offset_secs: sample.offset_secs,
+            })
+        })
+    }
+
+    /// 需要模糊的区域（过滤掉无效区域）
+    pub fn blur_regions(&self) -> Vec<ScreenRegion> {
+        self.state.read().unwrap()
+            .config
+            .blur_regions
+            .iter()
+            .filter(|r| r.is_valid())
+            .copied()
+            .collect()
+    }
+
+    /// 模糊处理用的临时目录
+    pub fn temp_dir(&self) -> PathBuf {
+        self.state.read().unwrap().config.temp_dir.clone()
+    }
+
+    /// 清洗任意文本，返回 (清洗后文本, 各类型命中次数)
+    pub fn scrub_text(&self, text: &str) -> (String, BTreeMap<String, usize>) {
+        let mut counts = BTreeMap::new();
+        let text = self.state.read().unwrap().scrubber.scrub(text, &mut counts);
+        (text, counts)
+    }
+
+    /// 清洗分析结果中的自由文本字段（含原始响应 analysis_json）
+    pub fn scrub_analysis(&self, analysis: &mut ScreenshotAnalysis) -> BTreeMap<String, usize> {
+        let state = self.state.read().unwrap();
+        let mut counts = BTreeMap::new();
+        if state.scrubber.is_empty() {
+            return counts;
+        }
+
+        let scrubber = &state.scrubber;
+        analysis.activity_description = scrubber.scrub(&analysis.activity_description, &mut counts);
+        analysis.activity_summary = scrubber.scrub(&analysis.activity_summary, &mut counts);
+        if let Some(ocr) = &analysis.ocr_text {
+            analysis.ocr_text = Some(scrubber.scrub(ocr, &mut counts));
+        }
+        for item in analysis.key_elements.iter_mut().chain(analysis.accomplishments.iter_mut()) {
+            *item = scrubber.scrub(item, &mut counts);
+        }
+        // 原始响应与上面字段内容重复，只清洗不重复计数
+        analysis.analysis_json = scrubber.scrub(&analysis.analysis_json, &mut BTreeMap::new());
+        counts
+    }
+
+    /// 写入审计日志；失败只记录警告，不影响分析流程
+    pub fn audit(&self, recording_id: &str, action: RedactionAction, detail: &str) {
+        info!("[Redaction] {} {}: {}", &recording_id[..8.min(recording_id.len())], action.as_str(), detail);
+        let result = self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO redaction_log (recording_id, timestamp, action, detail)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    recording_id,
+                    chrono::Utc::now().timestamp(),
+                    action.as_str(),
+                    detail,
+                ],
+            )?;
+            Ok(())
+        });
+        if let Err(e) = result {
+            warn!("[Redaction] 写入审计日志失败: {}", e);
+        }
+    }
+
+    /// 最近的审计记录（新的在前）
+    pub fn audit_log(&self, limit: usize) -> Result<Vec<RedactionLogEntry>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, recording_id, timestamp, action, detail
+                 FROM redaction_log ORDER BY timestamp DESC, id DESC LIMIT ?1",
+            )?;
+            let rows = stmt
+                .query_map([limit as i64], |row| {
+                    Ok(RedactionLogEntry {
+                        id: row.get(0)?,
+                        recording_id: row.get(1)?,
+                        timestamp: row.get(2)?,
+                        action: row.get(3)?,
+                        detail: row.get(4)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(rows)
+        })
+    }
+}
+
+/// 把命中次数格式化为审计详情，如 `email×2, phone×1`
+pub fn format_counts(counts: &BTreeMap<String, usize>) -> String {
+    counts
+        .iter()
+        .map(|(label, n)| format!("{}×{}", label, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrub(scrubber: &PiiScrubber, text: &str) -> (String, BTreeMap<String, usize>) {
+        let mut counts = BTreeMap::new();
+        (scrubber.scrub(text, &mut counts), counts)
+    }
+
+    fn sample(offset: u32, app: &str, title: &str) -> WindowSample {
+        WindowSample {
+            offset_secs: offset,
+            app_name: app.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_pii_rules() {
+        let scrubber = PiiScrubber::new(true, &[]).unwrap();
+        let (text, counts) = scrub(
+            &scrubber,
+            "联系 alice.w@example.com 或手机13812345678，身份证号110101199003071234，\
+             卡号 4111 1111 1111 1111，key sk-abcdefghijklmnop1234",
+        );
+        assert!(!text.contains("alice"));
+        assert!(!text.contains("13812345678"));
+        assert!(!text.contains("110101199003071234"));
+        assert!(!text.contains("4111"));
+        assert!(!text.contains("sk-abc"));
+        assert!(text.contains("[已脱敏:email]"));
+        assert_eq!(counts.get("phone"), Some(&1));
+        assert_eq!(counts.get("id_card"), Some(&1));
+        assert_eq!(counts.get("bank_card"), Some(&1));
+        assert_eq!(counts.get("secret"), Some(&1));
+    }
+
+    #[test]
+    fn test_bank_card_requires_luhn() {
+        let scrubber = PiiScrubber::new(true, &[]).unwrap();
+        let (text, counts) = scrub(&scrubber, "订单号 1234 5678 9012 3456 已发货");
+        assert!(text.contains("1234 5678 9012 3456"));
+        assert!(counts.is_empty());
+        assert!(luhn_valid("4111-1111-1111-1111"));
+    }
+
+    #[test]
+    fn test_plain_text_untouched() {
+        let scrubber = PiiScrubber::new(true, &[]).unwrap();
+        let input = "在VSCode中编写Rust代码，修改了 main.rs 第 120 行，版本 2.0.1";
+        let (text, counts) = scrub(&scrubber, input);
+        assert_eq!(text, input);
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let scrubber = PiiScrubber::new(false, &[r"PROJ-\d+".to_string()]).unwrap();
+        let (text, counts) = scrub(&scrubber, "处理 PROJ-42 和 PROJ-7，邮件 a@b.com");
+        assert_eq!(text, "处理 [已脱敏:custom] 和 [已脱敏:custom]，邮件 a@b.com");
+        assert_eq!(counts.get("custom"), Some(&2));
+
+        assert!(matches!(
+            PiiScrubber::new(false, &["(".to_string()]),
+            Err(AppError::Validation(21, _))
+        ));
+    }
+
+    #[test]
+    fn test_blocked_window_matching() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let redactor = Redactor::new(db, RedactionConfig {
+            windows: WindowRules {
+                apps: vec!["1password".to_string()],
+                title_patterns: vec!["网上银行".to_string()],
+            },
+            ..Default::default()
+        });
+
+        assert_eq!(redactor.blocked_window(&[sample(0, "Code", "main.rs")]), None);
+
+        let hit = redactor
+            .blocked_window(&[sample(0, "Code", "main.rs"), sample(20, "1Password", "Vault")])
+            .unwrap();
+        assert_eq!((hit.rule.as_str(), hit.app_name.as_str(), hit.offset_secs), ("1password", "1Password", 20));
+
+        let hit = redactor.blocked_window(&[sample(5, "Safari", "招商银行 - 网上银行")]).unwrap();
+        assert_eq!(hit.rule, "网上银行");
+
+        assert!(redactor.has_window_blocklist());
+        redactor.set_config(RedactionConfig {
+            windows: WindowRules { apps: vec![" ".to_string()], ..Default::default() },
+            ..Default::default()
+        });
+        assert!(!redactor.has_window_blocklist());
+    }
+
+    #[test]
+    fn test_window_rules_shared_with_privacy_guard() {
+        let mut settings = AppSettings::default();
+        settings.privacy_excluded_apps = vec!["Slack".to_string()];
+        settings.privacy_excluded_title_patterns = vec![r"(?:bank|银行)".to_string()];
+
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let redactor = Redactor::new(Arc::clone(&db), RedactionConfig::from(&settings));
+        let guard = crate::privacy::PrivacyGuard::new(db, crate::privacy::ExclusionRules::from(&settings));
+
+        // 同一条 "Slack" 规则既不录制，也不发送漏录进分段的画面
+        let slack = crate::capture::active_window::ActiveWindow {
+            app_name: "slack".to_string(),
+            title: "general".to_string(),
+        };
+        assert!(guard.check_window(&slack).is_some());
+        assert_eq!(redactor.blocked_window(&[sample(10, "slack", "general")]).unwrap().rule, "Slack");
+        assert_eq!(redactor.blocked_window(&[sample(0, "Safari", "Online Bank")]).unwrap().rule, "(?:bank|银行)");
+    }
+
+    #[test]
+    fn test_scrub_analysis_and_audit_log() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let redactor = Redactor::new(db, RedactionConfig {
+            scrub_pii: true,
+            ..Default::default()
+        });
+
+        let mut analysis = ScreenshotAnalysis {
+            screenshot_id: "rec-1".to_string(),
+            application: "Mail".to_string(),
+            activity_type: "communication".to_string(),
+            activity_description: "给 bob@example.com 回邮件".to_string(),
+            key_elements: vec!["收件人 bob@example.com".to_string()],
+            ocr_text: Some("电话 13900001111".to_string()),
+            context_tags: vec![],
+            productivity_score: 5,
+            analysis_json: r#"{"ocr_text":"电话 13900001111"}"#.to_string(),
+            analyzed_at: 0,
+            activity_category: "communication".to_string(),
+            activity_summary: String::new(),
+            project_name: None,
+            accomplishments: vec![],
+        };
+
+        let counts = redactor.scrub_analysis(&mut analysis);
+        assert_eq!(format_counts(&counts), "email×2, phone×1");
+        assert!(!analysis.analysis_json.contains("13900001111"));
+        assert_eq!(analysis.ocr_text.as_deref(), Some("电话 [已脱敏:phone]"));
+
+        redactor.audit("rec-1", RedactionAction::PiiScrubbed, &format_counts(&counts));
+        let log = redactor.audit_log(10).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].action, "pii_scrubbed");
+        assert!(!log[0].detail.contains("example.com"));
+    }
+
+    #[test]
+    fn test_blur_filter_graph() {
+        let regions = [
+            ScreenRegion { x: 0.0, y: 0.0, width: 0.25, height: 0.1 },
+            ScreenRegion { x: 0.5, y: 0.5, width: 0.5, height: 0.5 },
+        ];
+        let graph = blur_filter_graph(&regions);
+        assert!(graph.starts_with("[0:v]split=3[base][r0][r1];"));
+        assert!(graph.contains("[r1]crop=iw*0.5000:ih*0.5000:iw*0.5000:ih*0.5000,gblur=sigma=30[b1];"));
+        assert!(graph.contains("[base][b0]overlay=W*0.0000:H*0.0000[v0];"));
+        assert!(graph.ends_with("[v0][b1]overlay=W*0.5000:H*0.5000[out]"));
+
+        assert!(!ScreenRegion { x: 0.8, y: 0.0, width: 0.3, height: 0.1 }.is_valid());
+        assert!(!ScreenRegion { x: 0.0, y: 0.0, width: 0.0, height: 0.1 }.is_valid());
+    }
+}
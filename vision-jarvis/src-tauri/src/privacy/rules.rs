@@ -0,0 +1,485 @@
+/// 隐私排除规则
+///
+/// 决定什么时候完全不录制：
+/// - 手动暂停：`pause_recording_for` 写入 privacy_pause 表，重启后仍然有效
+/// - 时间段：如"周末不录"、"每天 22:00-08:00 不录"
+/// - 窗口：前台为排除应用、标题匹配排除正则或浏览器无痕窗口
+///
+/// 时间类规则让调度器暂停，不启动新分段；窗口规则在分段中途命中时丢弃整个分段，
+/// 并在该窗口离开前台之前保持暂停。
+/// 排除应用与标题正则（`WindowRules`）同时交给脱敏：采样间隙漏录进分段的排除窗口不会发给 AI
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use log::warn;
+use regex::Regex;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::capture::active_window::ActiveWindow;
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use crate::settings::AppSettings;
+
+/// 手动暂停的最长时长（7 天）
+pub const MAX_PAUSE_MINUTES: u32 = 7 * 24 * 60;
+
+/// 识别无痕/隐私窗口的浏览器（应用名子串，小写）
+const BROWSERS: &[&str] = &[
+    "chrome", "chromium", "firefox", "safari", "edge", "brave", "arc", "opera", "vivaldi",
+];
+
+/// 浏览器无痕窗口标题中的标记（小写）
+const INCOGNITO_MARKERS: &[&str] = &[
+    "incognito", "private browsing", "inprivate", "隐身", "无痕", "隐私浏览",
+];
+
+/// 不录制的时间段
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExclusionSchedule {
+    /// 生效的星期（1=周一 … 7=周日），为空表示每天
+    #[serde(default)]
+    pub days: Vec<u8>,
+    /// 开始时间 (HH:MM)
+    pub start: String,
+    /// 结束时间 (HH:MM)，早于开始时间表示跨午夜；与开始相同表示全天
+    pub end: String,
+}
+
+impl ExclusionSchedule {
+    /// 判断时刻是否落在该时间段内（跨午夜时，次日凌晨部分归属前一天的星期）
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let (Some(start), Some(end)) = (minute_of_day(&self.start), minute_of_day(&self.end)) else {
+            return false;
+        };
+        let minute = now.hour() * 60 + now.minute();
+        let today = now.weekday().number_from_monday() as u8;
+        let yesterday = if today == 1 { 7 } else { today - 1 };
+        let on_day = |day: u8| self.days.is_empty() || self.days.contains(&day);
+
+        if start == end {
+            on_day(today)
+        } else if start < end {
+            on_day(today) && (start..end).contains(&minute)
+        } else {
+            (on_day(today) && minute >= start) || (on_day(yesterday) && minute < end)
+        }
+    }
+
+    pub(crate) fn validate(&self) -> AppResult<()> {
+        if minute_of_day(&self.start).is_none() || minute_of_day(&self.end).is_none() {
+            return Err(AppError::validation(24, format!(
+                "排除时间段 {}-{} 格式无效，应为 HH:MM", self.start, self.end
+            )));
+        }
+        if self.days.iter().any(|d| !(1..=7).contains(d)) {
+            return Err(AppError::validation(24, "排除时间段的星期必须在 1-7 之间"));
+        }
+        Ok(())
+    }
+}
+
+/// "HH:MM" 转为当天分钟数
+fn minute_of_day(time: &str) -> Option<u32> {
+    let (hour, minute) = time.split_once(':')?;
+    let hour: u32 = hour.trim().parse().ok()?;
+    let minute: u32 = minute.trim().parse().ok()?;
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
+
+/// 排除窗口规则：录制排除与发送前脱敏共用同一份
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowRules {
+    /// 应用名（不区分大小写，完整匹配）
+    pub apps: Vec<String>,
+    /// 窗口标题正则（不区分大小写）
+    pub title_patterns: Vec<String>,
+}
+
+impl From<&AppSettings> for WindowRules {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            apps: settings.privacy_excluded_apps.clone(),
+            title_patterns: settings.privacy_excluded_title_patterns.clone(),
+        }
+    }
+}
+
+/// 命中的窗口规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowMatch {
+    /// 排除应用（规则原文）
+    App(String),
+    /// 标题正则（规则原文）
+    Title(String),
+}
+
+/// 编译后的窗口规则
+pub(crate) struct WindowMatcher {
+    apps: Vec<String>,
+    titles: Vec<(String, Regex)>,
+}
+
+impl WindowMatcher {
+    pub(crate) fn new(rules: &WindowRules) -> Self {
+        // 设置保存前已校验；这里跳过无效条目，避免一条坏正则让其他规则失效
+        let titles = rules.title_patterns
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .filter_map(|p| match title_regex(p) {
+                Ok(re) => Some((p.clone(), re)),
+                Err(e) => {
+                    warn!("[Privacy] 跳过无效标题正则 '{}': {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        let apps = rules.apps.iter().filter(|a| !a.trim().is_empty()).cloned().collect();
+        Self { apps, titles }
+    }
+
+    /// 没有任何有效规则
+    pub(crate) fn is_empty(&self) -> bool {
+        self.apps.is_empty() && self.titles.is_empty()
+    }
+
+    /// 应用名优先，其次标题
+    pub(crate) fn find(&self, app_name: &str, title: &str) -> Option<WindowMatch> {
+        let app = app_name.trim();
+        if let Some(rule) = self.apps.iter().find(|a| a.trim().eq_ignore_ascii_case(app)) {
+            return Some(WindowMatch::App(rule.clone()));
+        }
+        self.titles
+            .iter()
+            .find(|(_, re)| re.is_match(title))
+            .map(|(pattern, _)| WindowMatch::Title(pattern.clone()))
+    }
+}
+
+/// 排除规则配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExclusionRules {
+    /// 排除应用与标题正则
+    pub windows: WindowRules,
+    /// 不录制的时间段
+    pub schedules: Vec<ExclusionSchedule>,
+    /// 是否排除浏览器无痕窗口
+    pub exclude_incognito: bool,
+}
+
+impl From<&AppSettings> for ExclusionRules {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            windows: WindowRules::from(settings),
+            schedules: settings.privacy_schedules.clone(),
+            exclude_incognito: settings.privacy_exclude_incognito,
+        }
+    }
+}
+
+/// 校验排除规则（供设置校验使用）
+pub fn validate_rules(rules: &ExclusionRules) -> AppResult<()> {
+    for pattern in &rules.windows.title_patterns {
+        title_regex(pattern)
+            .map_err(|e| AppError::validation(23, format!("排除标题正则 '{}' 无效: {}", pattern, e)))?;
+    }
+    rules.schedules.iter().try_for_each(ExclusionSchedule::validate)
+}
+
+fn title_regex(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    Regex::new(&format!("(?i){}", pattern))
+}
+
+/// 不录制的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// 手动暂停，until 为恢复时间戳
+    Paused { until: i64 },
+    /// 命中排除时间段
+    Schedule { start: String, end: String },
+    /// 前台为排除应用
+    App { app_name: String },
+    /// 窗口标题匹配排除正则
+    Title { pattern: String },
+    /// 浏览器无痕窗口
+    Incognito { app_name: String },
+}
+
+impl std::fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Paused { until } => write!(f, "手动暂停至 {}", until),
+            Self::Schedule { start, end } => write!(f, "排除时间段 {}-{}", start, end),
+            Self::App { app_name } => write!(f, "排除应用 {}", app_name),
+            Self::Title { pattern } => write!(f, "排除标题 /{}/", pattern),
+            Self::Incognito { app_name } => write!(f, "{} 无痕窗口", app_name),
+        }
+    }
+}
+
+/// 手动暂停状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivacyPause {
+    pub paused_until: i64,
+    pub created_at: i64,
+}
+
+struct GuardState {
+    rules: ExclusionRules,
+    windows: WindowMatcher,
+}
+
+impl GuardState {
+    fn build(rules: ExclusionRules) -> Self {
+        let windows = WindowMatcher::new(&rules.windows);
+        Self { rules, windows }
+    }
+}
+
+/// 隐私守卫：录制调度器在启动分段前和采样窗口时询问是否应停止录制
+pub struct PrivacyGuard {
+    db: Arc<Database>,
+    state: RwLock<GuardState>,
+    /// 调度器当前因何暂停（供状态查询）
+    active: Mutex<Option<ExclusionReason>>,
+    /// 规则或暂停状态变化时唤醒调度器，使暂停/恢复立即生效
+    changed: Notify,
+}
+
+impl PrivacyGuard {
+    pub fn new(db: Arc<Database>, rules: ExclusionRules) -> Self {
+        Self {
+            db,
+            state: RwLock::new(GuardState::build(rules)),
+            active: Mutex::new(None),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn rules(&self) -> ExclusionRules {
+        self.state.read().unwrap().rules.clone()
+    }
+
+    pub fn set_rules(&self, rules: ExclusionRules) {
+        *self.state.write().unwrap() = GuardState::build(rules);
+        self.changed.notify_waiters();
+    }
+
+    /// 暂停录制 minutes 分钟，返回恢复时间戳
+    pub fn pause_for(&self, minutes: u32) -> AppResult<i64> {
+        if minutes == 0 || minutes > MAX_PAUSE_MINUTES {
+            return Err(AppError::validation(25, format!(
+                "暂停时长必须在 1-{} 分钟之间", MAX_PAUSE_MINUTES
+            )));
+        }
+        let now = chrono::Local::now().timestamp();
+        let until = now + minutes as i64 * 60;
+        self.db
+            .with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO privacy_pause (id, paused_until, created_at) VALUES (1, ?1, ?2)
+                     ON CONFLICT(id) DO UPDATE SET paused_until = ?1, created_at = ?2",
+                    rusqlite::params![until, now],
+                )?;
+                Ok(())
+            })
+            .map_err(|e| AppError::database(1, format!("保存暂停状态失败: {}", e)))?;
+        self.changed.notify_waiters();
+        Ok(until)
+    }
+
+    /// 取消手动暂停
+    pub fn resume(&self) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute("DELETE FROM privacy_pause", [])?;
+            Ok(())
+        })?;
+        self.changed.notify_waiters();
+        Ok(())
+    }
+
+    /// 当前仍有效的手动暂停
+    pub fn pause_state(&self, now: i64) -> Result<Option<PrivacyPause>> {
+        let pause = self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT paused_until, created_at FROM privacy_pause WHERE id = 1",
+                    [],
+                    |row| Ok(PrivacyPause { paused_until: row.get(0)?, created_at: row.get(1)? }),
+                )
+                .optional()?)
+        })?;
+        Ok(pause.filter(|p| p.paused_until > now))
+    }
+
+    /// 时间类规则：手动暂停与排除时间段
+    pub fn check_time(&self, now: chrono::DateTime<chrono::Local>) -> Option<ExclusionReason> {
+        match self.pause_state(now.timestamp()) {
+            Ok(Some(pause)) => return Some(ExclusionReason::Paused { until: pause.paused_until }),
+            Ok(None) => {}
+            // 读不到暂停状态时按暂停处理，宁可少录
+            Err(e) => {
+                warn!("[Privacy] 读取暂停状态失败: {}", e);
+                return Some(ExclusionReason::Paused { until: now.timestamp() });
+            }
+        }
+
+        let state = self.state.read().unwrap();
+        state.rules.schedules
+            .iter()
+            .find(|s| s.contains(now.naive_local()))
+            .map(|s| ExclusionReason::Schedule { start: s.start.clone(), end: s.end.clone() })
+    }
+
+    /// 窗口类规则：排除应用、标题正则、无痕窗口
+    pub fn check_window(&self, window: &ActiveWindow) -> Option<ExclusionReason> {
+        let state = self.state.read().unwrap();
+        match state.windows.find(&window.app_name, &window.title) {
+            Some(WindowMatch::App(_)) => return Some(ExclusionReason::App { app_name: window.app_name.clone() }),
+            Some(WindowMatch::Title(pattern)) => return Some(ExclusionReason::Title { pattern }),
+            None => {}
+        }
+        if state.rules.exclude_incognito && is_incognito(window) {
+            return Some(ExclusionReason::Incognito { app_name: window.app_name.clone() });
+        }
+        None
+    }
+
+    /// 调度器记录当前暂停原因（None 表示正常录制）
+    pub fn set_active(&self, reason: Option<ExclusionReason>) {
+        *self.active.lock().unwrap() = reason;
+    }
+
+    pub fn active(&self) -> Option<ExclusionReason> {
+        self.active.lock().unwrap().clone()
+    }
+
+    /// 等待 duration；期间规则或暂停状态变化时提前返回
+    pub async fn wait(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.changed.notified() => {}
+        }
+    }
+}
+
+/// 按标题标记识别浏览器无痕窗口（尽力而为：部分浏览器标题中不含标记）
+fn is_incognito(window: &ActiveWindow) -> bool {
+    let app = window.app_name.to_lowercase();
+    if !BROWSERS.iter().any(|b| app.contains(b)) {
+        return false;
+    }
+    let title = window.title.to_lowercase();
+    INCOGNITO_MARKERS.iter().any(|m| title.contains(m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    fn window(app: &str, title: &str) -> ActiveWindow {
+        ActiveWindow { app_name: app.to_string(), title: title.to_string() }
+    }
+
+    fn schedule(days: &[u8], start: &str, end: &str) -> ExclusionSchedule {
+        ExclusionSchedule { days: days.to_vec(), start: start.to_string(), end: end.to_string() }
+    }
+
+    #[test]
+    fn test_schedule_contains() {
+        // 2026-10-17 是周六
+        let weekends = schedule(&[6, 7], "00:00", "00:00");
+        assert!(weekends.contains(at(2026, 10, 17, 14, 0)));
+        assert!(!weekends.contains(at(2026, 10, 16, 14, 0)));
+
+        let evenings = schedule(&[], "22:00", "08:00");
+        assert!(evenings.contains(at(2026, 10, 16, 23, 30)));
+        assert!(evenings.contains(at(2026, 10, 16, 7, 59)));
+        assert!(!evenings.contains(at(2026, 10, 16, 8, 0)));
+
+        // 周五晚上开始的跨午夜时段覆盖到周六凌晨
+        let friday_night = schedule(&[5], "22:00", "02:00");
+        assert!(friday_night.contains(at(2026, 10, 17, 1, 0)));
+        assert!(!friday_night.contains(at(2026, 10, 16, 1, 0)));
+
+        let lunch = schedule(&[1, 2, 3, 4, 5], "12:00", "13:00");
+        assert!(lunch.contains(at(2026, 10, 16, 12, 30)));
+        assert!(!lunch.contains(at(2026, 10, 16, 13, 0)));
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let mut rules = ExclusionRules {
+            windows: WindowRules {
+                title_patterns: vec!["(?:bank|银行)".to_string()],
+                ..Default::default()
+            },
+            schedules: vec![schedule(&[6, 7], "00:00", "00:00")],
+            ..Default::default()
+        };
+        assert!(validate_rules(&rules).is_ok());
+
+        rules.schedules.push(schedule(&[8], "09:00", "10:00"));
+        assert!(validate_rules(&rules).is_err());
+
+        rules.schedules.pop();
+        rules.windows.title_patterns.push("(".to_string());
+        assert!(validate_rules(&rules).is_err());
+    }
+
+    #[test]
+    fn test_check_window() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let guard = PrivacyGuard::new(db, ExclusionRules {
+            windows: WindowRules {
+                apps: vec!["zoom.us".to_string()],
+                title_patterns: vec![r"网上银行|online banking".to_string()],
+            },
+            schedules: vec![],
+            exclude_incognito: true,
+        });
+
+        assert!(matches!(guard.check_window(&window("Zoom.us", "Meeting")), Some(ExclusionReason::App { .. })));
+        assert!(matches!(
+            guard.check_window(&window("Safari", "Online Banking - Login")),
+            Some(ExclusionReason::Title { .. })
+        ));
+        assert!(matches!(
+            guard.check_window(&window("firefox", "新标签页 — Mozilla Firefox 隐私浏览")),
+            Some(ExclusionReason::Incognito { .. })
+        ));
+        assert_eq!(guard.check_window(&window("Code", "incognito.rs - editor")), None);
+        assert_eq!(guard.check_window(&window("Google Chrome", "GitHub")), None);
+    }
+
+    #[test]
+    fn test_pause_persists_and_expires() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let guard = PrivacyGuard::new(Arc::clone(&db), ExclusionRules::default());
+        let now = chrono::Local::now();
+
+        assert_eq!(guard.check_time(now), None);
+        assert!(guard.pause_for(0).is_err());
+
+        let until = guard.pause_for(30).unwrap();
+        // 新实例（模拟重启）读到同一暂停状态
+        let restarted = PrivacyGuard::new(db, ExclusionRules::default());
+        assert_eq!(restarted.check_time(now), Some(ExclusionReason::Paused { until }));
+
+        let later = chrono::Local.timestamp_opt(until + 1, 0).unwrap();
+        assert_eq!(restarted.check_time(later), None);
+
+        restarted.pause_for(10).unwrap();
+        restarted.resume().unwrap();
+        assert_eq!(restarted.pause_state(now.timestamp()).unwrap(), None);
+    }
+}
@@ -0,0 +1,358 @@
+/// 核心运行时
+///
+/// 桌面应用（`run`）与无界面守护进程（`vision-jarvis-daemon`）共用的启动流程：
+/// 打开数据库、加载设置与 AI 配置、创建 `AppState`，再启动录制、记忆管道、
+/// 保留策略、归档、配置方案自动切换等后台任务。
+/// 需要窗口或系统通知的部分（悬浮窗、通知调度、Idle 回归提醒）由桌面应用自行启动。
+/// 命令行客户端只需读写数据，使用轻量的 `DataAccess`
+
+use anyhow::{bail, Context, Result};
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ai::config_state::{self, AIConfigState};
+use crate::ai::usage::{UsageBudget, UsageTracker};
+use crate::ai::AIClient;
+use crate::state::AppState;
+use crate::db::Database;
+use crate::memory::knowledge_base::{KnowledgeBase, KnowledgeBaseConfig};
+use crate::memory::pipeline::PipelineScheduler;
+use crate::notification::return_advisor::ReturnAdvisor;
+use crate::settings::store::SettingsStore;
+use crate::settings::{AppSettings, SettingsManager};
+
+/// 数据库文件名
+const DB_FILE: &str = "vision-jarvis.db";
+
+/// 运行时配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// 数据目录（数据库与凭据存储所在目录）
+    pub data_dir: PathBuf,
+    /// 启动时覆盖的设置项（键为 `AppSettings` 字段名），覆盖后写回数据库
+    pub settings: Map<String, Value>,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: default_data_dir(),
+            settings: Map::new(),
+        }
+    }
+}
+
+/// 默认数据目录（系统本地数据目录下的 vision-jarvis）
+pub fn default_data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("vision-jarvis")
+}
+
+/// 数据库路径
+pub fn db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(DB_FILE)
+}
+
+/// 已初始化的应用状态，尚未启动后台任务
+pub struct Runtime {
+    pub state: AppState,
+    pub ai_config: AIConfigState,
+}
+
+impl Runtime {
+    /// 打开数据目录并创建应用状态
+    ///
+    /// 不能在 tokio runtime 中调用：Linux 上探测系统钥匙串（Secret Service）时内部会 block_on
+    pub fn open(config: &RuntimeConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.data_dir)
+            .with_context(|| format!("创建数据目录失败: {}", config.data_dir.display()))?;
+
+        let db = Database::new(db_path(&config.data_dir)).context("打开数据库失败")?;
+        db.initialize().context("初始化数据库失败")?;
+        let db = Arc::new(db);
+
+        // 从数据库加载已保存的设置；覆盖项需在创建录制器前生效（存储路径、分段时长）
+        let settings = SettingsManager::load(Arc::clone(&db));
+        if !config.settings.is_empty() {
+            let merged = merge_settings(&settings.get(), &config.settings)?;
+            settings.update(merged).context("应用配置文件中的设置失败")?;
+        }
+
+        let state = AppState::new(db, settings);
+
+        // 凭据存储（系统钥匙串，不可用时为数据目录下的加密文件）
+        let secrets = crate::crypto::secrets::open(&config.data_dir).context("打开凭据存储失败")?;
+        let ai_config = AIConfigState::new(Arc::clone(&state.db), secrets);
+
+        Ok(Self { state, ai_config })
+    }
+
+    /// 启动后台任务（见 `start`）
+    pub fn start(&self) {
+        start(&self.state, &self.ai_config);
+    }
+
+    /// 停止录制与记忆管道（见 `shutdown`）
+    pub async fn shutdown(&self) {
+        shutdown(&self.state).await;
+    }
+}
+
+/// 轻量数据访问：只打开数据库、加载加密密钥与已保存的设置
+///
+/// 不执行数据库迁移、不回写设置，也不创建录制器、记忆管道与配置方案，
+/// 桌面应用或守护进程运行时也可以并发使用（命令行客户端 `vjctl`）
+pub struct DataAccess {
+    pub db: Arc<Database>,
+    pub settings: AppSettings,
+    data_dir: PathBuf,
+}
+
+impl DataAccess {
+    /// 打开已有的数据目录（数据库需已由桌面应用或守护进程创建）
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        let path = db_path(data_dir);
+        if !path.exists() {
+            bail!("数据库不存在: {}（请先启动桌面应用或守护进程）", path.display());
+        }
+        let db = Arc::new(Database::new(path).context("打开数据库失败")?);
+
+        let settings = match SettingsStore::new(Arc::clone(&db)).load() {
+            Ok(loaded) => loaded.map(|(settings, _)| settings).unwrap_or_default(),
+            Err(e) => {
+                warn!("Saved settings are unreadable, using defaults: {}", e);
+                AppSettings::default()
+            }
+        };
+
+        if let Err(e) = crate::crypto::setup::load_key(&db) {
+            error!("Failed to load encryption state: {}", e);
+        }
+
+        Ok(Self { db, settings, data_dir: data_dir.to_path_buf() })
+    }
+
+    /// 存储根目录
+    pub fn storage_path(&self) -> PathBuf {
+        PathBuf::from(&self.settings.storage_path)
+    }
+
+    /// 按 AI 配置创建客户端（未配置时为 None），调用计入用量与预算
+    ///
+    /// 与 `Runtime::open` 相同，需要在进入 tokio runtime 之前调用
+    pub fn ai_client(&self) -> Result<Option<AIClient>> {
+        let secrets = crate::crypto::secrets::open(&self.data_dir).context("打开凭据存储失败")?;
+        let config = AIConfigState::new(Arc::clone(&self.db), secrets).get();
+        if config.get_active_provider().is_none() {
+            return Ok(None);
+        }
+        let usage = Arc::new(UsageTracker::new(Arc::clone(&self.db), UsageBudget::from(&self.settings)));
+        let client = AIClient::from_ai_config(&config).context("创建 AI 客户端失败")?;
+        Ok(Some(client.with_usage_tracker(usage)))
+    }
+
+    /// 新总结写入后补齐知识库链接（未开启时为空操作）
+    pub async fn refresh_knowledge_base(&self) {
+        let knowledge_base = KnowledgeBase::new(
+            Arc::clone(&self.db),
+            self.storage_path(),
+            KnowledgeBaseConfig::from(&self.settings),
+        );
+        crate::memory::queries::refresh_knowledge_base(Arc::new(knowledge_base)).await;
+    }
+}
+
+/// 在已保存的设置上覆盖部分字段
+fn merge_settings(current: &AppSettings, overrides: &Map<String, Value>) -> Result<AppSettings> {
+    let mut value = serde_json::to_value(current)?;
+    let fields = value.as_object_mut().context("设置不是 JSON 对象")?;
+    for (key, override_value) in overrides {
+        if !fields.contains_key(key) {
+            warn!("Ignoring unknown setting '{}'", key);
+            continue;
+        }
+        fields.insert(key.clone(), override_value.clone());
+    }
+    serde_json::from_value(value).context("设置项格式错误")
+}
+
+/// 启动与界面无关的后台任务（需在 tokio runtime 中调用）
+///
+/// - 录制调度器（记忆功能启用时）
+/// - 保留策略引擎、关键帧归档
+/// - 连接 AI，记忆功能启用时启动记忆管道
+/// - 设置变更订阅、配置方案自动切换（切换时同步活动 AI 提供商）
+pub fn start(state: &AppState, ai_config: &AIConfigState) {
+    let memory_enabled = state.settings.is_memory_enabled();
+    let storage_path = state.settings.get_storage_path();
+    info!("memory_enabled={}, storage={}", memory_enabled, storage_path.display());
+
+    // 确保所有标准文件夹存在
+    if let Ok(manager) = crate::storage::StorageManager::new(storage_path) {
+        if let Err(e) = manager.ensure_all_folders() {
+            error!("创建标准文件夹失败: {}", e);
+        }
+    }
+
+    if memory_enabled {
+        let scheduler = Arc::clone(&state.scheduler);
+        tokio::spawn(async move {
+            let mut scheduler = scheduler.lock().await;
+            info!("Starting recorder (segment: {}s)", scheduler.interval_seconds);
+            match scheduler.start().await {
+                Ok(_) => info!("Recorder started"),
+                Err(e) => error!("Failed to start recorder: {}", e),
+            }
+        });
+    } else {
+        info!("Memory disabled, recorder skipped");
+    }
+
+    // 保留策略引擎（每小时清理过期文件并执行容量上限）
+    state.retention.start();
+    info!("Retention engine started");
+
+    // 关键帧归档（每小时把到期的已分析录制替换为关键帧）
+    state.archiver.start();
+    info!("Keyframe archiver started");
+
+    // 连接 AI 后再启动记忆管道
+    let pipeline = Arc::clone(&state.pipeline);
+    let ai = ai_config.clone();
+    tokio::spawn(async move {
+        let ai_status = connect_ai(&pipeline, &ai).await;
+        if memory_enabled {
+            pipeline.start();
+            info!("Pipeline started ({})", ai_status);
+        }
+    });
+
+    // 订阅设置变更，运行中的录制、记忆管道、Idle 检测与共享配置随之重新配置
+    state.watch_settings();
+    info!("Settings watchers started");
+
+    // 配置方案：自动切换检查，切换时同步活动 AI 提供商
+    state.profiles.start();
+    let mut profiles = state.profiles.subscribe();
+    let manager = Arc::clone(&state.profiles);
+    let pipeline = Arc::clone(&state.pipeline);
+    let ai_config = ai_config.clone();
+    tokio::spawn(async move {
+        while profiles.changed().await.is_ok() {
+            let profile = profiles.borrow_and_update().clone();
+            let current = ai_config.get().active_provider_id;
+            let provider_id = match manager.switch_provider(profile.as_ref(), current.as_deref()) {
+                Ok(Some(provider_id)) => provider_id,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to resolve AI provider for profile: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = config_state::activate_provider(&ai_config, &pipeline, &provider_id).await {
+                error!("Failed to switch AI provider for profile: {}", e);
+            }
+        }
+    });
+}
+
+/// 如果已有 AI 配置，连接到管道（其余已启用的提供商作为备用），返回连接状态描述
+pub async fn connect_ai(pipeline: &PipelineScheduler, ai_config: &AIConfigState) -> String {
+    let config = ai_config.get();
+    match config.get_active_provider() {
+        Some(provider) => match crate::ai::AIClient::from_ai_config(&config) {
+            Ok(client) => {
+                pipeline.connect_ai(client).await;
+                format!("AI: {} / {}", provider.name, provider.model)
+            }
+            Err(e) => {
+                error!("Failed to create AI client: {}", e);
+                "AI connection failed".to_string()
+            }
+        },
+        None => "no AI configured".to_string(),
+    }
+}
+
+/// 启动鼠标 Idle 检测（独立线程；关闭时空转，设置中开启后立即生效）
+///
+/// 用户从较长 idle 回归时生成回归提示，交给 `on_hint` 投递
+pub fn start_idle_watcher<F>(state: &AppState, ai_config: &AIConfigState, on_hint: F)
+where
+    F: Fn(String) + Send + Sync + 'static,
+{
+    let watcher = Arc::clone(&state.idle_watcher);
+    let idle_config = watcher.config();
+    let advisor = Arc::new(
+        ReturnAdvisor::new(Arc::clone(&state.db), ai_config.get_active_provider_config())
+            .with_usage_tracker(Arc::clone(&state.usage)),
+    );
+    let on_hint = Arc::new(on_hint);
+
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new()
+            .expect("Failed to create tokio runtime for IdleWatcher");
+
+        watcher.start(move |idle_secs| {
+            let advisor = Arc::clone(&advisor);
+            let on_hint = Arc::clone(&on_hint);
+            rt.spawn(async move {
+                if let Some(msg) = advisor.generate_return_hint(idle_secs).await {
+                    on_hint(msg);
+                }
+            });
+        });
+    });
+
+    if idle_config.enabled {
+        info!("IdleWatcher started (threshold={}s, min_trigger={}s)",
+            idle_config.threshold_secs, idle_config.min_trigger_secs);
+    } else {
+        info!("IdleWatcher disabled in settings");
+    }
+}
+
+/// 停止录制（当前分段写完后入库）与记忆管道
+pub async fn shutdown(state: &AppState) {
+    {
+        let mut scheduler = state.scheduler.lock().await;
+        if scheduler.is_running().await {
+            info!("Stopping recorder...");
+            match scheduler.stop().await {
+                Ok(_) => info!("Recorder stopped"),
+                Err(e) => error!("Failed to stop recorder: {}", e),
+            }
+        }
+    }
+    if state.pipeline.is_running() {
+        state.pipeline.stop();
+        info!("Pipeline stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_settings_overrides_known_fields() {
+        let overrides = json!({
+            "memory_enabled": false,
+            "capture_interval_seconds": 120,
+            "no_such_setting": 1,
+        });
+        let merged = merge_settings(&AppSettings::default(), overrides.as_object().unwrap()).unwrap();
+        assert!(!merged.memory_enabled);
+        assert_eq!(merged.capture_interval_seconds, 120);
+        assert_eq!(merged.storage_limit_mb, AppSettings::default().storage_limit_mb);
+
+        let invalid = json!({ "capture_interval_seconds": "fast" });
+        assert!(merge_settings(&AppSettings::default(), invalid.as_object().unwrap()).is_err());
+    }
+}
@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::privacy::{ExclusionSchedule, ScreenRegion};
+use crate::storage::retention::{self, RetentionPolicy};
+
 /// 应用设置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
@@ -15,9 +18,21 @@ pub struct AppSettings {
     /// 存储路径
     pub storage_path: String,
 
-    /// 存储容量限制（MB）
+    /// 存储容量限制（MB），超出时按保留策略从最旧的文件开始淘汰
     pub storage_limit_mb: u64,
 
+    /// 按目录/扩展名的保留策略（按顺序匹配）
+    pub retention_policies: Vec<RetentionPolicy>,
+
+    /// 已分析的录制在多少天后归档为关键帧并删除原视频，0 表示不归档
+    pub archive_after_days: u32,
+
+    /// 归档时每段保留的关键帧数: 1-20
+    pub archive_keyframe_count: u16,
+
+    /// 知识库模式：笔记间生成 wiki-link 并维护索引，存储目录可直接用 Obsidian / Logseq 打开
+    pub knowledge_base_mode: bool,
+
     /// 是否开机自启动
     pub auto_start: bool,
 
@@ -64,9 +79,6 @@ pub struct AppSettings {
     /// 屏幕无变化提醒：自定义消息（空字符串表示使用 AI 智能建议）
     pub screen_inactivity_message: String,
 
-    /// OpenAI API 密钥（已废弃，使用 AI 配置系统）
-    pub openai_api_key: Option<String>,
-
     // ========== Idle 检测 ==========
 
     /// 鼠标 idle 回归提醒：是否启用
@@ -75,6 +87,33 @@ pub struct AppSettings {
     pub idle_threshold_secs: u64,
     /// 最小触发时长（秒），idle 时长小于此值不发送提醒
     pub idle_min_trigger_secs: u64,
+
+    // ========== AI 用量 ==========
+
+    /// AI 每日花费预算（美元），超出后暂停后台 AI 任务；0 表示不限制
+    pub ai_daily_budget_usd: f64,
+    /// AI 每月花费预算（美元），0 表示不限制
+    pub ai_monthly_budget_usd: f64,
+
+    // ========== 隐私脱敏 ==========
+
+    /// 是否清洗分析结果中的邮箱、手机号、证件号、银行卡号、密钥
+    pub redaction_pii_enabled: bool,
+    /// 自定义清洗正则
+    pub redaction_custom_patterns: Vec<String>,
+    /// 发送前模糊的屏幕区域（相对坐标 0-1）
+    pub redaction_blur_regions: Vec<ScreenRegion>,
+
+    // ========== 隐私排除 ==========
+
+    /// 前台为这些应用时不录制，漏录进分段时整段不发送 AI（不区分大小写）
+    pub privacy_excluded_apps: Vec<String>,
+    /// 窗口标题匹配这些正则时不录制，漏录进分段时整段不发送 AI（不区分大小写）
+    pub privacy_excluded_title_patterns: Vec<String>,
+    /// 是否排除浏览器无痕/隐私窗口
+    pub privacy_exclude_incognito: bool,
+    /// 不录制的时间段
+    pub privacy_schedules: Vec<ExclusionSchedule>,
 }
 
 impl Default for AppSettings {
@@ -91,6 +130,10 @@ impl Default for AppSettings {
             capture_interval_seconds: 60,
             storage_path: default_storage_path,
             storage_limit_mb: 1024,
+            retention_policies: retention::default_policies(),
+            archive_after_days: 3,
+            archive_keyframe_count: 4,
+            knowledge_base_mode: false,
             auto_start: false,
             app_launch_text: String::from(
                 "If today were the last day of my life, would I want to do what I am about to do today?"
@@ -124,12 +167,31 @@ impl Default for AppSettings {
             screen_inactivity_minutes: 10,
             screen_inactivity_message: String::new(), // 空 = AI 智能建议
 
-            openai_api_key: None,
-
             // Idle 检测
             idle_reminder_enabled: true,
             idle_threshold_secs: 300,
             idle_min_trigger_secs: 60,
+
+            // AI 用量（默认不限制）
+            ai_daily_budget_usd: 0.0,
+            ai_monthly_budget_usd: 0.0,
+
+            // 隐私脱敏
+            redaction_pii_enabled: true,
+            redaction_custom_patterns: Vec::new(),
+            redaction_blur_regions: Vec::new(),
+
+            // 隐私排除：默认排除常见密码管理器与无痕窗口
+            privacy_excluded_apps: vec![
+                "1Password".to_string(),
+                "Bitwarden".to_string(),
+                "KeePassXC".to_string(),
+                "Keychain Access".to_string(),
+                "钥匙串访问".to_string(),
+            ],
+            privacy_excluded_title_patterns: Vec::new(),
+            privacy_exclude_incognito: true,
+            privacy_schedules: Vec::new(),
         }
     }
 }
@@ -195,5 +257,13 @@ mod tests {
         assert!(!settings.water_reminder_enabled);
         assert_eq!(settings.water_reminder_interval_minutes, 60);
         assert!(!settings.screen_inactivity_reminder_enabled);
+        assert_eq!(settings.ai_daily_budget_usd, 0.0);
+        assert!(settings.redaction_pii_enabled);
+        assert!(settings.privacy_excluded_apps.iter().any(|a| a == "1Password"));
+        assert!(settings.privacy_exclude_incognito);
+        assert!(settings.privacy_schedules.is_empty());
+        assert_eq!(settings.retention_policies, retention::default_policies());
+        assert_eq!(settings.archive_after_days, 3);
+        assert!(!settings.knowledge_base_mode);
     }
 }
@@ -1,38 +1,105 @@
 /// 应用设置管理模块
 ///
-/// 使用 tauri-plugin-store 进行持久化存储
+/// 设置保存在数据库 `settings` 表中（带版本号，见 `store`）；配置方案见 `profiles`；
+/// 运行中的子系统通过 `subscribe` 订阅变更并重新配置自身
 
+use crate::db::Database;
 use crate::error::{AppError, AppResult};
+use log::{info, warn};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use tokio::sync::watch;
 
 pub mod config;
+pub mod profiles;
+pub mod store;
 pub use config::AppSettings;
 
+use store::{SettingsStore, SETTINGS_VERSION};
+
 /// 设置管理器
 pub struct SettingsManager {
-    settings: Arc<Mutex<AppSettings>>,
+    /// 当前设置，同时作为变更通知的发送端
+    settings: watch::Sender<AppSettings>,
+    /// 持久化存储（None 时只保存在内存中）
+    store: Option<SettingsStore>,
 }
 
 impl SettingsManager {
-    /// 创建新的设置管理器
+    /// 创建只保存在内存中的设置管理器（默认设置）
     pub fn new() -> Self {
         Self {
-            settings: Arc::new(Mutex::new(AppSettings::default())),
+            settings: watch::Sender::new(AppSettings::default()),
+            store: None,
+        }
+    }
+
+    /// 从数据库加载设置，之后的更新都会写回
+    ///
+    /// 旧版本的设置迁移后立即回写；无法读取或校验不通过时记录告警、备份原值并使用默认设置
+    pub fn load(db: Arc<Database>) -> Self {
+        let store = SettingsStore::new(db);
+        let manager = Self::new();
+        if let Some(settings) = manager.restore(&store) {
+            manager.settings.send_replace(settings);
+        }
+        Self { store: Some(store), ..manager }
+    }
+
+    fn restore(&self, store: &SettingsStore) -> Option<AppSettings> {
+        let loaded = store.load().and_then(|loaded| {
+            let Some((mut settings, version)) = loaded else { return Ok(None) };
+            settings.capture_interval_seconds = settings.capture_interval_seconds.clamp(30, 300);
+            self.validate_settings(&settings)?;
+            Ok(Some((settings, version)))
+        });
+
+        match loaded {
+            Ok(Some((settings, version))) => {
+                if version < SETTINGS_VERSION {
+                    info!("Settings migrated from v{} to v{}", version, SETTINGS_VERSION);
+                    if let Err(e) = store.save(&settings) {
+                        warn!("Failed to save migrated settings: {}", e);
+                    }
+                }
+                Some(settings)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Saved settings are unreadable, falling back to defaults: {}", e);
+                if let Err(e) = store.quarantine() {
+                    warn!("Failed to back up unreadable settings: {}", e);
+                }
+                None
+            }
         }
     }
 
     /// 获取当前设置的副本
     pub fn get(&self) -> AppSettings {
-        self.settings.lock().unwrap().clone()
+        self.settings.borrow().clone()
+    }
+
+    /// 订阅设置变更（接收端总能读到最新设置，连续多次更新可能只通知一次）
+    pub fn subscribe(&self) -> watch::Receiver<AppSettings> {
+        self.settings.subscribe()
     }
 
-    /// 更新设置
+    /// 更新设置并通知订阅者（先写入存储，失败时当前设置保持不变）
     pub fn update(&self, new_settings: AppSettings) -> AppResult<()> {
         self.validate_settings(&new_settings)?;
-        let mut settings = self.settings.lock().unwrap();
-        *settings = new_settings;
-        Ok(())
+        let mut result = Ok(());
+        self.settings.send_if_modified(|settings| {
+            if let Some(store) = &self.store {
+                if let Err(e) = store.save(&new_settings) {
+                    result = Err(e);
+                    return false;
+                }
+            }
+            *settings = new_settings;
+            true
+        });
+        result
     }
 
     /// 验证设置
@@ -46,6 +113,8 @@ impl SettingsManager {
         if settings.storage_limit_mb == 0 {
             return Err(AppError::validation(2, "存储限制必须大于 0"));
         }
+        crate::storage::retention::validate_policies(&settings.retention_policies)?;
+        crate::storage::archive::validate_config(&crate::storage::archive::ArchiveConfig::from(settings))?;
 
         // 早安提醒
         self.validate_time_format(&settings.morning_reminder_time)?;
@@ -69,6 +138,15 @@ impl SettingsManager {
             return Err(AppError::validation(5, "屏幕无变化检测阈值必须大于 0"));
         }
 
+        // 隐私脱敏
+        crate::privacy::redaction::validate_patterns(&settings.redaction_custom_patterns)?;
+        if settings.redaction_blur_regions.iter().any(|r| !r.is_valid()) {
+            return Err(AppError::validation(22, "模糊区域必须位于屏幕内（相对坐标 0-1）"));
+        }
+
+        // 隐私排除
+        crate::privacy::rules::validate_rules(&crate::privacy::ExclusionRules::from(settings))?;
+
         Ok(())
     }
 
@@ -97,20 +175,17 @@ impl SettingsManager {
 
     /// 获取存储路径
     pub fn get_storage_path(&self) -> PathBuf {
-        let settings = self.settings.lock().unwrap();
-        PathBuf::from(&settings.storage_path)
+        PathBuf::from(&self.settings.borrow().storage_path)
     }
 
     /// 是否启用记忆功能
     pub fn is_memory_enabled(&self) -> bool {
-        let settings = self.settings.lock().unwrap();
-        settings.memory_enabled
+        self.settings.borrow().memory_enabled
     }
 
     /// 获取录制分段时长（秒）
     pub fn get_capture_interval(&self) -> u16 {
-        let settings = self.settings.lock().unwrap();
-        settings.capture_interval_seconds
+        self.settings.borrow().capture_interval_seconds
     }
 }
 
@@ -215,4 +290,103 @@ mod tests {
         // 原设置应该保持不变
         assert_eq!(manager.get().capture_interval_seconds, 60);
     }
+
+    #[test]
+    fn test_update_notifies_subscribers() {
+        let manager = SettingsManager::new();
+        let mut rx = manager.subscribe();
+        assert!(!rx.has_changed().unwrap());
+
+        let invalid = AppSettings { capture_interval_seconds: 0, ..Default::default() };
+        assert!(manager.update(invalid).is_err());
+        assert!(!rx.has_changed().unwrap());
+
+        let settings = AppSettings { memory_enabled: false, ..Default::default() };
+        manager.update(settings).unwrap();
+        assert!(rx.has_changed().unwrap());
+        assert!(!rx.borrow_and_update().memory_enabled);
+    }
+
+    #[test]
+    fn test_settings_persist_across_restarts() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+
+        let manager = SettingsManager::load(Arc::clone(&db));
+        assert_eq!(manager.get(), AppSettings::default());
+
+        let settings = AppSettings {
+            idle_threshold_secs: 900,
+            water_reminder_enabled: true,
+            ..Default::default()
+        };
+        manager.update(settings.clone()).unwrap();
+
+        assert_eq!(SettingsManager::load(db).get(), settings);
+    }
+
+    #[test]
+    fn test_load_falls_back_to_defaults() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let write = |value: &str| {
+            db.with_connection(|conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO settings (key, value) VALUES ('app_settings', ?1)",
+                    [value],
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        };
+
+        // 损坏的 JSON
+        write("{\"version\": 2, \"settings\": ");
+        assert_eq!(SettingsManager::load(Arc::clone(&db)).get(), AppSettings::default());
+
+        // 校验不通过（非法时间）
+        write(r#"{"version": 2, "settings": {"morning_reminder_time": "25:00"}}"#);
+        assert_eq!(SettingsManager::load(Arc::clone(&db)).get(), AppSettings::default());
+
+        // 旧版扁平 JSON 迁移后保留原值
+        write(r#"{"capture_interval_seconds": 5, "idle_min_trigger_secs": 120, "openai_api_key": "sk-x"}"#);
+        let settings = SettingsManager::load(Arc::clone(&db)).get();
+        assert_eq!(settings.capture_interval_seconds, 30);
+        assert_eq!(settings.idle_min_trigger_secs, 120);
+        let (_, version) = SettingsStore::new(db).load().unwrap().unwrap();
+        assert_eq!(version, SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_validate_redaction_settings() {
+        let manager = SettingsManager::new();
+
+        let mut settings = AppSettings::default();
+        settings.redaction_custom_patterns = vec!["[unclosed".to_string()];
+        assert!(matches!(manager.update(settings), Err(AppError::Validation(21, _))));
+
+        let mut settings = AppSettings::default();
+        settings.redaction_blur_regions = vec![crate::privacy::ScreenRegion {
+            x: 0.9,
+            y: 0.0,
+            width: 0.2,
+            height: 0.1,
+        }];
+        assert!(matches!(manager.update(settings), Err(AppError::Validation(22, _))));
+    }
+
+    #[test]
+    fn test_validate_privacy_exclusion_settings() {
+        let manager = SettingsManager::new();
+
+        let mut settings = AppSettings::default();
+        settings.privacy_excluded_title_patterns = vec!["(unclosed".to_string()];
+        assert!(matches!(manager.update(settings), Err(AppError::Validation(23, _))));
+
+        let mut settings = AppSettings::default();
+        settings.privacy_schedules = vec![crate::privacy::ExclusionSchedule {
+            days: vec![6, 7],
+            start: "25:00".to_string(),
+            end: "08:00".to_string(),
+        }];
+        assert!(matches!(manager.update(settings), Err(AppError::Validation(24, _))));
+    }
 }
@@ -0,0 +1,738 @@
+/// 设置配置方案（工作 / 个人 / 演示）
+///
+/// 配置方案是覆盖在 `AppSettings` 之上的一组开关：录制开关、提醒组合、隐私排除规则
+/// 与活动 AI 提供商。基础设置单独保存，生效设置 = 基础设置 + 当前方案的覆盖项；
+/// 切换时把生效设置写入 `SettingsManager`，运行中的子系统经设置订阅随之更新。
+/// 取消或删除当前方案时恢复基础设置。方案生效期间对未覆盖字段的修改会并入基础设置。
+/// AI 提供商不属于 `AppSettings`，由订阅 `subscribe` 的一方经 `switch_provider` 切换：
+/// 进入方案时记录当时的活动提供商，方案未指定提供商或取消方案时切回该提供商。
+///
+/// 开启自动切换后每分钟检查一次：前台应用命中某方案的切换应用时优先切换，
+/// 否则按切换时间段匹配。只在匹配结果变化时切换，手动选择的方案会一直保持到下一次变化。
+///
+/// 方案列表以 `{"version": N, ...}` 的 JSON 保存在 `settings` 表的 `settings_profiles` 行，
+/// 基础设置与 `app_settings` 同格式保存在 `settings_profile_base` 行，
+/// 基础 AI 提供商以 JSON 字符串（没有活动提供商时为 `null`）保存在 `settings_profile_base_provider` 行
+
+use chrono::NaiveDateTime;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::capture::active_window;
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use crate::privacy::ExclusionSchedule;
+use super::store::{self, SettingsStore};
+use super::{AppSettings, SettingsManager};
+
+/// 当前配置方案存储结构版本
+pub const PROFILES_VERSION: u64 = 1;
+
+const PROFILES_KEY: &str = "settings_profiles";
+const BASE_KEY: &str = "settings_profile_base";
+const BASE_PROVIDER_KEY: &str = "settings_profile_base_provider";
+
+/// 自动切换检查间隔（秒）
+const AUTO_SWITCH_INTERVAL_SECS: u64 = 60;
+
+/// 提醒组合
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSet {
+    pub morning: bool,
+    pub water: bool,
+    pub sedentary: bool,
+    pub screen_inactivity: bool,
+    pub idle: bool,
+}
+
+/// 隐私排除规则覆盖（整体替换设置中的排除规则）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyOverride {
+    pub excluded_apps: Vec<String>,
+    pub excluded_title_patterns: Vec<String>,
+    pub exclude_incognito: bool,
+    pub schedules: Vec<ExclusionSchedule>,
+}
+
+/// 配置方案（None 表示沿用当前设置）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    /// 唯一标识（字母、数字、- 和 _）
+    pub id: String,
+    pub name: String,
+    /// 是否录制
+    #[serde(default)]
+    pub memory_enabled: Option<bool>,
+    #[serde(default)]
+    pub reminders: Option<ReminderSet>,
+    #[serde(default)]
+    pub privacy: Option<PrivacyOverride>,
+    /// 活动 AI 提供商 ID（None 时使用方案生效前的提供商）
+    #[serde(default)]
+    pub ai_provider_id: Option<String>,
+    /// 自动切换：处于这些时间段时切换到该方案
+    #[serde(default)]
+    pub switch_schedules: Vec<ExclusionSchedule>,
+    /// 自动切换：前台为这些应用时切换到该方案（不区分大小写，优先于时间段）
+    #[serde(default)]
+    pub switch_apps: Vec<String>,
+}
+
+impl SettingsProfile {
+    /// 把覆盖项应用到设置上
+    pub fn apply(&self, settings: &AppSettings) -> AppSettings {
+        let mut settings = settings.clone();
+        if let Some(enabled) = self.memory_enabled {
+            settings.memory_enabled = enabled;
+        }
+        if let Some(reminders) = self.reminders {
+            settings.morning_reminder_enabled = reminders.morning;
+            settings.water_reminder_enabled = reminders.water;
+            settings.sedentary_reminder_enabled = reminders.sedentary;
+            settings.screen_inactivity_reminder_enabled = reminders.screen_inactivity;
+            settings.idle_reminder_enabled = reminders.idle;
+        }
+        if let Some(privacy) = &self.privacy {
+            settings.privacy_excluded_apps = privacy.excluded_apps.clone();
+            settings.privacy_excluded_title_patterns = privacy.excluded_title_patterns.clone();
+            settings.privacy_exclude_incognito = privacy.exclude_incognito;
+            settings.privacy_schedules = privacy.schedules.clone();
+        }
+        settings
+    }
+
+    /// 撤销覆盖项：被覆盖的字段取回 base 中的值，其余字段保留 settings 中的修改
+    pub fn revert(&self, settings: &AppSettings, base: &AppSettings) -> AppSettings {
+        let mut settings = settings.clone();
+        if self.memory_enabled.is_some() {
+            settings.memory_enabled = base.memory_enabled;
+        }
+        if self.reminders.is_some() {
+            settings.morning_reminder_enabled = base.morning_reminder_enabled;
+            settings.water_reminder_enabled = base.water_reminder_enabled;
+            settings.sedentary_reminder_enabled = base.sedentary_reminder_enabled;
+            settings.screen_inactivity_reminder_enabled = base.screen_inactivity_reminder_enabled;
+            settings.idle_reminder_enabled = base.idle_reminder_enabled;
+        }
+        if self.privacy.is_some() {
+            settings.privacy_excluded_apps = base.privacy_excluded_apps.clone();
+            settings.privacy_excluded_title_patterns = base.privacy_excluded_title_patterns.clone();
+            settings.privacy_exclude_incognito = base.privacy_exclude_incognito;
+            settings.privacy_schedules = base.privacy_schedules.clone();
+        }
+        settings
+    }
+
+    fn matches_app(&self, app_name: &str) -> bool {
+        let app_name = app_name.to_lowercase();
+        self.switch_apps.iter().any(|app| app.to_lowercase() == app_name)
+    }
+}
+
+/// 配置方案列表与当前状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileState {
+    pub profiles: Vec<SettingsProfile>,
+    /// 当前方案 ID
+    pub active: Option<String>,
+    /// 是否按时间段 / 前台应用自动切换
+    pub auto_switch: bool,
+}
+
+impl Default for ProfileState {
+    fn default() -> Self {
+        Self {
+            profiles: builtin_profiles(),
+            active: None,
+            auto_switch: false,
+        }
+    }
+}
+
+impl ProfileState {
+    pub fn get(&self, id: &str) -> Option<&SettingsProfile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    /// 自动切换应选的方案：前台应用优先，其次时间段，均按列表顺序取第一个
+    pub fn matching(&self, now: NaiveDateTime, app_name: Option<&str>) -> Option<&SettingsProfile> {
+        app_name
+            .and_then(|app| self.profiles.iter().find(|p| p.matches_app(app)))
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|p| p.switch_schedules.iter().any(|s| s.contains(now)))
+            })
+    }
+}
+
+/// 内置方案：工作日白天工作、个人、打开演示软件时演示
+fn builtin_profiles() -> Vec<SettingsProfile> {
+    vec![
+        SettingsProfile {
+            id: "work".to_string(),
+            name: "工作".to_string(),
+            memory_enabled: Some(true),
+            reminders: Some(ReminderSet {
+                morning: true,
+                water: true,
+                sedentary: true,
+                screen_inactivity: true,
+                idle: true,
+            }),
+            privacy: None,
+            ai_provider_id: None,
+            switch_schedules: vec![ExclusionSchedule {
+                days: vec![1, 2, 3, 4, 5],
+                start: "09:00".to_string(),
+                end: "18:00".to_string(),
+            }],
+            switch_apps: Vec::new(),
+        },
+        SettingsProfile {
+            id: "personal".to_string(),
+            name: "个人".to_string(),
+            memory_enabled: Some(true),
+            reminders: Some(ReminderSet { morning: true, ..Default::default() }),
+            privacy: None,
+            ai_provider_id: None,
+            switch_schedules: Vec::new(),
+            switch_apps: Vec::new(),
+        },
+        SettingsProfile {
+            id: "presentation".to_string(),
+            name: "演示".to_string(),
+            memory_enabled: Some(false),
+            reminders: Some(ReminderSet::default()),
+            privacy: None,
+            ai_provider_id: None,
+            switch_schedules: Vec::new(),
+            switch_apps: vec!["Keynote".to_string(), "Microsoft PowerPoint".to_string()],
+        },
+    ]
+}
+
+/// 配置方案管理器
+pub struct ProfileManager {
+    settings: Arc<SettingsManager>,
+    /// 持久化存储（None 时只保存在内存中）
+    store: Option<SettingsStore>,
+    state: Mutex<ProfileState>,
+    /// 方案生效前的基础设置（没有生效方案时为 None，此时基础设置即当前设置）
+    base: Mutex<Option<AppSettings>>,
+    /// 方案生效前的活动 AI 提供商（外层 None 表示尚未记录）
+    base_provider: Mutex<Option<Option<String>>>,
+    /// 上一次自动切换检查匹配到的方案
+    last_match: Mutex<Option<String>>,
+    /// 当前方案，切换时通知订阅者
+    active: watch::Sender<Option<SettingsProfile>>,
+}
+
+impl ProfileManager {
+    /// 创建只保存在内存中的管理器（内置方案）
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self::with_state(settings, None, ProfileState::default(), None, None)
+    }
+
+    /// 从数据库加载方案列表，之后的修改都会写回；无法读取时记录告警并使用内置方案
+    pub fn load(db: Arc<Database>, settings: Arc<SettingsManager>) -> Self {
+        let store = SettingsStore::new(db);
+        let state = match store.read(PROFILES_KEY).and_then(|raw| raw.map(|raw| decode(&raw)).transpose()) {
+            Ok(state) => state.unwrap_or_default(),
+            Err(e) => {
+                warn!("Saved settings profiles are unreadable, using built-in profiles: {}", e);
+                ProfileState::default()
+            }
+        };
+        let base = match store.read(BASE_KEY).and_then(|raw| raw.map(|raw| store::decode(&raw)).transpose()) {
+            Ok(base) => base.map(|(base, _)| base),
+            Err(e) => {
+                warn!("Saved base settings are unreadable, current settings become the base: {}", e);
+                None
+            }
+        };
+        let base_provider = match store.read(BASE_PROVIDER_KEY).and_then(|raw| {
+            raw.map(|raw| serde_json::from_str(&raw).map_err(AppError::from)).transpose()
+        }) {
+            Ok(provider) => provider,
+            Err(e) => {
+                warn!("Saved base AI provider is unreadable, current provider becomes the base: {}", e);
+                None
+            }
+        };
+        Self::with_state(settings, Some(store), state, base, base_provider)
+    }
+
+    fn with_state(
+        settings: Arc<SettingsManager>,
+        store: Option<SettingsStore>,
+        state: ProfileState,
+        base: Option<AppSettings>,
+        base_provider: Option<Option<String>>,
+    ) -> Self {
+        let active = state.active.as_deref().and_then(|id| state.get(id)).cloned();
+        let (base, base_provider) = if active.is_some() { (base, base_provider) } else { (None, None) };
+        Self {
+            settings,
+            store,
+            state: Mutex::new(state),
+            base: Mutex::new(base),
+            base_provider: Mutex::new(base_provider),
+            last_match: Mutex::new(None),
+            active: watch::Sender::new(active),
+        }
+    }
+
+    /// 方案列表与当前状态
+    pub fn state(&self) -> ProfileState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// 订阅方案切换
+    pub fn subscribe(&self) -> watch::Receiver<Option<SettingsProfile>> {
+        self.active.subscribe()
+    }
+
+    /// 新增或替换方案；修改的是当前方案时立即重新应用
+    pub fn save_profile(&self, profile: SettingsProfile) -> AppResult<()> {
+        self.validate_profile(&profile)?;
+        let is_active = self.modify(|state| {
+            match state.profiles.iter_mut().find(|p| p.id == profile.id) {
+                Some(existing) => *existing = profile.clone(),
+                None => state.profiles.push(profile.clone()),
+            }
+            state.active.as_deref() == Some(profile.id.as_str())
+        })?;
+        if is_active {
+            self.activate(&profile.id)?;
+        }
+        Ok(())
+    }
+
+    /// 删除方案；删除当前方案时先取消方案、恢复基础设置
+    pub fn delete_profile(&self, id: &str) -> AppResult<()> {
+        if self.state().get(id).is_none() {
+            return Err(unknown_profile(id));
+        }
+        if self.state().active.as_deref() == Some(id) {
+            self.deactivate()?;
+        }
+        self.modify(|state| state.profiles.retain(|p| p.id != id))?;
+        Ok(())
+    }
+
+    /// 基础设置：当前设置撤销生效方案的覆盖项（没有生效方案时即当前设置）
+    pub fn base_settings(&self) -> AppSettings {
+        let current = self.settings.get();
+        let base = self.base.lock().unwrap();
+        match (base.as_ref(), self.active.borrow().as_ref()) {
+            (Some(base), Some(active)) => active.revert(&current, base),
+            _ => current,
+        }
+    }
+
+    /// 切换到方案：基础设置加上方案覆盖项写入当前设置并通知订阅者
+    pub fn activate(&self, id: &str) -> AppResult<SettingsProfile> {
+        let profile = self.state().get(id).cloned().ok_or_else(|| unknown_profile(id))?;
+        let base = self.base_settings();
+        self.save_base(Some(&base))?;
+        self.settings.update(profile.apply(&base))?;
+        self.modify(|state| state.active = Some(profile.id.clone()))?;
+        self.active.send_replace(Some(profile.clone()));
+        info!("Switched to settings profile '{}'", profile.name);
+        Ok(profile)
+    }
+
+    /// 取消当前方案并恢复基础设置（没有生效方案时不做任何事）
+    pub fn deactivate(&self) -> AppResult<()> {
+        let Some(profile) = self.active.borrow().clone() else { return Ok(()) };
+        self.settings.update(self.base_settings())?;
+        self.modify(|state| state.active = None)?;
+        self.save_base(None)?;
+        self.active.send_replace(None);
+        *self.last_match.lock().unwrap() = None;
+        info!("Left settings profile '{}', base settings restored", profile.name);
+        Ok(())
+    }
+
+    /// 方案切换后应使用的 AI 提供商，由订阅 `subscribe` 的一方在每次切换后调用
+    ///
+    /// 第一次进入方案时把 `current` 记为基础提供商；方案未指定提供商时使用基础提供商，
+    /// 取消方案时切回基础提供商并清除记录。返回 None 表示保持当前提供商
+    pub fn switch_provider(
+        &self,
+        profile: Option<&SettingsProfile>,
+        current: Option<&str>,
+    ) -> AppResult<Option<String>> {
+        let mut base_provider = self.base_provider.lock().unwrap();
+        let target = match profile {
+            Some(profile) => {
+                if base_provider.is_none() {
+                    let recorded = current.map(str::to_string);
+                    self.save_base_provider(Some(&recorded))?;
+                    *base_provider = Some(recorded);
+                }
+                profile.ai_provider_id.clone().or_else(|| base_provider.clone().flatten())
+            }
+            None => {
+                let Some(recorded) = base_provider.clone() else { return Ok(None) };
+                self.save_base_provider(None)?;
+                *base_provider = None;
+                recorded
+            }
+        };
+        Ok(target.filter(|id| Some(id.as_str()) != current))
+    }
+
+    /// 开启或关闭自动切换（开启后下一次检查按当前匹配结果切换）
+    pub fn set_auto_switch(&self, enabled: bool) -> AppResult<()> {
+        self.modify(|state| state.auto_switch = enabled)?;
+        *self.last_match.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// 自动切换检查：匹配结果与上一次不同且不是当前方案时切换，返回切换到的方案
+    pub fn auto_switch(&self, now: NaiveDateTime, app_name: Option<&str>) -> AppResult<Option<SettingsProfile>> {
+        let (matched, active) = {
+            let state = self.state.lock().unwrap();
+            if !state.auto_switch {
+                return Ok(None);
+            }
+            (state.matching(now, app_name).map(|p| p.id.clone()), state.active.clone())
+        };
+
+        {
+            let mut last_match = self.last_match.lock().unwrap();
+            if *last_match == matched {
+                return Ok(None);
+            }
+            *last_match = matched.clone();
+        }
+
+        match matched {
+            Some(id) if active.as_ref() != Some(&id) => self.activate(&id).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// 启动自动切换检查（需在 tokio runtime 中调用）
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(AUTO_SWITCH_INTERVAL_SECS));
+            loop {
+                ticker.tick().await;
+                if !manager.state.lock().unwrap().auto_switch {
+                    continue;
+                }
+                let window = tokio::task::spawn_blocking(active_window::current).await.ok().flatten();
+                let now = chrono::Local::now().naive_local();
+                if let Err(e) = manager.auto_switch(now, window.as_ref().map(|w| w.app_name.as_str())) {
+                    error!("Failed to switch settings profile: {}", e);
+                }
+            }
+        })
+    }
+
+    /// 保存基础设置（None 时删除）
+    fn save_base(&self, base: Option<&AppSettings>) -> AppResult<()> {
+        if let Some(store) = &self.store {
+            match base {
+                Some(base) => store.write(BASE_KEY, &store::encode(base)?)?,
+                None => store.remove(BASE_KEY)?,
+            }
+        }
+        *self.base.lock().unwrap() = base.cloned();
+        Ok(())
+    }
+
+    /// 保存基础 AI 提供商（None 时删除）
+    fn save_base_provider(&self, provider: Option<&Option<String>>) -> AppResult<()> {
+        if let Some(store) = &self.store {
+            match provider {
+                Some(provider) => store.write(BASE_PROVIDER_KEY, &serde_json::to_string(provider)?)?,
+                None => store.remove(BASE_PROVIDER_KEY)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// 修改状态并写回存储，写入失败时状态保持不变
+    fn modify<R>(&self, f: impl FnOnce(&mut ProfileState) -> R) -> AppResult<R> {
+        let mut state = self.state.lock().unwrap();
+        let mut updated = state.clone();
+        let result = f(&mut updated);
+        if updated != *state {
+            if let Some(store) = &self.store {
+                store.write(PROFILES_KEY, &encode(&updated)?)?;
+            }
+            *state = updated;
+        }
+        Ok(result)
+    }
+
+    fn validate_profile(&self, profile: &SettingsProfile) -> AppResult<()> {
+        let valid_id = !profile.id.is_empty()
+            && profile.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(AppError::validation(32, "配置方案 ID 只能包含字母、数字、- 和 _"));
+        }
+        if profile.name.trim().is_empty() {
+            return Err(AppError::validation(32, "配置方案名称不能为空"));
+        }
+        if profile.switch_apps.iter().any(|app| app.trim().is_empty()) {
+            return Err(AppError::validation(32, "自动切换的应用名不能为空"));
+        }
+        profile.switch_schedules.iter().try_for_each(ExclusionSchedule::validate)?;
+        self.settings.validate_settings(&profile.apply(&self.base_settings()))
+    }
+}
+
+fn unknown_profile(id: &str) -> AppError {
+    AppError::settings(14, format!("配置方案不存在: {}", id))
+}
+
+/// 序列化为带版本号的 JSON
+fn encode(state: &ProfileState) -> AppResult<String> {
+    let mut value = serde_json::to_value(state)?;
+    value["version"] = PROFILES_VERSION.into();
+    Ok(serde_json::to_string(&value)?)
+}
+
+fn decode(raw: &str) -> AppResult<ProfileState> {
+    let value: Value = serde_json::from_str(raw)
+        .map_err(|e| AppError::settings(12, format!("配置方案内容损坏: {}", e)))?;
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version > PROFILES_VERSION {
+        return Err(AppError::settings(
+            13,
+            format!("配置方案由更新的版本写入（v{}，当前支持 v{}）", version, PROFILES_VERSION),
+        ));
+    }
+    serde_json::from_value(value)
+        .map_err(|e| AppError::settings(12, format!("配置方案内容损坏: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        // 2024-06-03 是周一
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn manager() -> ProfileManager {
+        ProfileManager::new(Arc::new(SettingsManager::new()))
+    }
+
+    #[test]
+    fn test_activate_applies_overrides() {
+        let manager = manager();
+        let mut rx = manager.subscribe();
+
+        manager.activate("presentation").unwrap();
+        let settings = manager.settings.get();
+        assert!(!settings.memory_enabled);
+        assert!(!settings.morning_reminder_enabled);
+        assert_eq!(manager.state().active.as_deref(), Some("presentation"));
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(rx.borrow_and_update().as_ref().unwrap().id, "presentation");
+
+        manager.activate("work").unwrap();
+        let settings = manager.settings.get();
+        assert!(settings.memory_enabled);
+        assert!(settings.water_reminder_enabled && settings.idle_reminder_enabled);
+
+        assert!(matches!(manager.activate("missing"), Err(AppError::Settings(14, _))));
+    }
+
+    #[test]
+    fn test_deactivate_restores_base_settings() {
+        let manager = manager();
+        let mut base = manager.settings.get();
+        base.water_reminder_enabled = false;
+        base.capture_interval_seconds = 90;
+        manager.settings.update(base.clone()).unwrap();
+
+        manager.activate("work").unwrap();
+        assert!(manager.settings.get().water_reminder_enabled);
+        // 方案之间切换时覆盖项基于基础设置，而不是上一个方案的结果
+        manager.activate("presentation").unwrap();
+        manager.activate("personal").unwrap();
+        let settings = manager.settings.get();
+        assert!(settings.memory_enabled && settings.morning_reminder_enabled);
+        assert!(!settings.water_reminder_enabled);
+
+        // 方案生效期间修改未覆盖的字段，取消后保留
+        let mut edited = manager.settings.get();
+        edited.capture_interval_seconds = 120;
+        manager.settings.update(edited).unwrap();
+
+        let mut rx = manager.subscribe();
+        manager.deactivate().unwrap();
+        let settings = manager.settings.get();
+        assert_eq!(settings, AppSettings { capture_interval_seconds: 120, ..base.clone() });
+        assert!(manager.state().active.is_none());
+        assert!(rx.borrow_and_update().is_none());
+
+        // 删除当前方案同样恢复基础设置
+        manager.activate("presentation").unwrap();
+        manager.delete_profile("presentation").unwrap();
+        assert_eq!(manager.settings.get(), AppSettings { capture_interval_seconds: 120, ..base });
+    }
+
+    #[test]
+    fn test_privacy_override_replaces_rules() {
+        let manager = manager();
+        let profile = SettingsProfile {
+            id: "client-demo".to_string(),
+            name: "客户演示".to_string(),
+            memory_enabled: None,
+            reminders: None,
+            privacy: Some(PrivacyOverride {
+                excluded_apps: vec!["Slack".to_string()],
+                exclude_incognito: true,
+                ..Default::default()
+            }),
+            ai_provider_id: None,
+            switch_schedules: Vec::new(),
+            switch_apps: Vec::new(),
+        };
+        manager.save_profile(profile).unwrap();
+        manager.activate("client-demo").unwrap();
+
+        let settings = manager.settings.get();
+        assert_eq!(settings.privacy_excluded_apps, vec!["Slack".to_string()]);
+        assert!(settings.privacy_exclude_incognito);
+        // 未覆盖的部分保持不变
+        assert!(settings.memory_enabled);
+    }
+
+    #[test]
+    fn test_switch_provider_restores_base_provider() {
+        let manager = manager();
+        let mut work = manager.state().get("work").cloned().unwrap();
+        work.ai_provider_id = Some("openai".to_string());
+        manager.save_profile(work.clone()).unwrap();
+        let personal = manager.state().get("personal").cloned().unwrap();
+
+        // 进入方案时记录当前提供商，切换到方案指定的提供商
+        let target = manager.switch_provider(Some(&work), Some("claude")).unwrap();
+        assert_eq!(target.as_deref(), Some("openai"));
+        // 未指定提供商的方案使用基础提供商，而不是沿用上一个方案的
+        let target = manager.switch_provider(Some(&personal), Some("openai")).unwrap();
+        assert_eq!(target.as_deref(), Some("claude"));
+        assert!(manager.switch_provider(Some(&personal), Some("claude")).unwrap().is_none());
+
+        // 取消方案时恢复基础提供商并清除记录
+        manager.switch_provider(Some(&work), Some("claude")).unwrap();
+        let target = manager.switch_provider(None, Some("openai")).unwrap();
+        assert_eq!(target.as_deref(), Some("claude"));
+        assert!(manager.switch_provider(None, Some("claude")).unwrap().is_none());
+
+        // 基础提供商重启后仍能恢复
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let settings = Arc::new(SettingsManager::load(Arc::clone(&db)));
+        let manager = ProfileManager::load(Arc::clone(&db), Arc::clone(&settings));
+        manager.save_profile(work.clone()).unwrap();
+        manager.activate("work").unwrap();
+        manager.switch_provider(Some(&work), Some("claude")).unwrap();
+
+        let reloaded = ProfileManager::load(db, settings);
+        let target = reloaded.switch_provider(None, Some("openai")).unwrap();
+        assert_eq!(target.as_deref(), Some("claude"));
+    }
+
+    #[test]
+    fn test_validate_profile() {
+        let manager = manager();
+        let mut profile = manager.state().get("work").cloned().unwrap();
+
+        profile.id = "work space".to_string();
+        assert!(matches!(manager.save_profile(profile.clone()), Err(AppError::Validation(32, _))));
+
+        profile.id = "work".to_string();
+        profile.switch_schedules[0].start = "9am".to_string();
+        assert!(matches!(manager.save_profile(profile.clone()), Err(AppError::Validation(24, _))));
+
+        profile.switch_schedules.clear();
+        profile.privacy = Some(PrivacyOverride {
+            excluded_title_patterns: vec!["(unclosed".to_string()],
+            ..Default::default()
+        });
+        assert!(matches!(manager.save_profile(profile), Err(AppError::Validation(23, _))));
+    }
+
+    #[test]
+    fn test_matching_prefers_foreground_app() {
+        let state = ProfileState::default();
+
+        assert_eq!(state.matching(at(3, 10), None).unwrap().id, "work");
+        assert!(state.matching(at(3, 20), None).is_none());
+        // 周六白天不在工作时间段
+        assert!(state.matching(at(8, 10), None).is_none());
+        assert_eq!(state.matching(at(3, 10), Some("keynote")).unwrap().id, "presentation");
+        assert_eq!(state.matching(at(3, 10), Some("Safari")).unwrap().id, "work");
+    }
+
+    #[test]
+    fn test_auto_switch_only_on_match_change() {
+        let manager = manager();
+        assert!(manager.auto_switch(at(3, 10), None).unwrap().is_none());
+
+        manager.set_auto_switch(true).unwrap();
+        assert_eq!(manager.auto_switch(at(3, 10), None).unwrap().unwrap().id, "work");
+
+        // 手动切换后，匹配结果不变时保持手动选择
+        manager.activate("personal").unwrap();
+        assert!(manager.auto_switch(at(3, 11), None).unwrap().is_none());
+        assert_eq!(manager.state().active.as_deref(), Some("personal"));
+
+        // 打开演示软件时切换，离开工作时间段不会切回
+        let switched = manager.auto_switch(at(3, 12), Some("Microsoft PowerPoint")).unwrap();
+        assert_eq!(switched.unwrap().id, "presentation");
+        assert!(manager.auto_switch(at(3, 20), None).unwrap().is_none());
+        assert_eq!(manager.state().active.as_deref(), Some("presentation"));
+    }
+
+    #[test]
+    fn test_profiles_persist_across_restarts() {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let settings = Arc::new(SettingsManager::load(Arc::clone(&db)));
+
+        let manager = ProfileManager::load(Arc::clone(&db), Arc::clone(&settings));
+        assert_eq!(manager.state(), ProfileState::default());
+        manager.set_auto_switch(true).unwrap();
+        manager.activate("presentation").unwrap();
+        manager.delete_profile("personal").unwrap();
+
+        let reloaded = ProfileManager::load(Arc::clone(&db), settings);
+        let state = reloaded.state();
+        assert!(state.auto_switch);
+        assert_eq!(state.active.as_deref(), Some("presentation"));
+        assert!(state.get("personal").is_none());
+        assert_eq!(reloaded.subscribe().borrow().as_ref().unwrap().id, "presentation");
+        assert!(!SettingsManager::load(Arc::clone(&db)).get().memory_enabled);
+
+        // 基础设置也会保存，重启后取消方案仍能恢复
+        reloaded.deactivate().unwrap();
+        assert!(SettingsManager::load(db).get().memory_enabled);
+    }
+
+    #[test]
+    fn test_decode_rejects_newer_version() {
+        assert!(matches!(decode("{broken"), Err(AppError::Settings(12, _))));
+        assert!(matches!(decode(r#"{"version": 9, "profiles": []}"#), Err(AppError::Settings(13, _))));
+        assert!(decode(r#"{"version": 1, "profiles": [], "auto_switch": true}"#).unwrap().auto_switch);
+    }
+}
@@ -0,0 +1,205 @@
+/// 设置持久化
+///
+/// 设置以 `{"version": N, "settings": {...}}` 的 JSON 保存在 `settings` 表的 `app_settings` 行。
+/// 读取时按版本逐级迁移到当前结构；内容损坏或由更新版本写入时，原值移到
+/// `app_settings.corrupt` 行保留，由调用方回退到默认设置
+
+use log::warn;
+use rusqlite::{params, OptionalExtension};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use super::AppSettings;
+
+/// 当前设置结构版本
+///
+/// - 1: 不带版本号的扁平 JSON
+/// - 2: 移除已废弃的 `openai_api_key`（API Key 改由 AI 配置与凭据存储管理）
+pub const SETTINGS_VERSION: u64 = 2;
+
+const SETTINGS_KEY: &str = "app_settings";
+const CORRUPT_KEY: &str = "app_settings.corrupt";
+
+/// 设置存储
+pub struct SettingsStore {
+    db: Arc<Database>,
+}
+
+impl SettingsStore {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// 读取并迁移设置，返回设置与保存时的版本（从未保存过时为 None）
+    pub fn load(&self) -> AppResult<Option<(AppSettings, u64)>> {
+        match self.read(SETTINGS_KEY)? {
+            Some(raw) => decode(&raw).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 以当前版本保存设置
+    pub fn save(&self, settings: &AppSettings) -> AppResult<()> {
+        self.write(SETTINGS_KEY, &encode(settings)?)
+    }
+
+    /// 把无法读取的原值移到备份行，下次启动不再重复告警
+    pub fn quarantine(&self) -> AppResult<()> {
+        let Some(raw) = self.read(SETTINGS_KEY)? else { return Ok(()) };
+        self.write(CORRUPT_KEY, &raw)?;
+        self.remove(SETTINGS_KEY)
+    }
+
+    pub(super) fn read(&self, key: &str) -> AppResult<Option<String>> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+                    .optional()?)
+            })
+            .map_err(|e| AppError::settings(11, format!("读取设置失败: {}", e)))
+    }
+
+    pub(super) fn write(&self, key: &str, value: &str) -> AppResult<()> {
+        self.db
+            .with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO settings (key, value, updated_at)
+                     VALUES (?1, ?2, strftime('%s', 'now'))
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                    params![key, value],
+                )?;
+                Ok(())
+            })
+            .map_err(|e| AppError::settings(10, format!("保存设置失败: {}", e)))
+    }
+
+    pub(super) fn remove(&self, key: &str) -> AppResult<()> {
+        self.db
+            .with_connection(|conn| {
+                conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+                Ok(())
+            })
+            .map_err(|e| AppError::settings(10, format!("保存设置失败: {}", e)))
+    }
+}
+
+/// 序列化为带版本号的 JSON
+pub fn encode(settings: &AppSettings) -> AppResult<String> {
+    Ok(serde_json::to_string(&json!({
+        "version": SETTINGS_VERSION,
+        "settings": settings,
+    }))?)
+}
+
+/// 解析设置 JSON 并迁移到当前版本，返回设置与原始版本
+pub fn decode(raw: &str) -> AppResult<(AppSettings, u64)> {
+    let value: Value = serde_json::from_str(raw)
+        .map_err(|e| AppError::settings(12, format!("设置内容损坏: {}", e)))?;
+
+    let (version, mut settings) = match value {
+        Value::Object(mut map) if map.contains_key("version") && map.contains_key("settings") => {
+            let version = map["version"]
+                .as_u64()
+                .ok_or_else(|| AppError::settings(12, "设置版本号无效"))?;
+            (version, map.remove("settings").unwrap_or_default())
+        }
+        flat => (1, flat),
+    };
+
+    if version > SETTINGS_VERSION {
+        return Err(AppError::settings(
+            13,
+            format!("设置由更新的版本写入（v{}，当前支持 v{}）", version, SETTINGS_VERSION),
+        ));
+    }
+
+    for from in version..SETTINGS_VERSION {
+        migrate(from, &mut settings);
+    }
+
+    let settings = serde_json::from_value(settings)
+        .map_err(|e| AppError::settings(12, format!("设置内容损坏: {}", e)))?;
+    Ok((settings, version))
+}
+
+/// 从 from 版本迁移到 from + 1
+fn migrate(from: u64, settings: &mut Value) {
+    let Some(map) = settings.as_object_mut() else { return };
+
+    if from == 1 {
+        if let Some(Value::String(key)) = map.remove("openai_api_key") {
+            if !key.is_empty() {
+                warn!("已移除废弃设置 openai_api_key，请在 AI 配置中重新填写 API Key");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> SettingsStore {
+        SettingsStore::new(Arc::new(Database::open_in_memory().unwrap()))
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let store = store();
+        assert!(store.load().unwrap().is_none());
+
+        let settings = AppSettings {
+            capture_interval_seconds: 120,
+            water_reminder_enabled: true,
+            ..Default::default()
+        };
+        store.save(&settings).unwrap();
+
+        let (loaded, version) = store.load().unwrap().unwrap();
+        assert_eq!(loaded, settings);
+        assert_eq!(version, SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_flat_v1_settings() {
+        let raw = r#"{
+            "capture_interval_seconds": 90,
+            "idle_threshold_secs": 600,
+            "openai_api_key": "sk-legacy"
+        }"#;
+
+        let (settings, version) = decode(raw).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(settings.capture_interval_seconds, 90);
+        assert_eq!(settings.idle_threshold_secs, 600);
+
+        // 重新保存后不再包含废弃字段
+        let encoded = encode(&settings).unwrap();
+        assert!(!encoded.contains("openai_api_key"));
+        assert_eq!(decode(&encoded).unwrap().1, SETTINGS_VERSION);
+    }
+
+    #[test]
+    fn test_reject_corrupt_and_newer_settings() {
+        assert!(matches!(decode("{not json"), Err(AppError::Settings(12, _))));
+        assert!(matches!(decode("[1, 2]"), Err(AppError::Settings(12, _))));
+        assert!(matches!(
+            decode(r#"{"version": 99, "settings": {}}"#),
+            Err(AppError::Settings(13, _))
+        ));
+    }
+
+    #[test]
+    fn test_quarantine_moves_raw_value() {
+        let store = store();
+        store.write(SETTINGS_KEY, "{broken").unwrap();
+        assert!(store.load().is_err());
+
+        store.quarantine().unwrap();
+        assert!(store.load().unwrap().is_none());
+        assert_eq!(store.read(CORRUPT_KEY).unwrap().as_deref(), Some("{broken"));
+    }
+}
@@ -0,0 +1,160 @@
+/// 应用状态
+///
+/// 桌面应用、守护进程共用的子系统集合（数据库、设置、录制、记忆管道、保留策略等），
+/// 不依赖 Tauri；桌面应用通过 `manage` 交给各命令使用
+
+use std::sync::Arc;
+use crate::db::Database;
+use crate::settings::SettingsManager;
+use crate::settings::profiles::ProfileManager;
+use crate::capture::screen_recorder::ScreenRecorder;
+use crate::capture::scheduler::CaptureScheduler;
+use crate::capture::idle_watcher::{IdleConfig, IdleWatcher};
+use crate::notification::scheduler::NotificationScheduler;
+use crate::memory::knowledge_base::{KnowledgeBase, KnowledgeBaseConfig};
+use crate::memory::pipeline::PipelineScheduler;
+use crate::ai::usage::{UsageBudget, UsageTracker};
+use crate::privacy::{ExclusionRules, PrivacyGuard, RedactionConfig, Redactor};
+use crate::storage::archive::{ArchiveConfig, KeyframeArchiver};
+use crate::storage::retention::{RetentionConfig, RetentionEngine};
+
+/// 应用状态
+pub struct AppState {
+    pub db: Arc<Database>,
+    pub settings: Arc<SettingsManager>,
+    pub profiles: Arc<ProfileManager>,
+    pub scheduler: Arc<tokio::sync::Mutex<CaptureScheduler>>,
+    pub notification_scheduler: Arc<NotificationScheduler>,
+    pub pipeline: Arc<PipelineScheduler>,
+    pub usage: Arc<UsageTracker>,
+    pub redactor: Arc<Redactor>,
+    pub privacy_guard: Arc<PrivacyGuard>,
+    pub retention: Arc<RetentionEngine>,
+    pub archiver: Arc<KeyframeArchiver>,
+    pub knowledge_base: Arc<KnowledgeBase>,
+    pub idle_watcher: Arc<IdleWatcher>,
+}
+
+impl AppState {
+    pub fn new(db: Arc<Database>, settings: SettingsManager) -> Self {
+        let settings = Arc::new(settings);
+        let profiles = Arc::new(ProfileManager::load(Arc::clone(&db), Arc::clone(&settings)));
+        let storage_path = settings.get_storage_path();
+        if let Err(e) = crate::crypto::setup::load(&db, &storage_path) {
+            log::error!("Failed to load encryption state: {}", e);
+        }
+        let interval = settings.get_capture_interval() as u64;
+        let usage = Arc::new(UsageTracker::new(
+            Arc::clone(&db),
+            UsageBudget::from(&settings.get()),
+        ));
+        let redactor = Arc::new(Redactor::new(
+            Arc::clone(&db),
+            RedactionConfig::from(&settings.get()),
+        ));
+        let privacy_guard = Arc::new(PrivacyGuard::new(
+            Arc::clone(&db),
+            ExclusionRules::from(&settings.get()),
+        ));
+
+        let retention = Arc::new(RetentionEngine::new(
+            Arc::clone(&db),
+            RetentionConfig::from(&settings.get()),
+        ));
+        let archiver = Arc::new(KeyframeArchiver::new(
+            Arc::clone(&db),
+            ArchiveConfig::from(&settings.get()),
+        ));
+        let knowledge_base = Arc::new(KnowledgeBase::new(
+            Arc::clone(&db),
+            storage_path.clone(),
+            KnowledgeBaseConfig::from(&settings.get()),
+        ));
+
+        let idle_watcher = Arc::new(IdleWatcher::new(IdleConfig::from(&settings.get())));
+
+        let recorder = ScreenRecorder::new(storage_path.clone(), interval, 2)
+            .expect("Failed to create ScreenRecorder");
+
+        let (analysis_tx, analysis_rx) = tokio::sync::mpsc::channel::<(String, std::path::PathBuf)>(8);
+
+        let scheduler = CaptureScheduler::new(recorder, interval)
+            .with_db(Arc::clone(&db))
+            .with_analysis_sender(analysis_tx)
+            .with_privacy_guard(Arc::clone(&privacy_guard));
+
+        let notification_scheduler = NotificationScheduler::new(
+            Arc::clone(&db),
+            Arc::clone(&settings),
+        );
+
+        let pipeline = PipelineScheduler::new(
+            Arc::clone(&db),
+            storage_path,
+            false,
+        ).expect("Failed to create PipelineScheduler")
+            .with_analysis_receiver(analysis_rx)
+            .with_usage_tracker(Arc::clone(&usage))
+            .with_redactor(Arc::clone(&redactor))
+            .with_knowledge_base(Arc::clone(&knowledge_base));
+
+        Self {
+            db,
+            settings,
+            profiles,
+            scheduler: Arc::new(tokio::sync::Mutex::new(scheduler)),
+            notification_scheduler: Arc::new(notification_scheduler),
+            pipeline: Arc::new(pipeline),
+            usage,
+            redactor,
+            privacy_guard,
+            retention,
+            archiver,
+            knowledge_base,
+            idle_watcher,
+        }
+    }
+
+    /// 订阅设置变更，把新设置应用到运行中的子系统（需在 tokio runtime 中调用）
+    ///
+    /// - 用量预算、脱敏、隐私排除、保留策略、归档、知识库：替换共享配置
+    /// - 录制：随 `memory_enabled` 启停，分段时长变化时重启
+    /// - 记忆管道：随 `memory_enabled` 启停
+    /// - Idle 检测：更新启用状态与阈值
+    pub fn watch_settings(&self) {
+        let mut settings = self.settings.subscribe();
+        let usage = Arc::clone(&self.usage);
+        let redactor = Arc::clone(&self.redactor);
+        let privacy_guard = Arc::clone(&self.privacy_guard);
+        let retention = Arc::clone(&self.retention);
+        let archiver = Arc::clone(&self.archiver);
+        let knowledge_base = Arc::clone(&self.knowledge_base);
+
+        tokio::spawn(async move {
+            while settings.changed().await.is_ok() {
+                let settings = settings.borrow_and_update().clone();
+                usage.set_budget(UsageBudget::from(&settings));
+                redactor.set_config(RedactionConfig::from(&settings));
+                privacy_guard.set_rules(ExclusionRules::from(&settings));
+                retention.set_config(RetentionConfig::from(&settings));
+                archiver.set_config(ArchiveConfig::from(&settings));
+
+                // 刚开启知识库模式时立即为已有笔记补齐链接
+                let was_enabled = knowledge_base.config().enabled;
+                knowledge_base.set_config(KnowledgeBaseConfig::from(&settings));
+                if settings.knowledge_base_mode && !was_enabled {
+                    let knowledge_base = Arc::clone(&knowledge_base);
+                    tokio::task::spawn_blocking(move || {
+                        if let Err(e) = knowledge_base.rebuild() {
+                            log::error!("Failed to rebuild knowledge base: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+
+        CaptureScheduler::watch_settings(Arc::clone(&self.scheduler), self.settings.subscribe());
+        self.pipeline.watch_settings(self.settings.subscribe());
+        self.idle_watcher.watch_settings(self.settings.subscribe());
+    }
+}
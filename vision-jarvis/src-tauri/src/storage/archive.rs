@@ -0,0 +1,353 @@
+/// 关键帧归档
+///
+/// 已分析且超过 `archive_after_days` 天的录制分段抽取少量 JPEG 关键帧存于视频旁
+/// （`<文件名>_kf00.jpg` …），写入 `recordings.keyframes` 与 `archived_at` 后删除原视频。
+/// 分析结果与时间线不受影响，磁盘占用降到原来的几十分之一。
+/// 关键帧与视频一样受静态加密保护；需要系统安装 ffmpeg
+
+use anyhow::Result;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+
+use crate::ai::frame_extractor::{self, FrameExtractConfig};
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use crate::settings::AppSettings;
+
+/// 后台检查间隔
+const CHECK_INTERVAL_SECS: u64 = 3600;
+/// 每次最多归档的分段数，避免一次占用 ffmpeg 过久
+const BATCH_SIZE: usize = 50;
+/// 关键帧宽度与 JPEG 质量（ffmpeg -q:v，2-31，越小越清晰）
+const KEYFRAME_WIDTH: u32 = 960;
+const KEYFRAME_QUALITY: u32 = 5;
+/// 每段关键帧数上限
+pub const MAX_KEYFRAMES: u16 = 20;
+
+/// 归档配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveConfig {
+    /// 录制多少天后归档，0 表示不归档
+    pub after_days: u32,
+    /// 每段保留的关键帧数
+    pub keyframes: u16,
+}
+
+impl From<&AppSettings> for ArchiveConfig {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            after_days: settings.archive_after_days,
+            keyframes: settings.archive_keyframe_count,
+        }
+    }
+}
+
+/// 校验归档设置
+pub fn validate_config(config: &ArchiveConfig) -> AppResult<()> {
+    if config.keyframes == 0 || config.keyframes > MAX_KEYFRAMES {
+        return Err(AppError::validation(
+            29,
+            format!("每段关键帧数必须在 1-{} 之间", MAX_KEYFRAMES),
+        ));
+    }
+    if config.after_days > super::retention::MAX_KEEP_DAYS {
+        return Err(AppError::validation(
+            29,
+            format!("归档天数不能超过 {} 天", super::retention::MAX_KEEP_DAYS),
+        ));
+    }
+    Ok(())
+}
+
+/// 归档报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    /// 已归档的分段数
+    pub archived: usize,
+    /// 抽帧或写入失败的分段数（下次重试）
+    pub failed: usize,
+    /// 视频文件已不存在、直接标记为已清理的分段数
+    pub missing: usize,
+    /// 删除视频释放的空间
+    pub freed_bytes: u64,
+    /// 新写入的关键帧占用
+    pub keyframe_bytes: u64,
+}
+
+/// 关键帧归档器
+pub struct KeyframeArchiver {
+    db: Arc<Database>,
+    config: RwLock<ArchiveConfig>,
+}
+
+impl KeyframeArchiver {
+    pub fn new(db: Arc<Database>, config: ArchiveConfig) -> Self {
+        Self {
+            db,
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn config(&self) -> ArchiveConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// 设置变更后更新配置，下次执行生效
+    pub fn set_config(&self, config: ArchiveConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 归档一批到期的分段
+    pub fn run(&self) -> Result<ArchiveReport> {
+        let config = self.config();
+        let mut report = ArchiveReport::default();
+        if config.after_days == 0 {
+            return Ok(report);
+        }
+        if self.db.vault().is_locked() {
+            return Err(AppError::storage(10, "数据已加密，请先解锁").into());
+        }
+
+        let cutoff = chrono::Utc::now().timestamp() - i64::from(config.after_days) * 86400;
+        let mut pending = Vec::new();
+        for (id, path) in self.candidates(cutoff)? {
+            if path.exists() {
+                pending.push((id, path));
+            } else {
+                self.mark_missing(&id)?;
+                report.missing += 1;
+            }
+        }
+        if pending.is_empty() {
+            return Ok(report);
+        }
+        // 没有 ffmpeg 时整批都会失败，提前报错
+        frame_extractor::find_ffmpeg()?;
+
+        let extract_config = FrameExtractConfig {
+            num_frames: usize::from(config.keyframes),
+            scale_width: KEYFRAME_WIDTH,
+            jpeg_quality: KEYFRAME_QUALITY,
+        };
+        for (id, path) in pending {
+            let result = self.db.vault()
+                .read_file(&path)
+                .and_then(|video| Ok(frame_extractor::extract_frame_bytes(&video, &extract_config)?))
+                .and_then(|frames| self.store_keyframes(&id, &path, &frames));
+            match result {
+                Ok((freed, written)) => {
+                    report.archived += 1;
+                    report.freed_bytes += freed;
+                    report.keyframe_bytes += written;
+                }
+                Err(e) => {
+                    warn!("[Archive] 归档失败 {}: {}", path.display(), e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// 启动后台定时归档
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let archiver = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+            loop {
+                ticker.tick().await;
+                let archiver = Arc::clone(&archiver);
+                match tokio::task::spawn_blocking(move || archiver.run()).await {
+                    Ok(Ok(report)) if report.archived + report.failed > 0 => info!(
+                        "[Archive] 归档 {} 段（失败 {}），释放 {} MB",
+                        report.archived,
+                        report.failed,
+                        report.freed_bytes.saturating_sub(report.keyframe_bytes) / (1024 * 1024),
+                    ),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => warn!("[Archive] 本轮跳过: {}", e),
+                    Err(e) => error!("[Archive] 归档任务异常: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 已分析、早于 cutoff、尚未归档且视频未被清理的分段（从旧到新）
+    fn candidates(&self, cutoff: i64) -> Result<Vec<(String, PathBuf)>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, path FROM recordings
+                 WHERE analyzed = 1 AND archived_at IS NULL AND purged_at IS NULL AND start_time < ?1
+                 ORDER BY start_time
+                 LIMIT ?2",
+            )?;
+            let rows = stmt
+                .query_map(rusqlite::params![cutoff, BATCH_SIZE as i64], |row| {
+                    Ok((row.get::<_, String>(0)?, PathBuf::from(row.get::<_, String>(1)?)))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// 写入关键帧、更新录制记录后删除原视频；返回（释放字节数，关键帧字节数）
+    fn store_keyframes(&self, id: &str, video_path: &Path, frames: &[Vec<u8>]) -> Result<(u64, u64)> {
+        let stem = video_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| id.to_string());
+
+        let mut paths = Vec::with_capacity(frames.len());
+        let mut written = 0;
+        for (i, frame) in frames.iter().enumerate() {
+            let path = video_path.with_file_name(format!("{}_kf{:02}.jpg", stem, i));
+            self.db.vault().write_file(&path, frame)?;
+            written += frame.len() as u64;
+            paths.push(path.to_string_lossy().to_string());
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let keyframes = serde_json::to_string(&paths)?;
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE recordings SET archived_at = ?1, keyframes = ?2 WHERE id = ?3",
+                rusqlite::params![now, keyframes, id],
+            )?;
+            Ok(())
+        })?;
+
+        let freed = std::fs::metadata(video_path).map(|m| m.len()).unwrap_or(0);
+        if let Err(e) = std::fs::remove_file(video_path) {
+            // 记录已指向关键帧，残留的视频交给保留策略清理
+            warn!("[Archive] 删除原视频失败 {}: {}", video_path.display(), e);
+            return Ok((0, written));
+        }
+        Ok((freed, written))
+    }
+
+    /// 视频已被手动删除：按保留策略的方式标记为已清理，不再重试
+    fn mark_missing(&self, id: &str) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE recordings SET purged_at = ?1 WHERE id = ?2 AND purged_at IS NULL",
+                rusqlite::params![now, id],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+/// 读取分段的关键帧（按需解密）；未归档时返回空列表
+pub fn load_keyframes(db: &Database, recording_id: &str) -> Result<Vec<Vec<u8>>> {
+    let keyframes: Option<String> = db.with_connection(|conn| {
+        Ok(conn.query_row(
+            "SELECT keyframes FROM recordings WHERE id = ?1",
+            [recording_id],
+            |row| row.get(0),
+        )?)
+    })?;
+
+    let paths: Vec<String> = match keyframes {
+        Some(json) => serde_json::from_str(&json)?,
+        None => return Ok(Vec::new()),
+    };
+    paths.iter().map(|p| db.vault().read_file(Path::new(p))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cipher::{self, DataKey};
+
+    const DAY: i64 = 86400;
+
+    fn insert_recording(db: &Database, id: &str, path: &Path, age_days: i64, analyzed: bool) {
+        let start = chrono::Utc::now().timestamp() - age_days * DAY;
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO recordings (id, path, start_time, analyzed) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![id, path.to_string_lossy(), start, analyzed as i32],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn archiver(after_days: u32) -> (Arc<Database>, KeyframeArchiver) {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let archiver = KeyframeArchiver::new(Arc::clone(&db), ArchiveConfig { after_days, keyframes: 4 });
+        (db, archiver)
+    }
+
+    #[test]
+    fn test_candidates_only_old_analyzed_live_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, archiver) = archiver(3);
+        insert_recording(&db, "old", &dir.path().join("old.mp4"), 5, true);
+        insert_recording(&db, "older", &dir.path().join("older.mp4"), 9, true);
+        insert_recording(&db, "recent", &dir.path().join("recent.mp4"), 1, true);
+        insert_recording(&db, "pending", &dir.path().join("pending.mp4"), 5, false);
+        insert_recording(&db, "purged", &dir.path().join("purged.mp4"), 5, true);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE recordings SET purged_at = 1 WHERE id = 'purged'", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let cutoff = chrono::Utc::now().timestamp() - 3 * DAY;
+        let ids: Vec<String> = archiver.candidates(cutoff).unwrap().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["older", "old"]);
+    }
+
+    #[test]
+    fn test_store_keyframes_replaces_video() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("10-00-00_a.mp4");
+        std::fs::write(&video, vec![0u8; 1000]).unwrap();
+        let (db, archiver) = archiver(3);
+        db.vault().set(true, Some(DataKey::generate()));
+        insert_recording(&db, "a", &video, 5, true);
+
+        let frames = vec![b"jpeg-0".to_vec(), b"jpeg-1".to_vec()];
+        let (freed, written) = archiver.store_keyframes("a", &video, &frames).unwrap();
+        assert_eq!((freed, written), (1000, 12));
+        assert!(!video.exists());
+
+        // 关键帧随静态加密落盘为密文，读取时解密
+        let first = dir.path().join("10-00-00_a_kf00.jpg");
+        assert!(cipher::is_sealed(&std::fs::read(&first).unwrap()));
+        assert_eq!(load_keyframes(&db, "a").unwrap(), frames);
+
+        // 已归档的分段不再是候选
+        assert!(archiver.candidates(chrono::Utc::now().timestamp()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_video_marked_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, archiver) = archiver(3);
+        insert_recording(&db, "gone", &dir.path().join("gone.mp4"), 5, true);
+
+        let report = archiver.run().unwrap();
+        assert_eq!(report.missing, 1);
+        assert_eq!(report.archived, 0);
+        assert!(archiver.candidates(chrono::Utc::now().timestamp()).unwrap().is_empty());
+        assert!(load_keyframes(&db, "gone").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_and_validation() {
+        let (db, archiver) = archiver(0);
+        insert_recording(&db, "old", Path::new("/nonexistent/old.mp4"), 30, true);
+        assert_eq!(archiver.run().unwrap().missing, 0);
+
+        assert!(validate_config(&ArchiveConfig { after_days: 3, keyframes: 4 }).is_ok());
+        assert!(validate_config(&ArchiveConfig { after_days: 3, keyframes: 0 }).is_err());
+        assert!(validate_config(&ArchiveConfig { after_days: 3, keyframes: MAX_KEYFRAMES + 1 }).is_err());
+    }
+}
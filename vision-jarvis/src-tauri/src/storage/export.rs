@@ -0,0 +1,646 @@
+/// 记忆库导出与导入
+///
+/// 导出包是一个 zip 文件：`manifest.json` 记录格式版本、数据库 schema 版本与各表行数，
+/// `tables/<表名>.json` 为逐行的列名-值对象，`markdown/` 下是存储目录中的 Markdown 树。
+/// 导出时按需解密，导出包本身为明文，便于迁移到另一台机器；导入时按当前加密设置重新加密。
+/// 导入按主键合并：本地不存在的行与文件直接写入，内容相同的跳过，内容不同的记为冲突，
+/// 由 `ConflictPolicy` 决定保留本地还是以导入为准
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use log::info;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::crypto::Vault;
+use crate::db::{migrations, Database};
+use crate::error::{AppError, AppResult};
+
+/// 导出包格式标识与版本
+pub const FORMAT: &str = "vision-jarvis-export";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 导出的表（按导入顺序）
+pub const EXPORT_TABLES: &[&str] = &[
+    "activities",
+    "projects",
+    "habits",
+    "summaries",
+    "screenshot_analyses",
+    "notifications",
+];
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const TABLES_DIR: &str = "tables";
+const MARKDOWN_DIR: &str = "markdown";
+/// BLOB 列导出为 `{"$base64": "..."}`
+const BLOB_KEY: &str = "$base64";
+
+/// 导出包清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format: String,
+    pub format_version: u32,
+    /// 导出时的数据库 schema 版本
+    pub schema_version: i32,
+    pub app_version: String,
+    pub exported_at: i64,
+    /// 各表导出的行数
+    pub tables: BTreeMap<String, usize>,
+    pub markdown_files: usize,
+}
+
+/// 导入冲突的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 保留本地数据（默认）
+    #[default]
+    KeepLocal,
+    /// 以导入的数据覆盖本地
+    Overwrite,
+}
+
+/// 一处冲突：同一主键（或同一路径）的本地数据与导入数据不同
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportConflict {
+    /// 表名，Markdown 文件为 "markdown"
+    pub target: String,
+    /// 主键，Markdown 文件为相对路径
+    pub id: String,
+    /// 是否已用导入的数据覆盖
+    pub overwritten: bool,
+}
+
+/// 导入报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// 各表新增的行数
+    pub inserted: BTreeMap<String, usize>,
+    /// 内容相同而跳过的行数
+    pub unchanged: usize,
+    pub files_added: usize,
+    pub files_unchanged: usize,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+/// 导出记忆库到 dest（zip 文件）
+pub fn export_vault(db: &Database, storage_root: &Path, dest: &Path) -> AppResult<ExportManifest> {
+    ensure_unlocked(db)?;
+    let vault = db.vault();
+
+    let (schema_version, tables) = db
+        .with_connection(|conn| {
+            let version = migrations::get_schema_version(conn)?;
+            let tables = EXPORT_TABLES
+                .iter()
+                .map(|table| Ok((table.to_string(), read_rows(conn, vault, table)?)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((version, tables))
+        })
+        .map_err(|e| AppError::database(2, format!("读取导出数据失败: {}", e)))?;
+    let markdown = markdown_files(storage_root);
+
+    let file = std::fs::File::create(dest).map_err(archive_error)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest = ExportManifest {
+        format: FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        schema_version,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().timestamp(),
+        tables: tables.iter().map(|(name, rows)| (name.clone(), rows.len())).collect(),
+        markdown_files: markdown.len(),
+    };
+    zip.start_file(MANIFEST_ENTRY, options).map_err(archive_error)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?).map_err(archive_error)?;
+
+    for (name, rows) in &tables {
+        zip.start_file(format!("{}/{}.json", TABLES_DIR, name), options).map_err(archive_error)?;
+        zip.write_all(&serde_json::to_vec(rows)?).map_err(archive_error)?;
+    }
+
+    for path in &markdown {
+        let relative = path.strip_prefix(storage_root).unwrap_or(path);
+        let data = vault
+            .read_file(path)
+            .map_err(|e| AppError::io(4, format!("读取 {} 失败: {}", path.display(), e)))?;
+        let name = format!("{}/{}", MARKDOWN_DIR, relative.to_string_lossy().replace('\\', "/"));
+        zip.start_file(name, options).map_err(archive_error)?;
+        zip.write_all(&data).map_err(archive_error)?;
+    }
+    zip.finish().map_err(archive_error)?;
+
+    info!(
+        "[Export] 已导出 {} 行数据、{} 个 Markdown 文件到 {}",
+        manifest.tables.values().sum::<usize>(),
+        manifest.markdown_files,
+        dest.display()
+    );
+    Ok(manifest)
+}
+
+/// 读取导出包的清单（导入前预览）
+pub fn read_manifest(src: &Path) -> AppResult<ExportManifest> {
+    let mut archive = open_archive(src)?;
+    load_manifest(&mut archive)
+}
+
+/// 从 src 导入并与现有数据合并
+pub fn import_vault(
+    db: &Database,
+    storage_root: &Path,
+    src: &Path,
+    policy: ConflictPolicy,
+) -> AppResult<ImportReport> {
+    ensure_unlocked(db)?;
+    let mut archive = open_archive(src)?;
+    load_manifest(&mut archive)?;
+
+    let mut tables = Vec::new();
+    for table in EXPORT_TABLES {
+        let name = format!("{}/{}.json", TABLES_DIR, table);
+        let rows: Vec<Map<String, Value>> = match archive.by_name(&name) {
+            Ok(entry) => serde_json::from_reader(entry)
+                .map_err(|e| AppError::validation(30, format!("{} 格式错误: {}", name, e)))?,
+            Err(zip::result::ZipError::FileNotFound) => continue,
+            Err(e) => return Err(archive_error(e)),
+        };
+        tables.push((*table, rows));
+    }
+
+    let mut report = ImportReport::default();
+    let vault = db.vault();
+    db.with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for (table, rows) in &tables {
+            let inserted = merge_rows(&tx, vault, table, rows, policy, &mut report)?;
+            report.inserted.insert(table.to_string(), inserted);
+        }
+        tx.commit()?;
+        Ok(())
+    })
+    .map_err(|e| AppError::database(2, format!("导入数据失败: {}", e)))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(archive_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // enclosed_name 拒绝绝对路径与 `..`，防止写到存储目录之外
+        let Some(relative) = entry
+            .enclosed_name()
+            .and_then(|p| p.strip_prefix(MARKDOWN_DIR).ok().map(Path::to_path_buf))
+        else {
+            continue;
+        };
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).map_err(archive_error)?;
+        merge_file(vault, storage_root, &relative, &data, policy, &mut report)?;
+    }
+
+    info!(
+        "[Export] 导入完成: 新增 {} 行、{} 个文件，{} 处冲突",
+        report.inserted.values().sum::<usize>(),
+        report.files_added,
+        report.conflicts.len()
+    );
+    Ok(report)
+}
+
+fn ensure_unlocked(db: &Database) -> AppResult<()> {
+    if db.vault().is_locked() {
+        return Err(AppError::storage(10, "数据已加密，请先解锁"));
+    }
+    Ok(())
+}
+
+fn archive_error(e: impl std::fmt::Display) -> AppError {
+    AppError::storage(15, format!("读写导出包失败: {}", e))
+}
+
+fn open_archive(src: &Path) -> AppResult<zip::ZipArchive<std::fs::File>> {
+    let file = std::fs::File::open(src).map_err(archive_error)?;
+    zip::ZipArchive::new(file).map_err(archive_error)
+}
+
+fn load_manifest(archive: &mut zip::ZipArchive<std::fs::File>) -> AppResult<ExportManifest> {
+    let entry = archive
+        .by_name(MANIFEST_ENTRY)
+        .map_err(|_| AppError::validation(30, "不是 Vision-Jarvis 导出包：缺少 manifest.json"))?;
+    let manifest: ExportManifest = serde_json::from_reader(entry)
+        .map_err(|e| AppError::validation(30, format!("manifest.json 格式错误: {}", e)))?;
+    if manifest.format != FORMAT {
+        return Err(AppError::validation(30, "不是 Vision-Jarvis 导出包"));
+    }
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(AppError::validation(
+            30,
+            format!("导出包版本 {} 高于当前支持的 {}，请先升级应用", manifest.format_version, FORMAT_VERSION),
+        ));
+    }
+    Ok(manifest)
+}
+
+/// 表的列名与主键列
+fn table_columns(conn: &Connection, table: &str) -> anyhow::Result<(Vec<String>, String)> {
+    let mut stmt = conn.prepare("SELECT name, pk FROM pragma_table_info(?1) ORDER BY cid")?;
+    let columns = stmt
+        .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let pk = columns
+        .iter()
+        .find(|(_, pk)| *pk == 1)
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| anyhow::anyhow!("表 {} 没有主键", table))?;
+    Ok((columns.into_iter().map(|(name, _)| name).collect(), pk))
+}
+
+/// 该表中静态加密覆盖的列
+fn encrypted_columns(table: &str) -> Vec<&'static str> {
+    migrations::ENCRYPTED_COLUMNS
+        .iter()
+        .filter(|(t, _, _)| *t == table)
+        .map(|(_, _, column)| *column)
+        .collect()
+}
+
+/// 读出整张表（敏感列解密为明文）
+fn read_rows(conn: &Connection, vault: &Vault, table: &str) -> anyhow::Result<Vec<Map<String, Value>>> {
+    let (_, pk) = table_columns(conn, table)?;
+    query_rows(conn, vault, table, &format!("SELECT * FROM {table} ORDER BY {pk}"), [])
+}
+
+fn query_rows<P: rusqlite::Params>(
+    conn: &Connection,
+    vault: &Vault,
+    table: &str,
+    sql: &str,
+    params: P,
+) -> anyhow::Result<Vec<Map<String, Value>>> {
+    let encrypted = encrypted_columns(table);
+    let mut stmt = conn.prepare(sql)?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query(params)?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut object = Map::new();
+        for (i, name) in names.iter().enumerate() {
+            let mut value = to_json(row.get_ref(i)?);
+            if let (true, Value::String(text)) = (encrypted.contains(&name.as_str()), &value) {
+                value = Value::String(vault.decrypt_text(text.clone())?);
+            }
+            object.insert(name.clone(), value);
+        }
+        result.push(object);
+    }
+    Ok(result)
+}
+
+/// 合并一张表，返回新增行数
+fn merge_rows(
+    conn: &Connection,
+    vault: &Vault,
+    table: &str,
+    rows: &[Map<String, Value>],
+    policy: ConflictPolicy,
+    report: &mut ImportReport,
+) -> anyhow::Result<usize> {
+    let (columns, pk) = table_columns(conn, table)?;
+    let encrypted = encrypted_columns(table);
+    let mut inserted = 0;
+
+    for row in rows {
+        let Some(id) = row.get(&pk).and_then(Value::as_str) else {
+            continue;
+        };
+        // 只导入本地 schema 中存在的列；导出包较旧时缺少的列使用默认值
+        let fields: Vec<(&String, &Value)> = row
+            .iter()
+            .filter(|(name, _)| columns.contains(name))
+            .collect();
+
+        let local = query_rows(
+            conn,
+            vault,
+            table,
+            &format!("SELECT * FROM {table} WHERE {pk} = ?1"),
+            [id],
+        )?
+        .pop();
+
+        let overwrite = match &local {
+            None => false,
+            Some(local) if fields.iter().all(|(name, value)| local.get(*name) == Some(*value)) => {
+                report.unchanged += 1;
+                continue;
+            }
+            Some(_) => {
+                let overwrite = policy == ConflictPolicy::Overwrite;
+                report.conflicts.push(ImportConflict {
+                    target: table.to_string(),
+                    id: id.to_string(),
+                    overwritten: overwrite,
+                });
+                if !overwrite {
+                    continue;
+                }
+                true
+            }
+        };
+
+        let values = fields
+            .iter()
+            .map(|(name, value)| {
+                let value = from_json(value);
+                match value {
+                    SqlValue::Text(text) if encrypted.contains(&name.as_str()) => {
+                        Ok(SqlValue::Text(vault.encrypt_text(&text)?))
+                    }
+                    other => Ok(other),
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+
+        if overwrite {
+            let assignments: Vec<String> = names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!("{} = ?{}", name, i + 1))
+                .collect();
+            let mut params = values;
+            params.push(SqlValue::Text(id.to_string()));
+            conn.execute(
+                &format!("UPDATE {table} SET {} WHERE {pk} = ?{}", assignments.join(", "), params.len()),
+                rusqlite::params_from_iter(params),
+            )?;
+        } else {
+            let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+            conn.execute(
+                &format!("INSERT INTO {table} ({}) VALUES ({})", names.join(", "), placeholders.join(", ")),
+                rusqlite::params_from_iter(values),
+            )?;
+            inserted += 1;
+        }
+    }
+    Ok(inserted)
+}
+
+/// 合并一个 Markdown 文件
+fn merge_file(
+    vault: &Vault,
+    storage_root: &Path,
+    relative: &Path,
+    data: &[u8],
+    policy: ConflictPolicy,
+    report: &mut ImportReport,
+) -> AppResult<()> {
+    let dest = storage_root.join(relative);
+    let io_error = |e: anyhow::Error| AppError::io(4, format!("写入 {} 失败: {}", dest.display(), e));
+
+    let existing = std::fs::metadata(&dest).is_ok();
+    if existing {
+        let local = vault.read_file(&dest).map_err(io_error)?;
+        if local == data {
+            report.files_unchanged += 1;
+            return Ok(());
+        }
+        let overwrite = policy == ConflictPolicy::Overwrite;
+        report.conflicts.push(ImportConflict {
+            target: MARKDOWN_DIR.to_string(),
+            id: relative.to_string_lossy().replace('\\', "/"),
+            overwritten: overwrite,
+        });
+        if !overwrite {
+            return Ok(());
+        }
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(e.into()))?;
+    }
+    vault.write_file(&dest, data).map_err(io_error)?;
+    if !existing {
+        report.files_added += 1;
+    }
+    Ok(())
+}
+
+/// 存储目录下全部 Markdown 文件
+fn markdown_files(storage_root: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => walk(&path, files),
+                Ok(t) if t.is_file() && path.extension().is_some_and(|e| e == "md") => files.push(path),
+                _ => {}
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(storage_root, &mut files);
+    files.sort();
+    files
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => {
+            let mut object = Map::new();
+            object.insert(BLOB_KEY.to_string(), Value::String(BASE64.encode(b)));
+            Value::Object(object)
+        }
+    }
+}
+
+fn from_json(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Object(object) => match object.get(BLOB_KEY).and_then(Value::as_str) {
+            Some(encoded) if object.len() == 1 => match BASE64.decode(encoded) {
+                Ok(bytes) => SqlValue::Blob(bytes),
+                Err(_) => SqlValue::Text(value.to_string()),
+            },
+            _ => SqlValue::Text(value.to_string()),
+        },
+        Value::Array(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::cipher::{self, DataKey};
+
+    fn seed(db: &Database, root: &Path) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO activities (id, title, start_time, end_time, duration_minutes, application,
+                    category, screenshot_ids, tags, markdown_path, created_at)
+                 VALUES ('act-1', '写代码', 100, 200, 2, 'Code', 'work', '[]', '[\"rust\"]',
+                    'activities/2026-10-16/act-1.md', 100)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO projects (id, title, start_date, last_activity_date, created_at, markdown_path)
+                 VALUES ('proj-1', 'Vision-Jarvis', 100, 200, 100, 'projects/vision-jarvis.md')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO habits (id, pattern_name, pattern_type, confidence, frequency,
+                    last_occurrence, occurrence_count, markdown_path, created_at, updated_at)
+                 VALUES ('habit-1', '早上写代码', 'time_based', 0.8, 'daily', 200, 5,
+                    'habits/morning.md', 100, 200)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO summaries (id, summary_type, date_start, date_end, content, markdown_path, created_at)
+                 VALUES ('sum-1', 'daily', '2026-10-16', '2026-10-16', '今天写了导出', 'daily/2026-10-16.md', 100)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO screenshot_analyses (screenshot_id, application, activity_type,
+                    activity_description, ocr_text, analysis_json, analyzed_at)
+                 VALUES ('rec-1', 'Code', 'work', '写代码', ?1, '{}', 150)",
+                [db.vault().encrypt_text("fn main()")?],
+            )?;
+            conn.execute(
+                "INSERT INTO notifications (id, type, priority, title, message, created_at)
+                 VALUES ('n-1', '\"Custom\"', 1, '喝水', '该喝水了', 120)",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let md = root.join("activities/2026-10-16/act-1.md");
+        std::fs::create_dir_all(md.parent().unwrap()).unwrap();
+        db.vault().write_file(&md, "# 写代码".as_bytes()).unwrap();
+    }
+
+    fn dump(db: &Database) -> Vec<(String, Vec<Map<String, Value>>)> {
+        db.with_connection(|conn| {
+            EXPORT_TABLES
+                .iter()
+                .map(|t| Ok((t.to_string(), read_rows(conn, db.vault(), t)?)))
+                .collect()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let src_db = Database::open_in_memory().unwrap();
+        let src_root = tempfile::tempdir().unwrap();
+        // 源库开启了静态加密：导出包中为明文
+        src_db.vault().set(true, Some(DataKey::generate()));
+        seed(&src_db, src_root.path());
+
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("vault.zip");
+        let manifest = export_vault(&src_db, src_root.path(), &archive).unwrap();
+        assert_eq!(manifest.tables.values().sum::<usize>(), EXPORT_TABLES.len());
+        assert_eq!(manifest.markdown_files, 1);
+        assert_eq!(read_manifest(&archive).unwrap(), manifest);
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&archive).unwrap()).unwrap();
+        let mut analyses = String::new();
+        zip.by_name("tables/screenshot_analyses.json").unwrap().read_to_string(&mut analyses).unwrap();
+        assert!(analyses.contains("fn main()"));
+        assert!(cipher::is_sealed_text(&src_db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT ocr_text FROM screenshot_analyses", [], |r| r.get::<_, String>(0))?)
+        }).unwrap()));
+
+        let dst_db = Database::open_in_memory().unwrap();
+        let dst_root = tempfile::tempdir().unwrap();
+        let report = import_vault(&dst_db, dst_root.path(), &archive, ConflictPolicy::KeepLocal).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.inserted.values().sum::<usize>(), EXPORT_TABLES.len());
+        assert_eq!(report.files_added, 1);
+        assert_eq!(dump(&dst_db), dump(&src_db));
+        assert_eq!(
+            std::fs::read_to_string(dst_root.path().join("activities/2026-10-16/act-1.md")).unwrap(),
+            "# 写代码"
+        );
+
+        // 再次导入：全部相同，不产生冲突
+        let again = import_vault(&dst_db, dst_root.path(), &archive, ConflictPolicy::KeepLocal).unwrap();
+        assert_eq!(again.unchanged, EXPORT_TABLES.len());
+        assert_eq!(again.files_unchanged, 1);
+        assert!(again.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_import_reports_conflicts() {
+        let src_db = Database::open_in_memory().unwrap();
+        let src_root = tempfile::tempdir().unwrap();
+        seed(&src_db, src_root.path());
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("vault.zip");
+        export_vault(&src_db, src_root.path(), &archive).unwrap();
+
+        let dst_db = Database::open_in_memory().unwrap();
+        let dst_root = tempfile::tempdir().unwrap();
+        import_vault(&dst_db, dst_root.path(), &archive, ConflictPolicy::KeepLocal).unwrap();
+        dst_db.with_connection(|conn| {
+            conn.execute("UPDATE projects SET title = '本地改名' WHERE id = 'proj-1'", [])?;
+            Ok(())
+        }).unwrap();
+        let md = dst_root.path().join("activities/2026-10-16/act-1.md");
+        std::fs::write(&md, "# 本地修改").unwrap();
+
+        let title = |db: &Database| -> String {
+            db.with_connection(|conn| {
+                Ok(conn.query_row("SELECT title FROM projects WHERE id = 'proj-1'", [], |r| r.get(0))?)
+            }).unwrap()
+        };
+
+        let kept = import_vault(&dst_db, dst_root.path(), &archive, ConflictPolicy::KeepLocal).unwrap();
+        assert_eq!(kept.conflicts, vec![
+            ImportConflict { target: "projects".into(), id: "proj-1".into(), overwritten: false },
+            ImportConflict { target: "markdown".into(), id: "activities/2026-10-16/act-1.md".into(), overwritten: false },
+        ]);
+        assert_eq!(title(&dst_db), "本地改名");
+        assert_eq!(std::fs::read_to_string(&md).unwrap(), "# 本地修改");
+
+        let overwritten = import_vault(&dst_db, dst_root.path(), &archive, ConflictPolicy::Overwrite).unwrap();
+        assert!(overwritten.conflicts.iter().all(|c| c.overwritten));
+        assert_eq!(title(&dst_db), "Vision-Jarvis");
+        assert_eq!(std::fs::read_to_string(&md).unwrap(), "# 写代码");
+    }
+
+    #[test]
+    fn test_rejects_foreign_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("other.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        zip.start_file("readme.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"hello").unwrap();
+        zip.finish().unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let result = import_vault(&db, dir.path(), &path, ConflictPolicy::KeepLocal);
+        assert!(matches!(result, Err(AppError::Validation(30, _))));
+    }
+}
@@ -0,0 +1,526 @@
+/// 保留策略引擎
+///
+/// 按目录与扩展名的保留天数删除过期文件；总占用超过 `storage_limit_mb` 时，
+/// 在可淘汰的文件中按修改时间从旧到新删除，直到回到上限以内。
+/// 录制视频被删除后把 `recordings.purged_at` 置为删除时间，行本身与分析结果保留。
+/// 尚未分析的录制（补跑台账也没有放弃）既不过期也不被淘汰，等分析完成后再按策略处理。
+/// 后台每小时执行一次，也可通过命令预览（dry run）或立即执行
+
+use anyhow::Result;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+
+use super::{FileInfo, FolderType, StorageManager};
+use crate::db::Database;
+use crate::error::{AppError, AppResult};
+use crate::memory::job_ledger::JobLedger;
+use crate::settings::AppSettings;
+
+/// 后台检查间隔
+const CHECK_INTERVAL_SECS: u64 = 3600;
+/// 最近修改过的文件不做容量淘汰（可能是正在录制或写入的文件）
+const MIN_EVICT_AGE_SECS: i64 = 600;
+/// 保留天数上限
+pub const MAX_KEEP_DAYS: u32 = 3650;
+
+/// 单条保留策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub folder: FolderType,
+    /// 只作用于这些扩展名（不区分大小写，空表示目录下全部文件）
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// 保留天数，0 表示永久保留
+    pub keep_days: u32,
+    /// 超出容量上限时是否可以提前删除
+    #[serde(default)]
+    pub evictable: bool,
+}
+
+impl RetentionPolicy {
+    fn new(folder: FolderType, extensions: &[&str], keep_days: u32, evictable: bool) -> Self {
+        Self {
+            folder,
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            keep_days,
+            evictable,
+        }
+    }
+
+    fn matches_extension(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        ext.is_some_and(|ext| self.extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)))
+    }
+}
+
+/// 默认策略：原始录制保留 7 天，分析 JSON 永久保留；遗留截图、临时文件与日志按天数清理。
+/// Markdown 记忆目录不在其中，永不自动删除
+pub fn default_policies() -> Vec<RetentionPolicy> {
+    vec![
+        RetentionPolicy::new(FolderType::Recordings, &["mp4"], 7, true),
+        RetentionPolicy::new(FolderType::Recordings, &["json"], 0, false),
+        RetentionPolicy::new(FolderType::Screenshots, &[], 7, true),
+        RetentionPolicy::new(FolderType::Temp, &[], 1, true),
+        RetentionPolicy::new(FolderType::Logs, &[], 30, true),
+    ]
+}
+
+/// 校验保留策略
+pub fn validate_policies(policies: &[RetentionPolicy]) -> AppResult<()> {
+    for policy in policies {
+        if policy.keep_days > MAX_KEEP_DAYS {
+            return Err(AppError::validation(
+                28,
+                format!("保留天数不能超过 {} 天", MAX_KEEP_DAYS),
+            ));
+        }
+        if policy.extensions.iter().any(|e| e.trim().is_empty() || e.contains('.')) {
+            return Err(AppError::validation(28, "扩展名不能为空且不含点号（如 mp4）"));
+        }
+    }
+    Ok(())
+}
+
+/// 引擎配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionConfig {
+    pub storage_root: PathBuf,
+    pub limit_bytes: u64,
+    /// 按顺序匹配，文件采用第一条命中的策略
+    pub policies: Vec<RetentionPolicy>,
+}
+
+impl From<&AppSettings> for RetentionConfig {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            storage_root: PathBuf::from(&settings.storage_path),
+            limit_bytes: settings.storage_limit_mb.saturating_mul(1024 * 1024),
+            policies: settings.retention_policies.clone(),
+        }
+    }
+}
+
+/// 删除原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PurgeReason {
+    /// 超过保留天数
+    Expired { keep_days: u32 },
+    /// 超出容量上限被淘汰
+    Quota,
+}
+
+/// 将被（或已被）删除的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeItem {
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified_at: i64,
+    pub reason: PurgeReason,
+}
+
+/// 清理报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// 预览模式：未删除任何文件
+    pub dry_run: bool,
+    /// 清理前的总占用
+    pub usage_bytes: u64,
+    pub limit_bytes: u64,
+    pub items: Vec<PurgeItem>,
+    /// 释放的空间（预览时为预计值）
+    pub freed_bytes: u64,
+    /// 标记为已清理的录制记录数（预览时为将要标记的数量）
+    pub recordings_purged: usize,
+    /// 清理后仍超出上限（没有更多可淘汰的文件）
+    pub over_limit: bool,
+    /// 因尚未分析而暂不删除的录制文件数
+    #[serde(default)]
+    pub awaiting_analysis: usize,
+    /// 删除失败的文件数
+    pub failed: usize,
+}
+
+/// 保留策略引擎
+pub struct RetentionEngine {
+    db: Arc<Database>,
+    config: RwLock<RetentionConfig>,
+}
+
+impl RetentionEngine {
+    pub fn new(db: Arc<Database>, config: RetentionConfig) -> Self {
+        Self {
+            db,
+            config: RwLock::new(config),
+        }
+    }
+
+    pub fn config(&self) -> RetentionConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// 设置变更后更新配置，下次执行生效
+    pub fn set_config(&self, config: RetentionConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 计算需要删除的文件，不做任何修改
+    pub fn plan(&self, now: SystemTime) -> Result<RetentionReport> {
+        let config = self.config();
+        let now = now.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        let manager = StorageManager::new(config.storage_root.clone())?;
+        let usage_bytes = manager.get_storage_info()?.total_used_bytes;
+        let awaiting = JobLedger::new(Arc::clone(&self.db)).awaiting_analysis_paths()?;
+
+        let mut items = Vec::new();
+        let mut evictable: Vec<FileInfo> = Vec::new();
+        let mut awaiting_analysis = 0;
+        for (folder, files) in Self::scan(&manager, &config.policies)? {
+            for file in files {
+                if awaiting.contains(&file.path) {
+                    awaiting_analysis += 1;
+                    continue;
+                }
+                let path = PathBuf::from(&file.path);
+                let Some(policy) = config.policies.iter()
+                    .find(|p| p.folder == folder && p.matches_extension(&path))
+                else {
+                    continue;
+                };
+
+                let expired = policy.keep_days > 0
+                    && file.modified_at < now - i64::from(policy.keep_days) * 86400;
+                if expired {
+                    items.push(PurgeItem {
+                        path: file.path,
+                        size_bytes: file.size_bytes,
+                        modified_at: file.modified_at,
+                        reason: PurgeReason::Expired { keep_days: policy.keep_days },
+                    });
+                } else if policy.evictable && file.modified_at <= now - MIN_EVICT_AGE_SECS {
+                    evictable.push(file);
+                }
+            }
+        }
+
+        // 先算过期删除后的占用，仍超出上限时从最旧的可淘汰文件开始删
+        let mut remaining = usage_bytes
+            .saturating_sub(items.iter().map(|i| i.size_bytes).sum());
+        evictable.sort_by_key(|f| f.modified_at);
+        for file in evictable {
+            if remaining <= config.limit_bytes {
+                break;
+            }
+            remaining = remaining.saturating_sub(file.size_bytes);
+            items.push(PurgeItem {
+                path: file.path,
+                size_bytes: file.size_bytes,
+                modified_at: file.modified_at,
+                reason: PurgeReason::Quota,
+            });
+        }
+
+        let paths: Vec<&str> = items.iter().map(|i| i.path.as_str()).collect();
+        Ok(RetentionReport {
+            dry_run: true,
+            usage_bytes,
+            limit_bytes: config.limit_bytes,
+            freed_bytes: items.iter().map(|i| i.size_bytes).sum(),
+            recordings_purged: self.count_live_recordings(&paths)?,
+            over_limit: remaining > config.limit_bytes,
+            awaiting_analysis,
+            items,
+            failed: 0,
+        })
+    }
+
+    /// 执行一次清理；dry_run 时只返回计划
+    pub fn run(&self, dry_run: bool) -> Result<RetentionReport> {
+        let mut report = self.plan(SystemTime::now())?;
+        if dry_run {
+            return Ok(report);
+        }
+        report.dry_run = false;
+
+        let root = self.config().storage_root;
+        let mut deleted = Vec::new();
+        let mut freed = 0;
+        for item in &report.items {
+            let path = Path::new(&item.path);
+            match std::fs::remove_file(path) {
+                Ok(()) => freed += item.size_bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    warn!("[Retention] 删除失败 {}: {}", item.path, e);
+                    report.failed += 1;
+                    continue;
+                }
+            }
+            remove_empty_parents(path, &root);
+            deleted.push(item.path.as_str());
+        }
+
+        report.freed_bytes = freed;
+        report.recordings_purged = self.mark_purged(&deleted)?;
+        report.over_limit = report.usage_bytes.saturating_sub(freed) > report.limit_bytes;
+        Ok(report)
+    }
+
+    /// 启动后台定时清理
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let engine = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+            loop {
+                ticker.tick().await;
+                let engine = Arc::clone(&engine);
+                match tokio::task::spawn_blocking(move || engine.run(false)).await {
+                    Ok(Ok(report)) if !report.items.is_empty() => info!(
+                        "[Retention] 清理 {} 个文件，释放 {} MB，标记 {} 条录制",
+                        report.items.len() - report.failed,
+                        report.freed_bytes / (1024 * 1024),
+                        report.recordings_purged,
+                    ),
+                    Ok(Ok(report)) if report.over_limit => {
+                        warn!("[Retention] 已无可淘汰的文件，存储仍超出上限")
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => error!("[Retention] 清理失败: {}", e),
+                    Err(e) => error!("[Retention] 清理任务异常: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 列出策略涉及的各目录中的文件
+    fn scan(
+        manager: &StorageManager,
+        policies: &[RetentionPolicy],
+    ) -> Result<Vec<(FolderType, Vec<FileInfo>)>> {
+        let mut folders: Vec<FolderType> = Vec::new();
+        for policy in policies {
+            if !folders.contains(&policy.folder) {
+                folders.push(policy.folder.clone());
+            }
+        }
+        folders.into_iter()
+            .map(|folder| {
+                let files = manager.list_files(&folder, None)?;
+                Ok((folder, files))
+            })
+            .collect()
+    }
+
+    /// 这些路径中尚未标记清理的录制记录数
+    fn count_live_recordings(&self, paths: &[&str]) -> Result<usize> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT COUNT(*) FROM recordings WHERE path = ?1 AND purged_at IS NULL",
+            )?;
+            let mut count = 0;
+            for path in paths {
+                count += stmt.query_row([path], |row| row.get::<_, i64>(0))? as usize;
+            }
+            Ok(count)
+        })
+    }
+
+    /// 把已删除视频对应的录制记录标记为已清理
+    fn mark_purged(&self, paths: &[&str]) -> Result<usize> {
+        let now = chrono::Utc::now().timestamp();
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut count = 0;
+            {
+                let mut stmt = tx.prepare(
+                    "UPDATE recordings SET purged_at = ?1 WHERE path = ?2 AND purged_at IS NULL",
+                )?;
+                for path in paths {
+                    count += stmt.execute(rusqlite::params![now, path])?;
+                }
+            }
+            tx.commit()?;
+            Ok(count)
+        })
+    }
+}
+
+/// 删除文件后清理空的上级目录（不越过存储根目录下的一级文件夹）
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current.parent() == Some(root) || !current.starts_with(root) {
+            break;
+        }
+        if std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration as StdDuration;
+
+    const DAY: u64 = 86400;
+
+    fn write_file(path: &Path, size: usize, age_secs: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; size]).unwrap();
+        let modified = SystemTime::now() - StdDuration::from_secs(age_secs);
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    fn insert_recording(db: &Database, id: &str, path: &Path) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO recordings (id, path, start_time, analyzed) VALUES (?1, ?2, 0, 1)",
+                rusqlite::params![id, path.to_string_lossy()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn purged_at(db: &Database, id: &str) -> Option<i64> {
+        db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT purged_at FROM recordings WHERE id = ?1", [id], |row| row.get(0))?)
+        })
+        .unwrap()
+    }
+
+    fn engine(root: &Path, limit_bytes: u64) -> (Arc<Database>, RetentionEngine) {
+        let db = Arc::new(Database::open_in_memory().unwrap());
+        let engine = RetentionEngine::new(Arc::clone(&db), RetentionConfig {
+            storage_root: root.to_path_buf(),
+            limit_bytes,
+            policies: default_policies(),
+        });
+        (db, engine)
+    }
+
+    #[test]
+    fn test_expired_recordings_are_purged_and_analyses_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let day_dir = dir.path().join("recordings/20260101/0_00-12_00");
+        let old_video = day_dir.join("10-00-00_old.mp4");
+        let old_json = day_dir.join("10-00-00_old.json");
+        let new_video = dir.path().join("recordings/20260110/0_00-12_00/10-00-00_new.mp4");
+        write_file(&old_video, 100, 8 * DAY);
+        write_file(&old_json, 10, 8 * DAY);
+        write_file(&new_video, 100, DAY);
+
+        let (db, engine) = engine(dir.path(), u64::MAX);
+        insert_recording(&db, "old", &old_video);
+        insert_recording(&db, "new", &new_video);
+
+        // 预览不改动任何东西
+        let preview = engine.run(true).unwrap();
+        assert!(preview.dry_run);
+        assert_eq!(preview.items.len(), 1);
+        assert_eq!(preview.items[0].reason, PurgeReason::Expired { keep_days: 7 });
+        assert_eq!(preview.recordings_purged, 1);
+        assert!(old_video.exists());
+        assert!(purged_at(&db, "old").is_none());
+
+        let report = engine.run(false).unwrap();
+        assert_eq!(report.freed_bytes, 100);
+        assert_eq!(report.recordings_purged, 1);
+        assert!(!old_video.exists());
+        assert!(old_json.exists());
+        assert!(new_video.exists());
+        assert!(purged_at(&db, "old").is_some());
+        assert!(purged_at(&db, "new").is_none());
+
+        // 再次执行不会重复标记
+        assert_eq!(engine.run(false).unwrap().recordings_purged, 0);
+    }
+
+    #[test]
+    fn test_quota_evicts_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let rec = dir.path().join("recordings/20260110/12_00-24_00");
+        let a = rec.join("a.mp4");
+        let b = rec.join("b.mp4");
+        let c = rec.join("c.mp4");
+        let analysis = rec.join("a.json");
+        write_file(&a, 400, 3 * DAY);
+        write_file(&b, 400, 2 * DAY);
+        write_file(&c, 400, 60);
+        write_file(&analysis, 50, 3 * DAY);
+
+        // 1250 字节占用，上限 900：只需淘汰最旧的 a；c 太新不参与淘汰，JSON 不可淘汰
+        let (_db, engine) = engine(dir.path(), 900);
+        let report = engine.run(false).unwrap();
+        assert_eq!(report.usage_bytes, 1250);
+        assert_eq!(report.items.len(), 1);
+        assert_eq!(report.items[0].reason, PurgeReason::Quota);
+        assert!(!a.exists());
+        assert!(b.exists() && c.exists() && analysis.exists());
+        assert!(!report.over_limit);
+
+        // 上限过低：淘汰完可淘汰的文件后仍超出
+        engine.set_config(RetentionConfig { limit_bytes: 10, ..engine.config() });
+        let report = engine.run(false).unwrap();
+        assert!(!b.exists());
+        assert!(c.exists());
+        assert!(report.over_limit);
+    }
+
+    #[test]
+    fn test_unanalyzed_recordings_survive_until_given_up() {
+        use crate::memory::job_ledger::{JobKind, MAX_ATTEMPTS};
+
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("recordings/20260101/0_00-12_00/10-00-00_pending.mp4");
+        write_file(&video, 400, 30 * DAY);
+
+        // 超过保留天数且超出容量上限，但还没分析：既不过期也不淘汰
+        let (db, engine) = engine(dir.path(), 10);
+        insert_recording(&db, "pending", &video);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE recordings SET analyzed = 0 WHERE id = 'pending'", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = engine.plan(SystemTime::now()).unwrap();
+        assert!(report.items.is_empty());
+        assert_eq!(report.awaiting_analysis, 1);
+        assert!(report.over_limit);
+        engine.run(false).unwrap();
+        assert!(video.exists());
+        assert!(purged_at(&db, "pending").is_none());
+
+        // 补跑台账放弃后按策略正常过期
+        let ledger = JobLedger::new(Arc::clone(&db));
+        for _ in 0..MAX_ATTEMPTS {
+            ledger.record_failure(JobKind::RecordingAnalysis, "pending", "err").unwrap();
+        }
+        let report = engine.plan(SystemTime::now()).unwrap();
+        assert_eq!(report.items.len(), 1);
+        assert_eq!(report.items[0].reason, PurgeReason::Expired { keep_days: 7 });
+    }
+
+    #[test]
+    fn test_validate_policies() {
+        assert!(validate_policies(&default_policies()).is_ok());
+        let mut bad = default_policies();
+        bad[0].extensions = vec![".mp4".to_string()];
+        assert!(validate_policies(&bad).is_err());
+        bad[0].extensions.clear();
+        bad[0].keep_days = MAX_KEEP_DAYS + 1;
+        assert!(validate_policies(&bad).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+/// 多轮对话测试：本地模拟服务器记录请求体，验证各供应商的消息格式转换
+
+mod common;
+
+use common::{json_reply, spawn_scripted_server};
+use vision_jarvis_lib::ai::{AIClient, AIProviderConfig, ChatMessage, ProviderType};
+
+fn client(base_url: &str, provider_type: ProviderType) -> AIClient {
+    let mut config = AIProviderConfig::new("mock", "Mock", base_url, "test-key", "text-model")
+        .with_provider_type(provider_type);
+    config.video_model = Some("vision-model".to_string());
+    AIClient::new(config).unwrap()
+}
+
+fn conversation() -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system("你是记忆助手"),
+        ChatMessage::user("周二下午在做什么？"),
+        ChatMessage::assistant("在调试 Rust 编译错误"),
+        ChatMessage::user("这张截图呢？").with_image("image/png", "aGVsbG8="),
+    ]
+}
+
+#[tokio::test]
+async fn test_openai_compatible_chat() {
+    let mut server = spawn_scripted_server(vec![json_reply(r#"{"choices":[{"message":{"content":"是终端报错"}}]}"#)]).await;
+
+    let answer = client(&server.url, ProviderType::OpenAI).chat(&conversation()).await.unwrap();
+    assert_eq!(answer, "是终端报错");
+
+    let body = server.next_request().await.json();
+    assert_eq!(body["model"], "vision-model");
+    let messages = body["messages"].as_array().unwrap();
+    let roles: Vec<&str> = messages.iter().map(|m| m["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+    assert_eq!(messages[3]["content"][1]["image_url"]["url"], "data:image/png;base64,aGVsbG8=");
+}
+
+#[tokio::test]
+async fn test_claude_chat_uses_top_level_system() {
+    let mut server = spawn_scripted_server(vec![json_reply(r#"{"content":[{"type":"text","text":"好的"}]}"#)]).await;
+
+    let answer = client(&server.url, ProviderType::Claude).chat(&conversation()).await.unwrap();
+    assert_eq!(answer, "好的");
+
+    let body = server.next_request().await.json();
+    assert_eq!(body["system"], "你是记忆助手");
+    let messages = body["messages"].as_array().unwrap();
+    let roles: Vec<&str> = messages.iter().map(|m| m["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, vec!["user", "assistant", "user"]);
+    let image = &messages[2]["content"][1];
+    assert_eq!(image["type"], "image");
+    assert_eq!(image["source"]["media_type"], "image/png");
+}
+
+#[tokio::test]
+async fn test_gemini_chat_maps_roles() {
+    let mut server = spawn_scripted_server(vec![json_reply(
+        r#"{"candidates":[{"content":{"parts":[{"text":"看起来是 VSCode"}],"role":"model"}}]}"#,
+    )]).await;
+
+    let answer = client(&server.url, ProviderType::Gemini).chat(&conversation()).await.unwrap();
+    assert_eq!(answer, "看起来是 VSCode");
+
+    let body = server.next_request().await.json();
+    assert_eq!(body["systemInstruction"]["parts"][0]["text"], "你是记忆助手");
+    let contents = body["contents"].as_array().unwrap();
+    let roles: Vec<&str> = contents.iter().map(|c| c["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, vec!["user", "model", "user"]);
+    assert_eq!(contents[2]["parts"][1]["inline_data"]["mime_type"], "image/png");
+}
+
+#[tokio::test]
+async fn test_text_only_chat_uses_text_model() {
+    let mut server = spawn_scripted_server(vec![json_reply(r#"{"choices":[{"message":{"content":"ok"}}]}"#)]).await;
+
+    let messages = vec![ChatMessage::user("你好")];
+    client(&server.url, ProviderType::Qwen).chat(&messages).await.unwrap();
+
+    let body = server.next_request().await.json();
+    assert_eq!(body["model"], "text-model");
+    assert!(body.get("stream").is_none());
+}
@@ -0,0 +1,101 @@
+/// 本地模型测试：模拟 Ollama / llama.cpp server，验证请求路径、图片字段与免鉴权
+
+mod common;
+
+use common::{json_reply, spawn_scripted_server};
+use vision_jarvis_lib::ai::factory::create_provider;
+use vision_jarvis_lib::ai::{AIClient, AIProviderConfig, ChatMessage, ProviderType};
+
+fn local_config(base_url: &str, provider_type: ProviderType) -> AIProviderConfig {
+    let mut config = AIProviderConfig::new("local", "Local", base_url, "", "text-model")
+        .with_provider_type(provider_type);
+    config.video_model = Some("vision-model".to_string());
+    config
+}
+
+#[tokio::test]
+async fn test_ollama_analyze_image_uses_native_chat() {
+    let mut server = spawn_scripted_server(vec![json_reply(
+        r#"{"model":"vision-model","message":{"role":"assistant","content":"在写代码"},"done":true,"prompt_eval_count":812,"eval_count":34}"#,
+    )]).await;
+
+    let provider = create_provider(local_config(&server.url, ProviderType::Ollama)).unwrap();
+    let completion = provider.analyze_image("aGVsbG8=", "描述屏幕").await.unwrap();
+    assert_eq!(completion.text, "在写代码");
+    let usage = completion.usage.unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (812, 34));
+
+    let captured = server.next_request().await;
+    let body = captured.json();
+    assert!(captured.request_line.starts_with("POST /api/chat "));
+    assert!(!captured.headers.contains("authorization:"));
+    assert_eq!(body["model"], "vision-model");
+    assert_eq!(body["stream"], false);
+    assert_eq!(body["messages"][0]["content"], "描述屏幕");
+    assert_eq!(body["messages"][0]["images"][0], "aGVsbG8=");
+}
+
+#[tokio::test]
+async fn test_ollama_chat_keeps_roles_and_images() {
+    let mut server = spawn_scripted_server(vec![json_reply(
+        r#"{"message":{"role":"assistant","content":"是终端报错"},"done":true}"#,
+    )]).await;
+
+    let messages = vec![
+        ChatMessage::system("你是记忆助手"),
+        ChatMessage::user("这张截图呢？").with_image("image/png", "aGVsbG8="),
+    ];
+    let answer = AIClient::new(local_config(&server.url, ProviderType::Ollama)).unwrap()
+        .chat(&messages).await.unwrap();
+    assert_eq!(answer, "是终端报错");
+
+    let body = server.next_request().await.json();
+    assert_eq!(body["model"], "vision-model");
+    assert_eq!(body["messages"][0]["role"], "system");
+    assert!(body["messages"][0].get("images").is_none());
+    assert_eq!(body["messages"][1]["role"], "user");
+    assert_eq!(body["messages"][1]["content"], "这张截图呢？");
+    assert_eq!(body["messages"][1]["images"][0], "aGVsbG8=");
+}
+
+#[tokio::test]
+async fn test_ollama_stream_ndjson() {
+    let lines = [
+        r#"{"message":{"role":"assistant","content":"你"},"done":false}"#,
+        r#"{"message":{"role":"assistant","content":"好"},"done":false}"#,
+        r#"{"message":{"role":"assistant","content":""},"done":true,"eval_count":2}"#,
+    ];
+    let reply = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n{}\n",
+        lines.join("\n")
+    );
+    let mut server = spawn_scripted_server(vec![reply]).await;
+
+    let stream = AIClient::new(local_config(&server.url, ProviderType::Ollama)).unwrap()
+        .send_text_stream("hello").await.unwrap();
+    assert_eq!(stream.collect_text().await.unwrap(), "你好");
+    assert_eq!(server.next_request().await.json()["stream"], true);
+}
+
+#[tokio::test]
+async fn test_openai_compatible_without_api_key() {
+    let mut server = spawn_scripted_server(vec![json_reply(
+        r#"{"choices":[{"message":{"content":"ok"}}],"usage":{"prompt_tokens":5,"completion_tokens":1}}"#,
+    )]).await;
+
+    let answer = AIClient::new(local_config(&server.url, ProviderType::OpenAICompatible)).unwrap()
+        .send_text("hello").await.unwrap();
+    assert_eq!(answer, "ok");
+
+    let captured = server.next_request().await;
+    let body = captured.json();
+    assert!(captured.request_line.starts_with("POST /v1/chat/completions "));
+    assert!(!captured.headers.contains("authorization:"));
+    assert_eq!(body["model"], "text-model");
+}
+
+#[tokio::test]
+async fn test_hosted_provider_still_requires_api_key() {
+    let config = local_config("http://127.0.0.1:1", ProviderType::OpenAI);
+    assert!(AIClient::new(config).is_err());
+}
@@ -0,0 +1,79 @@
+/// 重试与故障转移测试：本地模拟服务器按顺序返回预设响应
+
+mod common;
+
+use std::time::Duration;
+
+use common::{reply, spawn_scripted_server};
+use vision_jarvis_lib::ai::{AIClient, AIConfig, AIProviderConfig, ProviderType, RetryPolicy};
+use vision_jarvis_lib::AppError;
+
+const OK_BODY: &str = r#"{"choices":[{"message":{"content":"ok"}}]}"#;
+
+fn provider(id: &str, base_url: &str) -> AIProviderConfig {
+    AIProviderConfig::new(id, id, base_url, "test-key", "mock-model")
+        .with_provider_type(ProviderType::OpenAI)
+}
+
+fn fast_policy() -> RetryPolicy {
+    RetryPolicy {
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(50),
+        jitter: false,
+        ..RetryPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn test_retries_after_rate_limit() {
+    let server = spawn_scripted_server(vec![
+        reply("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+        reply("503 Service Unavailable", "", "overloaded"),
+        reply("200 OK", "", OK_BODY),
+    ]).await;
+
+    let client = AIClient::new(provider("primary", &server.url)).unwrap().with_retry_policy(fast_policy());
+    assert_eq!(client.send_text("hello").await.unwrap(), "ok");
+    assert_eq!(server.served(), 3);
+
+    let attempts = client.recent_attempts(10);
+    assert_eq!(attempts.len(), 3);
+    assert!(attempts[0].error.as_deref().unwrap().contains("Retry-After: 0s"));
+    assert!(attempts[2].success);
+}
+
+#[tokio::test]
+async fn test_fails_over_to_next_enabled_provider() {
+    let primary = spawn_scripted_server(vec![
+        reply("401 Unauthorized", "", "bad key"),
+    ]).await;
+    let backup = spawn_scripted_server(vec![reply("200 OK", "", OK_BODY)]).await;
+
+    let mut config = AIConfig::new();
+    config.add_provider(provider("backup", &backup.url)).unwrap();
+    config.add_provider(provider("primary", &primary.url)).unwrap();
+    config.set_active_provider("primary").unwrap();
+
+    let client = AIClient::from_ai_config(&config).unwrap().with_retry_policy(fast_policy());
+    assert_eq!(client.config().id, "primary");
+    assert_eq!(client.send_text("hello").await.unwrap(), "ok");
+    assert_eq!(primary.served(), 1);
+    assert_eq!(backup.served(), 1);
+
+    let attempts = client.recent_attempts(10);
+    assert_eq!(attempts[0].provider_id, "primary");
+    assert!(!attempts[0].retryable);
+    assert_eq!(attempts[1].provider_id, "backup");
+}
+
+#[tokio::test]
+async fn test_gives_up_on_long_retry_after() {
+    let server = spawn_scripted_server(vec![
+        reply("429 Too Many Requests", "Retry-After: 3600\r\n", ""),
+    ]).await;
+
+    let client = AIClient::new(provider("primary", &server.url)).unwrap().with_retry_policy(fast_policy());
+    let err = client.send_text("hello").await.unwrap_err();
+    assert!(matches!(err, AppError::Http { status: 429, .. }));
+    assert_eq!(server.served(), 1);
+}
@@ -0,0 +1,142 @@
+/// 流式响应测试：本地模拟 SSE 服务器，覆盖 OpenAI 兼容、Claude、Gemini 三种格式
+
+mod common;
+
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot};
+
+use common::Captured;
+use vision_jarvis_lib::ai::{AIClient, AIProviderConfig, ProviderType, RetryPolicy};
+use vision_jarvis_lib::AppError;
+
+/// 启动只处理一个连接的 SSE 服务器
+///
+/// 先发送 `first`，等待 `resume` 信号后再发送 `rest`，用于验证客户端是逐段收到内容的
+async fn spawn_sse_server(
+    status: &'static str,
+    first: &'static str,
+    rest: &'static str,
+) -> (String, oneshot::Receiver<Captured>, mpsc::Sender<()>) {
+    let (listener, url) = common::bind().await;
+    let (request_tx, request_rx) = oneshot::channel();
+    let (resume_tx, mut resume_rx) = mpsc::channel::<()>(1);
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = request_tx.send(common::read_request(&mut socket).await);
+
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+            status
+        );
+        socket.write_all(head.as_bytes()).await.unwrap();
+        socket.write_all(first.as_bytes()).await.unwrap();
+        socket.flush().await.unwrap();
+
+        if !rest.is_empty() {
+            let _ = tokio::time::timeout(Duration::from_secs(5), resume_rx.recv()).await;
+            socket.write_all(rest.as_bytes()).await.unwrap();
+        }
+        let _ = socket.shutdown().await;
+    });
+
+    (url, request_rx, resume_tx)
+}
+
+fn client(base_url: &str, provider_type: ProviderType) -> AIClient {
+    let config = AIProviderConfig::new("mock", "Mock", base_url, "test-key", "mock-model")
+        .with_provider_type(provider_type);
+    AIClient::new(config).unwrap()
+}
+
+/// 读取第一段后放行服务器，再收集剩余内容
+async fn read_stream(client: &AIClient, resume: mpsc::Sender<()>) -> Vec<String> {
+    let mut stream = client.send_text_stream("hello").await.unwrap();
+    let mut tokens = vec![stream.next().await.unwrap().unwrap()];
+    resume.send(()).await.unwrap();
+    while let Some(token) = stream.next().await {
+        tokens.push(token.unwrap());
+    }
+    tokens
+}
+
+#[tokio::test]
+async fn test_openai_compatible_stream() {
+    let (url, request, resume) = spawn_sse_server(
+        "200 OK",
+        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+         data: {\"choices\":[{\"delta\":{\"content\":\"你好\"}}]}\n\n",
+        ": keep-alive\n\n\
+         data: {\"choices\":[{\"delta\":{\"content\":\"，世界\"}}]}\n\n\
+         data: [DONE]\n\n\
+         data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n",
+    ).await;
+
+    let tokens = read_stream(&client(&url, ProviderType::OpenAI), resume).await;
+    assert_eq!(tokens, vec!["你好", "，世界"]);
+
+    let request = request.await.unwrap();
+    assert!(request.request_line.starts_with("POST /v1/chat/completions"));
+    assert!(request.text().contains("\"stream\":true"));
+    assert!(request.text().contains("\"stream_options\":{\"include_usage\":true}"));
+}
+
+#[tokio::test]
+async fn test_claude_stream() {
+    let (url, request, resume) = spawn_sse_server(
+        "200 OK",
+        "event: message_start\r\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\"}}\r\n\r\n\
+         event: content_block_delta\r\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\r\n\r\n",
+        "event: ping\r\ndata: {\"type\":\"ping\"}\r\n\r\n\
+         event: content_block_delta\r\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" there\"}}\r\n\r\n\
+         event: message_stop\r\ndata: {\"type\":\"message_stop\"}\r\n\r\n",
+    ).await;
+
+    let tokens = read_stream(&client(&url, ProviderType::Claude), resume).await;
+    assert_eq!(tokens, vec!["Hello", " there"]);
+
+    let request = request.await.unwrap();
+    assert!(request.request_line.starts_with("POST /v1/messages"));
+    assert!(request.text().contains("\"stream\":true"));
+}
+
+#[tokio::test]
+async fn test_gemini_stream() {
+    let (url, request, resume) = spawn_sse_server(
+        "200 OK",
+        "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"今天\"}],\"role\":\"model\"}}]}\n\n",
+        "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"写了代码\"}],\"role\":\"model\"},\"finishReason\":\"STOP\"}]}\n\n",
+    ).await;
+
+    let tokens = read_stream(&client(&url, ProviderType::Gemini), resume).await;
+    assert_eq!(tokens, vec!["今天", "写了代码"]);
+
+    let request = request.await.unwrap();
+    assert!(request
+        .request_line
+        .starts_with("POST /v1beta/models/mock-model:streamGenerateContent?alt=sse"));
+}
+
+#[tokio::test]
+async fn test_stream_error_event() {
+    let (url, _request, _resume) = spawn_sse_server(
+        "200 OK",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"部分\"}}]}\n\n\
+         data: {\"error\":{\"message\":\"upstream overloaded\"}}\n\n",
+        "",
+    ).await;
+
+    let stream = client(&url, ProviderType::OpenAI).send_text_stream("hello").await.unwrap();
+    let err = stream.collect_text().await.unwrap_err();
+    assert!(err.to_string().contains("upstream overloaded"));
+}
+
+#[tokio::test]
+async fn test_stream_http_error_status() {
+    let (url, _request, _resume) = spawn_sse_server("429 Too Many Requests", "", "").await;
+
+    let client = client(&url, ProviderType::OpenAI).with_retry_policy(RetryPolicy::no_retry());
+    let result = client.send_text_stream("hello").await;
+    assert!(matches!(result, Err(AppError::Http { status: 429, .. })));
+}
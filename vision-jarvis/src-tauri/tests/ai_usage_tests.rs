@@ -0,0 +1,60 @@
+/// 用量解析测试：模拟各供应商响应中的 usage 字段，验证 token 数与估算花费
+
+mod common;
+
+use common::{json_reply, spawn_scripted_server};
+use vision_jarvis_lib::ai::factory::create_provider;
+use vision_jarvis_lib::ai::{AIProviderConfig, ProviderType, TokenUsage};
+
+/// 发送一次请求，返回解析出的用量与按单价估算的花费
+async fn usage_of(provider_type: ProviderType, response: &'static str) -> (Option<TokenUsage>, f64) {
+    let server = spawn_scripted_server(vec![json_reply(response)]).await;
+    let mut config = AIProviderConfig::new("mock", "Mock", &server.url, "test-key", "mock-model")
+        .with_provider_type(provider_type);
+    config.input_price_per_mtok = Some(2.0);
+    config.output_price_per_mtok = Some(10.0);
+
+    let provider = create_provider(config.clone()).unwrap();
+    let completion = provider.send_text("hello").await.unwrap();
+    assert_eq!(completion.text, "ok");
+    let cost = config.estimate_cost("mock-model", &completion.usage.unwrap_or_default());
+    (completion.usage, cost)
+}
+
+#[tokio::test]
+async fn test_openai_usage() {
+    let (usage, cost) = usage_of(
+        ProviderType::OpenAI,
+        r#"{"choices":[{"message":{"content":"ok"}}],"usage":{"prompt_tokens":1200,"completion_tokens":300,"total_tokens":1500}}"#,
+    ).await;
+    assert_eq!(usage, Some(TokenUsage::new(1200, 300)));
+    assert!((cost - 0.0054).abs() < 1e-12);
+}
+
+#[tokio::test]
+async fn test_claude_usage() {
+    let (usage, _) = usage_of(
+        ProviderType::Claude,
+        r#"{"content":[{"type":"text","text":"ok"}],"usage":{"input_tokens":800,"output_tokens":50}}"#,
+    ).await;
+    assert_eq!(usage, Some(TokenUsage::new(800, 50)));
+}
+
+#[tokio::test]
+async fn test_gemini_usage() {
+    let (usage, _) = usage_of(
+        ProviderType::Gemini,
+        r#"{"candidates":[{"content":{"parts":[{"text":"ok"}]}}],"usageMetadata":{"promptTokenCount":2580,"candidatesTokenCount":120,"totalTokenCount":2700}}"#,
+    ).await;
+    assert_eq!(usage, Some(TokenUsage::new(2580, 120)));
+}
+
+#[tokio::test]
+async fn test_missing_usage() {
+    let (usage, cost) = usage_of(
+        ProviderType::Qwen,
+        r#"{"choices":[{"message":{"content":"ok"}}]}"#,
+    ).await;
+    assert_eq!(usage, None);
+    assert_eq!(cost, 0.0);
+}
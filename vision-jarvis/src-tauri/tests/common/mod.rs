@@ -0,0 +1,131 @@
+//! 集成测试共用的本地模拟 HTTP 服务器
+//!
+//! 每个连接读取一个完整请求（头部 + Content-Length 指定的请求体），按顺序写回预设响应后关闭连接
+
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// 服务器收到的请求
+pub struct Captured {
+    pub request_line: String,
+    /// 小写化的请求头
+    pub headers: String,
+    pub body: Vec<u8>,
+}
+
+impl Captured {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// 按顺序回放响应的服务器
+pub struct MockServer {
+    pub url: String,
+    requests: mpsc::UnboundedReceiver<Captured>,
+    served: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    /// 等待下一个收到的请求
+    pub async fn next_request(&mut self) -> Captured {
+        self.requests.recv().await.expect("服务器未收到请求")
+    }
+
+    /// 已处理的请求数
+    pub fn served(&self) -> usize {
+        self.served.load(Ordering::SeqCst)
+    }
+}
+
+/// 绑定本地随机端口，返回 (listener, base_url)
+pub async fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    (listener, url)
+}
+
+/// 读取一个完整请求；客户端提前断开时返回已读到的部分
+pub async fn read_request(socket: &mut TcpStream) -> Captured {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 8192];
+    let header_end = loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break raw.len();
+        }
+        raw.extend_from_slice(&buf[..n]);
+        if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&raw[..header_end]).to_string();
+    let (request_line, headers) = head.split_once("\r\n").unwrap_or((head.as_str(), ""));
+    let headers = headers.to_lowercase();
+    let content_length: usize = headers
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+        .unwrap_or(0);
+    while raw.len() < header_end + content_length {
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+
+    Captured {
+        request_line: request_line.to_string(),
+        headers,
+        body: raw[header_end..].to_vec(),
+    }
+}
+
+/// 启动按顺序回放响应的服务器（每个连接一个响应，原样写回，含状态行与头部）
+pub async fn spawn_scripted_server(responses: Vec<String>) -> MockServer {
+    let (listener, url) = bind().await;
+    let (tx, requests) = mpsc::unbounded_channel();
+    let served = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&served);
+
+    tokio::spawn(async move {
+        for response in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let captured = read_request(&mut socket).await;
+
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = tx.send(captured);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            let _ = socket.shutdown().await;
+        }
+    });
+
+    MockServer { url, requests, served }
+}
+
+/// 构造 JSON 响应，extra_headers 需自带 `\r\n`
+pub fn reply(status: &str, extra_headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        extra_headers,
+        body.len(),
+        body
+    )
+}
+
+/// 200 OK 的 JSON 响应
+pub fn json_reply(body: &str) -> String {
+    reply("200 OK", "", body)
+}
@@ -0,0 +1,95 @@
+/// 核心运行时测试：不启动 Tauri，在临时数据目录中完成启动、重新配置与关闭
+
+use serde_json::json;
+use std::time::Duration;
+
+use vision_jarvis_lib::runtime::{db_path, Runtime, RuntimeConfig};
+
+fn config(dir: &std::path::Path) -> RuntimeConfig {
+    RuntimeConfig {
+        data_dir: dir.join("data"),
+        settings: json!({
+            "memory_enabled": false,
+            "storage_path": dir.join("recordings"),
+        })
+        .as_object()
+        .unwrap()
+        .clone(),
+    }
+}
+
+/// 与守护进程相同：先打开运行时，再进入 tokio runtime
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Runtime::new().unwrap().block_on(future)
+}
+
+/// 轮询等待条件成立（最多 5 秒），避免依赖固定的 sleep 时长
+async fn wait_until(condition: impl Fn() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while tokio::time::Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    condition()
+}
+
+#[test]
+fn test_headless_start_and_shutdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(dir.path());
+
+    let runtime = Runtime::open(&config).unwrap();
+    assert!(db_path(&config.data_dir).exists());
+    block_on(async {
+        runtime.start();
+
+        // 标准文件夹在启动时创建；记忆功能关闭时录制与管道都不运行
+        assert!(dir.path().join("recordings").is_dir());
+        assert!(!runtime.state.pipeline.is_running());
+        assert!(!runtime.state.scheduler.lock().await.is_running().await);
+
+        runtime.shutdown().await;
+        assert!(!runtime.state.pipeline.is_running());
+    });
+}
+
+/// 只订阅管道：开启记忆功能不会在测试中启动真实的屏幕录制
+#[test]
+fn test_pipeline_follows_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let runtime = Runtime::open(&config(dir.path())).unwrap();
+    block_on(async {
+        runtime.state.pipeline.watch_settings(runtime.state.settings.subscribe());
+
+        let settings = runtime.state.settings.get();
+        let mut enabled = settings.clone();
+        enabled.memory_enabled = true;
+        runtime.state.settings.update(enabled).unwrap();
+        assert!(wait_until(|| runtime.state.pipeline.is_running()).await);
+
+        runtime.state.settings.update(settings).unwrap();
+        assert!(wait_until(|| !runtime.state.pipeline.is_running()).await);
+        runtime.shutdown().await;
+    });
+}
+
+#[test]
+fn test_settings_overrides_persist() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(dir.path());
+
+    let runtime = Runtime::open(&config).unwrap();
+    assert!(!runtime.state.settings.is_memory_enabled());
+    assert_eq!(runtime.state.settings.get_storage_path(), dir.path().join("recordings"));
+    drop(runtime);
+
+    // 不带覆盖项重新打开时沿用已写回的设置
+    let reopened = Runtime::open(&RuntimeConfig {
+        settings: Default::default(),
+        ..config
+    })
+    .unwrap();
+    assert!(!reopened.state.settings.is_memory_enabled());
+}
@@ -127,7 +127,7 @@ export function SettingsPage() {
     if (!selectedProviderId) { showNotification('请先选择一个提供商', 'error'); return null }
     const entry = PROVIDER_REGISTRY.find(p => p.id === selectedProviderId)
     if (!entry) { showNotification('未知的提供商', 'error'); return null }
-    if (!apiKey) { showNotification(`请输入 ${entry.name} API Key`, 'error'); return null }
+    if (!apiKey && !entry.isLocal) { showNotification(`请输入 ${entry.name} API Key`, 'error'); return null }
     if (!model) { showNotification('请选择模型', 'error'); return null }
     return {
       id: entry.id,
@@ -136,7 +136,7 @@ export function SettingsPage() {
       api_key: apiKey,
       model,
       provider_type: entry.providerType,
-      video_model: entry.isThirdParty && videoModel ? videoModel : null,
+      video_model: (entry.isThirdParty || entry.isLocal) && videoModel ? videoModel : null,
     }
   }
 
@@ -365,11 +365,11 @@ export function SettingsPage() {
                     <div>
                       <label className="text-xs text-muted block mb-2 uppercase tracking-wider">API Key</label>
                       <input type="password" value={apiKey} onChange={e => setApiKey(e.target.value)}
-                        className={`${INPUT} font-mono`} placeholder={`输入 ${entry.name} API Key`} />
+                        className={`${INPUT} font-mono`} placeholder={entry.isLocal ? '本地服务无需 API Key，可留空' : `输入 ${entry.name} API Key`} />
                     </div>
                     <div>
                       <label className="text-xs text-muted block mb-2 uppercase tracking-wider">
-                        {entry.isThirdParty ? '语言模型' : '模型'}
+                        {entry.isThirdParty || entry.isLocal ? '语言模型' : '模型'}
                       </label>
                       <select value={model} onChange={e => setModel(e.target.value)}
                         className={`${INPUT} appearance-none`}>
@@ -378,7 +378,7 @@ export function SettingsPage() {
                         ))}
                       </select>
                     </div>
-                    {entry.isThirdParty && entry.videoModels && (
+                    {(entry.isThirdParty || entry.isLocal) && entry.videoModels && (
                       <div>
                         <label className="text-xs text-muted block mb-2 uppercase tracking-wider">视频模型</label>
                         <select value={videoModel} onChange={e => setVideoModel(e.target.value)}
@@ -525,9 +525,8 @@ function AIStatus({ config }: { config: AIConfig | null }) {
     return <p className="text-sm text-muted">未配置任何 AI 提供商</p>
   const active = config.providers.find(p => p.id === config.active_provider_id)
   if (!active) return <p className="text-sm text-muted">已配置提供商但未激活</p>
-  const masked = active.api_key.length > 8
-    ? active.api_key.slice(0, 4) + '····' + active.api_key.slice(-4)
-    : '····'
+  // 后端只返回掩码后的 Key
+  const masked = active.has_api_key ? active.api_key : '未设置'
   return (
     <div className="text-sm">
       <div className="flex items-center gap-2 mb-3">
@@ -8,6 +8,8 @@ export interface ProviderRegistryEntry {
   models: string[]
   videoModels?: string[]
   isThirdParty: boolean
+  /** 本机运行的模型服务，API Key 可留空 */
+  isLocal?: boolean
 }
 
 export const PROVIDER_REGISTRY: ProviderRegistryEntry[] = [
@@ -52,4 +54,23 @@ export const PROVIDER_REGISTRY: ProviderRegistryEntry[] = [
     videoModels: ['Qwen/Qwen3-VL-8B-Instruct', 'Pro/zai-org/GLM-4.7'],
     isThirdParty: true,
   },
+  {
+    id: 'ollama',
+    name: 'Ollama (本地)',
+    apiBaseUrl: 'http://localhost:11434',
+    providerType: 'Ollama',
+    models: ['qwen2.5vl:7b', 'llama3.2-vision', 'gemma3:12b'],
+    videoModels: ['qwen2.5vl:7b', 'llama3.2-vision'],
+    isThirdParty: false,
+    isLocal: true,
+  },
+  {
+    id: 'llama-cpp',
+    name: 'llama.cpp (本地)',
+    apiBaseUrl: 'http://localhost:8080',
+    providerType: 'OpenAICompatible',
+    models: ['default'],
+    isThirdParty: false,
+    isLocal: true,
+  },
 ]
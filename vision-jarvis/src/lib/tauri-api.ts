@@ -31,20 +31,66 @@ export interface AppSettings {
   screen_inactivity_reminder_enabled: boolean
   screen_inactivity_minutes: number
   screen_inactivity_message: string
-
-  openai_api_key: string | null
 }
 
 export type UpdateSettingsParams = Partial<AppSettings>
 export type ResetSettingsParams = Record<string, never>
 
+export interface ExclusionSchedule {
+  days: number[]
+  start: string
+  end: string
+}
+
+export interface ReminderSet {
+  morning: boolean
+  water: boolean
+  sedentary: boolean
+  screen_inactivity: boolean
+  idle: boolean
+}
+
+export interface PrivacyOverride {
+  excluded_apps: string[]
+  excluded_title_patterns: string[]
+  exclude_incognito: boolean
+  schedules: ExclusionSchedule[]
+}
+
+export interface SettingsProfile {
+  id: string
+  name: string
+  memory_enabled: boolean | null
+  reminders: ReminderSet | null
+  privacy: PrivacyOverride | null
+  ai_provider_id: string | null
+  switch_schedules: ExclusionSchedule[]
+  switch_apps: string[]
+}
+
+export interface ProfileState {
+  profiles: SettingsProfile[]
+  active: string | null
+  auto_switch: boolean
+}
+
 export interface SchedulerStatus {
   is_running: boolean
   interval_seconds: number
   memory_enabled: boolean
   storage_path: string
+  capture_backend: string
+  privacy_paused_until: number | null
+  privacy_exclusion: ExclusionReason | null
 }
 
+export type ExclusionReason =
+  | { kind: 'paused'; until: number }
+  | { kind: 'schedule'; start: string; end: string }
+  | { kind: 'app'; app_name: string }
+  | { kind: 'title'; pattern: string }
+  | { kind: 'incognito'; app_name: string }
+
 export interface ScreenshotInfo {
   id: string
   path: string
@@ -52,18 +98,24 @@ export interface ScreenshotInfo {
   analyzed: boolean
 }
 
-export type ProviderType = 'OpenAI' | 'Claude' | 'Gemini' | 'Qwen' | 'AIHubMix' | 'OpenRouter' | 'SiliconFlow'
+export type ProviderType =
+  | 'OpenAI' | 'Claude' | 'Gemini' | 'Qwen' | 'AIHubMix' | 'OpenRouter' | 'SiliconFlow'
+  | 'Ollama' | 'OpenAICompatible'
 
 export interface AIProviderConfig {
   id: string
   name: string
   api_base_url: string
+  /** get_ai_config 返回掩码；原样提交回去表示不修改 */
   api_key: string
+  /** 仅 get_ai_config 返回 */
+  has_api_key?: boolean
   model: string
   enabled: boolean
   is_active: boolean
   provider_type: ProviderType
   video_model?: string | null
+  embedding_model?: string | null
 }
 
 export interface AIConfig {
@@ -114,6 +166,98 @@ export interface CleanupResult {
   freed_bytes: number
 }
 
+export interface PurgeItem {
+  path: string
+  size_bytes: number
+  modified_at: number
+  reason: { kind: 'expired'; keep_days: number } | { kind: 'quota' }
+}
+
+export interface RetentionReport {
+  dry_run: boolean
+  usage_bytes: number
+  limit_bytes: number
+  items: PurgeItem[]
+  freed_bytes: number
+  recordings_purged: number
+  over_limit: boolean
+  failed: number
+}
+
+export interface ArchiveReport {
+  archived: number
+  failed: number
+  missing: number
+  freed_bytes: number
+  keyframe_bytes: number
+}
+
+export interface SummaryInfo {
+  id: string
+  summary_type: 'daily' | 'weekly' | 'monthly' | 'range'
+  date_start: string
+  date_end: string
+  content: string
+  activity_count: number
+}
+
+export interface KnowledgeBaseReport {
+  notes: number
+  updated: number
+  missing: number
+  links: number
+  index_files: number
+}
+
+export interface CatchUpReport {
+  analyzed: number
+  analysis_failed: number
+  summaries: number
+  summary_failed: number
+  budget_exhausted: boolean
+}
+
+export interface ExportManifest {
+  format: string
+  format_version: number
+  schema_version: number
+  app_version: string
+  exported_at: number
+  tables: Record<string, number>
+  markdown_files: number
+}
+
+export type ConflictPolicy = 'keep_local' | 'overwrite'
+
+export interface ImportConflict {
+  target: string
+  id: string
+  overwritten: boolean
+}
+
+export interface ImportReport {
+  inserted: Record<string, number>
+  unchanged: number
+  files_added: number
+  files_unchanged: number
+  conflicts: ImportConflict[]
+}
+
+export type KeySource = 'passphrase' | 'keyring'
+
+export interface EncryptionStatus {
+  enabled: boolean
+  unlocked: boolean
+  key_source: KeySource | null
+  pending_migration: 'encrypt' | 'decrypt' | null
+  plaintext_search_index: boolean
+}
+
+export interface MigrationStats {
+  files: number
+  rows: number
+}
+
 interface ApiResponse<T> {
   success: boolean
   data: T | null
@@ -169,6 +313,38 @@ export const TauriAPI = {
     settingsCache = null
   },
 
+  // Settings profiles
+  async getSettingsProfiles(): Promise<ProfileState> {
+    return call<ProfileState>('get_settings_profiles')
+  },
+
+  async saveSettingsProfile(profile: SettingsProfile): Promise<boolean> {
+    settingsCache = null
+    return call<boolean>('save_settings_profile', { profile })
+  },
+
+  async deleteSettingsProfile(id: string): Promise<boolean> {
+    settingsCache = null
+    return call<boolean>('delete_settings_profile', { id })
+  },
+
+  async activateSettingsProfile(id: string): Promise<AppSettings> {
+    const settings = await call<AppSettings>('activate_settings_profile', { id })
+    settingsCache = settings
+    return settings
+  },
+
+  async deactivateSettingsProfile(): Promise<AppSettings> {
+    const settings = await call<AppSettings>('deactivate_settings_profile')
+    settingsCache = settings
+    return settings
+  },
+
+  async setProfileAutoSwitch(enabled: boolean): Promise<boolean> {
+    settingsCache = null
+    return call<boolean>('set_profile_auto_switch', { enabled })
+  },
+
   // Auto Start
   async enableAutoStart(): Promise<void> {
     await invoke('plugin:autostart|enable')
@@ -195,6 +371,14 @@ export const TauriAPI = {
     return call<SchedulerStatus>('get_scheduler_status')
   },
 
+  async pauseRecordingFor(minutes: number): Promise<number> {
+    return call<number>('pause_recording_for', { minutes })
+  },
+
+  async resumeRecording(): Promise<boolean> {
+    return call<boolean>('resume_recording')
+  },
+
   // Storage
   async getStorageInfo(): Promise<StorageInfo> {
     return call<StorageInfo>('get_storage_info')
@@ -208,6 +392,46 @@ export const TauriAPI = {
     return call<CleanupResult>('cleanup_old_files', { days })
   },
 
+  async previewRetention(): Promise<RetentionReport> {
+    return call<RetentionReport>('preview_retention')
+  },
+
+  async runRetention(): Promise<RetentionReport> {
+    return call<RetentionReport>('run_retention')
+  },
+
+  async runKeyframeArchive(): Promise<ArchiveReport> {
+    return call<ArchiveReport>('run_keyframe_archive')
+  },
+
+  async getRecordingKeyframes(recordingId: string): Promise<string[]> {
+    return call<string[]>('get_recording_keyframes', { recordingId })
+  },
+
+  async exportVault(destPath: string): Promise<ExportManifest> {
+    return call<ExportManifest>('export_vault', { destPath })
+  },
+
+  async previewVaultImport(srcPath: string): Promise<ExportManifest> {
+    return call<ExportManifest>('preview_vault_import', { srcPath })
+  },
+
+  async importVault(srcPath: string, onConflict?: ConflictPolicy): Promise<ImportReport> {
+    return call<ImportReport>('import_vault', { srcPath, onConflict })
+  },
+
+  async generateRangeSummary(startDate: string, endDate: string): Promise<SummaryInfo> {
+    return call<SummaryInfo>('generate_range_summary', { startDate, endDate })
+  },
+
+  async rebuildKnowledgeBase(): Promise<KnowledgeBaseReport> {
+    return call<KnowledgeBaseReport>('rebuild_knowledge_base')
+  },
+
+  async runCatchUp(): Promise<CatchUpReport> {
+    return call<CatchUpReport>('run_catch_up')
+  },
+
   async deleteFile(path: string): Promise<boolean> {
     return call<boolean>('delete_file', { path })
   },
@@ -216,6 +440,31 @@ export const TauriAPI = {
     return call<string>('open_folder', { path })
   },
 
+  // Encryption
+  async getEncryptionStatus(): Promise<EncryptionStatus> {
+    return call<EncryptionStatus>('get_encryption_status')
+  },
+
+  async enableEncryption(source: KeySource, passphrase?: string): Promise<MigrationStats> {
+    return call<MigrationStats>('enable_encryption', { source, passphrase })
+  },
+
+  async unlockEncryption(passphrase?: string): Promise<MigrationStats> {
+    return call<MigrationStats>('unlock_encryption', { passphrase })
+  },
+
+  async lockEncryption(): Promise<boolean> {
+    return call<boolean>('lock_encryption')
+  },
+
+  async changeEncryptionPassphrase(oldPassphrase: string, newPassphrase: string): Promise<boolean> {
+    return call<boolean>('change_encryption_passphrase', { oldPassphrase, newPassphrase })
+  },
+
+  async disableEncryption(): Promise<MigrationStats> {
+    return call<MigrationStats>('disable_encryption')
+  },
+
   // AI Config
   async getAIConfigSummary(): Promise<AIConfigSummary> {
     return call<AIConfigSummary>('get_ai_config_summary')
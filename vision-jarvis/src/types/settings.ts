@@ -58,6 +58,4 @@ export const DEFAULT_SETTINGS: AppSettings = {
   screen_inactivity_reminder_enabled: false,
   screen_inactivity_minutes: 10,
   screen_inactivity_message: '',
-
-  openai_api_key: null,
 }